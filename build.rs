@@ -8,6 +8,7 @@ const GENERATED_CONTRACTS_PATH: &str = "./src/contracts";
 fn main() -> anyhow::Result<()> {
     println!("cargo:rerun-if-changed=./abis");
     println!("cargo:rerun-if-changed=./src/contracts");
+    println!("cargo:rerun-if-changed=./proto");
 
     if Path::new(GENERATED_CONTRACTS_PATH)
         .try_exists()
@@ -24,5 +25,11 @@ fn main() -> anyhow::Result<()> {
         .write_to_module(GENERATED_CONTRACTS_PATH, false)
         .expect("could not write to module");
 
+    // a vendored protoc is used so that building doesn't require one to be installed
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["./proto/answerer.proto"], &["./proto"])?;
+
     Ok(())
 }
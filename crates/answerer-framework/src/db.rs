@@ -0,0 +1,18 @@
+pub mod pagination;
+
+use tokio::{
+    runtime::{Handle, RuntimeFlavor},
+    task::block_in_place,
+};
+
+// diesel and r2d2 block the calling thread until the database replies, stalling
+// every other task queued on the same tokio worker thread. database work done from
+// async code goes through here, so that the runtime hands those tasks over to another
+// thread in the meantime. outside of a multi threaded runtime (e.g. in tests) the work
+// simply runs in place
+pub fn blocking<T>(work: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => block_in_place(work),
+        _ => work(),
+    }
+}
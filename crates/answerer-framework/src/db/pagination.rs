@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use diesel::{
+    dsl::{sql, Asc, Desc},
+    expression::SqlLiteral,
+    query_dsl::methods::ThenOrderDsl,
+    sql_types::BigInt,
+    ExpressionMethods,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl FromStr for SortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(order: &str) -> Result<Self, Self::Err> {
+        match order {
+            "asc" => Ok(SortOrder::Ascending),
+            "desc" => Ok(SortOrder::Descending),
+            _ => Err(anyhow::anyhow!("unknown sort order {}", order)),
+        }
+    }
+}
+
+// the sort field is specific to each table, and is expected to be an enum of the
+// columns records can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination<S> {
+    pub limit: i64,
+    pub offset: i64,
+    pub sort: S,
+    pub order: SortOrder,
+}
+
+// a page of records along with how many records match the query overall, which is
+// only known when the page isn't empty
+#[derive(Debug, PartialEq)]
+pub struct Page<T> {
+    pub records: Vec<T>,
+    pub total: i64,
+}
+
+impl<T> Page<T> {
+    // records are expected to be loaded along with the total count
+    pub fn from_counted(records: Vec<(T, i64)>) -> Self {
+        let total = records.first().map(|(_, total)| *total).unwrap_or_default();
+        Self {
+            records: records.into_iter().map(|(record, _)| record).collect(),
+            total,
+        }
+    }
+}
+
+// selected along with the records so that matching records are counted by the same
+// query, before the limit and offset are applied
+pub fn total_count() -> SqlLiteral<BigInt> {
+    sql::<BigInt>("count(*) over ()")
+}
+
+pub fn then_order_by<Q, E>(query: Q, expression: E, order: SortOrder) -> Q
+where
+    E: ExpressionMethods,
+    Q: ThenOrderDsl<Asc<E>, Output = Q> + ThenOrderDsl<Desc<E>, Output = Q>,
+{
+    match order {
+        SortOrder::Ascending => ThenOrderDsl::then_order_by(query, expression.asc()),
+        SortOrder::Descending => ThenOrderDsl::then_order_by(query, expression.desc()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Page, SortOrder};
+
+    #[test]
+    fn parse_sort_order() {
+        assert_eq!("asc".parse::<SortOrder>().unwrap(), SortOrder::Ascending);
+        assert_eq!("desc".parse::<SortOrder>().unwrap(), SortOrder::Descending);
+        assert!("descending".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn page_from_counted() {
+        let page = Page::from_counted(vec![("foo", 5), ("bar", 5)]);
+        assert_eq!(page.records, vec!["foo", "bar"]);
+        assert_eq!(page.total, 5);
+
+        let page = Page::<&str>::from_counted(vec![]);
+        assert_eq!(page.total, 0);
+    }
+}
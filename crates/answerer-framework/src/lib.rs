@@ -0,0 +1,38 @@
+pub mod db;
+pub mod quorum;
+pub mod scanner;
+pub mod shutdown;
+pub mod submission;
+
+use async_trait::async_trait;
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, Log, U256};
+
+// turns a log matched by the chain's scanners into tracked oracles. the listener
+// calls it for every log found by the past and present scanners and by backfills,
+// leaving to implementations only what's specific to the oracle template
+#[async_trait]
+pub trait OracleDetector: Send + Sync {
+    // returns how many oracles were detected and acknowledged, so that the answering
+    // loop can be woken up right away when there are new ones
+    async fn on_creation_log(&self, log: Log) -> anyhow::Result<usize>;
+}
+
+// computes the answer of a tracked oracle from its specification. timeouts and
+// retries are left to the caller
+#[async_trait]
+pub trait AnswerComputer: Send + Sync {
+    type Oracle: Send + Sync;
+
+    // returns none when the oracle can't be answered with a value
+    async fn compute_answer(&self, oracle: &Self::Oracle) -> anyhow::Result<Option<U256>>;
+}
+
+// builds the transactions finalizing the template's oracles. filling, sending and
+// watching them is left to the submission module
+#[async_trait]
+pub trait AnswerSubmitter: Send + Sync {
+    // the only address the oracle accepts its answer from
+    async fn expected_answerer(&self, oracle: Address) -> anyhow::Result<Address>;
+
+    fn finalize_tx(&self, oracle: Address, answer: U256) -> TypedTransaction;
+}
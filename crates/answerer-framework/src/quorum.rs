@@ -0,0 +1,125 @@
+use std::{fmt::Debug, future::Future, sync::Arc};
+
+use anyhow::Context;
+use ethers::providers::{Http, Provider};
+
+// values deciding whether an oracle is answered, skipped or deleted are read through
+// the chain's main provider and then confirmed against every quorum endpoint, so that
+// a single malfunctioning rpc can't make the answerer act on bogus data. without
+// quorum endpoints every value is trusted as is
+pub struct QuorumReader {
+    providers: Vec<(String, Arc<Provider<Http>>)>,
+}
+
+impl QuorumReader {
+    pub fn new(urls: Vec<String>) -> anyhow::Result<Self> {
+        let providers = urls
+            .into_iter()
+            .map(|url| {
+                let provider = Provider::<Http>::try_from(url.as_str())
+                    .context(format!("could not parse quorum rpc endpoint {}", url))?;
+                Ok((url, Arc::new(provider)))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { providers })
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    // returns the value only if all the quorum endpoints agree with it. endpoints
+    // failing to respond count as disagreeing, as the value can't be confirmed
+    pub async fn confirm<T, F, Fut>(&self, what: &str, value: T, read: F) -> anyhow::Result<T>
+    where
+        T: PartialEq + Debug,
+        F: Fn(Arc<Provider<Http>>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        for (url, provider) in self.providers.iter() {
+            let quorum_value = read(provider.clone()).await.context(format!(
+                "could not read {} from quorum rpc endpoint {}",
+                what, url
+            ))?;
+            if quorum_value != value {
+                anyhow::bail!(
+                    "quorum rpc endpoint {} disagrees on {}: got {:?}, expected {:?}",
+                    url,
+                    what,
+                    quorum_value,
+                    value
+                );
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ethers::{
+        providers::{Http, Middleware, Provider},
+        types::U64,
+    };
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::QuorumReader;
+
+    async fn block_number_server(block_number: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                r#"{{"jsonrpc":"2.0","id":0,"result":"0x{:x}"}}"#,
+                block_number
+            )))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn confirm() {
+        let first_server = block_number_server(10).await;
+        let second_server = block_number_server(10).await;
+        let disagreeing_server = block_number_server(11).await;
+        let read =
+            |provider: Arc<Provider<Http>>| async move { Ok(provider.get_block_number().await?) };
+
+        let quorum_reader =
+            QuorumReader::new(vec![first_server.uri(), second_server.uri()]).unwrap();
+        assert_eq!(
+            quorum_reader
+                .confirm("block number", U64::from(10), read)
+                .await
+                .unwrap(),
+            U64::from(10)
+        );
+
+        let quorum_reader =
+            QuorumReader::new(vec![first_server.uri(), disagreeing_server.uri()]).unwrap();
+        assert!(quorum_reader
+            .confirm("block number", U64::from(10), read)
+            .await
+            .is_err());
+
+        // unreachable endpoints can't confirm anything
+        let quorum_reader =
+            QuorumReader::new(vec![first_server.uri(), "http://127.0.0.1:1".to_owned()]).unwrap();
+        assert!(quorum_reader
+            .confirm("block number", U64::from(10), read)
+            .await
+            .is_err());
+
+        assert_eq!(
+            QuorumReader::disabled()
+                .confirm("block number", U64::from(10), read)
+                .await
+                .unwrap(),
+            U64::from(10)
+        );
+    }
+}
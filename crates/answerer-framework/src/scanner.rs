@@ -0,0 +1,268 @@
+pub mod backfill;
+pub mod gaps;
+pub mod leader;
+pub mod past;
+pub mod present;
+pub mod reconciliation;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use ethers::types::{Address, Log};
+use tokio::sync::Notify;
+
+use crate::{db, OracleDetector};
+
+pub const PAST_LOGS_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+pub const CHECKPOINT_CATCH_UP_TIMEOUT: Duration = Duration::from_secs(600);
+
+// where the scanners keep their progress. methods block until the storage replies, and
+// are only ever called through the blocking helper
+pub trait ScanStore: Send + Sync {
+    fn checkpoint(&self, chain_id: u64) -> anyhow::Result<Option<u64>>;
+
+    fn update_checkpoint(&self, chain_id: u64, block_number: u64) -> anyhow::Result<()>;
+
+    // the given range (both ends included) is merged with the adjacent ones
+    fn record_scanned_range(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<()>;
+
+    // sorted by their starting block
+    fn scanned_ranges(&self, chain_id: u64) -> anyhow::Result<Vec<(u64, u64)>>;
+
+    fn record_scanned_factory(&self, chain_id: u64, address: Address) -> anyhow::Result<()>;
+
+    fn scanned_factories(&self, chain_id: u64) -> anyhow::Result<Vec<Address>>;
+
+    fn persist_log(&self, chain_id: u64, log: &Log) -> anyhow::Result<()>;
+
+    // the persisted logs between the given blocks (both included), in block order
+    fn persisted_logs(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<Log>>;
+
+    // takes or renews the chain's scanner lease, which only succeeds if it's free,
+    // expired or already held by the given holder
+    fn try_acquire_lease(
+        &self,
+        chain_id: u64,
+        holder: &str,
+        duration: Duration,
+    ) -> anyhow::Result<bool>;
+}
+
+// implemented by rpc clients that know when all of their endpoints are failing, in which
+// case polling them is skipped until they recover
+pub trait Degradable {
+    fn is_degraded(&self) -> bool;
+}
+
+pub enum Update {
+    NewBlocks { from_block: u64, to_block: u64 },
+    NewLog(Box<Log>),
+    PastBatchCompleted { from_block: u64, to_block: u64 },
+    PastScanningCompleted,
+}
+
+// cloned so that the past and present logs scanners share the same state
+#[derive(Clone)]
+pub struct Listener {
+    chain_id: u64,
+    checkpoint_confirmation_blocks: u64,
+    store: Arc<dyn ScanStore>,
+    scanning_past: Arc<AtomicBool>,
+    // latest block reported by the present logs scanner, 0 until the first report
+    present_head: Arc<AtomicU64>,
+    // latest block the checkpoint follows, be it from a past batch or from the present
+    // logs scanner once past scanning completed. 0 until the first report
+    last_processed_block: Arc<AtomicU64>,
+    // set when an operator manually changes the checkpoint, which is then left as is
+    // until the next restart
+    checkpoint_pinned: Arc<AtomicBool>,
+    // unset while another instance holds the chain's scanner lease, in which case logs
+    // are discarded and neither the checkpoint nor the scanned ranges are touched
+    leading: Arc<AtomicBool>,
+    // when set, every matched log is persisted to the store
+    persist_logs: bool,
+    detector: Arc<dyn OracleDetector>,
+    oracles_acknowledged: Arc<Notify>,
+}
+
+impl Listener {
+    pub fn new(
+        chain_id: u64,
+        checkpoint_confirmation_blocks: u64,
+        store: Arc<dyn ScanStore>,
+        persist_logs: bool,
+        detector: Arc<dyn OracleDetector>,
+        oracles_acknowledged: Arc<Notify>,
+    ) -> Self {
+        Self {
+            chain_id,
+            checkpoint_confirmation_blocks,
+            store,
+            persist_logs,
+            detector,
+            oracles_acknowledged,
+            scanning_past: Arc::new(AtomicBool::new(true)),
+            present_head: Arc::new(AtomicU64::new(0)),
+            last_processed_block: Arc::new(AtomicU64::new(0)),
+            checkpoint_pinned: Arc::new(AtomicBool::new(false)),
+            leading: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn store(&self) -> &Arc<dyn ScanStore> {
+        &self.store
+    }
+
+    pub fn present_head(&self) -> Option<u64> {
+        match self.present_head.load(Ordering::Relaxed) {
+            0 => None,
+            block_number => Some(block_number),
+        }
+    }
+
+    pub fn last_processed_block(&self) -> Option<u64> {
+        match self.last_processed_block.load(Ordering::Relaxed) {
+            0 => None,
+            block_number => Some(block_number),
+        }
+    }
+
+    pub fn pin_checkpoint(&self) {
+        self.checkpoint_pinned.store(true, Ordering::Relaxed);
+    }
+
+    pub fn unpin_checkpoint(&self) {
+        self.checkpoint_pinned.store(false, Ordering::Relaxed);
+    }
+
+    // forgets about the progress made after the given block, keeping the checkpoint
+    // pinned until the blocks after it are scanned again
+    pub fn rewind(&self, block_number: u64) {
+        self.pin_checkpoint();
+        self.last_processed_block
+            .store(block_number, Ordering::Relaxed);
+    }
+
+    pub fn is_checkpoint_pinned(&self) -> bool {
+        self.checkpoint_pinned.load(Ordering::Relaxed)
+    }
+
+    pub fn set_leading(&self, leading: bool) {
+        self.leading.store(leading, Ordering::Relaxed);
+    }
+
+    pub fn is_leading(&self) -> bool {
+        self.leading.load(Ordering::Relaxed)
+    }
+
+    async fn on_log(&self, log: Log) {
+        let block_number = match log.block_number {
+            Some(block_number) => block_number.as_u64(),
+            None => {
+                tracing::warn!("could not get block number from log {:?}", log);
+                return;
+            }
+        };
+
+        if self.persist_logs {
+            if let Err(error) = db::blocking(|| self.store.persist_log(self.chain_id, &log)) {
+                tracing::error!("could not persist indexed log: {:#}", error);
+            }
+        }
+
+        match self.detector.on_creation_log(log).await {
+            Ok(0) => {}
+            Ok(_) => self.oracles_acknowledged.notify_one(),
+            Err(error) => {
+                tracing::warn!(
+                    "could not extract oracles data from log at block {}: {:#}",
+                    block_number,
+                    error
+                );
+            }
+        }
+    }
+
+    // scanned ranges are what the gaps below the checkpoint are detected from on startup
+    fn record_scanned_range(&self, from_block: u64, to_block: u64) {
+        if !self.is_leading() {
+            return;
+        }
+        if let Err(error) = db::blocking(|| {
+            self.store
+                .record_scanned_range(self.chain_id, from_block, to_block)
+        }) {
+            tracing::error!("{:#}", error);
+        }
+    }
+
+    // logs from the blocks between the checkpoint and the scanned one are processed
+    // again after a restart, which is fine as acknowledging an oracle is idempotent
+    fn update_checkpoint_block_number(&self, block_number: u64) {
+        if self.is_checkpoint_pinned() {
+            return;
+        }
+        let block_number = block_number.saturating_sub(self.checkpoint_confirmation_blocks);
+        if let Err(error) =
+            db::blocking(|| self.store.update_checkpoint(self.chain_id, block_number))
+        {
+            tracing::error!("could not update snapshot block number - {:#}", error);
+        }
+    }
+}
+
+impl Listener {
+    pub async fn on_update(&self, update: Update) {
+        match update {
+            Update::NewLog(_) | Update::PastBatchCompleted { .. } if !self.is_leading() => {}
+            Update::NewLog(log) => self.on_log(*log).await,
+            Update::PastBatchCompleted {
+                from_block,
+                to_block,
+            } => {
+                self.record_scanned_range(from_block, to_block);
+                self.last_processed_block
+                    .fetch_max(to_block, Ordering::Relaxed);
+                self.update_checkpoint_block_number(to_block);
+            }
+            Update::PastScanningCompleted => {
+                tracing::info!("finished scanning past blocks");
+                self.scanning_past.store(false, Ordering::Relaxed);
+            }
+            Update::NewBlocks {
+                from_block,
+                to_block,
+            } => {
+                self.present_head.fetch_max(to_block, Ordering::Relaxed);
+                if !self.is_leading() {
+                    return;
+                }
+                self.record_scanned_range(from_block, to_block);
+                if !self.scanning_past.load(Ordering::Relaxed) {
+                    self.last_processed_block
+                        .fetch_max(to_block, Ordering::Relaxed);
+                    self.update_checkpoint_block_number(to_block);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use ethers::{
+    middleware::Middleware,
+    providers::{JsonRpcClient, Provider},
+    types::{Address, Filter},
+};
+use governor::{Quota, RateLimiter};
+use tokio::time::sleep;
+use tracing::{info_span, Instrument};
+
+use crate::db;
+
+use super::{
+    past::{is_range_too_wide, ChunkSize},
+    Listener, Update, PAST_LOGS_RETRY_INTERVAL,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackfillSource {
+    Rpc,
+    // logs previously persisted to the scan store, so that no rpc node has to be
+    // queried for them
+    IndexedLogs,
+}
+
+// rescans an explicit block range on operator request, acknowledging any oracle that
+// was missed (e.g. because the checkpoint skipped ahead) without ever touching the
+// checkpoint. only one backfill per chain can run at any given time
+pub struct Backfiller<P> {
+    chain_id: u64,
+    listener: Listener,
+    provider: Arc<Provider<P>>,
+    filter: Filter,
+    max_chunk_size: u64,
+    max_rps: u32,
+    running: AtomicBool,
+}
+
+impl<P: JsonRpcClient + 'static> Backfiller<P> {
+    pub fn new(
+        chain_id: u64,
+        listener: Listener,
+        provider: Arc<Provider<P>>,
+        filter: Filter,
+        max_chunk_size: u64,
+        max_rps: u32,
+    ) -> Self {
+        Self {
+            chain_id,
+            listener,
+            provider,
+            filter,
+            max_chunk_size,
+            max_rps,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    // returns false if another backfill is already running
+    pub fn start(self: Arc<Self>, from_block: u64, to_block: u64, source: BackfillSource) -> bool {
+        if !self.acquire() {
+            return false;
+        }
+        let span = info_span!("backfill", chain_id = self.chain_id);
+        tokio::spawn(
+            async move {
+                match source {
+                    BackfillSource::Rpc => self.backfill(from_block, to_block).await,
+                    BackfillSource::IndexedLogs => self.replay(from_block, to_block).await,
+                }
+                self.running.store(false, Ordering::Relaxed);
+            }
+            .instrument(span),
+        );
+        true
+    }
+
+    // like start, but waits for the backfill to be completed
+    pub async fn run(&self, from_block: u64, to_block: u64) -> bool {
+        if !self.acquire() {
+            return false;
+        }
+        self.backfill(from_block, to_block).await;
+        self.running.store(false, Ordering::Relaxed);
+        true
+    }
+
+    // like run, but only for the logs of a single factory. the range isn't recorded
+    // as scanned, as the other factories' logs aren't fetched
+    pub async fn run_for_factory(&self, address: Address, from_block: u64, to_block: u64) -> bool {
+        if !self.acquire() {
+            return false;
+        }
+        let filter = self.filter.clone().address(address);
+        self.scan(&filter, from_block, to_block, false).await;
+        self.running.store(false, Ordering::Relaxed);
+        true
+    }
+
+    // rewinds the listener to the given block and rescans everything after it in the
+    // background, releasing the checkpoint to the scanners once done. returns false if
+    // another backfill is already running
+    pub fn start_rewind(self: Arc<Self>, from_block: u64, to_block: u64) -> bool {
+        if !self.acquire() {
+            return false;
+        }
+        self.listener.rewind(from_block);
+        let span = info_span!("rewind", chain_id = self.chain_id);
+        tokio::spawn(
+            async move {
+                self.backfill(from_block, to_block).await;
+                self.listener.unpin_checkpoint();
+                tracing::info!(
+                    "rewind to block {} completed, checkpoint released",
+                    from_block
+                );
+                self.running.store(false, Ordering::Relaxed);
+            }
+            .instrument(span),
+        );
+        true
+    }
+
+    async fn replay(&self, from_block: u64, to_block: u64) {
+        tracing::info!(
+            "replaying indexed logs from block {} to {}",
+            from_block,
+            to_block
+        );
+        let logs = match db::blocking(|| {
+            self.listener
+                .store()
+                .persisted_logs(self.chain_id, from_block, to_block)
+        }) {
+            Ok(logs) => logs,
+            Err(error) => {
+                tracing::error!("could not get indexed logs: {:#}", error);
+                return;
+            }
+        };
+        let logs_count = logs.len();
+        for log in logs.into_iter() {
+            self.listener.on_update(Update::NewLog(Box::new(log))).await;
+        }
+        tracing::info!(
+            "finished replaying up to block {}, {} log(s) processed",
+            to_block,
+            logs_count
+        );
+    }
+
+    fn acquire(&self) -> bool {
+        self.running
+            .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    async fn backfill(&self, from_block: u64, to_block: u64) {
+        self.scan(&self.filter, from_block, to_block, true).await
+    }
+
+    async fn scan(&self, filter: &Filter, mut from_block: u64, to_block: u64, record: bool) {
+        tracing::info!("backfilling blocks {} to {}", from_block, to_block);
+        let rate_limiter = RateLimiter::direct(Quota::per_second(
+            NonZeroU32::new(self.max_rps.max(1)).unwrap(), // this should never panic
+        ));
+        let mut chunk_size = ChunkSize::new(self.max_chunk_size);
+        let mut logs_count = 0;
+        while from_block <= to_block {
+            let chunk_to_block = (from_block + chunk_size.get() - 1).min(to_block);
+            rate_limiter.until_ready().await;
+            match self
+                .provider
+                .get_logs(
+                    &filter
+                        .clone()
+                        .from_block(from_block)
+                        .to_block(chunk_to_block),
+                )
+                .await
+            {
+                Ok(logs) => {
+                    logs_count += logs.len();
+                    for log in logs.into_iter() {
+                        self.listener.on_update(Update::NewLog(Box::new(log))).await;
+                    }
+                    if record {
+                        self.listener
+                            .record_scanned_range(from_block, chunk_to_block);
+                    }
+                    from_block = chunk_to_block + 1;
+                    chunk_size.grow();
+                }
+                Err(error) if is_range_too_wide(&error) && chunk_size.shrink() => {
+                    tracing::warn!(
+                        "logs range from block {} to {} too wide, shrinking chunk size to {}: {:#}",
+                        from_block,
+                        chunk_to_block,
+                        chunk_size.get(),
+                        error
+                    );
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "error fetching logs from block {} to {}, retrying in {}s: {:#}",
+                        from_block,
+                        chunk_to_block,
+                        PAST_LOGS_RETRY_INTERVAL.as_secs(),
+                        error
+                    );
+                    sleep(PAST_LOGS_RETRY_INTERVAL).await;
+                }
+            }
+        }
+        tracing::info!(
+            "finished backfilling up to block {}, {} log(s) processed",
+            to_block,
+            logs_count
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use ethers::{providers::JsonRpcClient, types::Address};
+
+use crate::db;
+
+use super::{backfill::Backfiller, ScanStore};
+
+// returns the block ranges between the start and end blocks (both included) that
+// aren't covered by any of the given scanned ranges, which must be sorted by their
+// starting block
+fn find_gaps(scanned_ranges: &[(u64, u64)], start_block: u64, end_block: u64) -> Vec<(u64, u64)> {
+    let mut gaps = Vec::new();
+    let mut next_block = start_block;
+    for &(from_block, to_block) in scanned_ranges.iter() {
+        if next_block > end_block {
+            break;
+        }
+        if from_block > next_block {
+            gaps.push((next_block, (from_block - 1).min(end_block)));
+        }
+        next_block = next_block.max(to_block.saturating_add(1));
+    }
+    if next_block <= end_block {
+        gaps.push((next_block, end_block));
+    }
+    gaps
+}
+
+// the checkpoint only tells where scanning restarts from, and blocks before it might
+// have never been scanned (e.g. because the checkpoint was moved ahead, or because of
+// a bug like the one that kept it from being updated). the scanned ranges recorded by
+// the scanners are compared against the blocks between the factories deployment and
+// the checkpoint, and any hole is backfilled. the first time around nothing was
+// recorded yet, so those blocks are assumed to be covered
+pub async fn repair_block_gaps<P: JsonRpcClient + 'static>(
+    chain_id: u64,
+    backfiller: Arc<Backfiller<P>>,
+    store: Arc<dyn ScanStore>,
+    factories_deployment_block: u64,
+    checkpoint_block: u64,
+) -> anyhow::Result<()> {
+    // the past logs scanner restarts from the checkpoint block itself
+    if checkpoint_block <= factories_deployment_block {
+        return Ok(());
+    }
+    let end_block = checkpoint_block - 1;
+
+    let scanned_ranges =
+        db::blocking(|| store.scanned_ranges(chain_id)).context("could not get scanned ranges")?;
+    if scanned_ranges.is_empty() {
+        tracing::info!(
+            "no scanned ranges recorded yet, assuming blocks {} to {} were scanned",
+            factories_deployment_block,
+            end_block
+        );
+        db::blocking(|| {
+            store.record_scanned_range(chain_id, factories_deployment_block, end_block)
+        })?;
+        return Ok(());
+    }
+
+    let gaps = find_gaps(&scanned_ranges, factories_deployment_block, end_block);
+    if gaps.is_empty() {
+        tracing::info!(
+            "no gaps found between blocks {} and {}",
+            factories_deployment_block,
+            end_block
+        );
+        return Ok(());
+    }
+
+    tracing::warn!(
+        "{} gap(s) found between blocks {} and {}, repairing",
+        gaps.len(),
+        factories_deployment_block,
+        end_block
+    );
+    for (from_block, to_block) in gaps.into_iter() {
+        // backfilled ranges are recorded as scanned, closing the gap
+        if !backfiller.run(from_block, to_block).await {
+            tracing::error!(
+                "a backfill is already running, could not repair gap from block {} to {}",
+                from_block,
+                to_block
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// the checkpoint is shared by all the factories of a chain, so a factory added later
+// on would only be scanned from there onwards. factories, given as their address and
+// deployment block, that weren't recorded as scanned yet are backfilled from their
+// own deployment block up to the checkpoint. without a checkpoint the past logs
+// scanner covers every factory from the start, and the first time around nothing
+// was recorded yet, so every factory is assumed to be covered
+pub async fn backfill_new_factories<P: JsonRpcClient + 'static>(
+    chain_id: u64,
+    backfiller: Arc<Backfiller<P>>,
+    store: Arc<dyn ScanStore>,
+    factories: Vec<(Address, u64)>,
+    checkpoint_block: Option<u64>,
+) -> anyhow::Result<()> {
+    let scanned_factories = db::blocking(|| store.scanned_factories(chain_id))
+        .context("could not get scanned factories")?;
+
+    let checkpoint_block = match checkpoint_block {
+        Some(checkpoint_block) if !scanned_factories.is_empty() => checkpoint_block,
+        _ => {
+            for (address, _) in factories.iter() {
+                db::blocking(|| store.record_scanned_factory(chain_id, *address))?;
+            }
+            return Ok(());
+        }
+    };
+
+    for &(address, deployment_block) in factories
+        .iter()
+        .filter(|(address, _)| !scanned_factories.contains(address))
+    {
+        // the past logs scanner restarts from the checkpoint block itself
+        if deployment_block < checkpoint_block {
+            tracing::info!(
+                "factory 0x{:x} is new, scanning its logs from block {} to {}",
+                address,
+                deployment_block,
+                checkpoint_block - 1
+            );
+            if !backfiller
+                .run_for_factory(address, deployment_block, checkpoint_block - 1)
+                .await
+            {
+                tracing::error!(
+                    "a backfill is already running, could not scan factory 0x{:x} past logs",
+                    address
+                );
+                continue;
+            }
+        }
+        db::blocking(|| store.record_scanned_factory(chain_id, address))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_gaps;
+
+    #[test]
+    fn gaps() {
+        assert_eq!(find_gaps(&[(0, 100)], 0, 100), vec![]);
+        assert_eq!(find_gaps(&[(0, 200)], 10, 100), vec![]);
+        assert_eq!(find_gaps(&[], 10, 100), vec![(10, 100)]);
+        // holes at the start, in the middle and at the end
+        assert_eq!(
+            find_gaps(&[(20, 40), (51, 60), (61, 70)], 10, 100),
+            vec![(10, 19), (41, 50), (71, 100)]
+        );
+        // overlapping ranges and ranges outside the bounds
+        assert_eq!(
+            find_gaps(&[(0, 30), (10, 50), (45, 60), (90, 200)], 10, 100),
+            vec![(61, 89)]
+        );
+        assert_eq!(find_gaps(&[(0, 5), (150, 200)], 10, 100), vec![(10, 100)]);
+    }
+}
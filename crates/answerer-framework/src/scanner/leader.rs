@@ -0,0 +1,122 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethers::providers::JsonRpcClient;
+use tokio::time::interval;
+
+use crate::db;
+
+use super::{
+    backfill::{BackfillSource, Backfiller},
+    Listener, ScanStore,
+};
+
+pub struct LeaderElection {
+    pub instance_id: String,
+    pub lease_duration: Duration,
+}
+
+impl LeaderElection {
+    pub fn try_acquire(&self, chain_id: u64, store: &dyn ScanStore) -> anyhow::Result<bool> {
+        db::blocking(|| store.try_acquire_lease(chain_id, &self.instance_id, self.lease_duration))
+    }
+}
+
+// renews the chain's scanner lease while leading, and tries to take it over while
+// following so that a crashed leader doesn't stall indexing for longer than a lease.
+// followers keep their scanners running but discard what they find, so on takeover the
+// blocks between the stored checkpoint and the present head are backfilled
+pub async fn keep_scanner_lease<P: JsonRpcClient + 'static>(
+    chain_id: u64,
+    start_block: u64,
+    leader_election: Arc<LeaderElection>,
+    listener: Listener,
+    backfiller: Arc<Backfiller<P>>,
+) -> anyhow::Result<()> {
+    let mut interval = interval(leader_election.lease_duration / 3);
+    let mut renewed_at = listener.is_leading().then(Instant::now);
+    let mut catch_up_pending = false;
+
+    loop {
+        interval.tick().await;
+
+        let acquired = match leader_election.try_acquire(chain_id, listener.store().as_ref()) {
+            Ok(acquired) => acquired,
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                // leading past the lease's expiration could mean two leaders at once
+                if renewed_at.is_some_and(|renewed_at| {
+                    renewed_at.elapsed() >= leader_election.lease_duration
+                }) {
+                    false
+                } else {
+                    continue;
+                }
+            }
+        };
+
+        if acquired {
+            renewed_at = Some(Instant::now());
+            if !listener.is_leading() {
+                tracing::info!("took over scanning as {}", leader_election.instance_id);
+                listener.set_leading(true);
+                catch_up_pending = true;
+            }
+        } else {
+            renewed_at = None;
+            catch_up_pending = false;
+            if listener.is_leading() {
+                tracing::warn!("scanner lease lost, stepping down to follower");
+                listener.set_leading(false);
+            }
+        }
+
+        if catch_up_pending {
+            catch_up_pending = !catch_up(chain_id, start_block, &listener, &backfiller);
+        }
+    }
+}
+
+// returns whether catching up was started or wasn't needed
+fn catch_up<P: JsonRpcClient + 'static>(
+    chain_id: u64,
+    start_block: u64,
+    listener: &Listener,
+    backfiller: &Arc<Backfiller<P>>,
+) -> bool {
+    let head = match listener.present_head() {
+        Some(head) => head,
+        None => {
+            tracing::debug!("present head not known yet, delaying catch up");
+            return false;
+        }
+    };
+    let checkpoint = match db::blocking(|| listener.store().checkpoint(chain_id)) {
+        Ok(Some(checkpoint)) => checkpoint,
+        // the previous leader didn't get to store a checkpoint
+        Ok(None) => start_block,
+        Err(error) => {
+            tracing::error!("could not get checkpoint to catch up from: {:#}", error);
+            return false;
+        }
+    };
+    if checkpoint >= head {
+        return true;
+    }
+
+    if !backfiller
+        .clone()
+        .start(checkpoint, head, BackfillSource::Rpc)
+    {
+        tracing::info!("a backfill is already running, delaying catch up");
+        return false;
+    }
+    tracing::info!(
+        "catching up from the previous leader's checkpoint at block {} to block {}",
+        checkpoint,
+        head
+    );
+    true
+}
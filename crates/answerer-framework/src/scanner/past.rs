@@ -0,0 +1,319 @@
+use std::{
+    num::NonZeroU32,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethers::{
+    middleware::Middleware,
+    providers::{JsonRpcClient, Provider, ProviderError},
+    types::Filter,
+};
+use governor::{Quota, RateLimiter};
+use tokio::time::sleep;
+
+use super::{Listener, Update, CHECKPOINT_CATCH_UP_TIMEOUT, PAST_LOGS_RETRY_INTERVAL};
+
+// fragments of the errors rpc providers return when a logs query covers too many
+// blocks or would return too many logs
+const RANGE_TOO_WIDE_ERRORS: [&str; 9] = [
+    "block range",
+    "range is too wide",
+    "range too large",
+    "too many blocks",
+    "query returned more than",
+    "too many results",
+    "response size",
+    "limit exceeded",
+    "logs matched by query exceeds",
+];
+
+pub(super) fn is_range_too_wide(error: &ProviderError) -> bool {
+    let error = error.to_string().to_lowercase();
+    RANGE_TOO_WIDE_ERRORS
+        .iter()
+        .any(|fragment| error.contains(fragment))
+}
+
+// logs are fetched in chunks of blocks, halving the chunk size every time the provider
+// complains about the range and doubling it back up to the configured size on success
+pub(super) struct ChunkSize {
+    current: u64,
+    max: u64,
+}
+
+impl ChunkSize {
+    pub fn new(max: u64) -> Self {
+        let max = max.max(1);
+        Self { current: max, max }
+    }
+
+    pub fn get(&self) -> u64 {
+        self.current
+    }
+
+    // returns false if the chunk size can't be reduced any further
+    pub fn shrink(&mut self) -> bool {
+        if self.current == 1 {
+            return false;
+        }
+        self.current /= 2;
+        true
+    }
+
+    pub fn grow(&mut self) {
+        self.current = self.current.saturating_mul(2).min(self.max);
+    }
+}
+
+// a checkpoint ahead of the head usually means the rpc node was recycled and is still
+// syncing, so it's given some time to catch up before clamping the checkpoint to the
+// head. returns none while waiting
+fn past_scanning_start_block(checkpoint_block: u64, head: u64, waited: Duration) -> Option<u64> {
+    if checkpoint_block <= head {
+        Some(checkpoint_block)
+    } else if waited >= CHECKPOINT_CATCH_UP_TIMEOUT {
+        Some(head)
+    } else {
+        None
+    }
+}
+
+// scans the blocks between the checkpoint and the block from which the present logs
+// scanner started, feeding the listener the same updates the present scanner would.
+// the checkpoint and the head it was clamped to are handed to on_checkpoint_clamped
+pub async fn scan_past_logs<P, F>(
+    listener: Listener,
+    provider: Arc<Provider<P>>,
+    filter: Filter,
+    checkpoint_block: u64,
+    max_chunk_size: u64,
+    max_rps: u32,
+    on_checkpoint_clamped: F,
+) -> anyhow::Result<()>
+where
+    P: JsonRpcClient,
+    F: FnOnce(u64, u64),
+{
+    let rate_limiter = RateLimiter::direct(Quota::per_second(
+        NonZeroU32::new(max_rps.max(1)).unwrap(), // this should never panic
+    ));
+    let mut chunk_size = ChunkSize::new(max_chunk_size);
+
+    let waiting_since = Instant::now();
+    let (mut from_block, mut current_block) = loop {
+        let head = get_block_number(provider.clone()).await;
+        match past_scanning_start_block(checkpoint_block, head, waiting_since.elapsed()) {
+            Some(from_block) => {
+                if from_block != checkpoint_block {
+                    tracing::warn!(
+                        "checkpoint {} still ahead of head {} after {}s, clamping it to the head",
+                        checkpoint_block,
+                        head,
+                        CHECKPOINT_CATCH_UP_TIMEOUT.as_secs()
+                    );
+                    on_checkpoint_clamped(checkpoint_block, head);
+                }
+                break (from_block, head);
+            }
+            None => {
+                tracing::warn!(
+                    "checkpoint {} ahead of head {}, waiting {}s for the node to catch up",
+                    checkpoint_block,
+                    head,
+                    PAST_LOGS_RETRY_INTERVAL.as_secs()
+                );
+                sleep(PAST_LOGS_RETRY_INTERVAL).await;
+            }
+        }
+    };
+    tracing::info!(
+        "analyzing {} past blocks {} at a time",
+        current_block - from_block,
+        chunk_size.get()
+    );
+
+    loop {
+        let to_block = (from_block + chunk_size.get() - 1).min(current_block);
+        rate_limiter.until_ready().await;
+        match provider
+            .get_logs(&filter.clone().from_block(from_block).to_block(to_block))
+            .await
+        {
+            Ok(logs) => {
+                for log in logs.into_iter() {
+                    listener.on_update(Update::NewLog(Box::new(log))).await;
+                }
+                listener
+                    .on_update(Update::PastBatchCompleted {
+                        from_block,
+                        to_block,
+                    })
+                    .await;
+                chunk_size.grow();
+            }
+            Err(error) if is_range_too_wide(&error) => {
+                if chunk_size.shrink() {
+                    tracing::warn!(
+                        "logs range from block {} to {} too wide, shrinking chunk size to {}: {:#}",
+                        from_block,
+                        to_block,
+                        chunk_size.get(),
+                        error
+                    );
+                } else {
+                    tracing::error!(
+                        "logs range for single block {} too wide, retrying in {}s: {:#}",
+                        from_block,
+                        PAST_LOGS_RETRY_INTERVAL.as_secs(),
+                        error
+                    );
+                    sleep(PAST_LOGS_RETRY_INTERVAL).await;
+                }
+                continue;
+            }
+            Err(error) => {
+                tracing::error!(
+                    "error fetching logs from block {} to {}, retrying in {}s: {:#}",
+                    from_block,
+                    to_block,
+                    PAST_LOGS_RETRY_INTERVAL.as_secs(),
+                    error
+                );
+                sleep(PAST_LOGS_RETRY_INTERVAL).await;
+                continue;
+            }
+        }
+
+        if to_block < current_block {
+            from_block = to_block + 1;
+            continue;
+        }
+
+        // the present scanner starts from the head it saw on startup, so past scanning
+        // only stops once it caught up with it, leaving no gaps between the two
+        match listener.present_head() {
+            Some(present_head) if present_head <= current_block => break,
+            _ => {
+                sleep(PAST_LOGS_RETRY_INTERVAL).await;
+                let block_number = get_block_number(provider.clone()).await;
+                if block_number > current_block {
+                    from_block = current_block + 1;
+                    current_block = block_number;
+                } else if block_number < current_block {
+                    // the node was most likely recycled, wait for it to catch up
+                    tracing::warn!(
+                        "head moved backwards from {} to {}, waiting for the node to catch up",
+                        current_block,
+                        block_number
+                    );
+                }
+            }
+        }
+    }
+
+    listener.on_update(Update::PastScanningCompleted).await;
+
+    Ok(())
+}
+
+pub(super) async fn get_block_number<P: JsonRpcClient>(provider: Arc<Provider<P>>) -> u64 {
+    loop {
+        match provider.get_block_number().await {
+            Ok(block_number) => return block_number.as_u64(),
+            Err(error) => {
+                tracing::error!(
+                    "could not get current block number, retrying in {}s: {:#}",
+                    PAST_LOGS_RETRY_INTERVAL.as_secs(),
+                    error
+                );
+                sleep(PAST_LOGS_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::providers::{JsonRpcError, ProviderError};
+
+    use std::time::Duration;
+
+    use super::{
+        is_range_too_wide, past_scanning_start_block, ChunkSize, CHECKPOINT_CATCH_UP_TIMEOUT,
+    };
+
+    fn json_rpc_error(message: &str) -> ProviderError {
+        ProviderError::JsonRpcClientError(Box::new(
+            ethers::providers::HttpClientError::JsonRpcError(JsonRpcError {
+                code: -32_000,
+                message: message.to_owned(),
+                data: None,
+            }),
+        ))
+    }
+
+    #[test]
+    fn is_range_too_wide_errors() {
+        assert!(is_range_too_wide(&json_rpc_error(
+            "block range is too wide"
+        )));
+        assert!(is_range_too_wide(&json_rpc_error(
+            "query returned more than 10000 results"
+        )));
+        assert!(is_range_too_wide(&json_rpc_error(
+            "eth_getLogs is limited to a 10,000 Block Range"
+        )));
+        assert!(!is_range_too_wide(&json_rpc_error("header not found")));
+        assert!(!is_range_too_wide(&ProviderError::CustomError(
+            "connection reset".to_owned()
+        )));
+    }
+
+    #[test]
+    fn past_scanning_start_block_checkpoint_ahead() {
+        assert_eq!(
+            past_scanning_start_block(100, 200, Duration::ZERO),
+            Some(100)
+        );
+        assert_eq!(
+            past_scanning_start_block(200, 200, Duration::ZERO),
+            Some(200)
+        );
+
+        // the node is given time to catch up before clamping
+        assert_eq!(past_scanning_start_block(300, 200, Duration::ZERO), None);
+        assert_eq!(
+            past_scanning_start_block(300, 200, CHECKPOINT_CATCH_UP_TIMEOUT),
+            Some(200)
+        );
+    }
+
+    #[test]
+    fn chunk_size() {
+        let mut chunk_size = ChunkSize::new(5_000);
+        assert_eq!(chunk_size.get(), 5_000);
+
+        chunk_size.grow();
+        assert_eq!(chunk_size.get(), 5_000);
+
+        assert!(chunk_size.shrink());
+        assert!(chunk_size.shrink());
+        assert_eq!(chunk_size.get(), 1_250);
+
+        chunk_size.grow();
+        assert_eq!(chunk_size.get(), 2_500);
+        chunk_size.grow();
+        chunk_size.grow();
+        assert_eq!(chunk_size.get(), 5_000);
+
+        let mut chunk_size = ChunkSize::new(2);
+        assert!(chunk_size.shrink());
+        assert_eq!(chunk_size.get(), 1);
+        assert!(!chunk_size.shrink());
+        assert_eq!(chunk_size.get(), 1);
+
+        // zero sized chunks would never advance
+        assert_eq!(ChunkSize::new(0).get(), 1);
+    }
+}
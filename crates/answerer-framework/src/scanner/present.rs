@@ -0,0 +1,102 @@
+use std::{sync::Arc, time::Duration};
+
+use ethers::{
+    middleware::Middleware,
+    providers::{JsonRpcClient, Provider},
+    types::Filter,
+};
+use tokio::time::sleep;
+
+use super::{
+    past::{get_block_number, is_range_too_wide, ChunkSize},
+    Degradable, Listener, Update,
+};
+
+// polls the chain for new logs starting from the current head, reporting every scanned
+// block to the listener so that the past logs scanner knows when to stop. on_caught_up
+// is called whenever a poll caught up with the head
+pub async fn scan_present_logs<P, F>(
+    listener: Listener,
+    provider: Arc<Provider<P>>,
+    filter: Filter,
+    polling_interval: Duration,
+    max_chunk_size: u64,
+    on_caught_up: F,
+) -> anyhow::Result<()>
+where
+    P: JsonRpcClient + Degradable,
+    F: Fn(),
+{
+    let mut chunk_size = ChunkSize::new(max_chunk_size);
+    let mut from_block = get_block_number(provider.clone()).await;
+    tracing::info!("watching present logs from block {}", from_block);
+
+    loop {
+        sleep(polling_interval).await;
+
+        // the circuit breaker already reported the chain as degraded
+        if provider.as_ref().as_ref().is_degraded() {
+            continue;
+        }
+
+        let head = match provider.get_block_number().await {
+            Ok(head) => head.as_u64(),
+            Err(error) => {
+                tracing::error!("could not get current block number: {:#}", error);
+                continue;
+            }
+        };
+        if head + 1 < from_block {
+            // the node was most likely recycled, wait for it to catch up
+            tracing::warn!(
+                "head moved backwards from {} to {}, waiting for the node to catch up",
+                from_block - 1,
+                head
+            );
+            continue;
+        }
+
+        while from_block <= head {
+            let to_block = (from_block + chunk_size.get() - 1).min(head);
+            match provider
+                .get_logs(&filter.clone().from_block(from_block).to_block(to_block))
+                .await
+            {
+                Ok(logs) => {
+                    for log in logs.into_iter() {
+                        listener.on_update(Update::NewLog(Box::new(log))).await;
+                    }
+                    listener
+                        .on_update(Update::NewBlocks {
+                            from_block,
+                            to_block,
+                        })
+                        .await;
+                    from_block = to_block + 1;
+                    chunk_size.grow();
+                }
+                Err(error) if is_range_too_wide(&error) && chunk_size.shrink() => {
+                    tracing::warn!(
+                        "logs range from block {} to {} too wide, shrinking chunk size to {}: {:#}",
+                        from_block,
+                        to_block,
+                        chunk_size.get(),
+                        error
+                    );
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "error fetching logs from block {} to {}: {:#}",
+                        from_block,
+                        to_block,
+                        error
+                    );
+                    break;
+                }
+            }
+        }
+        if from_block > head {
+            on_caught_up();
+        }
+    }
+}
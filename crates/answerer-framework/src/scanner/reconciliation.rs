@@ -0,0 +1,39 @@
+use std::{sync::Arc, time::Duration};
+
+use ethers::providers::JsonRpcClient;
+use tokio::time::sleep;
+
+use super::{backfill::Backfiller, Listener};
+
+// periodically rescans the most recent blocks as a safety net against logs the
+// scanners might have missed (e.g. because a node returned an incomplete result).
+// sweeps run as backfills, so already acknowledged oracles are skipped and the
+// checkpoint is never touched
+pub async fn reconcile_recent_logs<P: JsonRpcClient + 'static>(
+    listener: Listener,
+    backfiller: Arc<Backfiller<P>>,
+    interval: Duration,
+    margin_blocks: u64,
+) -> anyhow::Result<()> {
+    loop {
+        sleep(interval).await;
+
+        if !listener.is_leading() {
+            continue;
+        }
+
+        let head = match listener.present_head() {
+            Some(head) => head,
+            None => {
+                tracing::debug!("present head not known yet, skipping reconciliation");
+                continue;
+            }
+        };
+        if !backfiller
+            .run(head.saturating_sub(margin_blocks), head)
+            .await
+        {
+            tracing::info!("a backfill is already running, skipping reconciliation");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+
+// cloned into every task that needs to stop taking new work once a shutdown was
+// requested, while still completing whatever it's currently doing
+#[derive(Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self(receiver))
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    // resolves right away if the shutdown was already requested
+    pub async fn triggered(&mut self) {
+        // an error means that the sender was dropped, which only happens on exit
+        let _ = self.0.wait_for(|triggered| *triggered).await;
+    }
+}
+
+// resolves on the first sigterm (sent by kubernetes when recycling pods) or sigint
+pub async fn wait_for_termination() -> anyhow::Result<()> {
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    tokio::select! {
+        _ = sigterm.recv() => tracing::info!("sigterm received"),
+        _ = sigint.recv() => tracing::info!("sigint received"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::ShutdownSignal;
+
+    #[tokio::test]
+    async fn triggered() {
+        let (sender, mut shutdown) = ShutdownSignal::channel();
+        assert!(!shutdown.is_triggered());
+
+        sender.send(true).unwrap();
+        assert!(shutdown.is_triggered());
+        // already triggered signals resolve right away, even when awaited again
+        shutdown.triggered().await;
+        shutdown.triggered().await;
+    }
+}
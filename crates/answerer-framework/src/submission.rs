@@ -0,0 +1,208 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use ethers::{
+    middleware::Middleware,
+    providers::PendingTransaction,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionReceipt, H256, U256},
+};
+use tokio::time::sleep;
+
+pub const RECEIPT_POLLING_INTERVAL: Duration = Duration::from_secs(5);
+
+// how long an answer tx is waited for before it's resubmitted with fees bumped by the
+// given percentage, up to the given number of times
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResubmissionPolicy {
+    pub timeout: Duration,
+    pub fee_bump_percentage: u64,
+    pub max_resubmissions: u32,
+}
+
+// turns the finalization tx built by the submitter into one ready to be sent through
+// the given signer. chains not supporting eip-1559 get legacy txs, and a sender only
+// has to be given when it differs from the signer's (e.g. when impersonating it)
+pub async fn prepare_answer_tx<M: Middleware + 'static>(
+    signer: &M,
+    mut tx: TypedTransaction,
+    legacy: bool,
+    from: Option<Address>,
+) -> anyhow::Result<TypedTransaction> {
+    if legacy {
+        if let TypedTransaction::Eip1559(inner) = tx {
+            tx = TypedTransaction::Legacy(inner.into());
+        }
+    }
+    if let Some(from) = from {
+        tx.set_from(from);
+    }
+    signer
+        .fill_transaction(&mut tx, None)
+        .await
+        .context("could not fill answer tx")?;
+    Ok(tx)
+}
+
+pub async fn send_answer_tx<M: Middleware + 'static>(
+    signer: &M,
+    tx: TypedTransaction,
+) -> anyhow::Result<H256> {
+    Ok(signer.send_transaction(tx, None).await?.tx_hash())
+}
+
+// waits for the answer tx to be mined however long it takes. returns none if it was
+// dropped from the mempool
+pub async fn wait_for_mined<M: Middleware>(
+    signer: &M,
+    tx_hash: H256,
+) -> anyhow::Result<Option<TransactionReceipt>> {
+    Ok(PendingTransaction::new(tx_hash, signer.provider()).await?)
+}
+
+// waits for the answer tx to be mined, resubmitting it with bumped fees every time the
+// timeout is hit if the policy allows it. replacements reuse the same nonce, so only one
+// of the submitted txs can ever be mined, and all of them are watched since it's not
+// necessarily the last one. returns none if none of them got mined in time
+pub async fn wait_for_receipt<M, F>(
+    signer: Arc<M>,
+    mut tx: TypedTransaction,
+    tx_hash: H256,
+    policy: &ResubmissionPolicy,
+    mut on_resubmission: F,
+) -> anyhow::Result<Option<TransactionReceipt>>
+where
+    M: Middleware,
+    F: FnMut(H256) -> anyhow::Result<()>,
+{
+    let ResubmissionPolicy {
+        timeout,
+        fee_bump_percentage,
+        max_resubmissions,
+    } = *policy;
+
+    let mut tx_hashes = vec![tx_hash];
+    let mut resubmissions = 0;
+    loop {
+        if let Some(receipt) = poll_receipts(signer.clone(), &tx_hashes, timeout).await {
+            return Ok(Some(receipt));
+        }
+        if resubmissions >= max_resubmissions {
+            return Ok(None);
+        }
+
+        resubmissions += 1;
+        bump_fees(&mut tx, fee_bump_percentage);
+        let replacement_tx_hash = match signer.send_transaction(tx.clone(), None).await {
+            Ok(pending_tx) => pending_tx.tx_hash(),
+            Err(error) => {
+                // most likely one of the previous txs got mined in the meantime
+                tracing::warn!(
+                    "could not resubmit answer tx 0x{:x}: {:#}",
+                    tx_hashes[tx_hashes.len() - 1],
+                    error
+                );
+                continue;
+            }
+        };
+        tracing::warn!(
+            "answer tx 0x{:x} not mined within {}s, resubmitted it as 0x{:x} with fees bumped by {}%",
+            tx_hashes[tx_hashes.len() - 1],
+            timeout.as_secs(),
+            replacement_tx_hash,
+            fee_bump_percentage
+        );
+        tx_hashes.push(replacement_tx_hash);
+        on_resubmission(replacement_tx_hash)?;
+    }
+}
+
+async fn poll_receipts<M: Middleware>(
+    signer: Arc<M>,
+    tx_hashes: &[H256],
+    timeout: Duration,
+) -> Option<TransactionReceipt> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        for tx_hash in tx_hashes.iter() {
+            match signer.get_transaction_receipt(*tx_hash).await {
+                Ok(Some(receipt)) if receipt.block_number.is_some() => return Some(receipt),
+                Ok(_) => {}
+                Err(error) => {
+                    tracing::warn!(
+                        "could not get receipt for answer tx 0x{:x}: {:#}",
+                        tx_hash,
+                        error
+                    );
+                }
+            }
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        sleep(RECEIPT_POLLING_INTERVAL).await;
+    }
+}
+
+fn bump_fees(tx: &mut TypedTransaction, fee_bump_percentage: u64) {
+    let bump = |fee: &mut Option<U256>| {
+        if let Some(fee) = fee.as_mut() {
+            *fee = fee.saturating_add(
+                (fee.saturating_mul(U256::from(fee_bump_percentage)) / U256::from(100))
+                    .max(U256::one()),
+            );
+        }
+    };
+    match tx {
+        TypedTransaction::Legacy(tx) => bump(&mut tx.gas_price),
+        TypedTransaction::Eip2930(tx) => bump(&mut tx.tx.gas_price),
+        TypedTransaction::Eip1559(tx) => {
+            bump(&mut tx.max_fee_per_gas);
+            bump(&mut tx.max_priority_fee_per_gas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::{
+        transaction::eip2718::TypedTransaction, Eip1559TransactionRequest, TransactionRequest, U256,
+    };
+
+    use super::bump_fees;
+
+    #[test]
+    fn bump_fees_legacy() {
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(100).into();
+        bump_fees(&mut tx, 20);
+        assert_eq!(tx.gas_price(), Some(U256::from(120)));
+
+        // tiny fees are still bumped
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(1).into();
+        bump_fees(&mut tx, 20);
+        assert_eq!(tx.gas_price(), Some(U256::from(2)));
+    }
+
+    #[test]
+    fn bump_fees_eip1559() {
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(1_000)
+            .max_priority_fee_per_gas(10)
+            .into();
+        bump_fees(&mut tx, 10);
+        match tx {
+            TypedTransaction::Eip1559(tx) => {
+                assert_eq!(tx.max_fee_per_gas, Some(U256::from(1_100)));
+                assert_eq!(tx.max_priority_fee_per_gas, Some(U256::from(11)));
+            }
+            _ => panic!("unexpected tx type"),
+        }
+
+        // missing fees are left to the node
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new().into();
+        bump_fees(&mut tx, 10);
+        assert_eq!(tx.gas_price(), None);
+    }
+}
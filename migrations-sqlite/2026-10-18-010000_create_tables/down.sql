@@ -0,0 +1,17 @@
+DROP TABLE scanned_factories;
+DROP TABLE cached_specifications;
+DROP TABLE scanner_leases;
+DROP TABLE answered_oracles;
+DROP TABLE scanned_ranges;
+DROP TABLE indexed_logs;
+DROP TABLE archived_oracles;
+DROP TABLE rate_limit_buckets;
+DROP TABLE answer_reviews;
+DROP TABLE answer_overrides;
+DROP TABLE audit_log;
+DROP TABLE dry_run_answers;
+DROP TABLE defillama_snapshots;
+DROP TABLE feature_gates;
+DROP TABLE gas_spendings;
+DROP TABLE checkpoints;
+DROP TABLE active_oracles;
@@ -0,0 +1,204 @@
+-- the schema reached by the postgres migrations, in sqlite terms: json documents
+-- are stored as text, timestamps as the microseconds elapsed since the unix epoch
+-- and 256 bits integers as raw bytes, as even bigint is too small to store
+-- 18-decimal formatted integers
+
+CREATE TABLE active_oracles (
+    address BLOB NOT NULL,
+    chain_id INTEGER NOT NULL,
+    measurement_timestamp INTEGER NOT NULL,
+    specification TEXT NOT NULL,
+    answer_tx_hash BLOB,
+    answer BLOB,
+    expiration INTEGER DEFAULT NULL,
+    answer_attempts INTEGER NOT NULL DEFAULT 0,
+    next_answer_attempt INTEGER DEFAULT NULL,
+    claim_expiration INTEGER DEFAULT NULL,
+    answer_tx_submitted_at INTEGER DEFAULT NULL,
+    source_missing_since INTEGER DEFAULT NULL,
+    defillama_snapshot_id INTEGER,
+
+    PRIMARY KEY(address, chain_id),
+    CONSTRAINT address_length CHECK (LENGTH(address) = 20),
+    CONSTRAINT answer_tx_hash_length CHECK (LENGTH(answer_tx_hash) = 32),
+    CONSTRAINT answer_length CHECK (LENGTH(answer_tx_hash) = 32)
+);
+
+CREATE TABLE checkpoints (
+    chain_id INTEGER PRIMARY KEY,
+    block_number INTEGER NOT NULL
+);
+
+CREATE TABLE gas_spendings (
+    chain_id INTEGER NOT NULL,
+    tx_hash BLOB NOT NULL,
+    oracle_address BLOB NOT NULL,
+    fee BLOB NOT NULL,
+    timestamp INTEGER NOT NULL,
+    fee_usd REAL DEFAULT NULL,
+    gas_used BLOB DEFAULT NULL,
+    effective_gas_price BLOB DEFAULT NULL,
+    kpi_token_address BLOB DEFAULT NULL,
+
+    PRIMARY KEY(chain_id, tx_hash),
+    CONSTRAINT tx_hash_length CHECK (LENGTH(tx_hash) = 32),
+    CONSTRAINT oracle_address_length CHECK (LENGTH(oracle_address) = 20),
+    CONSTRAINT fee_length CHECK (LENGTH(fee) = 32)
+);
+
+CREATE INDEX gas_spendings_chain_id_timestamp_idx ON gas_spendings (chain_id, timestamp);
+
+CREATE TABLE feature_gates (
+    chain_id INTEGER NOT NULL,
+    feature TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+
+    PRIMARY KEY(chain_id, feature)
+);
+
+CREATE TABLE defillama_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INTEGER NOT NULL,
+    oracle_address BLOB NOT NULL,
+    specification TEXT NOT NULL,
+    responses TEXT NOT NULL,
+    answer BLOB,
+    timestamp INTEGER NOT NULL,
+
+    CONSTRAINT oracle_address_length CHECK (LENGTH(oracle_address) = 20),
+    CONSTRAINT answer_length CHECK (LENGTH(answer) = 32)
+);
+
+CREATE INDEX defillama_snapshots_chain_id_oracle_address_idx ON defillama_snapshots (chain_id, oracle_address);
+
+CREATE TABLE dry_run_answers (
+    chain_id INTEGER NOT NULL,
+    oracle_address BLOB NOT NULL,
+    answer BLOB NOT NULL,
+    timestamp INTEGER NOT NULL,
+
+    PRIMARY KEY(chain_id, oracle_address)
+);
+
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INTEGER NOT NULL,
+    oracle_address BLOB,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    details TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX audit_log_chain_id_oracle_address_index ON audit_log (chain_id, oracle_address);
+
+CREATE TABLE answer_overrides (
+    chain_id INTEGER NOT NULL,
+    oracle_address BLOB NOT NULL,
+    answer BLOB NOT NULL,
+    reason TEXT NOT NULL,
+    proposed_by TEXT NOT NULL,
+    proposed_at INTEGER NOT NULL,
+    approved_by TEXT,
+    approved_at INTEGER,
+
+    PRIMARY KEY(chain_id, oracle_address)
+);
+
+CREATE TABLE answer_reviews (
+    chain_id INTEGER NOT NULL,
+    oracle_address BLOB NOT NULL,
+    answer BLOB NOT NULL,
+    reference_answer BLOB NOT NULL,
+    created_at INTEGER NOT NULL,
+
+    PRIMARY KEY(chain_id, oracle_address)
+);
+
+CREATE TABLE rate_limit_buckets (
+    name TEXT PRIMARY KEY,
+    tokens REAL NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+CREATE TABLE archived_oracles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    address BLOB NOT NULL,
+    chain_id INTEGER NOT NULL,
+    measurement_timestamp INTEGER NOT NULL,
+    specification TEXT NOT NULL,
+    expiration INTEGER,
+    answer BLOB,
+    answer_attempts INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    archived_at INTEGER NOT NULL
+);
+
+CREATE INDEX archived_oracles_chain_id_address_index ON archived_oracles (chain_id, address);
+
+CREATE TABLE indexed_logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    chain_id INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_hash BLOB,
+    tx_hash BLOB NOT NULL,
+    log_index INTEGER NOT NULL,
+    address BLOB NOT NULL,
+    topics TEXT NOT NULL,
+    data BLOB NOT NULL,
+    indexed_at INTEGER NOT NULL,
+
+    UNIQUE(chain_id, tx_hash, log_index)
+);
+
+CREATE INDEX indexed_logs_chain_id_block_number_index ON indexed_logs (chain_id, block_number);
+
+CREATE TABLE scanned_ranges (
+    chain_id INTEGER NOT NULL,
+    from_block INTEGER NOT NULL,
+    to_block INTEGER NOT NULL,
+
+    PRIMARY KEY(chain_id, from_block)
+);
+
+CREATE TABLE answered_oracles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    address BLOB NOT NULL,
+    chain_id INTEGER NOT NULL,
+    measurement_timestamp INTEGER NOT NULL,
+    specification TEXT NOT NULL,
+    expiration INTEGER,
+    answer BLOB,
+    answer_attempts INTEGER NOT NULL,
+    answer_tx_hash BLOB NOT NULL,
+    answer_tx_submitted_at INTEGER,
+    block_number INTEGER,
+    gas_used BLOB,
+    effective_gas_price BLOB,
+    fee BLOB,
+    fee_usd REAL,
+    answered_at INTEGER NOT NULL,
+    defillama_snapshot_id INTEGER,
+    proof_bundle_cid TEXT
+);
+
+CREATE INDEX answered_oracles_chain_id_address_index ON answered_oracles (chain_id, address);
+CREATE INDEX answered_oracles_chain_id_answered_at_index ON answered_oracles (chain_id, answered_at);
+
+CREATE TABLE scanner_leases (
+    chain_id INTEGER PRIMARY KEY,
+    holder TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+
+CREATE TABLE cached_specifications (
+    cid TEXT PRIMARY KEY,
+    specification TEXT NOT NULL,
+    fetched_at INTEGER NOT NULL
+);
+
+CREATE TABLE scanned_factories (
+    chain_id INTEGER NOT NULL,
+    address BLOB NOT NULL,
+    PRIMARY KEY (chain_id, address)
+);
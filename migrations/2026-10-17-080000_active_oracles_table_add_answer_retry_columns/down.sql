@@ -0,0 +1,3 @@
+ALTER TABLE active_oracles
+DROP COLUMN answer_attempts,
+DROP COLUMN next_answer_attempt;
\ No newline at end of file
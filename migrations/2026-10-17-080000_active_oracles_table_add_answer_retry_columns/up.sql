@@ -0,0 +1,3 @@
+ALTER TABLE active_oracles
+ADD COLUMN answer_attempts INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN next_answer_attempt TIMESTAMP(0) DEFAULT NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE gas_spendings;
\ No newline at end of file
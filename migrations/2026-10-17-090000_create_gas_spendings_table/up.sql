@@ -0,0 +1,16 @@
+CREATE TABLE gas_spendings (
+    chain_id INTEGER NOT NULL,
+    tx_hash BYTEA NOT NULL,
+    oracle_address BYTEA NOT NULL,
+    -- using raw bytes as even bigint is too small to store 
+    -- 18-decimal formatted integers
+    fee BYTEA NOT NULL,
+    timestamp TIMESTAMP(0) NOT NULL,
+
+    PRIMARY KEY(chain_id, tx_hash),
+    CONSTRAINT tx_hash_length CHECK (LENGTH(tx_hash) = 32),
+    CONSTRAINT oracle_address_length CHECK (LENGTH(oracle_address) = 20),
+    CONSTRAINT fee_length CHECK (LENGTH(fee) = 32)
+);
+
+CREATE INDEX gas_spendings_chain_id_timestamp_idx ON gas_spendings (chain_id, timestamp);
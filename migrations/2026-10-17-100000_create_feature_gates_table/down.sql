@@ -0,0 +1 @@
+DROP TABLE feature_gates;
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE feature_gates (
+    chain_id INTEGER NOT NULL,
+    feature TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+
+    PRIMARY KEY(chain_id, feature)
+);
@@ -0,0 +1 @@
+DROP TABLE defillama_snapshots;
\ No newline at end of file
@@ -0,0 +1,14 @@
+CREATE TABLE defillama_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id INTEGER NOT NULL,
+    oracle_address BYTEA NOT NULL,
+    specification JSONB NOT NULL,
+    responses JSONB NOT NULL,
+    answer BYTEA,
+    timestamp TIMESTAMP(0) NOT NULL,
+
+    CONSTRAINT oracle_address_length CHECK (LENGTH(oracle_address) = 20),
+    CONSTRAINT answer_length CHECK (LENGTH(answer) = 32)
+);
+
+CREATE INDEX defillama_snapshots_chain_id_oracle_address_idx ON defillama_snapshots (chain_id, oracle_address);
@@ -0,0 +1 @@
+ALTER TABLE gas_spendings DROP COLUMN fee_usd;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE gas_spendings
+ADD COLUMN fee_usd DOUBLE PRECISION DEFAULT NULL;
\ No newline at end of file
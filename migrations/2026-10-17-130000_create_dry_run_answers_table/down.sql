@@ -0,0 +1 @@
+DROP TABLE dry_run_answers;
\ No newline at end of file
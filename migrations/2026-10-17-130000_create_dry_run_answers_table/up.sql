@@ -0,0 +1,8 @@
+CREATE TABLE dry_run_answers (
+    chain_id INTEGER NOT NULL,
+    oracle_address BYTEA NOT NULL,
+    answer BYTEA NOT NULL,
+    timestamp TIMESTAMP NOT NULL,
+
+    PRIMARY KEY(chain_id, oracle_address)
+);
@@ -0,0 +1,11 @@
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id INTEGER NOT NULL,
+    oracle_address BYTEA NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    details JSONB NOT NULL,
+    timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX audit_log_chain_id_oracle_address_index ON audit_log (chain_id, oracle_address);
@@ -0,0 +1 @@
+DROP TABLE answer_overrides;
\ No newline at end of file
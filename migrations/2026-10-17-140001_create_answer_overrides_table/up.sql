@@ -0,0 +1,12 @@
+CREATE TABLE answer_overrides (
+    chain_id INTEGER NOT NULL,
+    oracle_address BYTEA NOT NULL,
+    answer BYTEA NOT NULL,
+    reason TEXT NOT NULL,
+    proposed_by TEXT NOT NULL,
+    proposed_at TIMESTAMP NOT NULL,
+    approved_by TEXT,
+    approved_at TIMESTAMP,
+
+    PRIMARY KEY(chain_id, oracle_address)
+);
@@ -0,0 +1 @@
+ALTER TABLE active_oracles DROP COLUMN claim_expiration;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE active_oracles
+ADD COLUMN claim_expiration TIMESTAMP DEFAULT NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE active_oracles DROP COLUMN answer_tx_submitted_at;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE active_oracles
+ADD COLUMN answer_tx_submitted_at TIMESTAMP DEFAULT NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE answer_reviews;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TABLE answer_reviews (
+    chain_id INTEGER NOT NULL,
+    oracle_address BYTEA NOT NULL,
+    answer BYTEA NOT NULL,
+    reference_answer BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+
+    PRIMARY KEY(chain_id, oracle_address)
+);
@@ -0,0 +1 @@
+ALTER TABLE active_oracles DROP COLUMN source_missing_since;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE active_oracles
+ADD COLUMN source_missing_since TIMESTAMP DEFAULT NULL;
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE gas_spendings
+DROP COLUMN gas_used,
+DROP COLUMN effective_gas_price,
+DROP COLUMN kpi_token_address;
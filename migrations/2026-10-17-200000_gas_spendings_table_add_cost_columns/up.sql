@@ -0,0 +1,4 @@
+ALTER TABLE gas_spendings
+ADD COLUMN gas_used BYTEA DEFAULT NULL,
+ADD COLUMN effective_gas_price BYTEA DEFAULT NULL,
+ADD COLUMN kpi_token_address BYTEA DEFAULT NULL;
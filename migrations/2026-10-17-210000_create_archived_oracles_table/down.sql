@@ -0,0 +1 @@
+DROP TABLE archived_oracles;
\ No newline at end of file
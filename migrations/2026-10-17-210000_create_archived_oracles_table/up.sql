@@ -0,0 +1,14 @@
+CREATE TABLE archived_oracles (
+    id BIGSERIAL PRIMARY KEY,
+    address BYTEA NOT NULL,
+    chain_id INTEGER NOT NULL,
+    measurement_timestamp TIMESTAMP NOT NULL,
+    specification JSONB NOT NULL,
+    expiration TIMESTAMP,
+    answer BYTEA,
+    answer_attempts INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    archived_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX archived_oracles_chain_id_address_index ON archived_oracles (chain_id, address);
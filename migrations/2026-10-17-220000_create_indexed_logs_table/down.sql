@@ -0,0 +1 @@
+DROP TABLE indexed_logs;
\ No newline at end of file
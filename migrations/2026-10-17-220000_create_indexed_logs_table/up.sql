@@ -0,0 +1,16 @@
+CREATE TABLE indexed_logs (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id INTEGER NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_hash BYTEA,
+    tx_hash BYTEA NOT NULL,
+    log_index BIGINT NOT NULL,
+    address BYTEA NOT NULL,
+    topics JSONB NOT NULL,
+    data BYTEA NOT NULL,
+    indexed_at TIMESTAMP NOT NULL,
+
+    UNIQUE(chain_id, tx_hash, log_index)
+);
+
+CREATE INDEX indexed_logs_chain_id_block_number_index ON indexed_logs (chain_id, block_number);
@@ -0,0 +1 @@
+DROP TABLE scanned_ranges;
\ No newline at end of file
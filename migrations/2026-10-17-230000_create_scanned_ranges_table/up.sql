@@ -0,0 +1,7 @@
+CREATE TABLE scanned_ranges (
+    chain_id INTEGER NOT NULL,
+    from_block BIGINT NOT NULL,
+    to_block BIGINT NOT NULL,
+
+    PRIMARY KEY(chain_id, from_block)
+);
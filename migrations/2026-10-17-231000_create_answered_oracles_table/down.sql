@@ -0,0 +1 @@
+DROP TABLE answered_oracles;
\ No newline at end of file
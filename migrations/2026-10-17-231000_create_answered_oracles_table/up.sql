@@ -0,0 +1,21 @@
+CREATE TABLE answered_oracles (
+    id BIGSERIAL PRIMARY KEY,
+    address BYTEA NOT NULL,
+    chain_id INTEGER NOT NULL,
+    measurement_timestamp TIMESTAMP NOT NULL,
+    specification JSONB NOT NULL,
+    expiration TIMESTAMP,
+    answer BYTEA,
+    answer_attempts INTEGER NOT NULL,
+    answer_tx_hash BYTEA NOT NULL,
+    answer_tx_submitted_at TIMESTAMP,
+    block_number BIGINT,
+    gas_used BYTEA,
+    effective_gas_price BYTEA,
+    fee BYTEA,
+    fee_usd DOUBLE PRECISION,
+    answered_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX answered_oracles_chain_id_address_index ON answered_oracles (chain_id, address);
+CREATE INDEX answered_oracles_chain_id_answered_at_index ON answered_oracles (chain_id, answered_at);
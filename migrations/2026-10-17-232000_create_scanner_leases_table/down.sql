@@ -0,0 +1 @@
+DROP TABLE scanner_leases;
\ No newline at end of file
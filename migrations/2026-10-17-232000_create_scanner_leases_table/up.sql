@@ -0,0 +1,5 @@
+CREATE TABLE scanner_leases (
+    chain_id INTEGER PRIMARY KEY,
+    holder TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL
+);
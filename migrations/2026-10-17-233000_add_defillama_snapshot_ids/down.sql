@@ -0,0 +1,2 @@
+ALTER TABLE active_oracles DROP COLUMN defillama_snapshot_id;
+ALTER TABLE answered_oracles DROP COLUMN defillama_snapshot_id;
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE active_oracles ADD COLUMN defillama_snapshot_id BIGINT;
+ALTER TABLE answered_oracles ADD COLUMN defillama_snapshot_id BIGINT;
@@ -0,0 +1,2 @@
+DELETE FROM audit_log WHERE oracle_address IS NULL;
+ALTER TABLE audit_log ALTER COLUMN oracle_address SET NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE audit_log ALTER COLUMN oracle_address DROP NOT NULL;
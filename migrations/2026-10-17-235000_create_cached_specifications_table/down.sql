@@ -0,0 +1 @@
+DROP TABLE cached_specifications;
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE cached_specifications (
+    cid TEXT PRIMARY KEY,
+    specification JSONB NOT NULL,
+    fetched_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE answered_oracles DROP COLUMN proof_bundle_cid;
\ No newline at end of file
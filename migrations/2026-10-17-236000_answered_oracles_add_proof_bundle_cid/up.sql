@@ -0,0 +1 @@
+ALTER TABLE answered_oracles ADD COLUMN proof_bundle_cid TEXT;
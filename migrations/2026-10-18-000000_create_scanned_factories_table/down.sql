@@ -0,0 +1 @@
+DROP TABLE scanned_factories;
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE scanned_factories (
+    chain_id INTEGER NOT NULL,
+    address BYTEA NOT NULL,
+    PRIMARY KEY (chain_id, address)
+);
@@ -0,0 +1,413 @@
+pub mod discord;
+pub mod pagerduty;
+pub mod slack;
+pub mod telegram;
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    future::Future,
+    sync::{LazyLock, Mutex},
+};
+
+use backoff::{future::retry, ExponentialBackoffBuilder};
+use ethers::{
+    types::{Address, H256, U256},
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::JoinSet,
+};
+use tracing::{info_span, Instrument};
+
+use crate::commons::{
+    AlertsConfig, ALERTS_CHANNEL_CAPACITY, ALERTS_DEFAULT_MIN_SEVERITY,
+    ALERT_DELIVERY_MAX_ELAPSED_TIME,
+};
+
+use self::{
+    discord::DiscordSink, pagerduty::PagerDutySink, slack::SlackSink, telegram::TelegramSink,
+};
+
+// conditions needing a human go through here on top of being logged, and every
+// configured alert sink subscribes to it
+static ALERTS: LazyLock<broadcast::Sender<Alert>> =
+    LazyLock::new(|| broadcast::channel(ALERTS_CHANNEL_CAPACITY).0);
+
+// keys of the conditions alerted about and not cleared yet, so that clearing alerts
+// only go out for conditions someone was told about
+static OPEN_CONDITIONS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Display for AlertSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertSeverity::Info => write!(f, "info"),
+            AlertSeverity::Warning => write!(f, "warning"),
+            AlertSeverity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertKind {
+    // the answer tx can't be tracked anymore, and the oracle might never be answered
+    StuckAnswerTx {
+        oracle_address: Address,
+        tx_hash: H256,
+    },
+    ExpiredUnanswered {
+        oracle_address: Address,
+    },
+    // the checkpoint is ahead of the chain head for longer than a node takes to sync
+    CheckpointCorrupted {
+        checkpoint_block: u64,
+        head: u64,
+    },
+    LowBalance {
+        answerer: Address,
+        balance: U256,
+        threshold: U256,
+    },
+    BalanceRestored {
+        answerer: Address,
+        balance: U256,
+    },
+    // the oracle's answer txs keep being mined but reverting
+    RepeatedReverts {
+        oracle_address: Address,
+        reverts: u32,
+    },
+    // answers are held back until enough of the budget's window goes by
+    GasBudgetExceeded {
+        period: &'static str,
+        budget: U256,
+        spent: U256,
+    },
+    // clears whatever condition was raised for the oracle
+    OracleFinalized {
+        oracle_address: Address,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub chain_id: u64,
+    pub kind: AlertKind,
+}
+
+impl Alert {
+    pub fn severity(&self) -> AlertSeverity {
+        match self.kind {
+            AlertKind::StuckAnswerTx { .. }
+            | AlertKind::ExpiredUnanswered { .. }
+            | AlertKind::CheckpointCorrupted { .. }
+            | AlertKind::RepeatedReverts { .. }
+            | AlertKind::GasBudgetExceeded { .. } => AlertSeverity::Critical,
+            AlertKind::LowBalance { .. } => AlertSeverity::Warning,
+            AlertKind::BalanceRestored { .. } | AlertKind::OracleFinalized { .. } => {
+                AlertSeverity::Info
+            }
+        }
+    }
+
+    // alerts about the same ongoing condition share a key, so that sinks tracking
+    // incidents can tell which one a clearing alert resolves
+    pub fn condition_key(&self) -> Option<String> {
+        match &self.kind {
+            AlertKind::StuckAnswerTx { oracle_address, .. }
+            | AlertKind::ExpiredUnanswered { oracle_address }
+            | AlertKind::RepeatedReverts { oracle_address, .. }
+            | AlertKind::OracleFinalized { oracle_address } => {
+                Some(format!("{}/oracle/0x{:x}", self.chain_id, oracle_address))
+            }
+            AlertKind::LowBalance { answerer, .. }
+            | AlertKind::BalanceRestored { answerer, .. } => {
+                Some(format!("{}/answerer/0x{:x}", self.chain_id, answerer))
+            }
+            AlertKind::GasBudgetExceeded { period, .. } => {
+                Some(format!("{}/gas-budget/{}", self.chain_id, period))
+            }
+            AlertKind::CheckpointCorrupted { .. } => None,
+        }
+    }
+
+    pub fn clears_condition(&self) -> bool {
+        matches!(
+            self.kind,
+            AlertKind::BalanceRestored { .. } | AlertKind::OracleFinalized { .. }
+        )
+    }
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "chain {}: ", self.chain_id)?;
+        match &self.kind {
+            AlertKind::StuckAnswerTx {
+                oracle_address,
+                tx_hash,
+            } => write!(
+                f,
+                "answer tx 0x{:x} for oracle 0x{:x} is stuck",
+                tx_hash, oracle_address
+            ),
+            AlertKind::ExpiredUnanswered { oracle_address } => {
+                write!(f, "oracle 0x{:x} expired without an answer", oracle_address)
+            }
+            AlertKind::CheckpointCorrupted {
+                checkpoint_block,
+                head,
+            } => write!(
+                f,
+                "checkpoint {} is ahead of head {}, it was clamped to the head",
+                checkpoint_block, head
+            ),
+            AlertKind::LowBalance {
+                answerer,
+                balance,
+                threshold,
+            } => write!(
+                f,
+                "answerer 0x{:x} balance {} is below the {} threshold",
+                answerer,
+                utils::format_ether(*balance),
+                utils::format_ether(*threshold)
+            ),
+            AlertKind::BalanceRestored { answerer, balance } => write!(
+                f,
+                "answerer 0x{:x} balance is back to {}",
+                answerer,
+                utils::format_ether(*balance)
+            ),
+            AlertKind::RepeatedReverts {
+                oracle_address,
+                reverts,
+            } => write!(
+                f,
+                "answer txs for oracle 0x{:x} reverted {} times in a row",
+                oracle_address, reverts
+            ),
+            AlertKind::GasBudgetExceeded {
+                period,
+                budget,
+                spent,
+            } => write!(
+                f,
+                "{} gas budget of {} exceeded with {} spent, answers are on hold",
+                period,
+                utils::format_ether(*budget),
+                utils::format_ether(*spent)
+            ),
+            AlertKind::OracleFinalized { oracle_address } => {
+                write!(f, "oracle 0x{:x} was finalized", oracle_address)
+            }
+        }
+    }
+}
+
+pub fn raise(chain_id: u64, kind: AlertKind) {
+    let alert = Alert { chain_id, kind };
+    if let Some(condition_key) = alert.condition_key() {
+        let mut open_conditions = OPEN_CONDITIONS.lock().unwrap();
+        if alert.clears_condition() {
+            if !open_conditions.remove(&condition_key) {
+                return;
+            }
+        } else {
+            open_conditions.insert(condition_key);
+        }
+    }
+    // sending only fails when there are no subscribers
+    let _ = ALERTS.send(alert);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Alert> {
+    ALERTS.subscribe()
+}
+
+// a destination alerts are posted to, such as a chat channel
+pub trait AlertSink {
+    fn name(&self) -> &'static str;
+
+    fn send(&self, alert: &Alert) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+// alerts are delivered to a single sink one after the other, retrying each of them
+// for a while before giving up. alerts clearing a condition are always delivered,
+// as the condition itself might have been above the minimum severity
+pub async fn deliver_alerts<S: AlertSink>(
+    sink: S,
+    min_severity: AlertSeverity,
+) -> anyhow::Result<()> {
+    let mut alerts = subscribe();
+    tracing::info!(
+        "delivering {} alerts and above to {}",
+        min_severity,
+        sink.name()
+    );
+
+    loop {
+        let alert = match alerts.recv().await {
+            Ok(alert) => alert,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "{} alerts are lagging behind, {} alert(s) were skipped",
+                    sink.name(),
+                    skipped
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        if alert.severity() < min_severity && !alert.clears_condition() {
+            continue;
+        }
+        if let Err(error) = retry(
+            ExponentialBackoffBuilder::new()
+                .with_max_elapsed_time(Some(ALERT_DELIVERY_MAX_ELAPSED_TIME))
+                .build(),
+            || async { sink.send(&alert).await.map_err(backoff::Error::transient) },
+        )
+        .await
+        {
+            tracing::error!("could not deliver alert to {}: {:#}", sink.name(), error);
+        }
+    }
+}
+
+// every configured sink gets its own delivery task, so that a slow or failing one
+// doesn't hold back the others
+pub fn start_sinks(
+    config: AlertsConfig,
+    join_set: &mut JoinSet<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    if let Some(slack_config) = config.slack {
+        let min_severity = slack_config
+            .min_severity
+            .unwrap_or(ALERTS_DEFAULT_MIN_SEVERITY);
+        join_set.spawn(
+            deliver_alerts(SlackSink::new(slack_config)?, min_severity)
+                .instrument(info_span!("slack-alerts")),
+        );
+    }
+    if let Some(telegram_config) = config.telegram {
+        let min_severity = telegram_config
+            .min_severity
+            .unwrap_or(ALERTS_DEFAULT_MIN_SEVERITY);
+        join_set.spawn(
+            deliver_alerts(TelegramSink::new(telegram_config)?, min_severity)
+                .instrument(info_span!("telegram-alerts")),
+        );
+    }
+    if let Some(discord_config) = config.discord {
+        let sink = DiscordSink::new(discord_config)?;
+        let min_severity = sink.min_severity();
+        join_set.spawn(deliver_alerts(sink, min_severity).instrument(info_span!("discord-alerts")));
+    }
+    if let Some(pagerduty_config) = config.pagerduty {
+        let sink = PagerDutySink::new(pagerduty_config)?;
+        let min_severity = sink.min_severity();
+        join_set
+            .spawn(deliver_alerts(sink, min_severity).instrument(info_span!("pagerduty-alerts")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::{Address, H256, U256};
+
+    use super::{raise, subscribe, Alert, AlertKind, AlertSeverity};
+
+    #[tokio::test]
+    async fn raise_and_subscribe() {
+        let mut alerts = subscribe();
+        raise(
+            100,
+            AlertKind::ExpiredUnanswered {
+                oracle_address: Address::zero(),
+            },
+        );
+        // other tests raise alerts concurrently
+        let alert = loop {
+            let alert = alerts.recv().await.unwrap();
+            if alert.chain_id == 100 {
+                break alert;
+            }
+        };
+        assert_eq!(alert.severity(), AlertSeverity::Critical);
+        assert_eq!(
+            alert.to_string(),
+            "chain 100: oracle 0x0000000000000000000000000000000000000000 expired without an answer"
+        );
+    }
+
+    #[test]
+    fn order_severities() {
+        let alert = Alert {
+            chain_id: 1,
+            kind: AlertKind::LowBalance {
+                answerer: Address::zero(),
+                balance: U256::exp10(17),
+                threshold: U256::exp10(18),
+            },
+        };
+        assert_eq!(alert.severity(), AlertSeverity::Warning);
+        assert!(alert.severity() > AlertSeverity::Info);
+        assert!(alert.severity() < AlertSeverity::Critical);
+        assert!(alert
+            .to_string()
+            .contains("balance 0.100000000000000000 is below the 1.000000000000000000 threshold"));
+    }
+
+    #[test]
+    fn clear_open_conditions_only() {
+        let mut alerts = subscribe();
+        // alerts and open conditions are global, so the test keeps to its own chain
+        let chain_id = H256::random().to_low_u64_be();
+        let balance_restored = AlertKind::BalanceRestored {
+            answerer: Address::zero(),
+            balance: U256::one(),
+        };
+        raise(chain_id, balance_restored.clone());
+        raise(
+            chain_id,
+            AlertKind::LowBalance {
+                answerer: Address::zero(),
+                balance: U256::zero(),
+                threshold: U256::one(),
+            },
+        );
+        raise(chain_id, balance_restored.clone());
+        raise(chain_id, balance_restored);
+
+        let mut severities = Vec::new();
+        while let Ok(alert) = alerts.try_recv() {
+            if alert.chain_id == chain_id {
+                assert_eq!(
+                    alert.condition_key().unwrap(),
+                    format!(
+                        "{}/answerer/0x0000000000000000000000000000000000000000",
+                        chain_id
+                    )
+                );
+                severities.push(alert.severity());
+            }
+        }
+        assert_eq!(
+            severities,
+            vec![AlertSeverity::Warning, AlertSeverity::Info]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use reqwest::Client;
+use serde_json::json;
+
+use crate::commons::{DiscordConfig, HTTP_TIMEOUT};
+
+use super::{Alert, AlertSeverity, AlertSink};
+
+// posts alerts through discord webhooks, each severity going to the webhook of the
+// closest configured severity below it. webhook urls embed their token, so they're
+// kept out of errors
+pub struct DiscordSink {
+    client: Client,
+    webhook_urls: BTreeMap<AlertSeverity, String>,
+}
+
+impl DiscordSink {
+    pub fn new(config: DiscordConfig) -> anyhow::Result<Self> {
+        if config.webhook_urls.is_empty() {
+            anyhow::bail!("no discord webhook url configured");
+        }
+        Ok(Self {
+            client: Client::builder()
+                .timeout(HTTP_TIMEOUT)
+                .build()
+                .context("could not build discord http client")?,
+            webhook_urls: config.webhook_urls,
+        })
+    }
+
+    // alerts below the lowest routed severity aren't sent at all
+    pub fn min_severity(&self) -> AlertSeverity {
+        self.webhook_urls
+            .keys()
+            .next()
+            .copied()
+            .unwrap_or(AlertSeverity::Critical)
+    }
+
+    fn webhook_url(&self, severity: AlertSeverity) -> Option<&str> {
+        self.webhook_urls
+            .range(..=severity)
+            .next_back()
+            .map(|(_, webhook_url)| webhook_url.as_str())
+    }
+}
+
+fn message(alert: &Alert) -> String {
+    let icon = match alert.severity() {
+        AlertSeverity::Info => ":information_source:",
+        AlertSeverity::Warning => ":warning:",
+        AlertSeverity::Critical => ":rotating_light:",
+    };
+    format!(
+        "{} **{}** {}",
+        icon,
+        alert.severity().to_string().to_uppercase(),
+        alert
+    )
+}
+
+impl AlertSink for DiscordSink {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let webhook_url = match self.webhook_url(alert.severity()) {
+            Some(webhook_url) => webhook_url,
+            None => return Ok(()),
+        };
+        self.client
+            .post(webhook_url)
+            .json(&json!({ "content": message(alert) }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| error.without_url())
+            .context(format!(
+                "could not post {} alert to discord",
+                alert.severity()
+            ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use ethers::types::{Address, U256};
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        alerts::{Alert, AlertKind, AlertSeverity, AlertSink},
+        commons::DiscordConfig,
+    };
+
+    use super::DiscordSink;
+
+    #[tokio::test]
+    async fn route_alerts_by_severity() {
+        let mock_server = MockServer::start().await;
+        let sink = DiscordSink::new(DiscordConfig {
+            webhook_urls: BTreeMap::from([
+                (
+                    AlertSeverity::Info,
+                    format!("{}/api/webhooks/1/info", mock_server.uri()),
+                ),
+                (
+                    AlertSeverity::Critical,
+                    format!("{}/api/webhooks/2/critical", mock_server.uri()),
+                ),
+            ]),
+        })
+        .unwrap();
+        assert_eq!(sink.min_severity(), AlertSeverity::Info);
+
+        Mock::given(method("POST"))
+            .and(path("/api/webhooks/2/critical"))
+            .and(body_json(json!({
+                "content": ":rotating_light: **CRITICAL** chain 100: oracle 0x0000000000000000000000000000000000000000 expired without an answer"
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // warnings have no webhook of their own and go to the info one
+        Mock::given(method("POST"))
+            .and(path("/api/webhooks/1/info"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        sink.send(&Alert {
+            chain_id: 100,
+            kind: AlertKind::ExpiredUnanswered {
+                oracle_address: Address::zero(),
+            },
+        })
+        .await
+        .unwrap();
+        for kind in [
+            AlertKind::LowBalance {
+                answerer: Address::zero(),
+                balance: U256::zero(),
+                threshold: U256::one(),
+            },
+            AlertKind::BalanceRestored {
+                answerer: Address::zero(),
+                balance: U256::one(),
+            },
+        ] {
+            sink.send(&Alert {
+                chain_id: 100,
+                kind,
+            })
+            .await
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn require_webhook_urls() {
+        assert!(DiscordSink::new(DiscordConfig {
+            webhook_urls: BTreeMap::new(),
+        })
+        .is_err());
+
+        // severities are routed by their lowercase name
+        let config: DiscordConfig = serde_json::from_value(json!({
+            "webhook_urls": { "warning": "https://discord.com/api/webhooks/1/foo" }
+        }))
+        .unwrap();
+        let sink = DiscordSink::new(config).unwrap();
+        assert_eq!(sink.min_severity(), AlertSeverity::Warning);
+        assert_eq!(sink.webhook_url(AlertSeverity::Info), None);
+        assert!(sink.webhook_url(AlertSeverity::Critical).is_some());
+    }
+}
@@ -0,0 +1,258 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use anyhow::Context;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use crate::commons::{
+    PagerDutyConfig, HTTP_TIMEOUT, PAGERDUTY_DEFAULT_MIN_SEVERITY, PAGERDUTY_EVENTS_URL,
+};
+
+use super::{Alert, AlertSeverity, AlertSink};
+
+// triggers pagerduty incidents through the events api v2, resolving them once the
+// condition they were triggered for clears. incidents are tracked in memory, so the
+// ones still open on restart have to be resolved by hand
+pub struct PagerDutySink {
+    client: Client,
+    events_url: String,
+    routing_key: String,
+    min_severity: AlertSeverity,
+    triggered: Mutex<HashSet<String>>,
+}
+
+impl PagerDutySink {
+    pub fn new(config: PagerDutyConfig) -> anyhow::Result<Self> {
+        Self::with_events_url(config, PAGERDUTY_EVENTS_URL)
+    }
+
+    fn with_events_url(config: PagerDutyConfig, events_url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::builder()
+                .timeout(HTTP_TIMEOUT)
+                .build()
+                .context("could not build pagerduty http client")?,
+            events_url: events_url.to_owned(),
+            routing_key: config.routing_key,
+            min_severity: config
+                .min_severity
+                .unwrap_or(PAGERDUTY_DEFAULT_MIN_SEVERITY),
+            triggered: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn min_severity(&self) -> AlertSeverity {
+        self.min_severity
+    }
+
+    async fn enqueue(&self, event: Value) -> anyhow::Result<()> {
+        self.client
+            .post(self.events_url.as_str())
+            .json(&event)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("could not enqueue pagerduty event")?;
+        Ok(())
+    }
+}
+
+impl AlertSink for PagerDutySink {
+    fn name(&self) -> &'static str {
+        "pagerduty"
+    }
+
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        let condition_key = alert.condition_key();
+        if alert.clears_condition() {
+            let condition_key = match condition_key {
+                Some(condition_key) => condition_key,
+                None => return Ok(()),
+            };
+            if !self.triggered.lock().unwrap().contains(&condition_key) {
+                return Ok(());
+            }
+            self.enqueue(json!({
+                "routing_key": self.routing_key,
+                "event_action": "resolve",
+                "dedup_key": condition_key,
+            }))
+            .await?;
+            self.triggered.lock().unwrap().remove(&condition_key);
+            return Ok(());
+        }
+
+        if alert.severity() < self.min_severity {
+            return Ok(());
+        }
+        // without a dedup key pagerduty opens a new incident for every event
+        let mut event = json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "payload": {
+                "summary": alert.to_string(),
+                "source": "defillama-answerer",
+                "severity": alert.severity().to_string(),
+                "custom_details": { "chain_id": alert.chain_id },
+            },
+        });
+        if let Some(condition_key) = condition_key.as_ref() {
+            event["dedup_key"] = json!(condition_key);
+        }
+        self.enqueue(event).await?;
+        if let Some(condition_key) = condition_key {
+            self.triggered.lock().unwrap().insert(condition_key);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::{Address, H256, U256};
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_json, body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        alerts::{Alert, AlertKind, AlertSink},
+        commons::PagerDutyConfig,
+    };
+
+    use super::PagerDutySink;
+
+    #[tokio::test]
+    async fn trigger_and_resolve() {
+        let mock_server = MockServer::start().await;
+        let sink = PagerDutySink::with_events_url(
+            PagerDutyConfig {
+                routing_key: "foo".to_owned(),
+                min_severity: None,
+            },
+            &format!("{}/v2/enqueue", mock_server.uri()),
+        )
+        .unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/v2/enqueue"))
+            .and(body_json(json!({
+                "routing_key": "foo",
+                "event_action": "trigger",
+                "dedup_key": "100/oracle/0x0000000000000000000000000000000000000000",
+                "payload": {
+                    "summary": "chain 100: answer txs for oracle 0x0000000000000000000000000000000000000000 reverted 3 times in a row",
+                    "source": "defillama-answerer",
+                    "severity": "critical",
+                    "custom_details": { "chain_id": 100 },
+                },
+            })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v2/enqueue"))
+            .and(body_json(json!({
+                "routing_key": "foo",
+                "event_action": "resolve",
+                "dedup_key": "100/oracle/0x0000000000000000000000000000000000000000",
+            })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        sink.send(&Alert {
+            chain_id: 100,
+            kind: AlertKind::RepeatedReverts {
+                oracle_address: Address::zero(),
+                reverts: 3,
+            },
+        })
+        .await
+        .unwrap();
+        // warnings don't page by default
+        sink.send(&Alert {
+            chain_id: 100,
+            kind: AlertKind::LowBalance {
+                answerer: Address::zero(),
+                balance: U256::zero(),
+                threshold: U256::one(),
+            },
+        })
+        .await
+        .unwrap();
+        sink.send(&Alert {
+            chain_id: 100,
+            kind: AlertKind::BalanceRestored {
+                answerer: Address::zero(),
+                balance: U256::one(),
+            },
+        })
+        .await
+        .unwrap();
+        // only incidents triggered by the sink are resolved, and only once
+        for _ in 0..2 {
+            sink.send(&Alert {
+                chain_id: 100,
+                kind: AlertKind::OracleFinalized {
+                    oracle_address: Address::zero(),
+                },
+            })
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn keep_incident_on_failed_resolution() {
+        let mock_server = MockServer::start().await;
+        let sink = PagerDutySink::with_events_url(
+            PagerDutyConfig {
+                routing_key: "foo".to_owned(),
+                min_severity: None,
+            },
+            &mock_server.uri(),
+        )
+        .unwrap();
+        let finalized = Alert {
+            chain_id: 100,
+            kind: AlertKind::OracleFinalized {
+                oracle_address: Address::zero(),
+            },
+        };
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "event_action": "trigger" })))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "event_action": "resolve" })))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "event_action": "resolve" })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        sink.send(&Alert {
+            chain_id: 100,
+            kind: AlertKind::StuckAnswerTx {
+                oracle_address: Address::zero(),
+                tx_hash: H256::zero(),
+            },
+        })
+        .await
+        .unwrap();
+        // a resolution failing is retried by the dispatcher
+        assert!(sink.send(&finalized).await.is_err());
+        sink.send(&finalized).await.unwrap();
+    }
+}
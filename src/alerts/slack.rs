@@ -0,0 +1,111 @@
+use anyhow::Context;
+use reqwest::Client;
+use serde_json::json;
+
+use crate::commons::{SlackConfig, HTTP_TIMEOUT};
+
+use super::{Alert, AlertSeverity, AlertSink};
+
+// posts alerts through a slack incoming webhook, whose url embeds its secret and
+// is thus kept out of errors
+pub struct SlackSink {
+    client: Client,
+    webhook_url: String,
+}
+
+impl SlackSink {
+    pub fn new(config: SlackConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::builder()
+                .timeout(HTTP_TIMEOUT)
+                .build()
+                .context("could not build slack http client")?,
+            webhook_url: config.webhook_url,
+        })
+    }
+}
+
+fn message(alert: &Alert) -> String {
+    let icon = match alert.severity() {
+        AlertSeverity::Info => ":information_source:",
+        AlertSeverity::Warning => ":warning:",
+        AlertSeverity::Critical => ":rotating_light:",
+    };
+    format!(
+        "{} *{}* {}",
+        icon,
+        alert.severity().to_string().to_uppercase(),
+        alert
+    )
+}
+
+impl AlertSink for SlackSink {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        self.client
+            .post(self.webhook_url.as_str())
+            .json(&json!({ "text": message(alert) }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| error.without_url())
+            .context("could not post alert to slack")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::Address;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        alerts::{Alert, AlertKind, AlertSink},
+        commons::SlackConfig,
+    };
+
+    use super::SlackSink;
+
+    #[tokio::test]
+    async fn post_alert() {
+        let mock_server = MockServer::start().await;
+        let sink = SlackSink::new(SlackConfig {
+            webhook_url: format!("{}/services/foo", mock_server.uri()),
+            min_severity: None,
+        })
+        .unwrap();
+        let alert = Alert {
+            chain_id: 100,
+            kind: AlertKind::ExpiredUnanswered {
+                oracle_address: Address::zero(),
+            },
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/services/foo"))
+            .and(body_json(json!({
+                "text": ":rotating_light: *CRITICAL* chain 100: oracle 0x0000000000000000000000000000000000000000 expired without an answer"
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        sink.send(&alert).await.unwrap();
+
+        // slack replying with an error is reported
+        mock_server.reset().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+        let error = sink.send(&alert).await.unwrap_err();
+        assert!(!format!("{:#}", error).contains("/services/foo"));
+    }
+}
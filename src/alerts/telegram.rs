@@ -0,0 +1,127 @@
+use anyhow::Context;
+use reqwest::Client;
+use serde_json::json;
+
+use crate::commons::{TelegramConfig, HTTP_TIMEOUT, TELEGRAM_API_URL};
+
+use super::{Alert, AlertSeverity, AlertSink};
+
+// sends alerts as messages from a telegram bot, which must have been added to the
+// chat. the bot token is part of the api urls, so urls are kept out of errors
+pub struct TelegramSink {
+    client: Client,
+    send_message_url: String,
+    chat_id: String,
+}
+
+impl TelegramSink {
+    pub fn new(config: TelegramConfig) -> anyhow::Result<Self> {
+        Self::with_api_url(config, TELEGRAM_API_URL)
+    }
+
+    fn with_api_url(config: TelegramConfig, api_url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::builder()
+                .timeout(HTTP_TIMEOUT)
+                .build()
+                .context("could not build telegram http client")?,
+            send_message_url: format!("{}/bot{}/sendMessage", api_url, config.bot_token),
+            chat_id: config.chat_id,
+        })
+    }
+}
+
+fn message(alert: &Alert) -> String {
+    let icon = match alert.severity() {
+        AlertSeverity::Info => "\u{2139}\u{fe0f}",
+        AlertSeverity::Warning => "\u{26a0}\u{fe0f}",
+        AlertSeverity::Critical => "\u{1f6a8}",
+    };
+    format!(
+        "{} {} {}",
+        icon,
+        alert.severity().to_string().to_uppercase(),
+        alert
+    )
+}
+
+impl AlertSink for TelegramSink {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        self.client
+            .post(self.send_message_url.as_str())
+            .json(&json!({
+                "chat_id": self.chat_id,
+                "text": message(alert),
+                "disable_web_page_preview": true,
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| error.without_url())
+            .context("could not send alert to telegram")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::Address;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        alerts::{Alert, AlertKind, AlertSink},
+        commons::TelegramConfig,
+    };
+
+    use super::TelegramSink;
+
+    #[tokio::test]
+    async fn send_alert() {
+        let mock_server = MockServer::start().await;
+        let sink = TelegramSink::with_api_url(
+            TelegramConfig {
+                bot_token: "123:token".to_owned(),
+                chat_id: "-100".to_owned(),
+                min_severity: None,
+            },
+            &mock_server.uri(),
+        )
+        .unwrap();
+        let alert = Alert {
+            chain_id: 100,
+            kind: AlertKind::ExpiredUnanswered {
+                oracle_address: Address::zero(),
+            },
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/bot123:token/sendMessage"))
+            .and(body_json(json!({
+                "chat_id": "-100",
+                "text": "\u{1f6a8} CRITICAL chain 100: oracle 0x0000000000000000000000000000000000000000 expired without an answer",
+                "disable_web_page_preview": true,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        sink.send(&alert).await.unwrap();
+
+        // the bot token never ends up in errors
+        mock_server.reset().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+        let error = sink.send(&alert).await.unwrap_err();
+        assert!(!format!("{:#}", error).contains("token"));
+    }
+}
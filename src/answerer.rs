@@ -1,72 +1,380 @@
+pub mod anomaly;
+pub mod balance;
+pub mod computation;
+pub mod diagnostics;
+pub mod finalizations;
+pub mod gas_budget;
+pub mod keys;
+pub mod native_token;
+pub mod orphaned_txs;
+pub mod proofs;
+pub mod purge;
+pub mod recovery;
+pub mod reorg;
+pub mod reverts;
+pub mod sampling;
+pub mod submission;
+
 use std::{
+    future::pending,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use answerer_framework::{
+    submission::{
+        prepare_answer_tx, send_answer_tx, wait_for_mined, wait_for_receipt, ResubmissionPolicy,
+    },
+    AnswerComputer, AnswerSubmitter,
+};
 use anyhow::Context;
-use carrot_commons::http_client::HttpClient;
 use diesel::{
     r2d2::{ConnectionManager, Pool},
-    PgConnection,
+    Connection,
 };
 use ethers::{
     middleware::{Middleware, SignerMiddleware},
-    providers::{Http, Provider},
-    signers::LocalWallet,
-    types::Address,
+    providers::Provider,
+    types::{Address, TransactionReceipt, H256, U256, U64},
     utils,
 };
-use tokio::time::interval;
+use tokio::{
+    sync::Notify,
+    task::JoinSet,
+    time::{interval, sleep, timeout},
+};
 use tracing::{info_span, Instrument};
 
 use crate::{
-    commons::{ChainConfig, ANSWERING_TASK_INTERVAL_SECONDS},
+    alerts::{self, AlertKind},
+    archive::ArchiveNode,
+    commons::{
+        AnomalyDetectionConfig, AnswerSamplingConfig, ChainConfig, GasBudgetConfig,
+        ReceiptTimeoutConfig, ReceiptTimeoutPolicy, ANSWERING_CONCURRENCY,
+        ANSWERING_TASK_INTERVAL_SECONDS, ANSWER_CLAIM_DURATION, ANSWER_COMPUTATION_TIMEOUT,
+        ANSWER_RETRY_INITIAL_BACKOFF, ANSWER_RETRY_MAX_BACKOFF, ANSWER_TX_FEE_BUMP_PERCENTAGE,
+        ANSWER_TX_MAX_RESUBMISSIONS, GAS_BUDGET_DAILY_WINDOW, GAS_BUDGET_WEEKLY_WINDOW,
+        INVALID_ANSWER, REORG_CONFIRMATION_BLOCKS, SOURCE_MISSING_RECHECK_INTERVAL,
+    },
     contracts::{defi_llama_oracle::DefiLlamaOracle, kpi_token::KPIToken},
-    db::models::{self, ActiveOracle},
+    db::{
+        self,
+        models::{self, ActiveOracle},
+        DbConnection,
+    },
+    defillama::{is_source_missing, DefiLlamaClient},
+    events::{self, OracleEventKind},
+    feature_gates::{Feature, FeatureGates},
+    heartbeat::Heartbeat,
+    metrics::{self, AnswerTxStatus},
+    quorum::QuorumReader,
+    rpc::FallbackHttp,
+    shutdown::ShutdownSignal,
+    signer::AnswererSigner,
     specification,
 };
 
+use self::{
+    anomaly::detect_anomaly,
+    computation::DefiLlamaAnswerComputer,
+    keys::AnswererKeys,
+    native_token::{default_coingecko_id, NativeTokenPriceFeed},
+    proofs::{ProofBundle, ProofPublisher},
+    reorg::{watch_for_reorg, FinalizedOracle},
+    sampling::sampling_duration,
+    submission::DefiLlamaAnswerSubmitter,
+};
+
+// everything needed to answer a chain's oracles, shared between the answering tasks
+struct AnsweringContext {
+    dev_mode: bool,
+    dry_run: bool,
+    record_defillama_responses: bool,
+    chain_id: u64,
+    answer_computation_timeout: Duration,
+    answer_sampling: Option<AnswerSamplingConfig>,
+    anomaly_detection: Option<AnomalyDetectionConfig>,
+    source_missing_fallback_window: Option<Duration>,
+    invalid_answer_window: Option<Duration>,
+    legacy_transactions: bool,
+    reorg_confirmation_blocks: u64,
+    gas_budget: Option<GasBudgetConfig>,
+    resubmission_policy: Option<ResubmissionPolicy>,
+    // the primary key's signer, used for reads
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    answerer_keys: Arc<AnswererKeys>,
+    answer_submitter: Arc<dyn AnswerSubmitter>,
+    archive_node: Option<Arc<ArchiveNode>>,
+    quorum_reader: Arc<QuorumReader>,
+    native_token_price_feed: Option<NativeTokenPriceFeed>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+    // absent when answers aren't published on ipfs
+    proof_publisher: Option<Arc<ProofPublisher>>,
+    heartbeat: Heartbeat,
+    shutdown: ShutdownSignal,
+}
+
+impl AnsweringContext {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        dev_mode: bool,
+        dry_run: bool,
+        record_defillama_responses: bool,
+        chain_id: u64,
+        chain_config: ChainConfig,
+        answerer_keys: Arc<AnswererKeys>,
+        archive_node: Option<Arc<ArchiveNode>>,
+        quorum_reader: Arc<QuorumReader>,
+        db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+        defillama: DefiLlamaClient,
+        proof_publisher: Option<Arc<ProofPublisher>>,
+        heartbeat: Heartbeat,
+        shutdown: ShutdownSignal,
+    ) -> Self {
+        let native_token_price_feed = match chain_config
+            .native_token_coingecko_id
+            .clone()
+            .or_else(|| default_coingecko_id(chain_id).map(str::to_owned))
+        {
+            Some(coingecko_id) => Some(NativeTokenPriceFeed::new(defillama.clone(), coingecko_id)),
+            None => {
+                tracing::warn!("unknown native token, fees won't be reported in usd");
+                None
+            }
+        };
+        Self {
+            dev_mode,
+            dry_run,
+            record_defillama_responses,
+            chain_id,
+            answer_computation_timeout: chain_config
+                .answer_computation_timeout_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(ANSWER_COMPUTATION_TIMEOUT),
+            answer_sampling: chain_config.answer_sampling,
+            anomaly_detection: chain_config.anomaly_detection,
+            source_missing_fallback_window: chain_config
+                .source_missing_fallback_window_seconds
+                .map(Duration::from_secs),
+            invalid_answer_window: chain_config
+                .invalid_answer_window_seconds
+                .map(Duration::from_secs),
+            legacy_transactions: chain_config.legacy_transactions.unwrap_or(false),
+            reorg_confirmation_blocks: chain_config
+                .reorg_confirmation_blocks
+                .unwrap_or(REORG_CONFIRMATION_BLOCKS),
+            gas_budget: chain_config.gas_budget,
+            resubmission_policy: chain_config
+                .receipt_timeout
+                .as_ref()
+                .map(resubmission_policy),
+            signer: answerer_keys.primary(),
+            answer_submitter: Arc::new(DefiLlamaAnswerSubmitter {
+                signer: answerer_keys.primary(),
+            }),
+            answerer_keys,
+            archive_node,
+            quorum_reader,
+            native_token_price_feed,
+            db_connection_pool,
+            defillama,
+            proof_publisher,
+            heartbeat,
+            shutdown,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn answer_active_oracles(
     dev_mode: bool,
+    dry_run: bool,
+    record_defillama_responses: bool,
     chain_id: u64,
     chain_config: ChainConfig,
-    signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    defillama_http_client: Arc<HttpClient>,
+    answerer_keys: Arc<AnswererKeys>,
+    archive_node: Option<Arc<ArchiveNode>>,
+    quorum_reader: Arc<QuorumReader>,
+    oracles_acknowledged: Arc<Notify>,
+    answering_trigger: Arc<Notify>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+    proof_publisher: Option<Arc<ProofPublisher>>,
+    heartbeat: Heartbeat,
+    mut shutdown: ShutdownSignal,
 ) -> anyhow::Result<()> {
     let duration = chain_config
         .answering_task_interval_seconds
-        .map(|seconds| Duration::from_secs(seconds))
+        .map(Duration::from_secs)
         .unwrap_or(ANSWERING_TASK_INTERVAL_SECONDS);
     let mut interval = interval(duration);
+    let answering_concurrency = chain_config
+        .answering_concurrency
+        .unwrap_or(ANSWERING_CONCURRENCY)
+        .max(1);
+    let context = Arc::new(AnsweringContext::new(
+        dev_mode,
+        dry_run,
+        record_defillama_responses,
+        chain_id,
+        chain_config,
+        answerer_keys,
+        archive_node,
+        quorum_reader,
+        db_connection_pool,
+        defillama,
+        proof_publisher,
+        heartbeat,
+        shutdown.clone(),
+    ));
+    let answer_computation_timeout = context.answer_computation_timeout;
 
-    tracing::info!("answering active oracles every {}s", duration.as_secs());
+    tracing::info!(
+        "answering up to {} active oracles concurrently every {}s with a {}s answer computation timeout",
+        answering_concurrency,
+        duration.as_secs(),
+        answer_computation_timeout.as_secs()
+    );
+    if context.legacy_transactions {
+        tracing::info!("answering oracles with legacy transactions");
+    }
+    if context.answerer_keys.len() > 1 {
+        tracing::info!(
+            "answering oracles with {} answerer keys",
+            context.answerer_keys.len()
+        );
+    }
 
     loop {
-        interval.tick().await;
+        // the polling interval acts as a safety net, while the measurement timer
+        // makes answers land as close as possible to the measurement timestamp
+        let next_measurement_timestamp = db::blocking(|| {
+            get_next_measurement_timestamp(chain_id, context.db_connection_pool.clone())
+        });
+        tokio::select! {
+            biased;
+            _ = shutdown.triggered() => {
+                tracing::info!("shutting down, not starting new answering runs");
+                return Ok(());
+            }
+            _ = interval.tick() => {}
+            _ = sleep_until(next_measurement_timestamp) => {
+                tracing::info!("measurement timestamp reached");
+            }
+            _ = answering_trigger.notified() => {
+                tracing::info!("answering run triggered");
+            }
+            _ = oracles_acknowledged.notified() => {
+                // new oracles might have an earlier measurement timestamp
+                continue;
+            }
+        }
 
-        if let Err(error) = handle_active_oracles_answering(
-            dev_mode,
-            chain_id,
-            signer.clone(),
-            db_connection_pool.clone(),
-            defillama_http_client.clone(),
-        )
-        .await
+        if let Err(error) =
+            handle_active_oracles_answering(context.clone(), answering_concurrency).await
         {
             tracing::error!("error while handling active oracles answering: {:#}", error);
         }
     }
 }
 
-pub async fn handle_active_oracles_answering(
+// answers a single oracle right away instead of waiting for an answering run, to
+// manually remediate oracles that got stuck. the oracle goes through the same
+// checks and submission flow as in answering runs
+#[allow(clippy::too_many_arguments)]
+pub async fn answer_oracle(
     dev_mode: bool,
+    dry_run: bool,
+    record_defillama_responses: bool,
     chain_id: u64,
-    signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    defillama_http_client: Arc<HttpClient>,
+    address: Address,
+    chain_config: ChainConfig,
+    answerer_keys: Arc<AnswererKeys>,
+    archive_node: Option<Arc<ArchiveNode>>,
+    quorum_reader: Arc<QuorumReader>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
 ) -> anyhow::Result<()> {
-    let mut db_connection = match db_connection_pool.get() {
+    let (_, shutdown) = ShutdownSignal::channel();
+    let context = Arc::new(AnsweringContext::new(
+        dev_mode,
+        dry_run,
+        record_defillama_responses,
+        chain_id,
+        chain_config,
+        answerer_keys,
+        archive_node,
+        quorum_reader,
+        db_connection_pool,
+        defillama,
+        // the process exits right after answering, before a bundle could be published
+        None,
+        Heartbeat::disabled(),
+        shutdown,
+    ));
+
+    let (active_oracle, claim_expiration, feature_gates) = db::blocking(|| {
+        let mut db_connection = context
+            .db_connection_pool
+            .get()
+            .context("could not get new connection from pool")?;
+        let active_oracle = models::ActiveOracle::get(&mut db_connection, chain_id, address)?
+            .context(format!(
+                "no active oracle 0x{:x} on chain {}",
+                address, chain_id
+            ))?;
+        if *active_oracle.measurement_timestamp > SystemTime::now() {
+            anyhow::bail!("measurement timestamp not reached yet");
+        }
+        let feature_gates = FeatureGates::load(&mut db_connection, chain_id)?;
+        let claim_expiration = active_oracle
+            .claim(&mut db_connection, ANSWER_CLAIM_DURATION)?
+            .context("oracle is being answered by an answering task")?;
+        Ok((active_oracle, claim_expiration, feature_gates))
+    })?;
+
+    let oracle_address = format!("0x{:x}", address);
+    answer_claimed_active_oracle(
+        context.clone(),
+        Arc::new(feature_gates),
+        active_oracle,
+        claim_expiration,
+    )
+    .instrument(info_span!("answer", chain_id, oracle_address))
+    .await?;
+
+    // failures are logged rather than returned, leaving the oracle in place. dry
+    // runs always leave it in place, so only the recorded answer tells
+    let answered = db::blocking(|| {
+        let mut db_connection = context
+            .db_connection_pool
+            .get()
+            .context("could not get new connection from pool")?;
+        if context.dry_run {
+            return Ok(models::DryRunAnswer::get(&mut db_connection, chain_id, address)?.is_some());
+        }
+        Ok::<_, anyhow::Error>(
+            models::ActiveOracle::get(&mut db_connection, chain_id, address)?.is_none(),
+        )
+    })?;
+    if !answered {
+        anyhow::bail!("oracle was not answered, see the logs for details");
+    }
+    Ok(())
+}
+
+async fn handle_active_oracles_answering(
+    context: Arc<AnsweringContext>,
+    answering_concurrency: usize,
+) -> anyhow::Result<()> {
+    let chain_id = context.chain_id;
+    if context.signer.inner().as_ref().is_degraded() {
+        tracing::debug!("chain degraded, skipping answering run");
+        return Ok(());
+    }
+
+    let mut db_connection = match db::blocking(|| context.db_connection_pool.get()) {
         Ok(connection) => connection,
         Err(error) => {
             tracing::error!("could not get new connection from pool: {:#}", error);
@@ -74,57 +382,133 @@ pub async fn handle_active_oracles_answering(
         }
     };
 
-    let active_oracles =
-        match models::ActiveOracle::get_all_answerable_for_chain_id(&mut db_connection, chain_id) {
-            Ok(oracles) => oracles,
+    let feature_gates = match db::blocking(|| FeatureGates::load(&mut db_connection, chain_id)) {
+        Ok(feature_gates) => Arc::new(feature_gates),
+        Err(error) => {
+            tracing::error!(
+                "could not get feature gates for chain with id {}: {:#}",
+                chain_id,
+                error
+            );
+            return Ok(());
+        }
+    };
+    drop(db_connection);
+
+    // oracles are claimed one at a time as answering slots free up, so that claims
+    // don't expire while waiting for a slot and other instances sharing the database
+    // can pick up the oracles this one doesn't get to
+    let mut handled = Vec::new();
+    let mut claim_failed = false;
+    let mut join_set = JoinSet::new();
+    loop {
+        // wait for a slot to free up before spawning more answering tasks
+        if join_set.len() >= answering_concurrency {
+            join_answering_task(&mut join_set).await;
+        }
+        // answers already being submitted are completed, the rest are left for
+        // the next start
+        if context.shutdown.is_triggered() {
+            break;
+        }
+
+        let (active_oracle, claim_expiration) = match db::blocking(|| {
+            let mut db_connection = context
+                .db_connection_pool
+                .get()
+                .context("could not get new connection from pool")?;
+            models::ActiveOracle::claim_next_answerable_for_chain_id(
+                &mut db_connection,
+                chain_id,
+                ANSWER_CLAIM_DURATION,
+                &handled,
+            )
+        }) {
+            Ok(Some(active_oracle)) => active_oracle,
+            Ok(None) => break,
             Err(error) => {
-                tracing::error!(
-                    "could not get currently active oracles in chain with id {}: {:#}",
-                    chain_id,
-                    error
-                );
-                return Ok(());
+                tracing::error!("{:#}", error);
+                claim_failed = true;
+                break;
             }
         };
-    drop(db_connection);
+        handled.push(active_oracle.address.0);
 
-    let active_oracles_len = active_oracles.len();
-    if active_oracles_len == 0 {
-        return Ok(());
+        let oracle_address = format!("0x{:x}", active_oracle.address.0);
+        join_set.spawn(
+            answer_claimed_active_oracle(
+                context.clone(),
+                feature_gates.clone(),
+                active_oracle,
+                claim_expiration,
+            )
+            .instrument(info_span!("answer", chain_id, oracle_address)),
+        );
+    }
+    while !join_set.is_empty() {
+        join_answering_task(&mut join_set).await;
     }
 
-    tracing::info!("trying to answer {} active oracles", active_oracles_len);
+    if !handled.is_empty() {
+        tracing::info!("handled {} active oracles", handled.len());
+    }
+    // runs interrupted by a shutdown or by the database are not considered successful
+    if !claim_failed && !context.shutdown.is_triggered() {
+        context.heartbeat.beat();
+    }
 
-    let chain_id = chain_id;
-    for active_oracle in active_oracles.into_iter() {
-        let oracle_address = format!("0x{:x}", active_oracle.address.0);
-        let oracle_address_clone = oracle_address.clone();
-        if let Err(err) = answer_active_oracle(
-            dev_mode,
-            signer.clone(),
-            db_connection_pool.clone(),
-            defillama_http_client.clone(),
-            active_oracle,
-        )
-        .instrument(info_span!("answer", chain_id, oracle_address))
-        .await
-        {
+    Ok(())
+}
+
+async fn join_answering_task(join_set: &mut JoinSet<anyhow::Result<()>>) {
+    match join_set.join_next().await {
+        Some(Ok(Err(error))) => {
             tracing::error!(
-                "error while answering oracle {}, ADDRESS IMMEDIATELY: {:#}",
-                oracle_address_clone,
-                err
+                "error while answering oracle, ADDRESS IMMEDIATELY: {:#}",
+                error
             );
         }
+        Some(Err(error)) => {
+            tracing::error!(
+                "an unexpected error happened while joining an answering task: {:#}",
+                error
+            );
+        }
+        Some(Ok(Ok(()))) | None => {}
     }
+}
 
-    Ok(())
+// the claim makes sure that the same oracle is never answered concurrently, be it by
+// overlapping answering runs or by other answerer instances sharing the same database.
+// the oracle is claimed when picked, and the claim is released here once handled
+async fn answer_claimed_active_oracle(
+    context: Arc<AnsweringContext>,
+    feature_gates: Arc<FeatureGates>,
+    active_oracle: models::ActiveOracle,
+    claim_expiration: SystemTime,
+) -> anyhow::Result<()> {
+    let address = active_oracle.address.0;
+    let chain_id = active_oracle.chain_id;
+    let result = answer_active_oracle(&context, &feature_gates, active_oracle).await;
+
+    // if the oracle was answered its row is gone and releasing the claim is a no-op
+    let mut db_connection = db::blocking(|| context.db_connection_pool.get())
+        .context("could not get database connection while trying to release oracle claim")?;
+    db::blocking(|| {
+        models::ActiveOracle::release_claim(
+            &mut db_connection,
+            chain_id as u64,
+            address,
+            claim_expiration,
+        )
+    })?;
+
+    result
 }
 
 async fn answer_active_oracle(
-    dev_mode: bool,
-    signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    defillama_http_client: Arc<HttpClient>,
+    context: &AnsweringContext,
+    feature_gates: &FeatureGates,
     mut active_oracle: models::ActiveOracle,
 ) -> anyhow::Result<()> {
     if let Some(tx_hash) = active_oracle.answer_tx_hash {
@@ -136,16 +520,16 @@ async fn answer_active_oracle(
     }
 
     match is_active_oracle_expired(
-        db_connection_pool.clone(),
-        signer.clone(),
+        context.db_connection_pool.clone(),
+        context.signer.clone(),
+        &context.quorum_reader,
         &mut active_oracle,
     )
     .await
     {
         Ok(expired) => {
             if expired {
-                let mut db_connection = match db_connection_pool
-                    .get()
+                let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
                     .context("could not get new connection from pool")
                 {
                     Ok(db_connection) => db_connection,
@@ -158,9 +542,22 @@ async fn answer_active_oracle(
                     }
                 };
 
-                tracing::warn!("oracle is expired, skipping and deleting");
-                if let Err(error) = active_oracle.delete(&mut db_connection) {
-                    tracing::error!("{:#}", error);
+                tracing::warn!("oracle is expired, skipping and archiving");
+                let (chain_id, address) = (active_oracle.chain_id as u64, active_oracle.address.0);
+                match db::blocking(|| {
+                    active_oracle.archive(&mut db_connection, models::ArchiveReason::Expired)
+                }) {
+                    Ok(()) => {
+                        reverts::forget(chain_id, address);
+                        events::emit(chain_id, address, OracleEventKind::Expired);
+                        alerts::raise(
+                            chain_id,
+                            AlertKind::ExpiredUnanswered {
+                                oracle_address: address,
+                            },
+                        );
+                    }
+                    Err(error) => tracing::error!("{:#}", error),
                 }
                 return Ok(());
             }
@@ -171,17 +568,245 @@ async fn answer_active_oracle(
         }
     }
 
+    // another answerer instance or a manual operator might have finalized the
+    // oracle in the meantime, in which case submitting an answer would just revert
+    let address = active_oracle.address.0;
+    let finalized = match is_active_oracle_finalized(context.signer.clone(), address).await {
+        Ok(finalized) => {
+            context
+                .quorum_reader
+                .confirm("finalization status", finalized, |provider| {
+                    is_active_oracle_finalized(provider, address)
+                })
+                .await
+        }
+        Err(error) => Err(error),
+    };
+    match finalized {
+        Ok(finalized) => {
+            if finalized {
+                let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
+                    .context("could not get new connection from pool")
+                {
+                    Ok(db_connection) => db_connection,
+                    Err(error) => {
+                        tracing::error!(
+                            "could not get database connection while trying to delete finalized oracle: {:#}",
+                            error
+                        );
+                        return Ok(());
+                    }
+                };
+
+                tracing::warn!("oracle already finalized on-chain, skipping and archiving");
+                let chain_id = active_oracle.chain_id as u64;
+                match db::blocking(|| {
+                    active_oracle.archive(&mut db_connection, models::ArchiveReason::Finalized)
+                }) {
+                    Ok(()) => {
+                        events::emit(
+                            chain_id,
+                            address,
+                            OracleEventKind::Finalized { tx_hash: None },
+                        );
+                        reverts::record_finalization(chain_id, address);
+                    }
+                    Err(error) => tracing::error!("{:#}", error),
+                }
+                return Ok(());
+            }
+        }
+        Err(error) => {
+            tracing::error!("could not get finalization status for oracle: {:#}", error);
+            return Ok(());
+        }
+    }
+
+    match db::blocking(|| {
+        apply_answer_override(context.db_connection_pool.clone(), &mut active_oracle)
+    }) {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::warn!("answer override pending approval, skipping");
+            return Ok(());
+        }
+        Err(error) => {
+            tracing::error!("could not apply answer override: {:#}", error);
+            return Ok(());
+        }
+    }
+
+    match db::blocking(|| is_held_for_review(context.db_connection_pool.clone(), &active_oracle)) {
+        Ok(false) => {}
+        Ok(true) => {
+            tracing::warn!("answer held for review, skipping");
+            return Ok(());
+        }
+        Err(error) => {
+            tracing::error!("could not get answer review status: {:#}", error);
+            return Ok(());
+        }
+    }
+
+    if let Err(error) = db::blocking(|| {
+        apply_source_missing_fallback(
+            context.db_connection_pool.clone(),
+            context.source_missing_fallback_window,
+            &mut active_oracle,
+        )
+    }) {
+        tracing::error!("could not apply fallback answer: {:#}", error);
+        return Ok(());
+    }
+
     let answer = match &active_oracle.answer {
         Some(answer) => {
             tracing::info!("reusing saved answer {}", answer.0);
             Some(answer.0)
         }
         None => {
-            let answer =
-                specification::answer(&active_oracle.specification, defillama_http_client).await;
+            let defillama = if context.record_defillama_responses {
+                context.defillama.recording()
+            } else {
+                context.defillama.clone()
+            };
+            let answer_computer = DefiLlamaAnswerComputer {
+                defillama: defillama.clone(),
+                archive_node: context.archive_node.clone(),
+                answer_sampling: context.answer_sampling.clone(),
+            };
+            let answer = timeout(
+                context.answer_computation_timeout
+                    + context
+                        .answer_sampling
+                        .as_ref()
+                        .map(sampling_duration)
+                        .unwrap_or_default(),
+                answer_computer.compute_answer(&active_oracle),
+            )
+            .await;
+            let answer = match answer {
+                Ok(Ok(answer)) => answer,
+                Ok(Err(error)) if is_source_missing(&error) => {
+                    // the fallback value takes precedence when one will be applied
+                    let fallback_pending = context.source_missing_fallback_window.is_some()
+                        && specification::fallback(&active_oracle.specification).is_some();
+                    if !fallback_pending
+                        && db::blocking(|| {
+                            apply_invalid_answer(
+                                context.db_connection_pool.clone(),
+                                context.invalid_answer_window,
+                                &mut active_oracle,
+                            )
+                        })
+                    {
+                        return Ok(());
+                    }
+                    db::blocking(|| {
+                        handle_missing_source(
+                            context.db_connection_pool.clone(),
+                            &mut active_oracle,
+                        )
+                    });
+                    return Ok(());
+                }
+                Ok(Err(error)) => {
+                    tracing::error!("answering failed for specification - {:#}", error);
+                    db::blocking(|| {
+                        apply_invalid_answer(
+                            context.db_connection_pool.clone(),
+                            context.invalid_answer_window,
+                            &mut active_oracle,
+                        )
+                    });
+                    None
+                }
+                Err(_) => {
+                    if db::blocking(|| {
+                        apply_invalid_answer(
+                            context.db_connection_pool.clone(),
+                            context.invalid_answer_window,
+                            &mut active_oracle,
+                        )
+                    }) {
+                        return Ok(());
+                    }
+
+                    if !feature_gates.is_enabled(Feature::AnswerRetryBackoff) {
+                        tracing::warn!(
+                            "answer computation timed out after {}s, retrying next tick",
+                            context.answer_computation_timeout.as_secs()
+                        );
+                        return Ok(());
+                    }
+
+                    let backoff = answer_retry_backoff(active_oracle.answer_attempts);
+                    tracing::warn!(
+                        "answer computation timed out after {}s, retrying in {}s",
+                        context.answer_computation_timeout.as_secs(),
+                        backoff.as_secs()
+                    );
+
+                    let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
+                        .context("could not get new connection from pool")
+                    {
+                        Ok(db_connection) => db_connection,
+                        Err(error) => {
+                            tracing::error!(
+                                "could not get database connection while trying to schedule oracle's answer retry: {:#}",
+                                error
+                            );
+                            return Ok(());
+                        }
+                    };
+                    if let Err(error) = db::blocking(|| {
+                        active_oracle
+                            .schedule_answer_retry(&mut db_connection, SystemTime::now() + backoff)
+                    }) {
+                        tracing::error!("{:#}", error);
+                    }
+                    return Ok(());
+                }
+            };
+            let defillama_snapshot_id = if context.record_defillama_responses {
+                db::blocking(|| {
+                    record_defillama_snapshot(
+                        context.db_connection_pool.clone(),
+                        &active_oracle,
+                        &defillama,
+                        answer,
+                    )
+                })
+            } else {
+                None
+            };
+            if let (Some(answer), Some(anomaly_detection)) = (answer, &context.anomaly_detection) {
+                if let Some(reference_answer) = detect_anomaly(
+                    &active_oracle.specification,
+                    &context.defillama,
+                    anomaly_detection,
+                    answer,
+                )
+                .await
+                {
+                    tracing::error!(
+                        "anomalous answer {} against reference {}, holding it for review - ACT IMMEDIATELY",
+                        answer,
+                        reference_answer
+                    );
+                    db::blocking(|| {
+                        hold_for_review(
+                            context.db_connection_pool.clone(),
+                            &active_oracle,
+                            answer,
+                            reference_answer,
+                        )
+                    });
+                    return Ok(());
+                }
+            }
             if let Some(answer) = answer {
-                let mut db_connection = match db_connection_pool
-                    .get()
+                let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
                     .context("could not get new connection from pool")
                 {
                     Ok(db_connection) => db_connection,
@@ -194,10 +819,39 @@ async fn answer_active_oracle(
                     }
                 };
 
-                if let Err(error) = active_oracle.update_answer(&mut db_connection, answer) {
+                if active_oracle.source_missing_since.is_some() {
+                    tracing::info!("data available on defillama again");
+                    if let Err(error) =
+                        db::blocking(|| active_oracle.clear_source_missing(&mut db_connection))
+                    {
+                        tracing::error!("{:#}", error);
+                    }
+                }
+                if let Err(error) =
+                    db::blocking(|| active_oracle.update_answer(&mut db_connection, answer))
+                {
                     tracing::error!("{:#}", error);
                     return Ok(());
                 }
+                metrics::record_answer_computed(active_oracle.chain_id as u64);
+                events::emit(
+                    active_oracle.chain_id as u64,
+                    active_oracle.address.0,
+                    OracleEventKind::AnswerComputed {
+                        answer: answer.to_string(),
+                        defillama_snapshot_id,
+                    },
+                );
+                // links the answer to the responses it was computed from, so that it
+                // can be audited later on
+                if let Some(defillama_snapshot_id) = defillama_snapshot_id {
+                    if let Err(error) = db::blocking(|| {
+                        active_oracle
+                            .update_defillama_snapshot_id(&mut db_connection, defillama_snapshot_id)
+                    }) {
+                        tracing::error!("{:#}", error);
+                    }
+                }
             }
             answer
         }
@@ -205,44 +859,154 @@ async fn answer_active_oracle(
     if let Some(answer) = answer {
         // if we arrive here, an answer is available and we should submit it
 
-        tracing::info!("answering with value {}", answer);
-        let oracle = DefiLlamaOracle::new(active_oracle.address.0, signer.clone());
-        let mut call = oracle.finalize(answer);
+        if context.dry_run {
+            let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
+                .context("could not get new connection from pool")
+            {
+                Ok(db_connection) => db_connection,
+                Err(error) => {
+                    tracing::error!(
+                        "could not get database connection while trying to record dry run answer: {:#}",
+                        error
+                    );
+                    return Ok(());
+                }
+            };
+            // the oracle is left in place so that it's answered for real once dry run
+            // is turned off. its answer is saved, so following runs only record it again
+            // when it changed
+            let recorded = match db::blocking(|| {
+                models::DryRunAnswer::get(
+                    &mut db_connection,
+                    active_oracle.chain_id as u64,
+                    active_oracle.address.0,
+                )
+            }) {
+                Ok(recorded) => recorded,
+                Err(error) => {
+                    tracing::error!("{:#}", error);
+                    return Ok(());
+                }
+            };
+            if recorded.is_some_and(|recorded| recorded.answer.0 == answer) {
+                tracing::debug!("dry run, answer {} already recorded", answer);
+                return Ok(());
+            }
+            tracing::info!("dry run, recording answer {} without submitting it", answer);
+            if let Err(error) = db::blocking(|| {
+                models::DryRunAnswer::create(
+                    &mut db_connection,
+                    active_oracle.chain_id as u64,
+                    active_oracle.address.0,
+                    answer,
+                )
+            }) {
+                tracing::error!("{:#}", error);
+            }
+            return Ok(());
+        }
 
-        if dev_mode {
-            let expected_answerer = match oracle.answerer().call().await {
-                Ok(answerer) => answerer,
-                Err(err) => {
-                    tracing::error!("could not fetch expected answerer while trying to answer in dev mode: {err:#}");
+        if let Some(gas_budget) = context.gas_budget.as_ref() {
+            let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
+                .context("could not get new connection from pool")
+            {
+                Ok(db_connection) => db_connection,
+                Err(error) => {
+                    tracing::error!(
+                        "could not get database connection while trying to check gas budget: {:#}",
+                        error
+                    );
                     return Ok(());
                 }
             };
-            call = call.from(expected_answerer);
+            if let Err(error) = db::blocking(|| {
+                check_gas_budget(
+                    &mut db_connection,
+                    active_oracle.chain_id as u64,
+                    gas_budget,
+                )
+            }) {
+                tracing::error!("refusing to submit answer, ACT IMMEDIATELY: {:#}", error);
+                return Ok(());
+            }
+        }
+
+        let expected_answerer = match context
+            .answer_submitter
+            .expected_answerer(active_oracle.address.0)
+            .await
+        {
+            Ok(answerer) => answerer,
+            Err(err) => {
+                tracing::error!("could not fetch expected answerer: {err:#}");
+                return Ok(());
+            }
+        };
+
+        // in dev mode the expected answerer is impersonated, so any key works
+        let answerer_key = match context
+            .answerer_keys
+            .select((!context.dev_mode).then_some(expected_answerer))
+        {
+            Some(answerer_key) => answerer_key,
+            None => {
+                tracing::error!(
+                    "no answerer key for expected answerer 0x{:x}, refusing to submit answer, ACT IMMEDIATELY",
+                    expected_answerer
+                );
+                return Ok(());
+            }
+        };
+        if let Err(error) = answerer_key.check_balance() {
+            tracing::error!("refusing to submit answer, ACT IMMEDIATELY: {:#}", error);
+            return Ok(());
         }
 
-        match signer.fill_transaction(&mut call.tx, None).await {
-            Ok(()) => {}
+        tracing::info!(
+            "answering with value {} from 0x{:x}",
+            answer,
+            answerer_key.address()
+        );
+        let signer = answerer_key.signer();
+        let answer_tx = match prepare_answer_tx(
+            signer.as_ref(),
+            context
+                .answer_submitter
+                .finalize_tx(active_oracle.address.0, answer),
+            context.legacy_transactions,
+            context.dev_mode.then_some(expected_answerer),
+        )
+        .await
+        {
+            Ok(answer_tx) => answer_tx,
             Err(error) => {
                 tracing::error!("could not fill answer call: {:#}", error);
                 return Ok(());
             }
         };
 
-        let tx = match call.send().await {
-            Ok(tx) => tx,
+        let tx_hash = match send_answer_tx(signer.as_ref(), answer_tx.clone()).await {
+            Ok(tx_hash) => tx_hash,
             Err(error) => {
                 tracing::error!(
                     "error while submitting answer transaction {:?}: {:#}",
-                    call.tx,
+                    answer_tx,
                     error
                 );
+                metrics::record_answer_tx(active_oracle.chain_id as u64, AnswerTxStatus::Failed);
+                events::emit(
+                    active_oracle.chain_id as u64,
+                    active_oracle.address.0,
+                    OracleEventKind::Failed {
+                        reason: format!("could not submit answer transaction: {}", error),
+                    },
+                );
                 return Ok(());
             }
         };
 
         {
-            let mut db_connection = match db_connection_pool
-                .get()
+            let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
                 .context("could not get new connection from pool")
             {
                 Ok(db_connection) => db_connection,
@@ -256,15 +1020,67 @@ async fn answer_active_oracle(
             };
 
             if let Err(error) =
-                active_oracle.update_answer_tx_hash(&mut db_connection, tx.tx_hash())
+                db::blocking(|| active_oracle.update_answer_tx_hash(&mut db_connection, tx_hash))
             {
                 tracing::error!("{:#}", error);
                 return Ok(());
             }
         }
+        metrics::record_answer_tx(active_oracle.chain_id as u64, AnswerTxStatus::Submitted);
+        events::emit(
+            active_oracle.chain_id as u64,
+            active_oracle.address.0,
+            OracleEventKind::TxSubmitted {
+                tx_hash: format!("0x{:x}", tx_hash),
+            },
+        );
 
-        let debug_tx = format!("{:?}", tx);
-        let receipt = match tx.await {
+        let debug_tx = format!("0x{:x}", tx_hash);
+        let receipt = match context.resubmission_policy.as_ref() {
+            Some(resubmission_policy) => match wait_for_receipt(
+                signer.clone(),
+                answer_tx,
+                tx_hash,
+                resubmission_policy,
+                |replacement_tx_hash| {
+                    let mut db_connection = db::blocking(|| context.db_connection_pool.get())
+                        .context("could not get new connection from pool")?;
+                    db::blocking(|| {
+                        active_oracle.update_answer_tx_hash(&mut db_connection, replacement_tx_hash)
+                    })
+                },
+            )
+            .await
+            {
+                Ok(Some(receipt)) => Ok(Some(receipt)),
+                Ok(None) => {
+                    tracing::warn!(
+                        "answer transaction {} not mined in time, clearing it so that the oracle is answered again",
+                        debug_tx
+                    );
+                    metrics::record_answer_tx(
+                        active_oracle.chain_id as u64,
+                        AnswerTxStatus::Failed,
+                    );
+                    events::emit(
+                        active_oracle.chain_id as u64,
+                        active_oracle.address.0,
+                        OracleEventKind::Failed {
+                            reason: "answer transaction not mined in time".to_owned(),
+                        },
+                    );
+                    let mut db_connection = db::blocking(|| context.db_connection_pool.get())
+                        .context("could not get database connection while trying to delete oracle's answer tx hash")?;
+                    db::blocking(|| active_oracle.delete_answer_tx_hash(&mut db_connection))
+                        .inspect_err(|_| raise_stuck_answer_tx(&active_oracle, tx_hash))
+                        .context("could not delete active answer transaction hash; the oracle resolution process is now stuck, ACT IMMEDIATELY")?;
+                    return Ok(());
+                }
+                Err(error) => Err(error),
+            },
+            None => wait_for_mined(signer.as_ref(), tx_hash).await,
+        };
+        let receipt = match receipt {
             Ok(receipt) => receipt,
             Err(error) => {
                 // we need to throw the following errors as these needs to be addressed immediately.
@@ -276,36 +1092,94 @@ async fn answer_active_oracle(
                     debug_tx,
                     error
                 );
-                let mut db_connection = db_connection_pool
-                    .get()
+                metrics::record_answer_tx(active_oracle.chain_id as u64, AnswerTxStatus::Failed);
+                events::emit(
+                    active_oracle.chain_id as u64,
+                    active_oracle.address.0,
+                    OracleEventKind::Failed {
+                        reason: format!("could not confirm answer transaction: {}", error),
+                    },
+                );
+                let mut db_connection = db::blocking(|| context.db_connection_pool.get())
                     .context("could not get database connection while trying to delete oracle's answer tx hash")?;
-                active_oracle.delete_answer_tx_hash(&mut db_connection).context("could not delete active answer transaction hash; the oracle resolution process is now stuck, ACT IMMEDIATELY")?;
+                db::blocking(|| active_oracle.delete_answer_tx_hash(&mut db_connection))
+                    .inspect_err(|_| raise_stuck_answer_tx(&active_oracle, tx_hash))
+                    .context("could not delete active answer transaction hash; the oracle resolution process is now stuck, ACT IMMEDIATELY")?;
 
                 return Ok(());
             }
         };
 
-        if let Some(receipt) = receipt {
-            if let (Some(gas_used), Some(effective_gas_price)) =
-                (receipt.gas_used, receipt.effective_gas_price)
-            {
-                // assuming it's always 18 decimals
-                let fee = gas_used * effective_gas_price;
-                let formatted = match utils::format_units(gas_used * effective_gas_price, 18) {
-                    Ok(formatted) => formatted,
-                    Err(error) => {
-                        tracing::error!("could not format units for raw fee {}: {:#}", fee, error);
-                        return Ok(());
-                    }
-                };
-                tracing::info!("paid {} to answer oracle", formatted);
+        // a reverted tx leaves the oracle unanswered, so it's cleared and the oracle
+        // answered again once its retry backoff elapses. the gas it burned still
+        // counts towards the budget
+        if let Some(receipt) = receipt
+            .as_ref()
+            .filter(|receipt| receipt.status != Some(U64::one()))
+        {
+            tracing::error!("answer tx 0x{:x} reverted", receipt.transaction_hash);
+            if let Some((fee, _)) = record_answer_tx_fee(context, &active_oracle, receipt).await {
+                tracing::warn!("paid {} for the reverted answer tx", fee);
             }
-        } else {
-            tracing::warn!("could not determine paid amount to answer oracle");
+            metrics::record_answer_tx(active_oracle.chain_id as u64, AnswerTxStatus::Failed);
+            events::emit(
+                active_oracle.chain_id as u64,
+                active_oracle.address.0,
+                OracleEventKind::Failed {
+                    reason: "answer transaction reverted".to_owned(),
+                },
+            );
+            reverts::record_revert(active_oracle.chain_id as u64, active_oracle.address.0);
+            let mut db_connection = db::blocking(|| context.db_connection_pool.get()).context(
+                "could not get database connection while trying to delete oracle's answer tx hash",
+            )?;
+            db::blocking(|| active_oracle.delete_answer_tx_hash(&mut db_connection))
+                .inspect_err(|_| raise_stuck_answer_tx(&active_oracle, receipt.transaction_hash))
+                .context("could not delete active answer transaction hash; the oracle resolution process is now stuck, ACT IMMEDIATELY")?;
+            let backoff = answer_retry_backoff(active_oracle.answer_attempts);
+            tracing::warn!("answering again in {}s", backoff.as_secs());
+            if let Err(error) = db::blocking(|| {
+                active_oracle.schedule_answer_retry(&mut db_connection, SystemTime::now() + backoff)
+            }) {
+                tracing::error!("{:#}", error);
+            }
+            return Ok(());
         }
 
-        let mut db_connection = match db_connection_pool
-            .get()
+        if receipt.is_some() {
+            metrics::record_answer_tx(active_oracle.chain_id as u64, AnswerTxStatus::Confirmed);
+        }
+
+        // a replacement tx might have been mined instead of the original one
+        let tx_hash = receipt
+            .as_ref()
+            .map(|receipt| receipt.transaction_hash)
+            .unwrap_or(tx_hash);
+        let mined_block_number = receipt.as_ref().and_then(|receipt| receipt.block_number);
+        let fee_usd = match receipt.as_ref() {
+            Some(receipt) => match record_answer_tx_fee(context, &active_oracle, receipt).await {
+                Some((fee, Some(fee_usd))) => {
+                    tracing::info!("paid {} ({:.4} usd) to answer oracle", fee, fee_usd);
+                    Some(fee_usd)
+                }
+                Some((fee, None)) => {
+                    tracing::info!("paid {} to answer oracle", fee);
+                    None
+                }
+                None => None,
+            },
+            None => {
+                tracing::warn!("could not determine paid amount to answer oracle");
+                None
+            }
+        };
+
+        metrics::observe_finalization(
+            active_oracle.chain_id as u64,
+            *active_oracle.measurement_timestamp,
+        );
+
+        let mut db_connection = match db::blocking(|| context.db_connection_pool.get())
             .context("could not get new connection from pool")
         {
             Ok(db_connection) => db_connection,
@@ -317,36 +1191,562 @@ async fn answer_active_oracle(
                 return Ok(());
             }
         };
-        if let Err(error) = active_oracle.delete(&mut db_connection) {
-            tracing::error!("could not delete oracle from database: {:#}", error);
+        let finalized_oracle = match (mined_block_number, active_oracle.expiration) {
+            (Some(block_number), Some(expiration)) => Some(FinalizedOracle {
+                address: active_oracle.address.0,
+                chain_id: active_oracle.chain_id as u64,
+                measurement_timestamp: *active_oracle.measurement_timestamp,
+                specification: active_oracle.specification.clone(),
+                expiration: *expiration,
+                tx_hash,
+                block_number,
+            }),
+            _ => None,
+        };
+        let (chain_id, address) = (active_oracle.chain_id as u64, active_oracle.address.0);
+        let proof_bundle = context.proof_publisher.is_some().then(|| {
+            (
+                ProofBundle::new(&active_oracle, answer, tx_hash, mined_block_number),
+                active_oracle.defillama_snapshot_id,
+            )
+        });
+        let answered_oracle_id = match db::blocking(|| {
+            active_oracle.archive_answered(&mut db_connection, tx_hash, receipt.as_ref(), fee_usd)
+        }) {
+            Ok(answered_oracle_id) => answered_oracle_id,
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                return Ok(());
+            }
+        };
+        events::emit(
+            chain_id,
+            address,
+            OracleEventKind::Finalized {
+                tx_hash: Some(format!("0x{:x}", tx_hash)),
+            },
+        );
+        reverts::record_finalization(chain_id, address);
+
+        tracing::info!("oracle successfully finalized with value {}", answer);
+
+        if let Some(((proof_bundle, defillama_snapshot_id), proof_publisher)) =
+            proof_bundle.zip(context.proof_publisher.clone())
+        {
+            tokio::spawn(
+                async move {
+                    if let Err(error) = proof_publisher
+                        .publish(proof_bundle, defillama_snapshot_id, answered_oracle_id)
+                        .await
+                    {
+                        tracing::error!("could not publish proof bundle: {:#}", error);
+                    }
+                }
+                .instrument(tracing::Span::current()),
+            );
+        }
+
+        // the oracle row is gone at this point, so a reorg dropping the answer tx
+        // would otherwise go unnoticed
+        if let Some(finalized_oracle) = finalized_oracle {
+            tokio::spawn(
+                watch_for_reorg(
+                    finalized_oracle,
+                    context.reorg_confirmation_blocks,
+                    context.signer.clone(),
+                    context.db_connection_pool.clone(),
+                )
+                .instrument(tracing::Span::current()),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// an approved override replaces the oracle's saved answer so that it's picked up by the
+// regular submission flow, while a pending one holds the oracle back until it's either
+// approved or replaced. returns whether the oracle can be answered
+fn apply_answer_override(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    active_oracle: &mut ActiveOracle,
+) -> anyhow::Result<bool> {
+    let mut db_connection = db_connection_pool
+        .get()
+        .context("could not get new connection from pool")?;
+    let answer_override = match models::AnswerOverride::get(
+        &mut db_connection,
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+    )? {
+        Some(answer_override) => answer_override,
+        None => return Ok(true),
+    };
+    if !answer_override.is_approved() {
+        return Ok(false);
+    }
+
+    let previous_answer = active_oracle.answer.as_ref().map(|answer| answer.0);
+    if previous_answer == Some(answer_override.answer.0) {
+        return Ok(true);
+    }
+
+    tracing::info!(
+        "applying answer override {} proposed by {} and approved by {}",
+        answer_override.answer.0,
+        answer_override.proposed_by,
+        answer_override.approved_by.as_deref().unwrap_or_default()
+    );
+    db_connection.transaction(|db_connection| {
+        active_oracle.update_answer(db_connection, answer_override.answer.0)?;
+        models::AuditLogEntry::create(
+            db_connection,
+            active_oracle.chain_id as u64,
+            active_oracle.address.0,
+            models::AuditAction::AnswerOverrideApplied,
+            "answerer",
+            serde_json::json!({
+                "answer": answer_override.answer.0.to_string(),
+                "previous_answer": previous_answer.map(|answer| answer.to_string()),
+            }),
+        )?;
+        Ok(true)
+    })
+}
+
+// records what an answer tx cost, reverted or not, so that it counts towards the gas
+// budget and shows up in the cost records. returns the formatted fee and its usd
+// value when known
+async fn record_answer_tx_fee(
+    context: &AnsweringContext,
+    active_oracle: &ActiveOracle,
+    receipt: &TransactionReceipt,
+) -> Option<(String, Option<f64>)> {
+    let (Some(gas_used), Some(effective_gas_price)) =
+        (receipt.gas_used, receipt.effective_gas_price)
+    else {
+        return None;
+    };
+    // assuming it's always 18 decimals
+    let fee = gas_used * effective_gas_price;
+    let formatted = match utils::format_units(fee, 18) {
+        Ok(formatted) => formatted,
+        Err(error) => {
+            tracing::error!("could not format units for raw fee {}: {:#}", fee, error);
+            return None;
+        }
+    };
+    let fee_usd = match context.native_token_price_feed.as_ref() {
+        Some(native_token_price_feed) => match native_token_price_feed.fetch_usd_price().await {
+            Ok(price) => formatted.parse::<f64>().ok().map(|fee| fee * price),
+            Err(error) => {
+                tracing::warn!("could not fetch native token usd price: {:#}", error);
+                None
+            }
+        },
+        None => None,
+    };
+    // costs are reconciled per campaign, so the kpi token is stored too
+    let kpi_token_address =
+        match fetch_kpi_token_address(context.signer.clone(), active_oracle.address.0).await {
+            Ok(kpi_token_address) => Some(kpi_token_address),
+            Err(error) => {
+                tracing::warn!("{:#}", error);
+                None
+            }
+        };
+    match db::blocking(|| context.db_connection_pool.get())
+        .context("could not get new connection from pool")
+    {
+        Ok(mut db_connection) => {
+            if let Err(error) = db::blocking(|| {
+                models::GasSpending::create(
+                    &mut db_connection,
+                    active_oracle.chain_id as u64,
+                    active_oracle.address.0,
+                    kpi_token_address,
+                    receipt,
+                    fee_usd,
+                )
+            }) {
+                tracing::error!("{:#}", error);
+            }
+        }
+        Err(error) => {
+            tracing::error!(
+                "could not get database connection while trying to record gas spending: {:#}",
+                error
+            );
+        }
+    };
+    metrics::record_answer_fee(
+        active_oracle.chain_id as u64,
+        gas_used.low_u64(),
+        formatted.parse::<f64>().unwrap_or_default(),
+        fee_usd,
+    );
+    Some((formatted, fee_usd))
+}
+
+// raised when an answer tx hash can't be cleared after the tx failed, which leaves
+// the oracle waiting for a tx that will never be mined
+fn raise_stuck_answer_tx(active_oracle: &ActiveOracle, tx_hash: H256) {
+    alerts::raise(
+        active_oracle.chain_id as u64,
+        AlertKind::StuckAnswerTx {
+            oracle_address: active_oracle.address.0,
+            tx_hash,
+        },
+    );
+}
+
+// delisted protocols never come back in most cases, so instead of retrying on
+// every tick the data is only checked again once in a while
+fn handle_missing_source(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    active_oracle: &mut ActiveOracle,
+) {
+    match active_oracle.source_missing_since {
+        Some(_) => tracing::warn!(
+            "data still missing from defillama, checking again in {}s",
+            SOURCE_MISSING_RECHECK_INTERVAL.as_secs()
+        ),
+        None => tracing::error!(
+            "data missing from defillama, the protocol might have been delisted - ACT IMMEDIATELY"
+        ),
+    }
+
+    let mut db_connection = match db_connection_pool
+        .get()
+        .context("could not get new connection from pool")
+    {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!(
+                "could not get database connection while trying to mark oracle's source as missing: {:#}",
+                error
+            );
+            return;
+        }
+    };
+    if let Err(error) = active_oracle.mark_source_missing(
+        &mut db_connection,
+        SystemTime::now() + SOURCE_MISSING_RECHECK_INTERVAL,
+    ) {
+        tracing::error!("{:#}", error);
+    }
+}
+
+fn apply_source_missing_fallback(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    source_missing_fallback_window: Option<Duration>,
+    active_oracle: &mut ActiveOracle,
+) -> anyhow::Result<()> {
+    if active_oracle.answer.is_some() || active_oracle.source_missing_since.is_none() {
+        return Ok(());
+    }
+    let (window, expiration) = match (source_missing_fallback_window, active_oracle.expiration) {
+        (Some(window), Some(expiration)) => (window, *expiration),
+        _ => return Ok(()),
+    };
+    if SystemTime::now() + window < expiration {
+        return Ok(());
+    }
+    let fallback = match specification::fallback(&active_oracle.specification) {
+        Some(fallback) => fallback,
+        None => {
+            tracing::warn!("oracle close to expiration with missing data and no fallback value");
             return Ok(());
         }
+    };
 
-        tracing::info!("oracle successfully finalized with value {}", answer);
+    tracing::warn!(
+        "oracle close to expiration with missing data, answering with fallback value {}",
+        fallback
+    );
+    let mut db_connection = db_connection_pool
+        .get()
+        .context("could not get new connection from pool")?;
+    active_oracle.update_answer(&mut db_connection, fallback)
+}
+
+// letting an oracle expire unanswered is the worst outcome for the campaign, so when
+// no answer can be computed this close to the expiration the template's invalid
+// answer is saved and submitted on the next run instead. returns whether it was saved
+fn apply_invalid_answer(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    invalid_answer_window: Option<Duration>,
+    active_oracle: &mut ActiveOracle,
+) -> bool {
+    if active_oracle.answer.is_some() {
+        return false;
+    }
+    let (window, expiration) = match (invalid_answer_window, active_oracle.expiration) {
+        (Some(window), Some(expiration)) => (window, *expiration),
+        _ => return false,
+    };
+    if SystemTime::now() + window < expiration {
+        return false;
     }
 
+    tracing::error!(
+        "oracle close to expiration with no answer available, answering with the invalid answer - ACT IMMEDIATELY"
+    );
+    let mut db_connection = match db_connection_pool
+        .get()
+        .context("could not get new connection from pool")
+    {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!(
+                "could not get database connection while trying to save the invalid answer: {:#}",
+                error
+            );
+            return false;
+        }
+    };
+    if let Err(error) = active_oracle.update_answer(&mut db_connection, INVALID_ANSWER) {
+        tracing::error!("{:#}", error);
+        return false;
+    }
+    events::emit(
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+        OracleEventKind::AnswerComputed {
+            answer: INVALID_ANSWER.to_string(),
+            defillama_snapshot_id: None,
+        },
+    );
+    true
+}
+
+// an oracle held for review is only answered again once an operator resolves it
+// with an approved answer override
+fn is_held_for_review(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    active_oracle: &ActiveOracle,
+) -> anyhow::Result<bool> {
+    let mut db_connection = db_connection_pool
+        .get()
+        .context("could not get new connection from pool")?;
+    let chain_id = active_oracle.chain_id as u64;
+    if models::AnswerReview::get(&mut db_connection, chain_id, active_oracle.address.0)?.is_none() {
+        return Ok(false);
+    }
+    Ok(
+        models::AnswerOverride::get(&mut db_connection, chain_id, active_oracle.address.0)?
+            .is_none_or(|answer_override| !answer_override.is_approved()),
+    )
+}
+
+fn hold_for_review(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    active_oracle: &ActiveOracle,
+    answer: U256,
+    reference_answer: U256,
+) {
+    let mut db_connection = match db_connection_pool
+        .get()
+        .context("could not get new connection from pool")
+    {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!(
+                "could not get database connection while trying to hold answer for review: {:#}",
+                error
+            );
+            return;
+        }
+    };
+    if let Err(error) = models::AnswerReview::create(
+        &mut db_connection,
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+        answer,
+        reference_answer,
+    ) {
+        tracing::error!("{:#}", error);
+    }
+}
+
+fn get_next_measurement_timestamp(
+    chain_id: u64,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Option<SystemTime> {
+    let mut db_connection = match db_connection_pool.get() {
+        Ok(connection) => connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return None;
+        }
+    };
+
+    match FeatureGates::load(&mut db_connection, chain_id) {
+        Ok(feature_gates) => {
+            if !feature_gates.is_enabled(Feature::ScheduledAnswering) {
+                return None;
+            }
+        }
+        Err(error) => {
+            tracing::error!(
+                "could not get feature gates for chain with id {}: {:#}",
+                chain_id,
+                error
+            );
+            return None;
+        }
+    }
+
+    match models::ActiveOracle::get_next_measurement_timestamp_for_chain_id(
+        &mut db_connection,
+        chain_id,
+    ) {
+        Ok(next_measurement_timestamp) => next_measurement_timestamp,
+        Err(error) => {
+            tracing::error!(
+                "could not get next measurement timestamp in chain with id {}: {:#}",
+                chain_id,
+                error
+            );
+            None
+        }
+    }
+}
+
+async fn sleep_until(timestamp: Option<SystemTime>) {
+    match timestamp {
+        Some(timestamp) => {
+            sleep(
+                timestamp
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default(),
+            )
+            .await
+        }
+        None => pending().await,
+    }
+}
+
+// failing to record a snapshot is not a reason to hold back an answer, so errors
+// are only logged here
+fn record_defillama_snapshot(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    active_oracle: &ActiveOracle,
+    defillama: &DefiLlamaClient,
+    answer: Option<U256>,
+) -> Option<i64> {
+    let mut db_connection = match db_connection_pool
+        .get()
+        .context("could not get new connection from pool")
+    {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!(
+                "could not get database connection while trying to record defillama snapshot: {:#}",
+                error
+            );
+            return None;
+        }
+    };
+    match models::DefiLlamaSnapshot::create(
+        &mut db_connection,
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+        active_oracle.specification.clone(),
+        defillama.recorded_responses(),
+        answer,
+    ) {
+        Ok(snapshot) => Some(snapshot.id),
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            None
+        }
+    }
+}
+
+fn check_gas_budget(
+    db_connection: &mut DbConnection,
+    chain_id: u64,
+    gas_budget: &GasBudgetConfig,
+) -> anyhow::Result<()> {
+    let now = SystemTime::now();
+    for (label, budget, window) in [
+        ("daily", gas_budget.daily, GAS_BUDGET_DAILY_WINDOW),
+        ("weekly", gas_budget.weekly, GAS_BUDGET_WEEKLY_WINDOW),
+    ] {
+        let budget = match budget {
+            Some(budget) => utils::parse_ether(budget)
+                .context(format!("could not parse {} gas budget {}", label, budget))?,
+            None => continue,
+        };
+        let spent = models::GasSpending::get_total_for_chain_id_since(
+            db_connection,
+            chain_id,
+            now - window,
+        )
+        .context(format!("could not get {} gas spending", label))?;
+        if spent >= budget {
+            gas_budget::record_exceeded(chain_id, label, window, budget, spent);
+            anyhow::bail!(
+                "{} gas budget of {} exceeded, {} already spent",
+                label,
+                utils::format_ether(budget),
+                utils::format_ether(spent)
+            );
+        }
+    }
     Ok(())
 }
 
+// answer txs are only resubmitted with the resubmit policy, the clear one giving up on
+// them after the first timeout
+fn resubmission_policy(receipt_timeout: &ReceiptTimeoutConfig) -> ResubmissionPolicy {
+    ResubmissionPolicy {
+        timeout: Duration::from_secs(receipt_timeout.timeout_seconds),
+        fee_bump_percentage: receipt_timeout
+            .fee_bump_percentage
+            .unwrap_or(ANSWER_TX_FEE_BUMP_PERCENTAGE),
+        max_resubmissions: match receipt_timeout.policy {
+            ReceiptTimeoutPolicy::Resubmit => receipt_timeout
+                .max_resubmissions
+                .unwrap_or(ANSWER_TX_MAX_RESUBMISSIONS),
+            ReceiptTimeoutPolicy::Clear => 0,
+        },
+    }
+}
+
+// exponential backoff on the number of previous attempts, capped to avoid
+// pushing retries beyond any reasonable expiration
+fn answer_retry_backoff(answer_attempts: i32) -> Duration {
+    let exponent = u32::try_from(answer_attempts).unwrap_or(0).min(16);
+    (ANSWER_RETRY_INITIAL_BACKOFF * 2u32.pow(exponent)).min(ANSWER_RETRY_MAX_BACKOFF)
+}
+
 async fn is_active_oracle_expired(
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    quorum_reader: &QuorumReader,
     active_oracle: &mut ActiveOracle,
 ) -> anyhow::Result<bool> {
     let expiration = match active_oracle.expiration {
-        Some(expiration) => expiration,
+        Some(expiration) => *expiration,
         None => {
-            let expiration =
-                fetch_active_oracle_expiration(signer.clone(), active_oracle.address.0)
-                    .await
-                    .context(format!(
-                        "could not fetch active oracle 0x{:x} expiration",
-                        active_oracle.address.0
-                    ))?;
-            let mut db_connection = db_connection_pool.get().context(format!(
-                "could not get database connection while trying to update oracle's expiration"
-            ))?;
-            active_oracle.update_expiration(&mut db_connection, expiration)?;
+            let address = active_oracle.address.0;
+            let expiration = fetch_active_oracle_expiration(signer.clone(), address)
+                .await
+                .context(format!(
+                    "could not fetch active oracle 0x{:x} expiration",
+                    address
+                ))?;
+            let expiration = quorum_reader
+                .confirm("expiration", expiration, |provider| {
+                    fetch_active_oracle_expiration(provider, address)
+                })
+                .await?;
+            let mut db_connection = db::blocking(|| db_connection_pool.get()).context(
+                "could not get database connection while trying to update oracle's expiration",
+            )?;
+            db::blocking(|| active_oracle.update_expiration(&mut db_connection, expiration))?;
             expiration
         }
     };
@@ -354,19 +1754,67 @@ async fn is_active_oracle_expired(
     Ok(expiration <= SystemTime::now())
 }
 
-async fn fetch_active_oracle_expiration(
-    signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+async fn fetch_active_oracle_expiration<M: Middleware + 'static>(
+    provider: Arc<M>,
     address: Address,
 ) -> anyhow::Result<SystemTime> {
-    let oracle = DefiLlamaOracle::new(address, signer.clone());
-    let kpi_token_address = oracle.kpi_token().call().await.context(format!(
-        "could not fetch kpi token address for oracle 0x{:x}",
-        address
-    ))?;
-    let kpi_token = KPIToken::new(kpi_token_address, signer.clone());
+    let kpi_token_address = fetch_kpi_token_address(provider.clone(), address).await?;
+    let kpi_token = KPIToken::new(kpi_token_address, provider);
     let expiration = kpi_token.expiration().call().await.context(format!(
         "could not fetch expiration timestamp for kpi token 0x{:x}",
         kpi_token_address
     ))?;
     Ok(UNIX_EPOCH + Duration::from_secs(expiration.as_u64()))
 }
+
+async fn fetch_kpi_token_address<M: Middleware + 'static>(
+    provider: Arc<M>,
+    address: Address,
+) -> anyhow::Result<Address> {
+    DefiLlamaOracle::new(address, provider)
+        .kpi_token()
+        .call()
+        .await
+        .context(format!(
+            "could not fetch kpi token address for oracle 0x{:x}",
+            address
+        ))
+}
+
+async fn is_active_oracle_finalized<M: Middleware + 'static>(
+    provider: Arc<M>,
+    address: Address,
+) -> anyhow::Result<bool> {
+    DefiLlamaOracle::new(address, provider)
+        .finalized()
+        .call()
+        .await
+        .context(format!(
+            "could not fetch finalization status for oracle 0x{:x}",
+            address
+        ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::commons::{ANSWER_RETRY_INITIAL_BACKOFF, ANSWER_RETRY_MAX_BACKOFF};
+
+    use super::answer_retry_backoff;
+
+    #[test]
+    fn answer_retry_backoff_growth() {
+        assert_eq!(answer_retry_backoff(0), ANSWER_RETRY_INITIAL_BACKOFF);
+        assert_eq!(answer_retry_backoff(1), ANSWER_RETRY_INITIAL_BACKOFF * 2);
+        assert_eq!(answer_retry_backoff(2), ANSWER_RETRY_INITIAL_BACKOFF * 4);
+
+        // negative attempts (should never happen) are treated as no attempts
+        assert_eq!(answer_retry_backoff(-1), ANSWER_RETRY_INITIAL_BACKOFF);
+
+        // the backoff is capped
+        assert_eq!(answer_retry_backoff(100), ANSWER_RETRY_MAX_BACKOFF);
+        assert!(answer_retry_backoff(6) <= ANSWER_RETRY_MAX_BACKOFF);
+        assert!(answer_retry_backoff(6) > Duration::ZERO);
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::{Duration, SystemTime};
+
+use ethers::types::U256;
+
+use crate::{
+    commons::{AnomalyDetectionConfig, ANOMALY_REFERENCE_AGE},
+    defillama::DefiLlamaClient,
+    specification::{self, Specification},
+};
+
+// returns the reference value the answer was compared against if the answer is
+// anomalous. failing to get a reference is not a reason to hold back an answer
+// forever, so in that case the answer is let through
+pub async fn detect_anomaly(
+    specification: &Specification,
+    defillama: &DefiLlamaClient,
+    anomaly_detection: &AnomalyDetectionConfig,
+    answer: U256,
+) -> Option<U256> {
+    let reference_age = anomaly_detection
+        .reference_age_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(ANOMALY_REFERENCE_AGE);
+    let reference =
+        match specification::reference(specification, defillama, SystemTime::now() - reference_age)
+            .await
+        {
+            Some(reference) => reference,
+            None => {
+                tracing::warn!("no reference value available, skipping anomaly detection");
+                return None;
+            }
+        };
+
+    if is_anomalous(answer, reference, anomaly_detection.max_change_percentage) {
+        Some(reference)
+    } else {
+        None
+    }
+}
+
+fn is_anomalous(answer: U256, reference: U256, max_change_percentage: f64) -> bool {
+    let change = if answer > reference {
+        answer - reference
+    } else {
+        reference - answer
+    };
+
+    // the change is compared in basis points to stay in integer math
+    let max_change_bps = U256::from((max_change_percentage * 100.0).max(0.0) as u64);
+    change.saturating_mul(U256::from(10_000)) > reference.saturating_mul(max_change_bps)
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::U256;
+
+    use super::is_anomalous;
+
+    #[test]
+    fn is_anomalous_change() {
+        // a change of 50 on a reference of 100 is exactly 50%
+        assert!(!is_anomalous(U256::from(150), U256::from(100), 50.0));
+        assert!(!is_anomalous(U256::from(50), U256::from(100), 50.0));
+        assert!(is_anomalous(U256::from(151), U256::from(100), 50.0));
+        assert!(is_anomalous(U256::from(49), U256::from(100), 50.0));
+
+        // any change from a zero reference is anomalous
+        assert!(is_anomalous(U256::from(1), U256::zero(), 50.0));
+        assert!(!is_anomalous(U256::zero(), U256::zero(), 0.0));
+
+        assert!(!is_anomalous(U256::MAX, U256::MAX, 0.0));
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use ethers::{
+    middleware::{Middleware, SignerMiddleware},
+    providers::Provider,
+    signers::Signer,
+    types::U256,
+    utils,
+};
+use tokio::time::interval;
+
+use crate::{
+    alerts::{self, AlertKind},
+    rpc::FallbackHttp,
+    signer::AnswererSigner,
+};
+
+pub struct AnswererBalance {
+    threshold: U256,
+    balance: RwLock<Option<U256>>,
+}
+
+impl AnswererBalance {
+    pub fn new(threshold: U256) -> Self {
+        Self {
+            threshold,
+            balance: RwLock::new(None),
+        }
+    }
+
+    pub fn threshold(&self) -> U256 {
+        self.threshold
+    }
+
+    pub fn get(&self) -> Option<U256> {
+        *self.balance.read().unwrap()
+    }
+
+    pub fn update(&self, balance: U256) {
+        *self.balance.write().unwrap() = Some(balance);
+    }
+
+    // an unknown balance (i.e. not checked yet) is not considered low so that
+    // answering isn't blocked on a single failed balance check at startup
+    pub fn is_low(&self) -> bool {
+        self.get()
+            .map(|balance| balance < self.threshold)
+            .unwrap_or(false)
+    }
+}
+
+// alerts are only raised when the balance goes below the threshold or back above it,
+// rather than on every check
+pub async fn monitor_answerer_balance(
+    chain_id: u64,
+    check_interval: Duration,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    answerer_balance: Arc<AnswererBalance>,
+) -> anyhow::Result<()> {
+    let address = signer.signer().address();
+    let mut interval = interval(check_interval);
+
+    tracing::info!(
+        "checking answerer 0x{:x} balance every {}s with a {} threshold",
+        address,
+        check_interval.as_secs(),
+        utils::format_ether(answerer_balance.threshold())
+    );
+
+    loop {
+        interval.tick().await;
+
+        let balance = match signer.get_balance(address, None).await {
+            Ok(balance) => balance,
+            Err(error) => {
+                tracing::error!(
+                    "could not get answerer 0x{:x} balance: {:#}",
+                    address,
+                    error
+                );
+                continue;
+            }
+        };
+        let was_low = answerer_balance.is_low();
+        answerer_balance.update(balance);
+
+        if answerer_balance.is_low() {
+            tracing::warn!(
+                "answerer 0x{:x} balance {} is below the {} threshold, top it up",
+                address,
+                utils::format_ether(balance),
+                utils::format_ether(answerer_balance.threshold())
+            );
+            if !was_low {
+                alerts::raise(
+                    chain_id,
+                    AlertKind::LowBalance {
+                        answerer: address,
+                        balance,
+                        threshold: answerer_balance.threshold(),
+                    },
+                );
+            }
+        } else if was_low {
+            alerts::raise(
+                chain_id,
+                AlertKind::BalanceRestored {
+                    answerer: address,
+                    balance,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::U256;
+
+    use super::AnswererBalance;
+
+    #[test]
+    fn is_low() {
+        let answerer_balance = AnswererBalance::new(U256::from(100));
+
+        // unknown balance
+        assert!(!answerer_balance.is_low());
+
+        answerer_balance.update(U256::from(99));
+        assert!(answerer_balance.is_low());
+
+        answerer_balance.update(U256::from(100));
+        assert!(!answerer_balance.is_low());
+
+        answerer_balance.update(U256::from(101));
+        assert!(!answerer_balance.is_low());
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use answerer_framework::AnswerComputer;
+use async_trait::async_trait;
+use ethers::types::U256;
+
+use crate::{
+    archive::{ArchiveNode, HistoricalState},
+    commons::AnswerSamplingConfig,
+    db::models::ActiveOracle,
+    defillama::DefiLlamaClient,
+    specification,
+};
+
+use super::sampling::sample_answer;
+
+// answers oracles from their specification through defillama, sampling the metric
+// multiple times when configured. built for every answer, so that the responses
+// recorded by the client can be linked to it
+pub struct DefiLlamaAnswerComputer {
+    pub defillama: DefiLlamaClient,
+    pub archive_node: Option<Arc<ArchiveNode>>,
+    pub answer_sampling: Option<AnswerSamplingConfig>,
+}
+
+#[async_trait]
+impl AnswerComputer for DefiLlamaAnswerComputer {
+    type Oracle = ActiveOracle;
+
+    async fn compute_answer(&self, oracle: &ActiveOracle) -> anyhow::Result<Option<U256>> {
+        // answers reading on-chain state do so at the measurement timestamp
+        let historical_state = self
+            .archive_node
+            .clone()
+            .map(|archive_node| HistoricalState::new(archive_node, *oracle.measurement_timestamp));
+        match &self.answer_sampling {
+            Some(answer_sampling) => {
+                sample_answer(
+                    &oracle.specification,
+                    &self.defillama,
+                    historical_state.as_ref(),
+                    answer_sampling,
+                )
+                .await
+            }
+            None => {
+                specification::answer(
+                    &oracle.specification,
+                    &self.defillama,
+                    historical_state.as_ref(),
+                )
+                .await
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::db::models::{ActiveOracle, AnswerOverride, AnswerReview};
+
+// mirrors the checks the answering flow goes through, in the same order, so that it's
+// possible to tell why an oracle hasn't been answered yet without digging in the logs
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NextAction {
+    #[serde(rename_all = "camelCase")]
+    WaitForMeasurementTimestamp {
+        measurement_timestamp: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    WaitForRetryBackoff {
+        next_answer_attempt: u64,
+        answer_attempts: i32,
+    },
+    #[serde(rename_all = "camelCase")]
+    WaitForSource {
+        source_missing_since: u64,
+        next_check: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    WaitForPendingTx {
+        tx_hash: String,
+        since: Option<u64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeleteExpired {
+        expiration: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    WaitForOverrideApproval {
+        proposed_by: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SubmitOverride {
+        answer: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    WaitForReview {
+        answer: String,
+        reference_answer: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SubmitSavedAnswer {
+        answer: String,
+    },
+    ComputeAnswer,
+}
+
+impl NextAction {
+    // the same as the serialized type
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NextAction::WaitForMeasurementTimestamp { .. } => "waitForMeasurementTimestamp",
+            NextAction::WaitForRetryBackoff { .. } => "waitForRetryBackoff",
+            NextAction::WaitForSource { .. } => "waitForSource",
+            NextAction::WaitForPendingTx { .. } => "waitForPendingTx",
+            NextAction::DeleteExpired { .. } => "deleteExpired",
+            NextAction::WaitForOverrideApproval { .. } => "waitForOverrideApproval",
+            NextAction::SubmitOverride { .. } => "submitOverride",
+            NextAction::WaitForReview { .. } => "waitForReview",
+            NextAction::SubmitSavedAnswer { .. } => "submitSavedAnswer",
+            NextAction::ComputeAnswer => "computeAnswer",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            NextAction::WaitForMeasurementTimestamp {
+                measurement_timestamp,
+            } => format!(
+                "waiting for the measurement timestamp {}",
+                measurement_timestamp
+            ),
+            NextAction::WaitForRetryBackoff {
+                next_answer_attempt,
+                answer_attempts,
+            } => format!(
+                "backing off until {} after {} failed answer attempts",
+                next_answer_attempt, answer_attempts
+            ),
+            NextAction::WaitForSource {
+                source_missing_since,
+                next_check,
+            } => format!(
+                "data missing from defillama since {}, checking again at {}",
+                source_missing_since, next_check
+            ),
+            NextAction::WaitForPendingTx { tx_hash, since } => match since {
+                Some(since) => format!("answer tx {} pending since {}", tx_hash, since),
+                None => format!("answer tx {} pending", tx_hash),
+            },
+            NextAction::DeleteExpired { expiration } => {
+                format!("deleting the oracle as it expired at {}", expiration)
+            }
+            NextAction::WaitForOverrideApproval { proposed_by } => format!(
+                "waiting for the approval of the answer override proposed by {}",
+                proposed_by
+            ),
+            NextAction::SubmitOverride { answer } => {
+                format!("submitting the approved answer override {}", answer)
+            }
+            NextAction::WaitForReview {
+                answer,
+                reference_answer,
+            } => format!(
+                "anomalous answer {} against reference {} held for review, resolve it with an answer override",
+                answer, reference_answer
+            ),
+            NextAction::SubmitSavedAnswer { answer } => {
+                format!("submitting the previously computed answer {}", answer)
+            }
+            NextAction::ComputeAnswer => "computing and submitting the answer".to_owned(),
+        }
+    }
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn next_action(
+    active_oracle: &ActiveOracle,
+    answer_override: Option<&AnswerOverride>,
+    answer_review: Option<&AnswerReview>,
+    now: SystemTime,
+) -> NextAction {
+    if *active_oracle.measurement_timestamp >= now {
+        return NextAction::WaitForMeasurementTimestamp {
+            measurement_timestamp: to_unix_timestamp(*active_oracle.measurement_timestamp),
+        };
+    }
+
+    if let Some(next_answer_attempt) = active_oracle
+        .next_answer_attempt
+        .map(|timestamp| *timestamp)
+    {
+        if next_answer_attempt > now {
+            if let Some(source_missing_since) = active_oracle.source_missing_since {
+                return NextAction::WaitForSource {
+                    source_missing_since: to_unix_timestamp(*source_missing_since),
+                    next_check: to_unix_timestamp(next_answer_attempt),
+                };
+            }
+            return NextAction::WaitForRetryBackoff {
+                next_answer_attempt: to_unix_timestamp(next_answer_attempt),
+                answer_attempts: active_oracle.answer_attempts,
+            };
+        }
+    }
+
+    if let Some(answer_tx_hash) = &active_oracle.answer_tx_hash {
+        return NextAction::WaitForPendingTx {
+            tx_hash: format!("0x{:x}", answer_tx_hash.0),
+            since: active_oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+        };
+    }
+
+    if let Some(expiration) = active_oracle.expiration.map(|timestamp| *timestamp) {
+        if expiration <= now {
+            return NextAction::DeleteExpired {
+                expiration: to_unix_timestamp(expiration),
+            };
+        }
+    }
+
+    if let Some(answer_override) = answer_override {
+        if !answer_override.is_approved() {
+            return NextAction::WaitForOverrideApproval {
+                proposed_by: answer_override.proposed_by.clone(),
+            };
+        }
+        return NextAction::SubmitOverride {
+            answer: answer_override.answer.0.to_string(),
+        };
+    }
+
+    if let Some(answer_review) = answer_review {
+        return NextAction::WaitForReview {
+            answer: answer_review.answer.0.to_string(),
+            reference_answer: answer_review.reference_answer.0.to_string(),
+        };
+    }
+
+    match &active_oracle.answer {
+        Some(answer) => NextAction::SubmitSavedAnswer {
+            answer: answer.0.to_string(),
+        },
+        None => NextAction::ComputeAnswer,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use ethers::types::{Address, H256, U256};
+
+    use crate::{
+        db::{
+            models::{ActiveOracle, AnswerOverride, AnswerReview},
+            DbAddress, DbTimestamp, DbTxHash, DbU256,
+        },
+        specification::{handlers::tvl::TvlPayload, Specification},
+    };
+
+    use super::{next_action, NextAction};
+
+    fn active_oracle(now: SystemTime) -> ActiveOracle {
+        ActiveOracle {
+            address: DbAddress(Address::zero()),
+            chain_id: 100,
+            measurement_timestamp: DbTimestamp(now - Duration::from_secs(10)),
+            specification: Specification::Tvl(TvlPayload {
+                protocol: "foo".to_owned(),
+                fallback: None,
+            }),
+            expiration: Some(DbTimestamp(now + Duration::from_secs(10))),
+            answer_tx_hash: None,
+            answer: None,
+            answer_attempts: 0,
+            next_answer_attempt: None,
+            answer_tx_submitted_at: None,
+            source_missing_since: None,
+            defillama_snapshot_id: None,
+        }
+    }
+
+    fn answer_override(approved_by: Option<&str>) -> AnswerOverride {
+        AnswerOverride {
+            chain_id: 100,
+            oracle_address: DbAddress(Address::zero()),
+            answer: DbU256(U256::from(42)),
+            reason: "wrong tvl".to_owned(),
+            proposed_by: "alice".to_owned(),
+            proposed_at: DbTimestamp(UNIX_EPOCH),
+            approved_by: approved_by.map(str::to_owned),
+            approved_at: approved_by.map(|_| DbTimestamp(UNIX_EPOCH)),
+        }
+    }
+
+    fn answer_review() -> AnswerReview {
+        AnswerReview {
+            chain_id: 100,
+            oracle_address: DbAddress(Address::zero()),
+            answer: DbU256(U256::from(1_000)),
+            reference_answer: DbU256(U256::from(10)),
+            created_at: DbTimestamp(UNIX_EPOCH),
+        }
+    }
+
+    #[test]
+    fn next_action_order() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+
+        let mut oracle = active_oracle(now);
+        assert_eq!(
+            next_action(&oracle, None, None, now),
+            NextAction::ComputeAnswer
+        );
+
+        oracle.answer = Some(DbU256(U256::from(10)));
+        assert_eq!(
+            next_action(&oracle, None, None, now),
+            NextAction::SubmitSavedAnswer {
+                answer: "10".to_owned()
+            }
+        );
+
+        assert_eq!(
+            next_action(&oracle, None, Some(&answer_review()), now),
+            NextAction::WaitForReview {
+                answer: "1000".to_owned(),
+                reference_answer: "10".to_owned()
+            }
+        );
+
+        // overrides take precedence over saved answers and reviews
+        assert_eq!(
+            next_action(
+                &oracle,
+                Some(&answer_override(Some("bob"))),
+                Some(&answer_review()),
+                now
+            ),
+            NextAction::SubmitOverride {
+                answer: "42".to_owned()
+            }
+        );
+        assert_eq!(
+            next_action(&oracle, Some(&answer_override(Some("bob"))), None, now),
+            NextAction::SubmitOverride {
+                answer: "42".to_owned()
+            }
+        );
+        assert_eq!(
+            next_action(&oracle, Some(&answer_override(None)), None, now),
+            NextAction::WaitForOverrideApproval {
+                proposed_by: "alice".to_owned()
+            }
+        );
+
+        oracle.expiration = Some(DbTimestamp(now - Duration::from_secs(1)));
+        assert_eq!(
+            next_action(&oracle, Some(&answer_override(None)), None, now),
+            NextAction::DeleteExpired { expiration: 999 }
+        );
+
+        oracle.answer_tx_hash = Some(DbTxHash(H256::zero()));
+        oracle.answer_tx_submitted_at = Some(DbTimestamp(now - Duration::from_secs(100)));
+        assert_eq!(
+            next_action(&oracle, None, None, now),
+            NextAction::WaitForPendingTx {
+                tx_hash: format!("0x{:x}", H256::zero()),
+                since: Some(900)
+            }
+        );
+
+        oracle.answer_attempts = 2;
+        oracle.next_answer_attempt = Some(DbTimestamp(now + Duration::from_secs(60)));
+        assert_eq!(
+            next_action(&oracle, None, None, now),
+            NextAction::WaitForRetryBackoff {
+                next_answer_attempt: 1_060,
+                answer_attempts: 2
+            }
+        );
+
+        oracle.source_missing_since = Some(DbTimestamp(now - Duration::from_secs(200)));
+        assert_eq!(
+            next_action(&oracle, None, None, now),
+            NextAction::WaitForSource {
+                source_missing_since: 800,
+                next_check: 1_060
+            }
+        );
+
+        oracle.measurement_timestamp = DbTimestamp(now + Duration::from_secs(30));
+        assert_eq!(
+            next_action(&oracle, None, None, now),
+            NextAction::WaitForMeasurementTimestamp {
+                measurement_timestamp: 1_030
+            }
+        );
+    }
+}
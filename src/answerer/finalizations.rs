@@ -0,0 +1,153 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::{
+    contract::Multicall, middleware::SignerMiddleware, providers::Provider, types::Address,
+};
+use tokio::time::interval;
+
+use crate::{
+    commons::ANSWER_CLAIM_DURATION,
+    contracts::defi_llama_oracle::DefiLlamaOracle,
+    db::{
+        self,
+        models::{self, ArchiveReason},
+        DbConnection,
+    },
+    events::{self, OracleEventKind},
+    quorum::QuorumReader,
+    rpc::FallbackHttp,
+    signer::AnswererSigner,
+};
+
+use super::reverts;
+
+// how many finalization statuses are fetched with a single multicall
+const FINALIZATION_STATUSES_BATCH_SIZE: usize = 50;
+
+// oracles finalized by another party (e.g. a manual operator or another answerer
+// deployment) are otherwise only noticed right before answering them, after their
+// measurement timestamp. closing their records early keeps them out of the answering
+// runs and of anything else reading the active oracles
+pub async fn collect_finalized_oracles(
+    chain_id: u64,
+    check_interval: Duration,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    multicall_address: Option<Address>,
+    quorum_reader: Arc<QuorumReader>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut interval = interval(check_interval);
+
+    tracing::info!(
+        "collecting oracles finalized on-chain every {}s",
+        check_interval.as_secs()
+    );
+
+    loop {
+        interval.tick().await;
+
+        if let Err(error) = handle_finalized_oracles(
+            chain_id,
+            signer.clone(),
+            multicall_address,
+            &quorum_reader,
+            db_connection_pool.clone(),
+        )
+        .await
+        {
+            tracing::error!("error while collecting finalized oracles: {:#}", error);
+        }
+    }
+}
+
+async fn handle_finalized_oracles(
+    chain_id: u64,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    multicall_address: Option<Address>,
+    quorum_reader: &QuorumReader,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut db_connection = db::blocking(|| db_connection_pool.get())
+        .context("could not get new connection from pool")?;
+    // oracles with an answer tx are being finalized by us, and are taken care of by
+    // the answering task that submitted it
+    let active_oracles: Vec<_> =
+        db::blocking(|| models::ActiveOracle::get_all_for_chain_id(&mut db_connection, chain_id))
+            .context("could not get active oracles")?
+            .into_iter()
+            .filter(|active_oracle| active_oracle.answer_tx_hash.is_none())
+            .collect();
+    drop(db_connection);
+
+    let mut finalization_statuses = Vec::with_capacity(active_oracles.len());
+    let mut multicall =
+        Multicall::new_with_chain_id(signer.clone(), multicall_address, Some(chain_id))?;
+    for active_oracles in active_oracles.chunks(FINALIZATION_STATUSES_BATCH_SIZE) {
+        multicall.clear_calls();
+        for active_oracle in active_oracles.iter() {
+            multicall.add_call(
+                DefiLlamaOracle::new(active_oracle.address.0, signer.clone()).finalized(),
+                false,
+            );
+        }
+        finalization_statuses.extend(
+            multicall
+                .call_array::<bool>()
+                .await
+                .context("could not get finalization statuses")?,
+        );
+    }
+
+    for (active_oracle, finalized) in active_oracles.into_iter().zip(finalization_statuses) {
+        if !finalized {
+            continue;
+        }
+
+        let address = active_oracle.address.0;
+        if let Err(error) = quorum_reader
+            .confirm("finalization status", true, |provider| async move {
+                Ok(DefiLlamaOracle::new(address, provider)
+                    .finalized()
+                    .call()
+                    .await?)
+            })
+            .await
+        {
+            tracing::error!(
+                "could not confirm finalization status for oracle 0x{:x}, skipping: {:#}",
+                address,
+                error
+            );
+            continue;
+        }
+
+        let mut db_connection = db::blocking(|| db_connection_pool.get())
+            .context("could not get new connection from pool")?;
+        // an answering task currently handling the oracle holds its claim, and it will
+        // notice the finalization itself
+        if db::blocking(|| active_oracle.claim(&mut db_connection, ANSWER_CLAIM_DURATION))?
+            .is_none()
+        {
+            tracing::info!(
+                "oracle 0x{:x} claimed by an answering task, skipping",
+                address
+            );
+            continue;
+        }
+        tracing::warn!(
+            "oracle 0x{:x} was finalized on-chain by another party, archiving",
+            address
+        );
+        db::blocking(|| active_oracle.archive(&mut db_connection, ArchiveReason::Finalized))?;
+        reverts::record_finalization(chain_id, address);
+        events::emit(
+            chain_id,
+            address,
+            OracleEventKind::Finalized { tx_hash: None },
+        );
+    }
+
+    Ok(())
+}
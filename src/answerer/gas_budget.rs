@@ -0,0 +1,99 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use ethers::types::U256;
+
+use crate::alerts::{self, AlertKind};
+
+// when each chain's budgets were last alerted about, kept in memory only so that a
+// restart alerts again about budgets that are still exceeded
+static ALERTED_AT: LazyLock<Mutex<HashMap<(u64, &'static str), SystemTime>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// answers are held back on every answering run while the budget is exceeded, but
+// the alert only goes out once per budget window
+pub fn record_exceeded(
+    chain_id: u64,
+    period: &'static str,
+    window: Duration,
+    budget: U256,
+    spent: U256,
+) {
+    let now = SystemTime::now();
+    {
+        let mut alerted_at = ALERTED_AT.lock().unwrap();
+        match alerted_at.get(&(chain_id, period)) {
+            Some(last) if *last + window > now => return,
+            _ => alerted_at.insert((chain_id, period), now),
+        };
+    }
+    alerts::raise(
+        chain_id,
+        AlertKind::GasBudgetExceeded {
+            period,
+            budget,
+            spent,
+        },
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use ethers::types::{H256, U256};
+
+    use crate::alerts::{self, AlertKind, AlertSeverity};
+
+    use super::record_exceeded;
+
+    #[tokio::test]
+    async fn alert_once_per_window() {
+        let mut alerts = alerts::subscribe();
+        let chain_id = H256::random().to_low_u64_be();
+        for _ in 0..3 {
+            record_exceeded(
+                chain_id,
+                "daily",
+                Duration::from_secs(86_400),
+                U256::one(),
+                U256::from(2),
+            );
+        }
+        // an elapsed window alerts again
+        record_exceeded(
+            chain_id,
+            "weekly",
+            Duration::ZERO,
+            U256::one(),
+            U256::from(2),
+        );
+        record_exceeded(
+            chain_id,
+            "weekly",
+            Duration::ZERO,
+            U256::one(),
+            U256::from(3),
+        );
+
+        let mut alerted = Vec::new();
+        while let Ok(alert) = alerts.try_recv() {
+            if alert.chain_id == chain_id {
+                assert_eq!(alert.severity(), AlertSeverity::Critical);
+                alerted.push(alert.kind);
+            }
+        }
+        assert_eq!(alerted.len(), 3);
+        assert_eq!(
+            alerted[0],
+            AlertKind::GasBudgetExceeded {
+                period: "daily",
+                budget: U256::one(),
+                spent: U256::from(2),
+            }
+        );
+    }
+}
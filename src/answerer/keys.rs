@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use ethers::{
+    middleware::SignerMiddleware, providers::Provider, signers::Signer, types::Address, utils,
+};
+
+use crate::{rpc::FallbackHttp, signer::AnswererSigner};
+
+use super::balance::AnswererBalance;
+
+pub struct AnswererKey {
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    balance: Option<Arc<AnswererBalance>>,
+}
+
+impl AnswererKey {
+    pub fn new(
+        signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+        balance: Option<Arc<AnswererBalance>>,
+    ) -> Self {
+        Self { signer, balance }
+    }
+
+    pub fn signer(&self) -> Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>> {
+        self.signer.clone()
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.signer().address()
+    }
+
+    // missing when no minimum balance is configured, in which case it isn't monitored
+    pub fn balance(&self) -> Option<&AnswererBalance> {
+        self.balance.as_deref()
+    }
+
+    // answers aren't submitted from a key low on funds, as the tx would most likely
+    // fail with a cryptic error or leave nothing for the next ones
+    pub fn check_balance(&self) -> anyhow::Result<()> {
+        match self.balance.as_deref() {
+            Some(balance) if balance.is_low() => anyhow::bail!(
+                "answerer 0x{:x} balance {} is below the {} threshold",
+                self.address(),
+                utils::format_ether(balance.get().unwrap_or_default()),
+                utils::format_ether(balance.threshold())
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+// oracles only accept answers from their own answerer address, so each key serves
+// the oracles whose answerer it is. the first key is the primary one, used for
+// every read. there's no failover nor rotation between keys, as no other key
+// could answer the same oracle
+pub struct AnswererKeys {
+    keys: Vec<AnswererKey>,
+}
+
+impl AnswererKeys {
+    pub fn new(keys: Vec<AnswererKey>) -> Self {
+        assert!(!keys.is_empty(), "at least one answerer key is needed");
+        Self { keys }
+    }
+
+    pub fn primary(&self) -> Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>> {
+        self.keys[0].signer()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AnswererKey> {
+        self.keys.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // picks the key to submit an answer with, which is the primary one when the
+    // oracle's answerer is impersonated (i.e. in dev mode)
+    pub fn select(&self, answerer: Option<Address>) -> Option<&AnswererKey> {
+        match answerer {
+            Some(answerer) => self.keys.iter().find(|key| key.address() == answerer),
+            None => self.keys.first(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ethers::{
+        middleware::SignerMiddleware,
+        providers::Provider,
+        types::{Address, U256},
+    };
+
+    use crate::{answerer::balance::AnswererBalance, rpc::FallbackHttp, signer::AnswererSigner};
+
+    use super::{AnswererKey, AnswererKeys};
+
+    fn key(private_key: &str, balance: Option<Arc<AnswererBalance>>) -> AnswererKey {
+        let provider = Provider::new(
+            FallbackHttp::new(1, vec![("http://127.0.0.1:8545".to_owned(), None)], 1).unwrap(),
+        );
+        let signer = AnswererSigner::from_private_key(1, private_key).unwrap();
+        AnswererKey::new(Arc::new(SignerMiddleware::new(provider, signer)), balance)
+    }
+
+    #[test]
+    fn select() {
+        let keys = AnswererKeys::new(vec![
+            key(&format!("{:064x}", 1), None),
+            key(&format!("{:064x}", 2), None),
+        ]);
+        let primary = keys.keys[0].address();
+        let other = keys.keys[1].address();
+
+        assert_eq!(keys.select(None).unwrap().address(), primary);
+        assert_eq!(keys.select(Some(primary)).unwrap().address(), primary);
+        assert_eq!(keys.select(Some(other)).unwrap().address(), other);
+        // no key can answer oracles of other answerers
+        assert!(keys.select(Some(Address::random())).is_none());
+    }
+
+    #[test]
+    fn check_balance() {
+        // unmonitored balances never prevent answering
+        assert!(key(&format!("{:064x}", 1), None).check_balance().is_ok());
+
+        let balance = Arc::new(AnswererBalance::new(U256::from(100)));
+        let key = key(&format!("{:064x}", 1), Some(balance.clone()));
+        // nor do balances that weren't checked yet
+        assert!(key.check_balance().is_ok());
+
+        balance.update(U256::from(100));
+        assert!(key.check_balance().is_ok());
+
+        balance.update(U256::from(99));
+        let error = key.check_balance().unwrap_err().to_string();
+        assert!(error.contains(&format!("0x{:x}", key.address())));
+    }
+}
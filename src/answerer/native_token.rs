@@ -0,0 +1,88 @@
+use anyhow::Context;
+
+use crate::defillama::DefiLlamaClient;
+
+// coingecko ids of the native tokens of well-known chains. testnets map to their
+// mainnet counterpart so that fees can still be compared
+pub fn default_coingecko_id(chain_id: u64) -> Option<&'static str> {
+    match chain_id {
+        1 | 10 | 8453 | 42161 | 534352 | 11155111 | 534351 => Some("ethereum"),
+        100 | 10200 => Some("xdai"),
+        137 | 80001 => Some("matic-network"),
+        56 => Some("binancecoin"),
+        _ => None,
+    }
+}
+
+pub struct NativeTokenPriceFeed {
+    defillama: DefiLlamaClient,
+    coingecko_id: String,
+}
+
+impl NativeTokenPriceFeed {
+    pub fn new(defillama: DefiLlamaClient, coingecko_id: String) -> Self {
+        Self {
+            defillama,
+            coingecko_id,
+        }
+    }
+
+    pub async fn fetch_usd_price(&self) -> anyhow::Result<f64> {
+        let key = format!("coingecko:{}", self.coingecko_id);
+        Ok(self
+            .defillama
+            .current_prices(std::slice::from_ref(&key))
+            .await?
+            .remove(&key)
+            .context(format!("no current price available for {}", key))?
+            .price)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::defillama::DefiLlamaClient;
+
+    use super::NativeTokenPriceFeed;
+
+    #[tokio::test]
+    async fn fetch_usd_price() {
+        let coins_mock_server = MockServer::start().await;
+        let price_feed = NativeTokenPriceFeed::new(
+            DefiLlamaClient::mock(coins_mock_server.uri()),
+            "xdai".to_owned(),
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/prices/current/coingecko:xdai"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"coins":{"coingecko:xdai":{"price":0.999,"symbol":"XDAI","timestamp":1697500000,"confidence":0.99}}}"#,
+            ))
+            .mount(&coins_mock_server)
+            .await;
+        assert_eq!(price_feed.fetch_usd_price().await.unwrap(), 0.999);
+
+        // missing coin
+        coins_mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/prices/current/coingecko:xdai"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"coins":{}}"#))
+            .mount(&coins_mock_server)
+            .await;
+        assert!(price_feed.fetch_usd_price().await.is_err());
+
+        // upstream error
+        coins_mock_server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/prices/current/coingecko:xdai"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&coins_mock_server)
+            .await;
+        assert!(price_feed.fetch_usd_price().await.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::{
+    middleware::{Middleware, SignerMiddleware},
+    providers::Provider,
+};
+use tokio::time::interval;
+
+use crate::{
+    alerts::{self, AlertKind},
+    commons::ANSWER_CLAIM_DURATION,
+    db::{self, models, DbConnection},
+    metrics,
+    rpc::FallbackHttp,
+    signer::AnswererSigner,
+};
+
+// answer tx hashes are only cleared by the answering task that submitted them, so a
+// crash or a dropped transaction would otherwise leave the oracle stuck forever
+pub async fn collect_orphaned_answer_txs(
+    chain_id: u64,
+    check_interval: Duration,
+    threshold: Duration,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut interval = interval(check_interval);
+
+    tracing::info!(
+        "collecting answer txs pending for more than {}s every {}s",
+        threshold.as_secs(),
+        check_interval.as_secs()
+    );
+
+    loop {
+        interval.tick().await;
+
+        if let Err(error) = handle_orphaned_answer_txs(
+            chain_id,
+            threshold,
+            signer.clone(),
+            db_connection_pool.clone(),
+        )
+        .await
+        {
+            tracing::error!("error while collecting orphaned answer txs: {:#}", error);
+        }
+    }
+}
+
+async fn handle_orphaned_answer_txs(
+    chain_id: u64,
+    threshold: Duration,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut db_connection = db::blocking(|| db_connection_pool.get())
+        .context("could not get new connection from pool")?;
+    let active_oracles = db::blocking(|| {
+        models::ActiveOracle::get_all_with_answer_tx_submitted_before(
+            &mut db_connection,
+            chain_id,
+            SystemTime::now() - threshold,
+        )
+    })
+    .context("could not get active oracles with a pending answer tx")?;
+
+    for mut active_oracle in active_oracles.into_iter() {
+        let tx_hash = match &active_oracle.answer_tx_hash {
+            Some(tx_hash) => tx_hash.0,
+            None => continue,
+        };
+
+        match signer.get_transaction(tx_hash).await {
+            Ok(Some(tx)) => {
+                if tx.block_number.is_none() {
+                    tracing::warn!(
+                        "answer tx 0x{:x} for oracle 0x{:x} still pending in the mempool",
+                        tx_hash,
+                        active_oracle.address.0
+                    );
+                    continue;
+                }
+                // the answering task died before handling the receipt. clearing the
+                // hash lets the next answering run see the oracle as finalized and
+                // delete it, or answer it again if the tx reverted
+                tracing::warn!(
+                    "answer tx 0x{:x} for oracle 0x{:x} was mined but never handled, clearing it",
+                    tx_hash,
+                    active_oracle.address.0
+                );
+            }
+            Ok(None) => {
+                tracing::error!(
+                    "answer tx 0x{:x} for oracle 0x{:x} is neither mined nor in the mempool, clearing it, ACT IMMEDIATELY",
+                    tx_hash,
+                    active_oracle.address.0
+                );
+                metrics::record_orphaned_answer_tx(chain_id);
+                alerts::raise(
+                    chain_id,
+                    AlertKind::StuckAnswerTx {
+                        oracle_address: active_oracle.address.0,
+                        tx_hash,
+                    },
+                );
+            }
+            Err(error) => {
+                tracing::error!("could not get answer tx 0x{:x}: {:#}", tx_hash, error);
+                continue;
+            }
+        }
+
+        // an answering task currently handling the oracle holds its claim, and it will
+        // take care of the tx hash itself
+        let claim_expiration = match db::blocking(|| {
+            active_oracle.claim(&mut db_connection, ANSWER_CLAIM_DURATION)
+        })? {
+            Some(claim_expiration) => claim_expiration,
+            None => {
+                tracing::info!(
+                    "oracle 0x{:x} claimed by an answering task, skipping",
+                    active_oracle.address.0
+                );
+                continue;
+            }
+        };
+        let result = db::blocking(|| active_oracle.delete_answer_tx_hash(&mut db_connection));
+        db::blocking(|| {
+            models::ActiveOracle::release_claim(
+                &mut db_connection,
+                chain_id,
+                active_oracle.address.0,
+                claim_expiration,
+            )
+        })?;
+        result?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use backoff::{future::retry, ExponentialBackoffBuilder};
+use carrot_commons::{data, http_client::HttpClient};
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::{H256, U256, U64};
+use serde::Serialize;
+
+use crate::{
+    commons::STORE_CID_MAX_ELAPSED_TIME,
+    db::{
+        self,
+        models::{self, ActiveOracle},
+        DbConnection,
+    },
+    defillama::RecordedResponse,
+    ipfs::IpfsFetcher,
+    specification::Specification,
+};
+
+// an auditable artifact describing how an oracle was resolved, published on ipfs
+// once the oracle is finalized
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofBundle {
+    pub chain_id: u64,
+    pub oracle_address: String,
+    pub specification: Specification,
+    pub measurement_timestamp: u64,
+    pub answer: String,
+    pub answer_tx_hash: String,
+    pub answer_tx_submitted_at: Option<u64>,
+    pub block_number: Option<u64>,
+    pub answered_at: u64,
+    // only available when defillama responses are recorded
+    pub defillama_responses: Option<Vec<RecordedResponse>>,
+}
+
+impl ProofBundle {
+    pub fn new(
+        active_oracle: &ActiveOracle,
+        answer: U256,
+        tx_hash: H256,
+        block_number: Option<U64>,
+    ) -> Self {
+        Self {
+            chain_id: active_oracle.chain_id as u64,
+            oracle_address: format!("0x{:x}", active_oracle.address.0),
+            specification: active_oracle.specification.clone(),
+            measurement_timestamp: to_unix_timestamp(*active_oracle.measurement_timestamp),
+            answer: answer.to_string(),
+            answer_tx_hash: format!("0x{:x}", tx_hash),
+            answer_tx_submitted_at: active_oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            block_number: block_number.map(|block_number| block_number.as_u64()),
+            answered_at: to_unix_timestamp(SystemTime::now()),
+            defillama_responses: None,
+        }
+    }
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// uploads bundles to the ipfs node, records their cid on the answered oracle and
+// forwards it to the data manager so that they're retained there too
+pub struct ProofPublisher {
+    ipfs_fetcher: Arc<IpfsFetcher>,
+    data_manager_http_client: Arc<HttpClient>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+}
+
+impl ProofPublisher {
+    pub fn new(
+        ipfs_fetcher: Arc<IpfsFetcher>,
+        data_manager_http_client: Arc<HttpClient>,
+        db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    ) -> Self {
+        Self {
+            ipfs_fetcher,
+            data_manager_http_client,
+            db_connection_pool,
+        }
+    }
+
+    pub async fn publish(
+        &self,
+        mut proof_bundle: ProofBundle,
+        defillama_snapshot_id: Option<i64>,
+        answered_oracle_id: i64,
+    ) -> anyhow::Result<()> {
+        if let Some(defillama_snapshot_id) = defillama_snapshot_id {
+            let mut db_connection = db::blocking(|| self.db_connection_pool.get())
+                .context("could not get new connection from pool")?;
+            let snapshot = db::blocking(|| {
+                models::DefiLlamaSnapshot::get(&mut db_connection, defillama_snapshot_id)
+            })?
+            .context(format!(
+                "defillama snapshot {} not found",
+                defillama_snapshot_id
+            ))?;
+            proof_bundle.defillama_responses = Some(snapshot.recorded_responses()?);
+        }
+
+        let name = format!(
+            "proof-{}-{}.json",
+            proof_bundle.chain_id, proof_bundle.oracle_address
+        );
+        let cid = retry(
+            ExponentialBackoffBuilder::new()
+                .with_max_elapsed_time(Some(STORE_CID_MAX_ELAPSED_TIME))
+                .build(),
+            || async {
+                self.ipfs_fetcher
+                    .add_json(&name, &proof_bundle)
+                    .await
+                    .map_err(backoff::Error::transient)
+            },
+        )
+        .await?;
+        tracing::info!("proof bundle published with cid {}", cid);
+
+        let mut db_connection = db::blocking(|| self.db_connection_pool.get())
+            .context("could not get new connection from pool")?;
+        db::blocking(|| {
+            models::AnsweredOracle::update_proof_bundle_cid(
+                &mut db_connection,
+                answered_oracle_id,
+                &cid,
+            )
+        })?;
+
+        data::store_cid_ipfs_with_retry(
+            cid.clone(),
+            self.data_manager_http_client.clone(),
+            ExponentialBackoffBuilder::new()
+                .with_max_elapsed_time(Some(STORE_CID_MAX_ELAPSED_TIME))
+                .build(),
+        )
+        .await
+        .map_err(|error| {
+            anyhow::anyhow!(
+                "could not store proof bundle cid {} through the data manager service: {:?}",
+                cid,
+                error
+            )
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use tokio::time::interval;
+
+use crate::{
+    alerts::{self, AlertKind},
+    commons::ANSWER_CLAIM_DURATION,
+    db::{
+        self,
+        models::{self, ArchiveReason},
+        DbConnection,
+    },
+    events::{self, OracleEventKind},
+};
+
+use super::reverts;
+
+// expired oracles are otherwise only archived when an answering run picks them up,
+// which never happens for oracles expiring before their measurement timestamp
+pub async fn purge_expired_oracles(
+    chain_id: u64,
+    purge_interval: Duration,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut interval = interval(purge_interval);
+
+    tracing::info!(
+        "archiving expired oracles every {}s",
+        purge_interval.as_secs()
+    );
+
+    loop {
+        interval.tick().await;
+
+        if let Err(error) =
+            db::blocking(|| handle_expired_oracles(chain_id, db_connection_pool.clone()))
+        {
+            tracing::error!("error while archiving expired oracles: {:#}", error);
+        }
+    }
+}
+
+fn handle_expired_oracles(
+    chain_id: u64,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut db_connection = db_connection_pool
+        .get()
+        .context("could not get new connection from pool")?;
+    let active_oracles =
+        models::ActiveOracle::get_all_expired_for_chain_id(&mut db_connection, chain_id)
+            .context("could not get expired active oracles")?;
+
+    let mut archived = 0;
+    for active_oracle in active_oracles.into_iter() {
+        // an answering task currently handling the oracle holds its claim, and it will
+        // notice the expiration itself
+        if active_oracle
+            .claim(&mut db_connection, ANSWER_CLAIM_DURATION)?
+            .is_none()
+        {
+            continue;
+        }
+        let address = active_oracle.address.0;
+        active_oracle.archive(&mut db_connection, ArchiveReason::Expired)?;
+        reverts::forget(chain_id, address);
+        events::emit(chain_id, address, OracleEventKind::Expired);
+        alerts::raise(
+            chain_id,
+            AlertKind::ExpiredUnanswered {
+                oracle_address: address,
+            },
+        );
+        archived += 1;
+    }
+    if archived > 0 {
+        tracing::info!("archived {} expired oracle(s)", archived);
+    }
+
+    Ok(())
+}
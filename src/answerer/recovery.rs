@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::{
+    middleware::{Middleware, SignerMiddleware},
+    providers::Provider,
+    types::U64,
+    utils,
+};
+
+use crate::{
+    commons::ANSWER_CLAIM_DURATION,
+    db::{
+        self,
+        models::{self, ActiveOracle},
+        DbConnection,
+    },
+    events::{self, OracleEventKind},
+    metrics::{self, AnswerTxStatus},
+    rpc::FallbackHttp,
+    signer::AnswererSigner,
+};
+
+use super::{fetch_kpi_token_address, reverts};
+
+// answer txs submitted right before a restart are never awaited again, so on
+// startup their outcome is looked up directly. txs still in the mempool are left
+// to the orphaned answer txs collector
+pub async fn recover_in_flight_answer_txs(
+    chain_id: u64,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    // failing here is not a reason to stop the service, as the orphaned answer txs
+    // collector eventually takes care of the txs anyway
+    if let Err(error) = handle_in_flight_answer_txs(chain_id, signer, db_connection_pool).await {
+        tracing::error!("error while recovering in-flight answer txs: {:#}", error);
+    }
+    Ok(())
+}
+
+async fn handle_in_flight_answer_txs(
+    chain_id: u64,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<()> {
+    let mut db_connection = db::blocking(|| db_connection_pool.get())
+        .context("could not get new connection from pool")?;
+    let active_oracles = db::blocking(|| {
+        models::ActiveOracle::get_all_with_answer_tx_hash(&mut db_connection, chain_id)
+    })
+    .context("could not get active oracles with an answer tx")?;
+    if active_oracles.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!("recovering {} in-flight answer txs", active_oracles.len());
+    for active_oracle in active_oracles.into_iter() {
+        let address = active_oracle.address.0;
+        // another instance sharing the database might be waiting for the same tx, in
+        // which case it holds the oracle's claim and takes care of it itself
+        let claim_expiration = match db::blocking(|| {
+            active_oracle.claim(&mut db_connection, ANSWER_CLAIM_DURATION)
+        })? {
+            Some(claim_expiration) => claim_expiration,
+            None => {
+                tracing::info!(
+                    "oracle 0x{:x} claimed by an answering task, skipping",
+                    address
+                );
+                continue;
+            }
+        };
+        let result = recover_in_flight_answer_tx(
+            chain_id,
+            signer.clone(),
+            &mut db_connection,
+            active_oracle,
+        )
+        .await;
+        // if the oracle was finalized its row is gone and releasing the claim is a no-op
+        db::blocking(|| {
+            models::ActiveOracle::release_claim(
+                &mut db_connection,
+                chain_id,
+                address,
+                claim_expiration,
+            )
+        })?;
+        if let Err(error) = result {
+            tracing::error!(
+                "could not recover answer tx for oracle 0x{:x}: {:#}",
+                address,
+                error
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn recover_in_flight_answer_tx(
+    chain_id: u64,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    db_connection: &mut DbConnection,
+    mut active_oracle: ActiveOracle,
+) -> anyhow::Result<()> {
+    let tx_hash = match &active_oracle.answer_tx_hash {
+        Some(tx_hash) => tx_hash.0,
+        None => return Ok(()),
+    };
+
+    let receipt = signer
+        .get_transaction_receipt(tx_hash)
+        .await
+        .context(format!(
+            "could not get receipt for answer tx 0x{:x}",
+            tx_hash
+        ))?;
+    let receipt = match receipt {
+        Some(receipt) => receipt,
+        None => {
+            let tx = signer
+                .get_transaction(tx_hash)
+                .await
+                .context(format!("could not get answer tx 0x{:x}", tx_hash))?;
+            if tx.is_some() {
+                tracing::info!(
+                    "answer tx 0x{:x} for oracle 0x{:x} still pending, leaving it",
+                    tx_hash,
+                    active_oracle.address.0
+                );
+                return Ok(());
+            }
+            tracing::warn!(
+                "answer tx 0x{:x} for oracle 0x{:x} was dropped, clearing it",
+                tx_hash,
+                active_oracle.address.0
+            );
+            metrics::record_orphaned_answer_tx(chain_id);
+            return db::blocking(|| active_oracle.delete_answer_tx_hash(db_connection));
+        }
+    };
+
+    if receipt.status != Some(U64::one()) {
+        tracing::warn!(
+            "answer tx 0x{:x} for oracle 0x{:x} reverted, clearing it",
+            tx_hash,
+            active_oracle.address.0
+        );
+        metrics::record_answer_tx(chain_id, AnswerTxStatus::Failed);
+        reverts::record_revert(chain_id, active_oracle.address.0);
+        return db::blocking(|| active_oracle.delete_answer_tx_hash(db_connection));
+    }
+
+    tracing::info!(
+        "answer tx 0x{:x} for oracle 0x{:x} was mined, finalizing",
+        tx_hash,
+        active_oracle.address.0
+    );
+    let kpi_token_address = match fetch_kpi_token_address(signer, active_oracle.address.0).await {
+        Ok(kpi_token_address) => Some(kpi_token_address),
+        Err(error) => {
+            tracing::warn!("{:#}", error);
+            None
+        }
+    };
+    // fees can't be converted to usd at this point anymore, so only the native
+    // fee is recorded
+    if let Err(error) = db::blocking(|| {
+        models::GasSpending::create(
+            db_connection,
+            chain_id,
+            active_oracle.address.0,
+            kpi_token_address,
+            &receipt,
+            None,
+        )
+    }) {
+        tracing::error!("{:#}", error);
+    }
+    metrics::record_answer_tx(chain_id, AnswerTxStatus::Confirmed);
+    if let (Some(gas_used), Some(effective_gas_price)) =
+        (receipt.gas_used, receipt.effective_gas_price)
+    {
+        // assuming it's always 18 decimals
+        let fee = utils::format_units(gas_used * effective_gas_price, 18)
+            .ok()
+            .and_then(|fee| fee.parse::<f64>().ok())
+            .unwrap_or_default();
+        metrics::record_answer_fee(chain_id, gas_used.low_u64(), fee, None);
+    }
+    metrics::observe_finalization(chain_id, *active_oracle.measurement_timestamp);
+    let address = active_oracle.address.0;
+    db::blocking(|| active_oracle.archive_answered(db_connection, tx_hash, Some(&receipt), None))?;
+    events::emit(
+        chain_id,
+        address,
+        OracleEventKind::Finalized {
+            tx_hash: Some(format!("0x{:x}", tx_hash)),
+        },
+    );
+    reverts::record_finalization(chain_id, address);
+    Ok(())
+}
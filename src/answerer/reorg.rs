@@ -0,0 +1,150 @@
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::{
+    middleware::{Middleware, SignerMiddleware},
+    providers::Provider,
+    types::{Address, H256, U64},
+};
+use tokio::time::sleep;
+
+use crate::{
+    commons::{REORG_WATCH_MAX_DURATION, REORG_WATCH_POLLING_INTERVAL},
+    contracts::defi_llama_oracle::DefiLlamaOracle,
+    db::{self, models, DbConnection},
+    rpc::FallbackHttp,
+    signer::AnswererSigner,
+    specification::Specification,
+};
+
+// everything needed to restore a finalized oracle's row in case its answer tx
+// gets reorged out
+pub struct FinalizedOracle {
+    pub address: Address,
+    pub chain_id: u64,
+    pub measurement_timestamp: SystemTime,
+    pub specification: Specification,
+    pub expiration: SystemTime,
+    pub tx_hash: H256,
+    pub block_number: U64,
+}
+
+pub async fn watch_for_reorg(
+    finalized_oracle: FinalizedOracle,
+    confirmation_blocks: u64,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) {
+    let target_block_number = finalized_oracle.block_number + confirmation_blocks;
+    let started_at = Instant::now();
+    loop {
+        sleep(REORG_WATCH_POLLING_INTERVAL).await;
+        if started_at.elapsed() > REORG_WATCH_MAX_DURATION {
+            tracing::warn!(
+                "block {} not reached in {}s, giving up on reorg detection for answer tx 0x{:x}",
+                target_block_number,
+                REORG_WATCH_MAX_DURATION.as_secs(),
+                finalized_oracle.tx_hash
+            );
+            return;
+        }
+        match signer.get_block_number().await {
+            Ok(block_number) => {
+                if block_number >= target_block_number {
+                    break;
+                }
+            }
+            Err(error) => {
+                tracing::warn!("could not get current block number: {:#}", error);
+            }
+        }
+    }
+
+    match is_answer_tx_reorged(&finalized_oracle, signer).await {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(error) => {
+            tracing::error!(
+                "could not check answer tx 0x{:x} for reorgs: {:#}",
+                finalized_oracle.tx_hash,
+                error
+            );
+            return;
+        }
+    }
+
+    tracing::error!(
+        "answer tx 0x{:x} was reorged out, restoring oracle 0x{:x} so that it's answered again",
+        finalized_oracle.tx_hash,
+        finalized_oracle.address
+    );
+    let mut db_connection = match db::blocking(|| db_connection_pool.get())
+        .context("could not get new connection from pool")
+    {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!(
+                "could not get database connection while trying to restore reorged oracle, ACT IMMEDIATELY: {:#}",
+                error
+            );
+            return;
+        }
+    };
+    if let Err(error) = db::blocking(|| {
+        models::ActiveOracle::create(
+            &mut db_connection,
+            finalized_oracle.address,
+            finalized_oracle.chain_id,
+            finalized_oracle.measurement_timestamp,
+            finalized_oracle.specification,
+            finalized_oracle.expiration,
+        )
+    }) {
+        tracing::error!(
+            "could not restore reorged oracle, ACT IMMEDIATELY: {:#}",
+            error
+        );
+        return;
+    }
+    if let Err(error) = db::blocking(|| {
+        models::AnsweredOracle::delete_for_answer_tx(
+            &mut db_connection,
+            finalized_oracle.chain_id,
+            finalized_oracle.tx_hash,
+        )
+    }) {
+        tracing::error!("{:#}", error);
+    }
+}
+
+async fn is_answer_tx_reorged(
+    finalized_oracle: &FinalizedOracle,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+) -> anyhow::Result<bool> {
+    let receipt = signer
+        .get_transaction_receipt(finalized_oracle.tx_hash)
+        .await
+        .context("could not get answer tx receipt")?;
+    if let Some(block_number) = receipt.and_then(|receipt| receipt.block_number) {
+        if block_number != finalized_oracle.block_number {
+            tracing::info!(
+                "answer tx 0x{:x} was re-mined in block {} after a reorg",
+                finalized_oracle.tx_hash,
+                block_number
+            );
+        }
+        return Ok(false);
+    }
+
+    // the oracle might have been finalized by someone else in the meantime
+    let finalized = DefiLlamaOracle::new(finalized_oracle.address, signer)
+        .finalized()
+        .call()
+        .await
+        .context("could not fetch oracle finalization status")?;
+    Ok(!finalized)
+}
@@ -0,0 +1,111 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use ethers::types::Address;
+
+use crate::{
+    alerts::{self, AlertKind},
+    commons::ANSWER_TX_REVERTS_ALERT_THRESHOLD,
+};
+
+// consecutive reverted answer txs per chain and oracle. counts are kept in memory
+// only, so a restart gives every oracle a clean slate
+static REVERTS: LazyLock<Mutex<HashMap<(u64, Address), u32>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// alerts once an oracle's answer txs keep reverting, which usually means its state
+// changed in a way the answerer doesn't expect
+pub fn record_revert(chain_id: u64, oracle_address: Address) {
+    let reverts = {
+        let mut reverts = REVERTS.lock().unwrap();
+        let oracle_reverts = reverts.entry((chain_id, oracle_address)).or_default();
+        *oracle_reverts += 1;
+        *oracle_reverts
+    };
+    if reverts == ANSWER_TX_REVERTS_ALERT_THRESHOLD {
+        alerts::raise(
+            chain_id,
+            AlertKind::RepeatedReverts {
+                oracle_address,
+                reverts,
+            },
+        );
+    }
+}
+
+// clears any condition raised for the oracle, which only results in an alert if
+// there was one
+pub fn record_finalization(chain_id: u64, oracle_address: Address) {
+    forget(chain_id, oracle_address);
+    alerts::raise(chain_id, AlertKind::OracleFinalized { oracle_address });
+}
+
+// oracles archived without being answered (expired or removed) won't be answered
+// anymore, so their counts are dropped too
+pub fn forget(chain_id: u64, oracle_address: Address) {
+    REVERTS.lock().unwrap().remove(&(chain_id, oracle_address));
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::{Address, H256};
+
+    use crate::{
+        alerts::{self, AlertKind},
+        commons::ANSWER_TX_REVERTS_ALERT_THRESHOLD,
+    };
+
+    use super::{forget, record_finalization, record_revert};
+
+    #[tokio::test]
+    async fn alert_on_repeated_reverts() {
+        let mut alerts = alerts::subscribe();
+        // alerts and revert counts are global, so the test keeps to its own chain
+        let chain_id = H256::random().to_low_u64_be();
+        let oracle_address = Address::random();
+        for _ in 0..ANSWER_TX_REVERTS_ALERT_THRESHOLD + 1 {
+            record_revert(chain_id, oracle_address);
+        }
+        record_finalization(chain_id, oracle_address);
+        // finalizing again doesn't clear anything
+        record_finalization(chain_id, oracle_address);
+
+        let mut kinds = Vec::new();
+        while let Ok(alert) = alerts.try_recv() {
+            if alert.chain_id == chain_id {
+                kinds.push(alert.kind);
+            }
+        }
+        assert_eq!(
+            kinds,
+            vec![
+                AlertKind::RepeatedReverts {
+                    oracle_address,
+                    reverts: ANSWER_TX_REVERTS_ALERT_THRESHOLD,
+                },
+                AlertKind::OracleFinalized { oracle_address },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn forget_reverts() {
+        let mut alerts = alerts::subscribe();
+        let chain_id = H256::random().to_low_u64_be();
+        let oracle_address = Address::random();
+        for _ in 0..ANSWER_TX_REVERTS_ALERT_THRESHOLD - 1 {
+            record_revert(chain_id, oracle_address);
+        }
+        forget(chain_id, oracle_address);
+        // counting starts over, so the threshold isn't reached
+        for _ in 0..ANSWER_TX_REVERTS_ALERT_THRESHOLD - 1 {
+            record_revert(chain_id, oracle_address);
+        }
+
+        while let Ok(alert) = alerts.try_recv() {
+            assert_ne!(alert.chain_id, chain_id);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use ethers::types::U256;
+use tokio::time::sleep;
+
+use crate::{
+    archive::HistoricalState,
+    commons::AnswerSamplingConfig,
+    defillama::DefiLlamaClient,
+    specification::{self, Specification},
+};
+
+// the answer computation timeout only covers the computation itself, so the time
+// spent waiting between samples is added on top of it
+pub fn sampling_duration(sampling: &AnswerSamplingConfig) -> Duration {
+    Duration::from_secs(sampling.interval_seconds) * (sampling.samples.max(1) as u32 - 1)
+}
+
+// fetching the metric multiple times defends against transient glitches in the
+// data source, which would otherwise be committed on-chain forever
+pub async fn sample_answer(
+    specification: &Specification,
+    defillama: &DefiLlamaClient,
+    historical_state: Option<&HistoricalState>,
+    sampling: &AnswerSamplingConfig,
+) -> anyhow::Result<Option<U256>> {
+    let samples_count = sampling.samples.max(1);
+    let mut samples = Vec::with_capacity(samples_count);
+    for i in 0..samples_count {
+        if i > 0 {
+            sleep(Duration::from_secs(sampling.interval_seconds)).await;
+        }
+        match specification::answer(specification, defillama, historical_state).await? {
+            Some(sample) => samples.push(sample),
+            None => return Ok(None),
+        }
+    }
+
+    match aggregate_samples(samples.clone(), sampling.max_spread_percentage) {
+        Ok(answer) => {
+            tracing::info!("sampled answers {:?}, using median {}", samples, answer);
+            Ok(Some(answer))
+        }
+        Err(error) => {
+            tracing::error!("rejecting sampled answers {:?}: {:#}", samples, error);
+            Ok(None)
+        }
+    }
+}
+
+fn aggregate_samples(mut samples: Vec<U256>, max_spread_percentage: f64) -> anyhow::Result<U256> {
+    if samples.is_empty() {
+        anyhow::bail!("no samples to aggregate");
+    }
+    samples.sort();
+
+    let middle = samples.len() / 2;
+    let median = if samples.len() % 2 == 1 {
+        samples[middle]
+    } else {
+        // averaging this way avoids overflows
+        let (lower, upper) = (samples[middle - 1], samples[middle]);
+        lower / 2 + upper / 2 + (lower % 2 + upper % 2) / 2
+    };
+
+    // the spread is compared in basis points to stay in integer math
+    let spread = samples[samples.len() - 1] - samples[0];
+    let max_spread_bps = U256::from((max_spread_percentage * 100.0).max(0.0) as u64);
+    if spread.saturating_mul(U256::from(10_000)) > median.saturating_mul(max_spread_bps) {
+        anyhow::bail!(
+            "spread {} exceeds {}% of the median {}",
+            spread,
+            max_spread_percentage,
+            median
+        );
+    }
+
+    Ok(median)
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::U256;
+
+    use super::aggregate_samples;
+
+    fn samples(values: &[u64]) -> Vec<U256> {
+        values.iter().map(|value| U256::from(*value)).collect()
+    }
+
+    #[test]
+    fn aggregate_samples_median() {
+        assert_eq!(
+            aggregate_samples(samples(&[101, 100, 102]), 5.0).unwrap(),
+            U256::from(101)
+        );
+        assert_eq!(
+            aggregate_samples(samples(&[100, 103, 101, 102]), 5.0).unwrap(),
+            U256::from(101)
+        );
+        assert_eq!(
+            aggregate_samples(samples(&[100]), 0.0).unwrap(),
+            U256::from(100)
+        );
+        assert_eq!(
+            aggregate_samples(vec![U256::MAX, U256::MAX], 0.0).unwrap(),
+            U256::MAX
+        );
+    }
+
+    #[test]
+    fn aggregate_samples_spread() {
+        // spread of 10 on a median of 100 is exactly 10%
+        assert!(aggregate_samples(samples(&[95, 100, 105]), 10.0).is_ok());
+        assert!(aggregate_samples(samples(&[95, 100, 105]), 9.9).is_err());
+
+        // a single glitched sample is enough to reject the answer
+        assert!(aggregate_samples(samples(&[100, 100, 1_000_000]), 10.0).is_err());
+
+        // any spread around a zero median is rejected
+        assert!(aggregate_samples(samples(&[0, 0, 1]), 10.0).is_err());
+        assert!(aggregate_samples(samples(&[0, 0, 0]), 0.0).is_ok());
+
+        assert!(aggregate_samples(Vec::new(), 10.0).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use answerer_framework::AnswerSubmitter;
+use async_trait::async_trait;
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::Provider,
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+
+use crate::{
+    contracts::defi_llama_oracle::DefiLlamaOracle, rpc::FallbackHttp, signer::AnswererSigner,
+};
+
+// finalizes defillama oracles with their answer, reading through the primary key's
+// signer. txs are sent through whichever key the oracle expects its answer from
+pub struct DefiLlamaAnswerSubmitter {
+    pub signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+}
+
+#[async_trait]
+impl AnswerSubmitter for DefiLlamaAnswerSubmitter {
+    async fn expected_answerer(&self, oracle: Address) -> anyhow::Result<Address> {
+        Ok(DefiLlamaOracle::new(oracle, self.signer.clone())
+            .answerer()
+            .call()
+            .await?)
+    }
+
+    fn finalize_tx(&self, oracle: Address, answer: U256) -> TypedTransaction {
+        DefiLlamaOracle::new(oracle, self.signer.clone())
+            .finalize(answer)
+            .tx
+    }
+}
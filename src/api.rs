@@ -1,19 +1,101 @@
+mod backfills;
+mod chains;
+mod checkpoints;
+mod costs;
+mod diagnostics;
 mod documentation;
+mod events;
+mod graphql;
+mod health;
+mod logging;
+mod metrics;
+mod oracles;
+mod overrides;
+mod pagination;
+mod snapshots;
 mod specifications;
+mod status;
 
-use std::{net::Ipv4Addr, sync::Arc};
+use std::{collections::HashMap, fs, sync::Arc};
 
-use carrot_commons::http_client::HttpClient;
-use warp::Filter;
+use anyhow::Context;
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use warp::{header, reply, Filter, Rejection};
+
+use crate::defillama::DefiLlamaClient;
+use crate::{chains::Chains, commons::ApiConfig, db::DbConnection};
+
+// resolves the operator name associated with the api key passed as a bearer token
+fn with_operator(
+    operators: Arc<HashMap<String, String>>,
+) -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+    header::optional::<String>("authorization").map(move |authorization: Option<String>| {
+        let api_key = authorization?.strip_prefix("Bearer ")?.to_owned();
+        operators
+            .iter()
+            .find(|(_, operator_api_key)| **operator_api_key == api_key)
+            .map(|(operator, _)| operator.clone())
+    })
+}
 
 pub async fn serve(
-    host: Ipv4Addr,
-    port: u16,
-    defillama_http_client: Arc<HttpClient>,
+    config: ApiConfig,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
 ) -> anyhow::Result<()> {
-    warp::serve(documentation::handlers().or(specifications::handlers(defillama_http_client)))
-        .run((host, port))
-        .await;
+    let operators = Arc::new(config.operators);
+    let probes = health::handlers(
+        chains.clone(),
+        db_connection_pool.clone(),
+        defillama.clone(),
+    )
+    .or(metrics::handlers());
+    let routes = specifications::handlers(defillama)
+        .or(snapshots::handlers(db_connection_pool.clone()))
+        .or(overrides::handlers(
+            operators.clone(),
+            db_connection_pool.clone(),
+        ))
+        .or(backfills::handlers(operators.clone(), chains.clone()))
+        .or(checkpoints::handlers(
+            operators.clone(),
+            chains.clone(),
+            db_connection_pool.clone(),
+        ))
+        .or(status::handlers(chains.clone(), db_connection_pool.clone()))
+        .or(chains::handlers(operators.clone(), chains))
+        .or(diagnostics::handlers(db_connection_pool.clone()))
+        .or(oracles::handlers(operators, db_connection_pool.clone()))
+        .or(costs::handlers(db_connection_pool.clone()))
+        .or(graphql::handlers(config.graphql, db_connection_pool))
+        .or(events::handlers());
+    let server = warp::serve(logging::with_request_logging(
+        documentation::handlers()
+            .or(warp::path("v1").and(probes.clone().or(routes.clone())))
+            // unversioned paths predate /v1 and are kept for existing clients, which
+            // are told to move on. Probes stay unversioned for orchestrators
+            .or(probes)
+            .or(routes.with(reply::with::header("deprecation", "true"))),
+    ));
+
+    match config.tls {
+        Some(tls) => {
+            // warp panics on unreadable files, so they're checked upfront
+            for path in [&tls.cert_path, &tls.key_path] {
+                fs::read(path).context(format!("could not read tls file {}", path.display()))?;
+            }
+            tracing::info!("serving https on {}:{}", config.host, config.port);
+            server
+                .tls()
+                .cert_path(tls.cert_path)
+                .key_path(tls.key_path)
+                .run((config.host, config.port))
+                .await;
+        }
+        None => server.run((config.host, config.port)).await,
+    }
 
     Ok(())
 }
@@ -0,0 +1,94 @@
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
+
+use serde::Deserialize;
+use utoipa::ToSchema;
+use warp::{body, http, path, post, Filter, Rejection, Reply};
+
+use crate::{chains::Chains, listener::backfill::BackfillSource};
+
+use super::with_operator;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackfillRequest {
+    pub from_block: u64,
+    pub to_block: u64,
+    // replays the logs stored in the indexed logs table instead of querying the rpc
+    #[serde(default)]
+    pub from_indexed_logs: bool,
+}
+
+pub fn handlers(
+    operators: Arc<HashMap<String, String>>,
+    chains: Arc<Chains>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods([http::Method::POST])
+        .allow_headers(["Content-Type", "Authorization"])
+        .max_age(600);
+
+    let with_chains = warp::any().map(move || chains.clone());
+
+    path!("backfills" / u64)
+        .and(post())
+        .and(with_operator(operators))
+        .and(body::json())
+        .and(with_chains)
+        .and_then(start_backfill)
+        .with(cors)
+}
+
+/// Starts a backfill.
+///
+/// Rescans the given block range on a chain in the background, acknowledging any oracle created in it that was missed. The stored checkpoint is left untouched. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    post,
+    path = "/backfills/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The chain id to backfill.")
+    ),
+    request_body = BackfillRequest,
+    responses(
+        (status = 202, description = "The backfill was started."),
+        (status = 400, description = "The given block range is invalid."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "The given chain is not supported."),
+        (status = 409, description = "A backfill is already running on the given chain.")
+    )
+)]
+pub async fn start_backfill(
+    chain_id: u64,
+    operator: Option<String>,
+    request: BackfillRequest,
+    chains: Arc<Chains>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    if request.from_block > request.to_block {
+        return Ok(Box::new(http::StatusCode::BAD_REQUEST));
+    }
+    let backfiller = match chains.backfiller(chain_id) {
+        Some(backfiller) => backfiller,
+        None => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+    };
+
+    let source = if request.from_indexed_logs {
+        BackfillSource::IndexedLogs
+    } else {
+        BackfillSource::Rpc
+    };
+    if !backfiller.start(request.from_block, request.to_block, source) {
+        return Ok(Box::new(http::StatusCode::CONFLICT));
+    }
+    tracing::info!(
+        "operator {} started backfilling blocks {} to {} on chain {}",
+        operator,
+        request.from_block,
+        request.to_block,
+        chain_id
+    );
+    Ok(Box::new(http::StatusCode::ACCEPTED))
+}
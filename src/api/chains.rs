@@ -0,0 +1,140 @@
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
+
+use warp::{body, delete, get, http, path, post, reply, Filter, Rejection, Reply};
+
+use crate::{chains::Chains, commons::ChainConfig, config::presets::apply_chain_preset};
+
+use super::with_operator;
+
+pub fn handlers(
+    operators: Arc<HashMap<String, String>>,
+    chains: Arc<Chains>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods([http::Method::GET, http::Method::POST, http::Method::DELETE])
+        .allow_headers(["Content-Type", "Authorization"])
+        .max_age(600);
+
+    let with_chains = warp::any().map(move || chains.clone());
+
+    let get_chains = path!("chains")
+        .and(get())
+        .and(with_operator(operators.clone()))
+        .and(with_chains.clone())
+        .and_then(get_chains);
+
+    let add_chain = path!("chains" / u64)
+        .and(post())
+        .and(with_operator(operators.clone()))
+        .and(body::json())
+        .and(with_chains.clone())
+        .and_then(add_chain);
+
+    let remove_chain = path!("chains" / u64)
+        .and(delete())
+        .and(with_operator(operators))
+        .and(with_chains)
+        .and_then(remove_chain);
+
+    get_chains.or(add_chain).or(remove_chain).with(cors)
+}
+
+/// Gets the running chains.
+///
+/// Gets the ids of the chains whose logs are currently being scanned and whose oracles are currently being answered. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    get,
+    path = "/chains",
+    responses(
+        (status = 200, description = "The running chains' ids.", body = [u64]),
+        (status = 401, description = "No valid operator api key was given.")
+    )
+)]
+pub async fn get_chains(
+    operator: Option<String>,
+    chains: Arc<Chains>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    if operator.is_none() {
+        return Ok(Box::new(http::StatusCode::UNAUTHORIZED));
+    }
+    Ok(Box::new(reply::json(&chains.chain_ids())))
+}
+
+/// Adds a chain.
+///
+/// Starts scanning logs and answering oracles on a chain without restarting the service. The body is the chain's configuration, in the same format as the chain configs in the configuration file, presets included. Chains added this way are only kept until the next restart. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    post,
+    path = "/chains/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The id of the chain to add.")
+    ),
+    request_body(content = Object, description = "The chain's configuration."),
+    responses(
+        (status = 201, description = "The chain was added."),
+        (status = 400, description = "The given configuration is invalid or the chain could not be started with it."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 409, description = "The given chain is already running.")
+    )
+)]
+pub async fn add_chain(
+    chain_id: u64,
+    operator: Option<String>,
+    mut chain_config: serde_json::Value,
+    chains: Arc<Chains>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    if chains.contains(chain_id) {
+        return Ok(Box::new(http::StatusCode::CONFLICT));
+    }
+    apply_chain_preset(chain_id, &mut chain_config);
+    let chain_config: ChainConfig = match serde_json::from_value(chain_config) {
+        Ok(chain_config) => chain_config,
+        Err(error) => {
+            tracing::error!("invalid config for chain {}: {:#}", chain_id, error);
+            return Ok(Box::new(http::StatusCode::BAD_REQUEST));
+        }
+    };
+
+    if let Err(error) = chains.add(chain_id, chain_config).await {
+        tracing::error!("could not add chain {}: {:#}", chain_id, error);
+        return Ok(Box::new(http::StatusCode::BAD_REQUEST));
+    }
+    tracing::info!("operator {} added chain {}", operator, chain_id);
+    Ok(Box::new(http::StatusCode::CREATED))
+}
+
+/// Removes a chain.
+///
+/// Stops scanning logs and answering oracles on a chain without restarting the service. Answers being submitted when the chain is removed are completed. Chains in the configuration file are started again on the next restart. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    delete,
+    path = "/chains/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The id of the chain to remove.")
+    ),
+    responses(
+        (status = 204, description = "The chain was removed."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "The given chain is not running.")
+    )
+)]
+pub async fn remove_chain(
+    chain_id: u64,
+    operator: Option<String>,
+    chains: Arc<Chains>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    if !chains.remove(chain_id) {
+        return Ok(Box::new(http::StatusCode::NOT_FOUND));
+    }
+    tracing::info!("operator {} removed chain {}", operator, chain_id);
+    Ok(Box::new(http::StatusCode::NO_CONTENT))
+}
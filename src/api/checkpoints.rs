@@ -0,0 +1,338 @@
+use std::{collections::HashMap, convert::Infallible, sync::Arc};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use warp::{body, get, http, path, post, put, reply, Filter, Rejection, Reply};
+
+use crate::{
+    chains::Chains,
+    db::{self, models, DbConnection},
+    listener::Listener,
+};
+
+use super::with_operator;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub chain_id: u64,
+    pub block_number: u64,
+    // pinned checkpoints were manually set and are not moved until the next restart,
+    // or are being rewound and are not moved until the rescan completes
+    pub pinned: bool,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointUpdate {
+    pub block_number: u64,
+}
+
+pub fn handlers(
+    operators: Arc<HashMap<String, String>>,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods([http::Method::GET, http::Method::PUT, http::Method::POST])
+        .allow_headers(["Content-Type", "Authorization"])
+        .max_age(600);
+
+    let with_chains = warp::any().map(move || chains.clone());
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+
+    let get_checkpoints = path!("checkpoints")
+        .and(get())
+        .and(with_operator(operators.clone()))
+        .and(with_chains.clone())
+        .and(with_db_connection_pool.clone())
+        .and_then(get_checkpoints);
+
+    let get_checkpoint = path!("checkpoints" / u64)
+        .and(get())
+        .and(with_operator(operators.clone()))
+        .and(with_chains.clone())
+        .and(with_db_connection_pool.clone())
+        .and_then(get_checkpoint);
+
+    let update_checkpoint = path!("checkpoints" / u64)
+        .and(put())
+        .and(with_operator(operators.clone()))
+        .and(body::json())
+        .and(with_chains.clone())
+        .and(with_db_connection_pool.clone())
+        .and_then(update_checkpoint);
+
+    let rewind_checkpoint = path!("checkpoints" / u64 / "rewind")
+        .and(post())
+        .and(with_operator(operators))
+        .and(body::json())
+        .and(with_chains)
+        .and(with_db_connection_pool)
+        .and_then(rewind_checkpoint);
+
+    get_checkpoints
+        .or(get_checkpoint)
+        .or(update_checkpoint)
+        .or(rewind_checkpoint)
+        .with(cors)
+}
+
+/// Gets the checkpoints.
+///
+/// Gets the stored checkpoint of every chain, from which logs scanning resumes after a restart. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    get,
+    path = "/checkpoints",
+    responses(
+        (status = 200, description = "The stored checkpoints.", body = [Checkpoint]),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 500, description = "The checkpoints could not be fetched.")
+    )
+)]
+pub async fn get_checkpoints(
+    operator: Option<String>,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    if operator.is_none() {
+        return Ok(Box::new(http::StatusCode::UNAUTHORIZED));
+    }
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    match db::blocking(|| models::Checkpoint::get_all(&mut db_connection)) {
+        Ok(checkpoints) => {
+            let checkpoints: Vec<_> = checkpoints
+                .into_iter()
+                .map(|checkpoint| {
+                    let chain_id = checkpoint.chain_id as u64;
+                    Checkpoint {
+                        chain_id,
+                        block_number: checkpoint.block_number as u64,
+                        pinned: chains
+                            .listener(chain_id)
+                            .as_ref()
+                            .is_some_and(Listener::is_checkpoint_pinned),
+                    }
+                })
+                .collect();
+            Ok(Box::new(reply::json(&checkpoints)))
+        }
+        Err(error) => {
+            tracing::error!("could not get checkpoints: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Gets a chain's checkpoint.
+///
+/// Gets the stored checkpoint of a chain, from which logs scanning resumes after a restart. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    get,
+    path = "/checkpoints/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The checkpoint's chain id.")
+    ),
+    responses(
+        (status = 200, description = "The stored checkpoint.", body = Checkpoint),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "No checkpoint is stored for the given chain."),
+        (status = 500, description = "The checkpoint could not be fetched.")
+    )
+)]
+pub async fn get_checkpoint(
+    chain_id: u64,
+    operator: Option<String>,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    if operator.is_none() {
+        return Ok(Box::new(http::StatusCode::UNAUTHORIZED));
+    }
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    match db::blocking(|| models::Checkpoint::get_for_chain_id(&mut db_connection, chain_id)) {
+        Ok(Some(checkpoint)) => Ok(Box::new(reply::json(&Checkpoint {
+            chain_id,
+            block_number: checkpoint.block_number as u64,
+            pinned: chains
+                .listener(chain_id)
+                .as_ref()
+                .is_some_and(Listener::is_checkpoint_pinned),
+        }))),
+        Ok(None) => Ok(Box::new(http::StatusCode::NOT_FOUND)),
+        Err(error) => {
+            tracing::error!("could not get checkpoint: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Updates a chain's checkpoint.
+///
+/// Sets the block from which logs scanning resumes on a chain after the next restart. The checkpoint is pinned until then, so that the running scanners don't move it. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    put,
+    path = "/checkpoints/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The checkpoint's chain id.")
+    ),
+    request_body = CheckpointUpdate,
+    responses(
+        (status = 200, description = "The checkpoint was updated.", body = Checkpoint),
+        (status = 400, description = "The given block number is invalid."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "The given chain is not supported."),
+        (status = 500, description = "The checkpoint could not be updated.")
+    )
+)]
+pub async fn update_checkpoint(
+    chain_id: u64,
+    operator: Option<String>,
+    update: CheckpointUpdate,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    let listener = match chains.listener(chain_id) {
+        Some(listener) => listener,
+        None => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+    };
+    let block_number = match i64::try_from(update.block_number) {
+        Ok(block_number) => block_number,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    // pinned before writing so that the scanners stop moving the checkpoint right away
+    listener.pin_checkpoint();
+    match db::blocking(|| models::Checkpoint::update(&mut db_connection, chain_id, block_number)) {
+        Ok(()) => {
+            tracing::info!(
+                "operator {} set checkpoint for chain {} to block {}",
+                operator,
+                chain_id,
+                update.block_number
+            );
+            Ok(Box::new(reply::json(&Checkpoint {
+                chain_id,
+                block_number: update.block_number,
+                pinned: true,
+            })))
+        }
+        Err(error) => {
+            tracing::error!("could not update checkpoint: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Rewinds a chain's checkpoint.
+///
+/// Sets a chain's checkpoint back to the given block and rescans every block after it up to the latest known one in the background, acknowledging any oracle created in them that was missed. The running scanners' progress is reset to the given block and the checkpoint is pinned until the rescan completes, after which the scanners move it again. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    post,
+    path = "/checkpoints/{chain_id}/rewind",
+    params(
+        ("chain_id" = u64, Path, description = "The checkpoint's chain id.")
+    ),
+    request_body = CheckpointUpdate,
+    responses(
+        (status = 202, description = "The checkpoint was rewound and the rescan started.", body = Checkpoint),
+        (status = 400, description = "The given block number is after the latest known block."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "The given chain is not supported."),
+        (status = 409, description = "The chain's scanning is led by another instance, its latest block is not known yet or a backfill is already running on it."),
+        (status = 500, description = "The checkpoint could not be stored.")
+    )
+)]
+pub async fn rewind_checkpoint(
+    chain_id: u64,
+    operator: Option<String>,
+    update: CheckpointUpdate,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    let (listener, backfiller) = match (chains.listener(chain_id), chains.backfiller(chain_id)) {
+        (Some(listener), Some(backfiller)) => (listener, backfiller),
+        _ => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+    };
+    // followers discard the logs they scan, so the rescan would be pointless
+    if !listener.is_leading() {
+        return Ok(Box::new(http::StatusCode::CONFLICT));
+    }
+    let head = match listener.present_head() {
+        Some(head) => head,
+        None => return Ok(Box::new(http::StatusCode::CONFLICT)),
+    };
+    if update.block_number > head {
+        return Ok(Box::new(http::StatusCode::BAD_REQUEST));
+    }
+    let block_number = match i64::try_from(update.block_number) {
+        Ok(block_number) => block_number,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    // the checkpoint is pinned right away, so it's safe to store it after the start
+    if !backfiller.start_rewind(update.block_number, head) {
+        return Ok(Box::new(http::StatusCode::CONFLICT));
+    }
+    match db::blocking(|| models::Checkpoint::update(&mut db_connection, chain_id, block_number)) {
+        Ok(()) => {
+            tracing::info!(
+                "operator {} rewound checkpoint for chain {} to block {}, rescanning up to block {}",
+                operator,
+                chain_id,
+                update.block_number,
+                head
+            );
+            Ok(Box::new(reply::with_status(
+                reply::json(&Checkpoint {
+                    chain_id,
+                    block_number: update.block_number,
+                    pinned: true,
+                }),
+                http::StatusCode::ACCEPTED,
+            )))
+        }
+        Err(error) => {
+            tracing::error!("could not rewind checkpoint: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
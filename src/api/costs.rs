@@ -0,0 +1,305 @@
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use warp::{get, http, path, query, reply, Filter, Rejection, Reply};
+
+use crate::db::{
+    self,
+    models::{self, GasSpending},
+    DbConnection,
+};
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Costs {
+    /// Total fee in wei.
+    pub fee: String,
+    /// Total fee in usd, only accounting for transactions whose fee could be priced.
+    pub fee_usd: f64,
+    pub transactions: usize,
+    pub unpriced_transactions: usize,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleCosts {
+    pub address: String,
+    pub costs: Costs,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CampaignCosts {
+    /// The campaign's kpi token address, missing for transactions whose campaign is unknown.
+    pub kpi_token: Option<String>,
+    pub costs: Costs,
+    pub oracles: Vec<OracleCosts>,
+}
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CostReport {
+    pub chain_id: u64,
+    pub from: u64,
+    pub to: u64,
+    pub costs: Costs,
+    pub campaigns: Vec<CampaignCosts>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CostReportQuery {
+    /// Unix timestamp from which answering costs are included, defaults to the beginning of time.
+    pub from: Option<u64>,
+    /// Unix timestamp until which answering costs are included, defaults to now.
+    pub to: Option<u64>,
+}
+
+#[derive(Default)]
+struct CostsAccumulator {
+    fee: U256,
+    fee_usd: f64,
+    transactions: usize,
+    unpriced_transactions: usize,
+}
+
+impl CostsAccumulator {
+    fn add(&mut self, gas_spending: &GasSpending) {
+        self.fee = self.fee.saturating_add(gas_spending.fee.0);
+        match gas_spending.fee_usd {
+            Some(fee_usd) => self.fee_usd += fee_usd,
+            None => self.unpriced_transactions += 1,
+        }
+        self.transactions += 1;
+    }
+}
+
+impl From<CostsAccumulator> for Costs {
+    fn from(accumulator: CostsAccumulator) -> Self {
+        Self {
+            fee: accumulator.fee.to_string(),
+            fee_usd: accumulator.fee_usd,
+            transactions: accumulator.transactions,
+            unpriced_transactions: accumulator.unpriced_transactions,
+        }
+    }
+}
+
+pub fn handlers(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::GET)
+        .max_age(600);
+
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+
+    path!("costs" / u64)
+        .and(get())
+        .and(query::<CostReportQuery>())
+        .and(with_db_connection_pool)
+        .and_then(get_cost_report)
+        .with(cors)
+}
+
+fn build_cost_report(
+    chain_id: u64,
+    from: u64,
+    to: u64,
+    gas_spendings: Vec<GasSpending>,
+) -> CostReport {
+    let mut costs = CostsAccumulator::default();
+    let mut campaigns: BTreeMap<
+        Option<Address>,
+        (CostsAccumulator, BTreeMap<Address, CostsAccumulator>),
+    > = BTreeMap::new();
+    for gas_spending in gas_spendings.iter() {
+        costs.add(gas_spending);
+        let (campaign_costs, oracles) = campaigns
+            .entry(gas_spending.kpi_token_address.map(|address| address.0))
+            .or_default();
+        campaign_costs.add(gas_spending);
+        oracles
+            .entry(gas_spending.oracle_address.0)
+            .or_default()
+            .add(gas_spending);
+    }
+
+    CostReport {
+        chain_id,
+        from,
+        to,
+        costs: costs.into(),
+        campaigns: campaigns
+            .into_iter()
+            .map(|(kpi_token, (costs, oracles))| CampaignCosts {
+                kpi_token: kpi_token.map(|kpi_token| format!("0x{:x}", kpi_token)),
+                costs: costs.into(),
+                oracles: oracles
+                    .into_iter()
+                    .map(|(address, costs)| OracleCosts {
+                        address: format!("0x{:x}", address),
+                        costs: costs.into(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Gets a chain's answering costs.
+///
+/// Gets the fees paid to answer oracles on a chain in the given period, aggregated per campaign and per oracle.
+#[utoipa::path(
+    get,
+    path = "/costs/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The chain id."),
+        CostReportQuery
+    ),
+    responses(
+        (status = 200, description = "The chain's answering costs.", body = CostReport),
+        (status = 400, description = "The given period is invalid."),
+        (status = 500, description = "The answering costs could not be fetched.")
+    )
+)]
+pub async fn get_cost_report(
+    chain_id: u64,
+    query: CostReportQuery,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let from = query.from.unwrap_or(0);
+    let to = match query.to {
+        Some(to) => to,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+    };
+    if from > to {
+        return Ok(Box::new(http::StatusCode::BAD_REQUEST));
+    }
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    match db::blocking(|| {
+        models::GasSpending::get_all_for_chain_id_between(
+            &mut db_connection,
+            chain_id,
+            UNIX_EPOCH + Duration::from_secs(from),
+            UNIX_EPOCH + Duration::from_secs(to),
+        )
+    }) {
+        Ok(gas_spendings) => Ok(Box::new(reply::json(&build_cost_report(
+            chain_id,
+            from,
+            to,
+            gas_spendings,
+        )))),
+        Err(error) => {
+            tracing::error!("could not get gas spendings: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::UNIX_EPOCH;
+
+    use ethers::types::{Address, H256, U256};
+
+    use crate::db::{models::GasSpending, DbAddress, DbTimestamp, DbTxHash, DbU256};
+
+    use super::{build_cost_report, CampaignCosts, Costs, OracleCosts};
+
+    fn gas_spending(
+        oracle_address: Address,
+        kpi_token_address: Option<Address>,
+        fee: u64,
+        fee_usd: Option<f64>,
+    ) -> GasSpending {
+        GasSpending {
+            chain_id: 100,
+            tx_hash: DbTxHash(H256::random()),
+            oracle_address: DbAddress(oracle_address),
+            fee: DbU256(U256::from(fee)),
+            timestamp: DbTimestamp(UNIX_EPOCH),
+            fee_usd,
+            gas_used: None,
+            effective_gas_price: None,
+            kpi_token_address: kpi_token_address.map(DbAddress),
+        }
+    }
+
+    fn costs(fee: u64, fee_usd: f64, transactions: usize, unpriced: usize) -> Costs {
+        Costs {
+            fee: fee.to_string(),
+            fee_usd,
+            transactions,
+            unpriced_transactions: unpriced,
+        }
+    }
+
+    #[test]
+    fn build_cost_report_aggregation() {
+        let kpi_token = Address::from_low_u64_be(1);
+        let first_oracle = Address::from_low_u64_be(2);
+        let second_oracle = Address::from_low_u64_be(3);
+        let orphan_oracle = Address::from_low_u64_be(4);
+
+        let report = build_cost_report(
+            100,
+            0,
+            10,
+            vec![
+                gas_spending(first_oracle, Some(kpi_token), 100, Some(1.0)),
+                gas_spending(first_oracle, Some(kpi_token), 200, Some(2.0)),
+                gas_spending(second_oracle, Some(kpi_token), 300, None),
+                gas_spending(orphan_oracle, None, 400, Some(4.0)),
+            ],
+        );
+
+        assert_eq!(report.costs, costs(1_000, 7.0, 4, 1));
+        assert_eq!(
+            report.campaigns,
+            vec![
+                CampaignCosts {
+                    kpi_token: None,
+                    costs: costs(400, 4.0, 1, 0),
+                    oracles: vec![OracleCosts {
+                        address: format!("0x{:x}", orphan_oracle),
+                        costs: costs(400, 4.0, 1, 0),
+                    }],
+                },
+                CampaignCosts {
+                    kpi_token: Some(format!("0x{:x}", kpi_token)),
+                    costs: costs(600, 3.0, 3, 1),
+                    oracles: vec![
+                        OracleCosts {
+                            address: format!("0x{:x}", first_oracle),
+                            costs: costs(300, 3.0, 2, 0),
+                        },
+                        OracleCosts {
+                            address: format!("0x{:x}", second_oracle),
+                            costs: costs(300, 0.0, 1, 1),
+                        },
+                    ],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::HashMap, convert::Infallible, str::FromStr, time::SystemTime};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use warp::{get, http, path, query, reply, Filter, Rejection, Reply};
+
+use crate::{
+    answerer::diagnostics::{next_action, NextAction},
+    db::{self, models, DbConnection},
+};
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleDiagnostics {
+    pub address: String,
+    pub next_action: NextAction,
+    pub description: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DiagnosticsQuery {
+    /// Whether to also log the diagnostics on the service's side.
+    pub log: Option<bool>,
+}
+
+pub fn handlers(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::GET)
+        .max_age(600);
+
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+
+    let chain_diagnostics = path!("diagnostics" / u64)
+        .and(get())
+        .and(query::<DiagnosticsQuery>())
+        .and(with_db_connection_pool.clone())
+        .and_then(get_chain_diagnostics);
+
+    let oracle_diagnostics = path!("diagnostics" / u64 / String)
+        .and(get())
+        .and(query::<DiagnosticsQuery>())
+        .and(with_db_connection_pool)
+        .and_then(get_oracle_diagnostics);
+
+    chain_diagnostics.or(oracle_diagnostics).with(cors)
+}
+
+fn get_diagnostics(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: u64,
+    address: Option<Address>,
+) -> anyhow::Result<Vec<OracleDiagnostics>> {
+    let mut db_connection = db_connection_pool.get()?;
+    let active_oracles = match address {
+        Some(address) => models::ActiveOracle::get(&mut db_connection, chain_id, address)?
+            .into_iter()
+            .collect(),
+        None => models::ActiveOracle::get_all_for_chain_id(&mut db_connection, chain_id)?,
+    };
+    let answer_overrides =
+        models::AnswerOverride::get_all_for_chain_id(&mut db_connection, chain_id)?
+            .into_iter()
+            .map(|answer_override| (answer_override.oracle_address.0, answer_override))
+            .collect::<HashMap<_, _>>();
+    let answer_reviews = models::AnswerReview::get_all_for_chain_id(&mut db_connection, chain_id)?
+        .into_iter()
+        .map(|answer_review| (answer_review.oracle_address.0, answer_review))
+        .collect::<HashMap<_, _>>();
+
+    let now = SystemTime::now();
+    Ok(active_oracles
+        .iter()
+        .map(|active_oracle| {
+            let next_action = next_action(
+                active_oracle,
+                answer_overrides.get(&active_oracle.address.0),
+                answer_reviews.get(&active_oracle.address.0),
+                now,
+            );
+            OracleDiagnostics {
+                address: format!("0x{:x}", active_oracle.address.0),
+                description: next_action.description(),
+                next_action,
+            }
+        })
+        .collect())
+}
+
+fn log_diagnostics(chain_id: u64, diagnostics: &[OracleDiagnostics]) {
+    for oracle_diagnostics in diagnostics.iter() {
+        tracing::info!(
+            "oracle {} on chain {}: {}",
+            oracle_diagnostics.address,
+            chain_id,
+            oracle_diagnostics.description
+        );
+    }
+}
+
+/// Gets a chain's oracles diagnostics.
+///
+/// Gets the next action the answerer will take for every active oracle on a chain, and why.
+#[utoipa::path(
+    get,
+    path = "/diagnostics/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The chain id."),
+        DiagnosticsQuery
+    ),
+    responses(
+        (status = 200, description = "The diagnostics of the chain's active oracles.", body = [OracleDiagnostics]),
+        (status = 500, description = "The diagnostics could not be computed.")
+    )
+)]
+pub async fn get_chain_diagnostics(
+    chain_id: u64,
+    query: DiagnosticsQuery,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    match db::blocking(|| get_diagnostics(db_connection_pool, chain_id, None)) {
+        Ok(diagnostics) => {
+            if query.log.unwrap_or(false) {
+                log_diagnostics(chain_id, &diagnostics);
+            }
+            Ok(Box::new(reply::json(&diagnostics)))
+        }
+        Err(error) => {
+            tracing::error!("could not get oracles diagnostics: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Gets an oracle's diagnostics.
+///
+/// Gets the next action the answerer will take for an active oracle, and why.
+#[utoipa::path(
+    get,
+    path = "/diagnostics/{chain_id}/{address}",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address."),
+        DiagnosticsQuery
+    ),
+    responses(
+        (status = 200, description = "The oracle's diagnostics.", body = OracleDiagnostics),
+        (status = 400, description = "The given oracle address is invalid."),
+        (status = 404, description = "No active oracle exists for the given address."),
+        (status = 500, description = "The diagnostics could not be computed.")
+    )
+)]
+pub async fn get_oracle_diagnostics(
+    chain_id: u64,
+    address: String,
+    query: DiagnosticsQuery,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    match db::blocking(|| get_diagnostics(db_connection_pool, chain_id, Some(address))) {
+        Ok(diagnostics) => {
+            if query.log.unwrap_or(false) {
+                log_diagnostics(chain_id, &diagnostics);
+            }
+            match diagnostics.into_iter().next() {
+                Some(oracle_diagnostics) => Ok(Box::new(reply::json(&oracle_diagnostics))),
+                None => Ok(Box::new(http::StatusCode::NOT_FOUND)),
+            }
+        }
+        Err(error) => {
+            tracing::error!("could not get oracle diagnostics: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
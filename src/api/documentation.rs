@@ -9,7 +9,11 @@ use warp::{
     redirect, Filter, Rejection, Reply,
 };
 
-use super::{super::specification, specifications};
+use super::{
+    super::{answerer, events as oracle_events, logging, specification},
+    backfills, chains, checkpoints, costs, diagnostics, events, graphql, health, oracles,
+    overrides, pagination, snapshots, specifications, status,
+};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -18,8 +22,70 @@ use super::{super::specification, specifications};
         description = "DefiLlama answerer API",
         contact(name = "Carrot Labs", email = "tech@carrot-labs.xyz",)
     ),
-    paths(specifications::validate_specification),
-    components(schemas(specification::Specification, specification::handlers::tvl::TvlPayload))
+    servers((url = "/v1", description = "Current API version")),
+    paths(
+        health::get_health,
+        health::get_readiness,
+        status::get_status,
+        specifications::validate_specification,
+        snapshots::get_snapshot,
+        snapshots::replay_snapshot,
+        overrides::get_answer_override,
+        overrides::propose_answer_override,
+        overrides::approve_answer_override,
+        diagnostics::get_chain_diagnostics,
+        diagnostics::get_oracle_diagnostics,
+        oracles::list_oracles,
+        oracles::get_oracle,
+        oracles::remove_oracle,
+        costs::get_cost_report,
+        events::stream_events,
+        graphql::execute_query,
+        backfills::start_backfill,
+        checkpoints::get_checkpoints,
+        checkpoints::get_checkpoint,
+        checkpoints::update_checkpoint,
+        checkpoints::rewind_checkpoint,
+        chains::get_chains,
+        chains::add_chain,
+        chains::remove_chain
+    ),
+    components(schemas(
+        health::Health,
+        health::ChainHealth,
+        health::AnswererHealth,
+        health::BalanceStatus,
+        status::Status,
+        status::ChainStatus,
+        status::AnswererStatus,
+        logging::ChainError,
+        specification::Specification,
+        specification::handlers::tvl::TvlPayload,
+        snapshots::Snapshot,
+        snapshots::SnapshotResponse,
+        snapshots::SnapshotReplay,
+        overrides::AnswerOverride,
+        overrides::AnswerOverrideProposal,
+        diagnostics::OracleDiagnostics,
+        answerer::diagnostics::NextAction,
+        oracles::OracleSummary,
+        pagination::OracleSummaryPage,
+        oracles::OracleDetail,
+        oracles::OracleStatus,
+        oracles::OracleAnswer,
+        oracles::OracleAuditLogEntry,
+        oracles::OracleRemoval,
+        costs::CostReport,
+        costs::CampaignCosts,
+        costs::OracleCosts,
+        costs::Costs,
+        oracle_events::OracleEvent,
+        oracle_events::OracleEventKind,
+        graphql::GraphQlRequest,
+        backfills::BackfillRequest,
+        checkpoints::Checkpoint,
+        checkpoints::CheckpointUpdate
+    ))
 )]
 struct ApiDoc;
 
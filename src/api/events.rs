@@ -0,0 +1,82 @@
+use std::convert::Infallible;
+
+use futures_util::stream;
+use serde::Deserialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use utoipa::IntoParams;
+use warp::{get, http, path, query, sse, Filter, Rejection, Reply};
+
+use crate::events::{self, OracleEvent};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct EventsQuery {
+    /// Only streams the events of the given chain.
+    pub chain_id: Option<u64>,
+}
+
+pub fn handlers() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::GET)
+        .max_age(600);
+
+    path!("events")
+        .and(get())
+        .and(query::<EventsQuery>())
+        .and_then(stream_events)
+        .with(cors)
+}
+
+// waits for the next event matching the query. slow clients miss the events that
+// don't fit in the channel, in which case a warning is logged and streaming goes on
+async fn next_event(
+    events: &mut Receiver<OracleEvent>,
+    query: &EventsQuery,
+) -> Option<OracleEvent> {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if query
+                    .chain_id
+                    .is_none_or(|chain_id| chain_id == event.chain_id)
+                {
+                    return Some(event);
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "events stream client is lagging behind, {} event(s) were skipped",
+                    skipped
+                );
+            }
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Streams oracle lifecycle events.
+///
+/// Pushes oracle lifecycle events as server-sent events as soon as they happen, optionally only for a single chain. Each event is named after its type and carries the same JSON payload delivered to webhooks.
+#[utoipa::path(
+    get,
+    path = "/events",
+    params(EventsQuery),
+    responses(
+        (status = 200, description = "The stream of oracle lifecycle events.", content_type = "text/event-stream", body = OracleEvent)
+    )
+)]
+pub async fn stream_events(query: EventsQuery) -> Result<Box<dyn Reply>, Infallible> {
+    let events = stream::unfold(
+        (events::subscribe(), query),
+        |(mut events, query)| async move {
+            let event = next_event(&mut events, &query).await?;
+            let sse_event = sse::Event::default()
+                .event(event.kind.name())
+                .json_data(&event);
+            Some((sse_event, (events, query)))
+        },
+    );
+    Ok(Box::new(sse::reply(sse::keep_alive().stream(events))))
+}
@@ -0,0 +1,507 @@
+use std::{
+    convert::Infallible,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context as _;
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Json, Object, Schema, SimpleObject,
+    Variables,
+};
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::{Address, H256};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use warp::{body, http, path, post, reply, Filter, Rejection, Reply};
+
+use crate::{
+    commons::{
+        GRAPHQL_DEFAULT_PAGE_SIZE, GRAPHQL_MAX_COMPLEXITY, GRAPHQL_MAX_DEPTH, GRAPHQL_MAX_PAGE_SIZE,
+    },
+    db::{
+        self,
+        models::{self, RecordFilter},
+        DbConnection,
+    },
+    specification::Specification,
+};
+
+pub type AnalyticsSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlRequest {
+    pub query: String,
+    pub operation_name: Option<String>,
+    pub variables: Option<serde_json::Value>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct ActiveOracle {
+    pub chain_id: u64,
+    pub address: String,
+    pub measurement_timestamp: u64,
+    pub expiration: Option<u64>,
+    pub specification: Json<Specification>,
+    pub answer: Option<String>,
+    pub answer_tx_hash: Option<String>,
+    pub answer_tx_submitted_at: Option<u64>,
+    pub answer_attempts: i32,
+    pub next_answer_attempt: Option<u64>,
+    pub defillama_snapshot_id: Option<i64>,
+    #[graphql(skip)]
+    pub raw_address: Address,
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct ArchivedOracle {
+    pub chain_id: u64,
+    pub address: String,
+    pub measurement_timestamp: u64,
+    pub expiration: Option<u64>,
+    pub specification: Json<Specification>,
+    pub answer: Option<String>,
+    pub answer_attempts: i32,
+    pub reason: String,
+    pub archived_at: u64,
+    #[graphql(skip)]
+    pub raw_address: Address,
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Answer {
+    pub chain_id: u64,
+    pub oracle_address: String,
+    pub measurement_timestamp: u64,
+    pub answer: Option<String>,
+    pub answer_attempts: i32,
+    pub answer_tx_hash: String,
+    pub answer_tx_submitted_at: Option<u64>,
+    pub block_number: Option<i64>,
+    pub defillama_snapshot_id: Option<i64>,
+    pub proof_bundle_cid: Option<String>,
+    pub answered_at: u64,
+    #[graphql(skip)]
+    pub raw_answer_tx_hash: H256,
+}
+
+#[derive(SimpleObject)]
+pub struct GasCost {
+    pub chain_id: u64,
+    pub tx_hash: String,
+    pub oracle_address: String,
+    pub kpi_token_address: Option<String>,
+    /// The fee in wei.
+    pub fee: String,
+    pub fee_usd: Option<f64>,
+    pub gas_used: Option<String>,
+    pub effective_gas_price: Option<String>,
+    pub timestamp: u64,
+}
+
+#[derive(SimpleObject)]
+pub struct Checkpoint {
+    pub chain_id: u64,
+    pub block_number: i64,
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn from_unix_timestamp(timestamp: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(timestamp)
+}
+
+fn parse_address(address: &str) -> async_graphql::Result<Address> {
+    Address::from_str(address)
+        .map_err(|_| async_graphql::Error::new(format!("invalid address {}", address)))
+}
+
+fn record_filter(
+    chain_id: Option<u64>,
+    oracle_address: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> async_graphql::Result<RecordFilter> {
+    let limit = limit.unwrap_or(GRAPHQL_DEFAULT_PAGE_SIZE);
+    if !(1..=GRAPHQL_MAX_PAGE_SIZE).contains(&limit) {
+        return Err(async_graphql::Error::new(format!(
+            "limit must be between 1 and {}",
+            GRAPHQL_MAX_PAGE_SIZE
+        )));
+    }
+    let offset = offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(async_graphql::Error::new("offset can't be negative"));
+    }
+    Ok(RecordFilter {
+        chain_id,
+        oracle_address: oracle_address.as_deref().map(parse_address).transpose()?,
+        from: from.map(from_unix_timestamp),
+        to: to.map(from_unix_timestamp),
+        limit,
+        offset,
+    })
+}
+
+// database errors are logged and not exposed to clients
+fn load<T>(
+    ctx: &Context<'_>,
+    work: impl FnOnce(&mut DbConnection) -> anyhow::Result<T>,
+) -> async_graphql::Result<T> {
+    let db_connection_pool = ctx.data::<Pool<ConnectionManager<DbConnection>>>()?;
+    db::blocking(|| {
+        let mut db_connection = db_connection_pool
+            .get()
+            .context("could not get new connection from pool")?;
+        work(&mut db_connection)
+    })
+    .map_err(|error| {
+        tracing::error!("could not resolve graphql query: {:#}", error);
+        async_graphql::Error::new("could not fetch data")
+    })
+}
+
+impl From<models::ActiveOracle> for ActiveOracle {
+    fn from(oracle: models::ActiveOracle) -> Self {
+        Self {
+            chain_id: oracle.chain_id as u64,
+            address: format!("0x{:x}", oracle.address.0),
+            measurement_timestamp: to_unix_timestamp(*oracle.measurement_timestamp),
+            expiration: oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            specification: Json(oracle.specification),
+            answer: oracle.answer.map(|answer| answer.0.to_string()),
+            answer_tx_hash: oracle
+                .answer_tx_hash
+                .map(|answer_tx_hash| format!("0x{:x}", answer_tx_hash.0)),
+            answer_tx_submitted_at: oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer_attempts: oracle.answer_attempts,
+            next_answer_attempt: oracle
+                .next_answer_attempt
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            defillama_snapshot_id: oracle.defillama_snapshot_id,
+            raw_address: oracle.address.0,
+        }
+    }
+}
+
+impl From<models::ArchivedOracle> for ArchivedOracle {
+    fn from(oracle: models::ArchivedOracle) -> Self {
+        Self {
+            chain_id: oracle.chain_id as u64,
+            address: format!("0x{:x}", oracle.address.0),
+            measurement_timestamp: to_unix_timestamp(*oracle.measurement_timestamp),
+            expiration: oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            specification: Json(oracle.specification),
+            answer: oracle.answer.map(|answer| answer.0.to_string()),
+            answer_attempts: oracle.answer_attempts,
+            reason: oracle.reason,
+            archived_at: to_unix_timestamp(*oracle.archived_at),
+            raw_address: oracle.address.0,
+        }
+    }
+}
+
+impl From<models::AnsweredOracle> for Answer {
+    fn from(answered_oracle: models::AnsweredOracle) -> Self {
+        Self {
+            chain_id: answered_oracle.chain_id as u64,
+            oracle_address: format!("0x{:x}", answered_oracle.address.0),
+            measurement_timestamp: to_unix_timestamp(*answered_oracle.measurement_timestamp),
+            answer: answered_oracle.answer.map(|answer| answer.0.to_string()),
+            answer_attempts: answered_oracle.answer_attempts,
+            answer_tx_hash: format!("0x{:x}", answered_oracle.answer_tx_hash.0),
+            answer_tx_submitted_at: answered_oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            block_number: answered_oracle.block_number,
+            defillama_snapshot_id: answered_oracle.defillama_snapshot_id,
+            proof_bundle_cid: answered_oracle.proof_bundle_cid,
+            answered_at: to_unix_timestamp(*answered_oracle.answered_at),
+            raw_answer_tx_hash: answered_oracle.answer_tx_hash.0,
+        }
+    }
+}
+
+impl From<models::GasSpending> for GasCost {
+    fn from(gas_spending: models::GasSpending) -> Self {
+        Self {
+            chain_id: gas_spending.chain_id as u64,
+            tx_hash: format!("0x{:x}", gas_spending.tx_hash.0),
+            oracle_address: format!("0x{:x}", gas_spending.oracle_address.0),
+            kpi_token_address: gas_spending
+                .kpi_token_address
+                .map(|kpi_token_address| format!("0x{:x}", kpi_token_address.0)),
+            fee: gas_spending.fee.0.to_string(),
+            fee_usd: gas_spending.fee_usd,
+            gas_used: gas_spending.gas_used.map(|gas_used| gas_used.0.to_string()),
+            effective_gas_price: gas_spending
+                .effective_gas_price
+                .map(|effective_gas_price| effective_gas_price.0.to_string()),
+            timestamp: to_unix_timestamp(*gas_spending.timestamp),
+        }
+    }
+}
+
+fn oracle_answers(
+    ctx: &Context<'_>,
+    chain_id: u64,
+    address: Address,
+) -> async_graphql::Result<Vec<Answer>> {
+    let answered_oracles = load(ctx, |db_connection| {
+        models::AnsweredOracle::get_all_for_oracle(db_connection, chain_id, address)
+    })?;
+    Ok(answered_oracles.into_iter().map(Answer::from).collect())
+}
+
+fn oracle_gas_costs(
+    ctx: &Context<'_>,
+    chain_id: u64,
+    address: Address,
+) -> async_graphql::Result<Vec<GasCost>> {
+    let filter = RecordFilter {
+        chain_id: Some(chain_id),
+        oracle_address: Some(address),
+        limit: GRAPHQL_MAX_PAGE_SIZE,
+        ..Default::default()
+    };
+    let gas_spendings = load(ctx, |db_connection| {
+        models::GasSpending::get_all_filtered(db_connection, &filter, None)
+    })?;
+    Ok(gas_spendings.into_iter().map(GasCost::from).collect())
+}
+
+#[ComplexObject]
+impl ActiveOracle {
+    /// The oracle's past answers, from the earliest.
+    async fn answers(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Answer>> {
+        oracle_answers(ctx, self.chain_id, self.raw_address)
+    }
+
+    /// The gas paid to answer the oracle.
+    async fn gas_costs(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<GasCost>> {
+        oracle_gas_costs(ctx, self.chain_id, self.raw_address)
+    }
+}
+
+#[ComplexObject]
+impl ArchivedOracle {
+    /// The oracle's past answers, from the earliest.
+    async fn answers(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Answer>> {
+        oracle_answers(ctx, self.chain_id, self.raw_address)
+    }
+
+    /// The gas paid to answer the oracle.
+    async fn gas_costs(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<GasCost>> {
+        oracle_gas_costs(ctx, self.chain_id, self.raw_address)
+    }
+}
+
+#[ComplexObject]
+impl Answer {
+    /// The gas paid for the answer transaction, if it was recorded.
+    async fn gas_cost(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<GasCost>> {
+        let gas_spending = load(ctx, |db_connection| {
+            models::GasSpending::get(db_connection, self.chain_id, self.raw_answer_tx_hash)
+        })?;
+        Ok(gas_spending.map(GasCost::from))
+    }
+}
+
+pub struct Query;
+
+// the from and to arguments are unix timestamps delimiting the half-open range
+// [from, to) each record's reference timestamp must fall in
+#[Object]
+impl Query {
+    /// Oracles waiting to be answered, filtered by measurement timestamp.
+    #[allow(clippy::too_many_arguments)]
+    async fn active_oracles(
+        &self,
+        ctx: &Context<'_>,
+        chain_id: Option<u64>,
+        address: Option<String>,
+        from: Option<u64>,
+        to: Option<u64>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<Vec<ActiveOracle>> {
+        let filter = record_filter(chain_id, address, from, to, limit, offset)?;
+        let oracles = load(ctx, |db_connection| {
+            models::ActiveOracle::get_all_filtered(db_connection, &filter)
+        })?;
+        Ok(oracles.into_iter().map(ActiveOracle::from).collect())
+    }
+
+    /// Oracles that are not active anymore, filtered by archival timestamp.
+    #[allow(clippy::too_many_arguments)]
+    async fn archived_oracles(
+        &self,
+        ctx: &Context<'_>,
+        chain_id: Option<u64>,
+        address: Option<String>,
+        reason: Option<String>,
+        from: Option<u64>,
+        to: Option<u64>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<Vec<ArchivedOracle>> {
+        let filter = record_filter(chain_id, address, from, to, limit, offset)?;
+        let oracles = load(ctx, |db_connection| {
+            models::ArchivedOracle::get_all_filtered(db_connection, &filter, reason.as_deref())
+        })?;
+        Ok(oracles.into_iter().map(ArchivedOracle::from).collect())
+    }
+
+    /// Answers submitted on chain, filtered by answer timestamp.
+    #[allow(clippy::too_many_arguments)]
+    async fn answers(
+        &self,
+        ctx: &Context<'_>,
+        chain_id: Option<u64>,
+        oracle_address: Option<String>,
+        from: Option<u64>,
+        to: Option<u64>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<Vec<Answer>> {
+        let filter = record_filter(chain_id, oracle_address, from, to, limit, offset)?;
+        let answered_oracles = load(ctx, |db_connection| {
+            models::AnsweredOracle::get_all_filtered(db_connection, &filter)
+        })?;
+        Ok(answered_oracles.into_iter().map(Answer::from).collect())
+    }
+
+    /// Gas paid for answer transactions, filtered by payment timestamp.
+    #[allow(clippy::too_many_arguments)]
+    async fn gas_costs(
+        &self,
+        ctx: &Context<'_>,
+        chain_id: Option<u64>,
+        oracle_address: Option<String>,
+        kpi_token_address: Option<String>,
+        from: Option<u64>,
+        to: Option<u64>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<Vec<GasCost>> {
+        let filter = record_filter(chain_id, oracle_address, from, to, limit, offset)?;
+        let kpi_token_address = kpi_token_address
+            .as_deref()
+            .map(parse_address)
+            .transpose()?;
+        let gas_spendings = load(ctx, |db_connection| {
+            models::GasSpending::get_all_filtered(db_connection, &filter, kpi_token_address)
+        })?;
+        Ok(gas_spendings.into_iter().map(GasCost::from).collect())
+    }
+
+    /// The chains' stored checkpoints.
+    async fn checkpoints(
+        &self,
+        ctx: &Context<'_>,
+        chain_id: Option<u64>,
+    ) -> async_graphql::Result<Vec<Checkpoint>> {
+        let checkpoints = load(ctx, |db_connection| match chain_id {
+            Some(chain_id) => Ok(
+                models::Checkpoint::get_for_chain_id(db_connection, chain_id)?
+                    .into_iter()
+                    .collect(),
+            ),
+            None => models::Checkpoint::get_all(db_connection),
+        })?;
+        Ok(checkpoints
+            .into_iter()
+            .map(|checkpoint| Checkpoint {
+                chain_id: checkpoint.chain_id as u64,
+                block_number: checkpoint.block_number,
+            })
+            .collect())
+    }
+}
+
+pub fn schema(db_connection_pool: Pool<ConnectionManager<DbConnection>>) -> AnalyticsSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(db_connection_pool)
+        .limit_depth(GRAPHQL_MAX_DEPTH)
+        .limit_complexity(GRAPHQL_MAX_COMPLEXITY)
+        .finish()
+}
+
+pub fn handlers(
+    enabled: bool,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::POST)
+        .allow_headers(["Content-Type"])
+        .max_age(600);
+
+    let schema = schema(db_connection_pool);
+    let with_schema = warp::any().map(move || schema.clone());
+
+    // the route is rejected as not found when graphql is disabled
+    let with_enabled = warp::any()
+        .and_then(move || async move {
+            if enabled {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one();
+
+    path!("graphql")
+        .and(post())
+        .and(with_enabled)
+        .and(body::json())
+        .and(with_schema)
+        .and_then(execute_query)
+        .with(cors)
+}
+
+/// Runs a GraphQL query.
+///
+/// Runs a read-only GraphQL query over active and archived oracles, answers, gas costs and checkpoints, with filtering and pagination on every collection and nested answers and gas costs on oracles. Only available when GraphQL is enabled in the api configuration.
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    request_body = GraphQlRequest,
+    responses(
+        (status = 200, description = "The query's data along with any error that happened while resolving it.", content_type = "application/json"),
+        (status = 400, description = "The request is malformed."),
+        (status = 404, description = "GraphQL is not enabled.")
+    )
+)]
+pub async fn execute_query(
+    request: GraphQlRequest,
+    schema: AnalyticsSchema,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let mut graphql_request = async_graphql::Request::new(request.query);
+    if let Some(operation_name) = request.operation_name {
+        graphql_request = graphql_request.operation_name(operation_name);
+    }
+    if let Some(variables) = request.variables {
+        graphql_request = graphql_request.variables(Variables::from_json(variables));
+    }
+    Ok(Box::new(reply::json(
+        &schema.execute(graphql_request).await,
+    )))
+}
@@ -0,0 +1,242 @@
+use std::{convert::Infallible, sync::Arc};
+
+use diesel::{
+    r2d2::{ConnectionManager, Pool},
+    RunQueryDsl,
+};
+use ethers::{providers::Middleware, utils};
+use serde::Serialize;
+use tokio::time::timeout;
+use utoipa::ToSchema;
+use warp::{get, http, path, reply, Filter, Rejection, Reply};
+
+use crate::defillama::DefiLlamaClient;
+use crate::{
+    chains::Chains,
+    commons::HEALTH_CHECK_TIMEOUT,
+    db::{self, DbConnection},
+};
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BalanceStatus {
+    Ok,
+    Low,
+    // not checked yet
+    Unknown,
+    // no minimum balance configured
+    Unmonitored,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswererHealth {
+    pub address: String,
+    pub balance: Option<String>,
+    pub balance_status: BalanceStatus,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainHealth {
+    pub chain_id: u64,
+    pub ready: bool,
+    pub rpc_reachable: bool,
+    pub head_block: Option<u64>,
+    pub last_processed_block: Option<u64>,
+    pub leading: bool,
+    pub answerers: Vec<AnswererHealth>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+    pub live: bool,
+    pub ready: bool,
+    pub database_reachable: bool,
+    pub defillama_reachable: bool,
+    pub chains: Vec<ChainHealth>,
+}
+
+pub fn handlers(
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::GET)
+        .max_age(600);
+
+    let with_chains = warp::any().map(move || chains.clone());
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+    let with_defillama = warp::any().map(move || defillama.clone());
+
+    let health = path!("health")
+        .and(get())
+        .and(with_chains.clone())
+        .and(with_db_connection_pool.clone())
+        .and(with_defillama.clone())
+        .and_then(get_health);
+
+    let readiness = path!("health" / "ready")
+        .and(get())
+        .and(with_chains)
+        .and(with_db_connection_pool)
+        .and(with_defillama)
+        .and_then(get_readiness);
+
+    health.or(readiness).with(cors)
+}
+
+fn check_database(db_connection_pool: Pool<ConnectionManager<DbConnection>>) -> bool {
+    let result = db_connection_pool
+        .get_timeout(HEALTH_CHECK_TIMEOUT)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut db_connection| {
+            diesel::sql_query("SELECT 1").execute(&mut db_connection)?;
+            Ok(())
+        });
+    if let Err(error) = &result {
+        tracing::warn!("database health check failed: {:#}", error);
+    }
+    result.is_ok()
+}
+
+async fn check_defillama(defillama: DefiLlamaClient) -> bool {
+    match timeout(HEALTH_CHECK_TIMEOUT, defillama.ping()).await {
+        Ok(Ok(())) => true,
+        Ok(Err(error)) => {
+            tracing::warn!("defillama health check failed: {:#}", error);
+            false
+        }
+        Err(_) => {
+            tracing::warn!("defillama health check timed out");
+            false
+        }
+    }
+}
+
+async fn check_chain(chains: &Chains, chain_id: u64) -> Option<ChainHealth> {
+    let listener = chains.listener(chain_id)?;
+    let provider = chains.provider(chain_id)?;
+    let answerer_keys = chains.answerer_keys(chain_id)?;
+
+    let rpc_reachable = match timeout(HEALTH_CHECK_TIMEOUT, provider.get_block_number()).await {
+        Ok(Ok(_)) => true,
+        Ok(Err(error)) => {
+            tracing::warn!("rpc health check failed on chain {}: {:#}", chain_id, error);
+            false
+        }
+        Err(_) => {
+            tracing::warn!("rpc health check timed out on chain {}", chain_id);
+            false
+        }
+    };
+
+    let answerers: Vec<_> = answerer_keys
+        .iter()
+        .map(|answerer_key| {
+            let balance = answerer_key
+                .balance()
+                .and_then(|answerer_balance| answerer_balance.get());
+            let balance_status = match answerer_key.balance() {
+                None => BalanceStatus::Unmonitored,
+                Some(_) if balance.is_none() => BalanceStatus::Unknown,
+                Some(answerer_balance) if answerer_balance.is_low() => BalanceStatus::Low,
+                Some(_) => BalanceStatus::Ok,
+            };
+            AnswererHealth {
+                address: format!("0x{:x}", answerer_key.address()),
+                balance: balance.map(utils::format_ether),
+                balance_status,
+            }
+        })
+        .collect();
+
+    // answers can still be submitted as long as a single key has funds
+    let ready = rpc_reachable
+        && answerers
+            .iter()
+            .any(|answerer| answerer.balance_status != BalanceStatus::Low);
+    Some(ChainHealth {
+        chain_id,
+        ready,
+        rpc_reachable,
+        head_block: listener.present_head(),
+        last_processed_block: listener.last_processed_block(),
+        leading: listener.is_leading(),
+        answerers,
+    })
+}
+
+async fn check_health(
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+) -> Health {
+    let database_reachable = db::blocking(|| check_database(db_connection_pool));
+    let defillama_reachable = check_defillama(defillama).await;
+    let mut chains_health = Vec::new();
+    for chain_id in chains.chain_ids() {
+        // the chain might have been removed in the meantime
+        if let Some(chain_health) = check_chain(&chains, chain_id).await {
+            chains_health.push(chain_health);
+        }
+    }
+
+    Health {
+        live: true,
+        ready: database_reachable
+            && defillama_reachable
+            && chains_health.iter().all(|chain_health| chain_health.ready),
+        database_reachable,
+        defillama_reachable,
+        chains: chains_health,
+    }
+}
+
+/// Gets the service's health.
+///
+/// Checks whether the database, DefiLlama and every running chain's rpc are reachable, along with the chains' scanning progress and answerer balances. Always replies with a 200 status as long as the service is up, making it suitable for liveness probes.
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "The service's health.", body = Health)
+    )
+)]
+pub async fn get_health(
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+) -> Result<Box<dyn Reply>, Infallible> {
+    Ok(Box::new(reply::json(
+        &check_health(chains, db_connection_pool, defillama).await,
+    )))
+}
+
+/// Gets the service's readiness.
+///
+/// Runs the same checks as the health endpoint, but replies with a 503 status unless the database and DefiLlama are reachable and every running chain's rpc is reachable with at least one funded answerer, making it suitable for readiness probes.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "The service is ready.", body = Health),
+        (status = 503, description = "The service is not ready.", body = Health)
+    )
+)]
+pub async fn get_readiness(
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let health = check_health(chains, db_connection_pool, defillama).await;
+    let status = if health.ready {
+        http::StatusCode::OK
+    } else {
+        http::StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok(Box::new(reply::with_status(reply::json(&health), status)))
+}
@@ -0,0 +1,174 @@
+use std::{convert::Infallible, time::Instant};
+
+use ethers::types::H128;
+use warp::{
+    body::BodyDeserializeError,
+    cors::CorsForbidden,
+    header, http,
+    hyper::{
+        header::{HeaderValue, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS},
+        HeaderMap,
+    },
+    path::{self, FullPath},
+    reject::{
+        InvalidHeader, InvalidQuery, LengthRequired, MethodNotAllowed, MissingHeader,
+        PayloadTooLarge, UnsupportedMediaType,
+    },
+    reply::{self, Response},
+    Filter, Rejection, Reply,
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// ids set by a proxy in front of the api are kept, so that they can be followed
+// across services, as long as they look reasonable
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            !value.is_empty()
+                && value.len() <= 64
+                && value
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("{:x}", H128::random()))
+}
+
+// replicates warp's default handling of rejections, which can't be reused, so that
+// requests that didn't match any route are also logged and tagged
+async fn handle_rejection(rejection: Rejection) -> Result<Response, Infallible> {
+    let (status, message) = if rejection.is_not_found() {
+        (http::StatusCode::NOT_FOUND, String::new())
+    } else if let Some(error) = rejection.find::<MethodNotAllowed>() {
+        (http::StatusCode::METHOD_NOT_ALLOWED, error.to_string())
+    } else if let Some(error) = rejection.find::<InvalidHeader>() {
+        (http::StatusCode::BAD_REQUEST, error.to_string())
+    } else if let Some(error) = rejection.find::<MissingHeader>() {
+        (http::StatusCode::BAD_REQUEST, error.to_string())
+    } else if let Some(error) = rejection.find::<InvalidQuery>() {
+        (http::StatusCode::BAD_REQUEST, error.to_string())
+    } else if let Some(error) = rejection.find::<BodyDeserializeError>() {
+        (http::StatusCode::BAD_REQUEST, error.to_string())
+    } else if let Some(error) = rejection.find::<LengthRequired>() {
+        (http::StatusCode::LENGTH_REQUIRED, error.to_string())
+    } else if let Some(error) = rejection.find::<PayloadTooLarge>() {
+        (http::StatusCode::PAYLOAD_TOO_LARGE, error.to_string())
+    } else if let Some(error) = rejection.find::<UnsupportedMediaType>() {
+        (http::StatusCode::UNSUPPORTED_MEDIA_TYPE, error.to_string())
+    } else if let Some(error) = rejection.find::<CorsForbidden>() {
+        (http::StatusCode::FORBIDDEN, error.to_string())
+    } else {
+        tracing::error!("unhandled rejection: {:?}", rejection);
+        (
+            http::StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unhandled rejection: {:?}", rejection),
+        )
+    };
+    Ok(reply::with_status(message, status).into_response())
+}
+
+// tags every response with a request id, also logged along with the request's
+// outcome so that errors reported by clients can be found in the logs
+pub fn with_request_logging<F, R>(
+    routes: F,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::any()
+        .map(Instant::now)
+        .and(header::headers_cloned())
+        .and(warp::method())
+        .and(path::full())
+        .and(
+            routes
+                .map(|reply: R| reply.into_response())
+                .recover(handle_rejection)
+                .unify(),
+        )
+        .map(
+            |start: Instant,
+             headers: HeaderMap,
+             method: http::Method,
+             path: FullPath,
+             mut response: Response| {
+                let request_id = request_id(&headers);
+                let status = response.status();
+                let latency_ms = start.elapsed().as_millis() as u64;
+                if status.is_server_error() {
+                    tracing::warn!(
+                        request_id,
+                        method = %method,
+                        path = path.as_str(),
+                        status = status.as_u16(),
+                        latency_ms,
+                        "api request failed"
+                    );
+                } else {
+                    tracing::info!(
+                        request_id,
+                        method = %method,
+                        path = path.as_str(),
+                        status = status.as_u16(),
+                        latency_ms,
+                        "api request handled"
+                    );
+                }
+
+                let headers = response.headers_mut();
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    headers.insert(REQUEST_ID_HEADER, value);
+                }
+                // cross origin clients can't read the id otherwise
+                if headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN) {
+                    headers.insert(
+                        ACCESS_CONTROL_EXPOSE_HEADERS,
+                        HeaderValue::from_static(REQUEST_ID_HEADER),
+                    );
+                }
+                response
+            },
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use warp::{hyper::HeaderMap, Filter};
+
+    use super::{request_id, with_request_logging, REQUEST_ID_HEADER};
+
+    #[test]
+    fn keep_valid_request_ids() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, "abc-123_DEF".parse().unwrap());
+        assert_eq!(request_id(&headers), "abc-123_DEF");
+
+        // invalid ids are replaced
+        headers.insert(REQUEST_ID_HEADER, "abc 123".parse().unwrap());
+        let generated = request_id(&headers);
+        assert_eq!(generated.len(), 32);
+        assert_ne!(generated, request_id(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn tag_responses() {
+        let routes = with_request_logging(warp::path!("foo").map(warp::reply));
+
+        let response = warp::test::request()
+            .path("/foo")
+            .header(REQUEST_ID_HEADER, "foo-request")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "foo-request");
+
+        // rejections are tagged too
+        let response = warp::test::request().path("/bar").reply(&routes).await;
+        assert_eq!(response.status(), 404);
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+    }
+}
@@ -0,0 +1,21 @@
+use warp::{get, http, path, reply, Filter, Rejection, Reply};
+
+use crate::metrics;
+
+pub fn handlers() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    path!("metrics").and(get()).map(|| match metrics::encode() {
+        Ok(encoded) => reply::with_header(
+            reply::with_status(encoded, http::StatusCode::OK),
+            http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4",
+        ),
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            reply::with_header(
+                reply::with_status(String::new(), http::StatusCode::INTERNAL_SERVER_ERROR),
+                http::header::CONTENT_TYPE,
+                "text/plain; version=0.0.4",
+            )
+        }
+    })
+}
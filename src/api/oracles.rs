@@ -0,0 +1,489 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use warp::{body, delete, get, http, path, query, reply, Filter, Rejection, Reply};
+
+use crate::{
+    answerer::{
+        diagnostics::{next_action, NextAction},
+        reverts,
+    },
+    commons::ANSWER_CLAIM_DURATION,
+    db::{
+        self,
+        models::{self, ActiveOracleSort},
+        DbConnection,
+    },
+    events::{self, OracleEventKind},
+    specification::Specification,
+};
+
+use super::{
+    pagination::{OracleSummaryPage, PageQuery},
+    with_operator,
+};
+
+#[derive(Serialize, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OracleStatus {
+    Active,
+    Answered,
+    Expired,
+    Finalized,
+    Removed,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleAnswer {
+    pub answer: Option<String>,
+    pub answer_tx_hash: String,
+    pub answer_tx_submitted_at: Option<u64>,
+    pub block_number: Option<i64>,
+    pub fee: Option<String>,
+    pub fee_usd: Option<f64>,
+    pub defillama_snapshot_id: Option<i64>,
+    pub proof_bundle_cid: Option<String>,
+    pub answered_at: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleAuditLogEntry {
+    pub action: String,
+    pub actor: String,
+    pub details: serde_json::Value,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleDetail {
+    pub chain_id: u64,
+    pub address: String,
+    pub status: OracleStatus,
+    // only known for active oracles
+    pub next_action: Option<NextAction>,
+    pub specification: Specification,
+    pub measurement_timestamp: u64,
+    pub expiration: Option<u64>,
+    pub answer: Option<String>,
+    pub answer_tx_hash: Option<String>,
+    pub answer_tx_submitted_at: Option<u64>,
+    pub answer_attempts: i32,
+    pub next_answer_attempt: Option<u64>,
+    pub source_missing_since: Option<u64>,
+    pub defillama_snapshot_id: Option<i64>,
+    // when the oracle stopped being active, either by being answered or archived
+    pub archived_at: Option<u64>,
+    pub answers: Vec<OracleAnswer>,
+    pub audit_log: Vec<OracleAuditLogEntry>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleSummary {
+    pub chain_id: u64,
+    pub address: String,
+    pub specification: Specification,
+    pub measurement_timestamp: u64,
+    pub expiration: Option<u64>,
+    pub answer_tx_hash: Option<String>,
+    pub answer_attempts: i32,
+    pub next_answer_attempt: Option<u64>,
+}
+
+impl From<models::ActiveOracle> for OracleSummary {
+    fn from(oracle: models::ActiveOracle) -> Self {
+        Self {
+            chain_id: oracle.chain_id as u64,
+            address: format!("0x{:x}", oracle.address.0),
+            specification: oracle.specification,
+            measurement_timestamp: to_unix_timestamp(*oracle.measurement_timestamp),
+            expiration: oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer_tx_hash: oracle
+                .answer_tx_hash
+                .map(|answer_tx_hash| format!("0x{:x}", answer_tx_hash.0)),
+            answer_attempts: oracle.answer_attempts,
+            next_answer_attempt: oracle
+                .next_answer_attempt
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct OracleRemoval {
+    pub reason: String,
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn handlers(
+    operators: Arc<HashMap<String, String>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods([http::Method::GET, http::Method::DELETE])
+        .allow_headers(["Content-Type", "Authorization"])
+        .max_age(600);
+
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+
+    let list_oracles = path!("oracles" / u64)
+        .and(get())
+        .and(query::<PageQuery>())
+        .and(with_db_connection_pool.clone())
+        .and_then(list_oracles);
+
+    let get_oracle = path!("oracles" / u64 / String)
+        .and(get())
+        .and(with_db_connection_pool.clone())
+        .and_then(get_oracle);
+
+    let remove_oracle = path!("oracles" / u64 / String)
+        .and(delete())
+        .and(with_operator(operators))
+        .and(body::json())
+        .and(with_db_connection_pool)
+        .and_then(remove_oracle);
+
+    list_oracles.or(get_oracle).or(remove_oracle).with(cors)
+}
+
+// oracles live in the active oracles table until they're answered or archived, at
+// which point the answered and archived oracles tables hold their last known state
+fn get_oracle_detail(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: u64,
+    address: Address,
+) -> anyhow::Result<Option<OracleDetail>> {
+    let mut db_connection = db_connection_pool.get()?;
+    let active_oracle = models::ActiveOracle::get(&mut db_connection, chain_id, address)?;
+    let answered_oracles =
+        models::AnsweredOracle::get_all_for_oracle(&mut db_connection, chain_id, address)?;
+    let archived_oracle =
+        models::ArchivedOracle::get_all_for_oracle(&mut db_connection, chain_id, address)?.pop();
+    let audit_log =
+        models::AuditLogEntry::get_all_for_oracle(&mut db_connection, chain_id, address)?
+            .into_iter()
+            .map(|entry| OracleAuditLogEntry {
+                action: entry.action,
+                actor: entry.actor,
+                details: entry.details.0,
+                timestamp: to_unix_timestamp(*entry.timestamp),
+            })
+            .collect();
+
+    let answers = answered_oracles
+        .iter()
+        .map(|answered_oracle| OracleAnswer {
+            answer: answered_oracle
+                .answer
+                .as_ref()
+                .map(|answer| answer.0.to_string()),
+            answer_tx_hash: format!("0x{:x}", answered_oracle.answer_tx_hash.0),
+            answer_tx_submitted_at: answered_oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            block_number: answered_oracle.block_number,
+            fee: answered_oracle.fee.as_ref().map(|fee| fee.0.to_string()),
+            fee_usd: answered_oracle.fee_usd,
+            defillama_snapshot_id: answered_oracle.defillama_snapshot_id,
+            proof_bundle_cid: answered_oracle.proof_bundle_cid.clone(),
+            answered_at: to_unix_timestamp(*answered_oracle.answered_at),
+        })
+        .collect();
+
+    let detail = if let Some(active_oracle) = active_oracle {
+        let answer_override = models::AnswerOverride::get(&mut db_connection, chain_id, address)?;
+        let answer_review = models::AnswerReview::get(&mut db_connection, chain_id, address)?;
+        OracleDetail {
+            chain_id,
+            address: format!("0x{:x}", address),
+            status: OracleStatus::Active,
+            next_action: Some(next_action(
+                &active_oracle,
+                answer_override.as_ref(),
+                answer_review.as_ref(),
+                SystemTime::now(),
+            )),
+            measurement_timestamp: to_unix_timestamp(*active_oracle.measurement_timestamp),
+            expiration: active_oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer: active_oracle.answer.map(|answer| answer.0.to_string()),
+            answer_tx_hash: active_oracle
+                .answer_tx_hash
+                .map(|answer_tx_hash| format!("0x{:x}", answer_tx_hash.0)),
+            answer_tx_submitted_at: active_oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer_attempts: active_oracle.answer_attempts,
+            next_answer_attempt: active_oracle
+                .next_answer_attempt
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            source_missing_since: active_oracle
+                .source_missing_since
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            defillama_snapshot_id: active_oracle.defillama_snapshot_id,
+            archived_at: None,
+            specification: active_oracle.specification,
+            answers,
+            audit_log,
+        }
+    } else if let Some(answered_oracle) = answered_oracles.into_iter().last() {
+        OracleDetail {
+            chain_id,
+            address: format!("0x{:x}", address),
+            status: OracleStatus::Answered,
+            next_action: None,
+            measurement_timestamp: to_unix_timestamp(*answered_oracle.measurement_timestamp),
+            expiration: answered_oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer: answered_oracle.answer.map(|answer| answer.0.to_string()),
+            answer_tx_hash: Some(format!("0x{:x}", answered_oracle.answer_tx_hash.0)),
+            answer_tx_submitted_at: answered_oracle
+                .answer_tx_submitted_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer_attempts: answered_oracle.answer_attempts,
+            next_answer_attempt: None,
+            source_missing_since: None,
+            defillama_snapshot_id: answered_oracle.defillama_snapshot_id,
+            archived_at: Some(to_unix_timestamp(*answered_oracle.answered_at)),
+            specification: answered_oracle.specification,
+            answers,
+            audit_log,
+        }
+    } else if let Some(archived_oracle) = archived_oracle {
+        OracleDetail {
+            chain_id,
+            address: format!("0x{:x}", address),
+            status: match archived_oracle.reason.as_str() {
+                "expired" => OracleStatus::Expired,
+                "removed" => OracleStatus::Removed,
+                _ => OracleStatus::Finalized,
+            },
+            next_action: None,
+            measurement_timestamp: to_unix_timestamp(*archived_oracle.measurement_timestamp),
+            expiration: archived_oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer: archived_oracle.answer.map(|answer| answer.0.to_string()),
+            answer_tx_hash: None,
+            answer_tx_submitted_at: None,
+            answer_attempts: archived_oracle.answer_attempts,
+            next_answer_attempt: None,
+            source_missing_since: None,
+            defillama_snapshot_id: None,
+            archived_at: Some(to_unix_timestamp(*archived_oracle.archived_at)),
+            specification: archived_oracle.specification,
+            answers,
+            audit_log,
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(detail))
+}
+
+/// Lists a chain's active oracles.
+///
+/// Lists the oracles of a chain that are waiting to be answered, a page at a time. They can be sorted by `measurementTimestamp`, the default, `expiration` or `answerAttempts`.
+#[utoipa::path(
+    get,
+    path = "/oracles/{chain_id}",
+    params(
+        ("chain_id" = u64, Path, description = "The chain id."),
+        PageQuery
+    ),
+    responses(
+        (status = 200, description = "A page of the chain's active oracles.", body = OracleSummaryPage),
+        (status = 400, description = "The given pagination is invalid."),
+        (status = 500, description = "The active oracles could not be fetched.")
+    )
+)]
+pub async fn list_oracles(
+    chain_id: u64,
+    query: PageQuery,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let pagination = match query.pagination::<ActiveOracleSort>() {
+        Some(pagination) => pagination,
+        None => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    match db::blocking(|| {
+        let mut db_connection = db_connection_pool.get()?;
+        models::ActiveOracle::get_page_for_chain_id(&mut db_connection, chain_id, &pagination)
+    }) {
+        Ok(page) => Ok(Box::new(reply::json(&OracleSummaryPage::new(
+            page,
+            &pagination,
+        )))),
+        Err(error) => {
+            tracing::error!("could not list active oracles: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Gets an oracle's details.
+///
+/// Gets the full stored state of an oracle, whether it's still active, answered or archived: its specification, measurement timestamp, expiration, computed answer and answer tx, along with every answer submitted for it, its audit log and its current lifecycle status.
+#[utoipa::path(
+    get,
+    path = "/oracles/{chain_id}/{address}",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address.")
+    ),
+    responses(
+        (status = 200, description = "The oracle's details.", body = OracleDetail),
+        (status = 400, description = "The given oracle address is invalid."),
+        (status = 404, description = "No oracle is known for the given address."),
+        (status = 500, description = "The oracle's details could not be fetched.")
+    )
+)]
+pub async fn get_oracle(
+    chain_id: u64,
+    address: String,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    match db::blocking(|| get_oracle_detail(db_connection_pool, chain_id, address)) {
+        Ok(Some(detail)) => Ok(Box::new(reply::json(&detail))),
+        Ok(None) => Ok(Box::new(http::StatusCode::NOT_FOUND)),
+        Err(error) => {
+            tracing::error!("could not get oracle details: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Removes an oracle.
+///
+/// Archives an active oracle so that it's never answered, for example because its specification was maliciously crafted or its campaign was cancelled. The removal is recorded in the oracle's audit log, and the oracle isn't tracked again if its creation log is scanned anew. Oracles being answered can't be removed. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    delete,
+    path = "/oracles/{chain_id}/{address}",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address.")
+    ),
+    request_body = OracleRemoval,
+    responses(
+        (status = 204, description = "The oracle was removed."),
+        (status = 400, description = "The given oracle address is invalid."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "No active oracle exists for the given address."),
+        (status = 409, description = "The oracle is being answered."),
+        (status = 500, description = "The oracle could not be removed.")
+    )
+)]
+pub async fn remove_oracle(
+    chain_id: u64,
+    address: String,
+    operator: Option<String>,
+    removal: OracleRemoval,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    let active_oracle =
+        match db::blocking(|| models::ActiveOracle::get(&mut db_connection, chain_id, address)) {
+            Ok(Some(active_oracle)) => active_oracle,
+            Ok(None) => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+            Err(error) => {
+                tracing::error!("could not get active oracle: {:#}", error);
+                return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+            }
+        };
+
+    // an oracle whose answer tx is in flight might get finalized anyway, while claimed
+    // ones are being worked on by an answering task right now
+    if active_oracle.answer_tx_hash.is_some() {
+        return Ok(Box::new(http::StatusCode::CONFLICT));
+    }
+    let claim_expiration =
+        match db::blocking(|| active_oracle.claim(&mut db_connection, ANSWER_CLAIM_DURATION)) {
+            Ok(Some(claim_expiration)) => claim_expiration,
+            Ok(None) => return Ok(Box::new(http::StatusCode::CONFLICT)),
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+            }
+        };
+
+    match db::blocking(|| active_oracle.remove(&mut db_connection, &operator, &removal.reason)) {
+        Ok(()) => {
+            reverts::forget(chain_id, address);
+            events::emit(
+                chain_id,
+                address,
+                OracleEventKind::Removed {
+                    reason: removal.reason.clone(),
+                },
+            );
+            tracing::info!(
+                "operator {} removed oracle 0x{:x} on chain {}: {}",
+                operator,
+                address,
+                chain_id,
+                removal.reason
+            );
+            Ok(Box::new(http::StatusCode::NO_CONTENT))
+        }
+        Err(error) => {
+            tracing::error!("could not remove oracle: {:#}", error);
+            if let Err(error) = db::blocking(|| {
+                models::ActiveOracle::release_claim(
+                    &mut db_connection,
+                    chain_id,
+                    address,
+                    claim_expiration,
+                )
+            }) {
+                tracing::error!("{:#}", error);
+            }
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
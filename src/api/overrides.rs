@@ -0,0 +1,311 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use warp::{body, get, http, path, post, reply, Filter, Rejection, Reply};
+
+use crate::db::{self, models, DbConnection};
+
+use super::with_operator;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerOverride {
+    pub chain_id: u64,
+    pub oracle_address: String,
+    pub answer: String,
+    pub reason: String,
+    pub proposed_by: String,
+    pub proposed_at: u64,
+    pub approved_by: Option<String>,
+    pub approved_at: Option<u64>,
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+impl From<models::AnswerOverride> for AnswerOverride {
+    fn from(answer_override: models::AnswerOverride) -> Self {
+        Self {
+            chain_id: answer_override.chain_id as u64,
+            oracle_address: format!("0x{:x}", answer_override.oracle_address.0),
+            answer: answer_override.answer.0.to_string(),
+            reason: answer_override.reason,
+            proposed_by: answer_override.proposed_by,
+            proposed_at: to_unix_timestamp(*answer_override.proposed_at),
+            approved_by: answer_override.approved_by,
+            approved_at: answer_override
+                .approved_at
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AnswerOverrideProposal {
+    pub answer: String,
+    pub reason: String,
+}
+
+pub fn handlers(
+    operators: Arc<HashMap<String, String>>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_methods([http::Method::GET, http::Method::POST])
+        .allow_headers(["Content-Type", "Authorization"])
+        .max_age(600);
+
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+
+    let get_override = path!("overrides" / u64 / String)
+        .and(get())
+        .and(with_operator(operators.clone()))
+        .and(with_db_connection_pool.clone())
+        .and_then(get_answer_override);
+
+    let propose_override = path!("overrides" / u64 / String)
+        .and(post())
+        .and(with_operator(operators.clone()))
+        .and(body::json())
+        .and(with_db_connection_pool.clone())
+        .and_then(propose_answer_override);
+
+    let approve_override = path!("overrides" / u64 / String / "approval")
+        .and(post())
+        .and(with_operator(operators))
+        .and(with_db_connection_pool)
+        .and_then(approve_answer_override);
+
+    get_override
+        .or(propose_override)
+        .or(approve_override)
+        .with(cors)
+}
+
+/// Gets an oracle's answer override.
+///
+/// Gets the manual answer override currently set for an oracle, along with its approval status. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    get,
+    path = "/overrides/{chain_id}/{address}",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address.")
+    ),
+    responses(
+        (status = 200, description = "The oracle's answer override.", body = AnswerOverride),
+        (status = 400, description = "The given oracle address is invalid."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "No answer override is set for the given oracle."),
+        (status = 500, description = "The answer override could not be fetched.")
+    )
+)]
+pub async fn get_answer_override(
+    chain_id: u64,
+    address: String,
+    operator: Option<String>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    if operator.is_none() {
+        return Ok(Box::new(http::StatusCode::UNAUTHORIZED));
+    }
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    match db::blocking(|| models::AnswerOverride::get(&mut db_connection, chain_id, address)) {
+        Ok(Some(answer_override)) => Ok(Box::new(reply::json(&AnswerOverride::from(
+            answer_override,
+        )))),
+        Ok(None) => Ok(Box::new(http::StatusCode::NOT_FOUND)),
+        Err(error) => {
+            tracing::error!("could not get answer override: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Proposes an oracle's answer override.
+///
+/// Proposes a manual answer for an active oracle, replacing any previous override for it. The answer is not submitted until a different operator approves it. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    post,
+    path = "/overrides/{chain_id}/{address}",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address.")
+    ),
+    request_body = AnswerOverrideProposal,
+    responses(
+        (status = 201, description = "The answer override was proposed and is pending approval.", body = AnswerOverride),
+        (status = 400, description = "The given oracle address or answer is invalid."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 404, description = "No active oracle exists for the given address."),
+        (status = 500, description = "The answer override could not be saved.")
+    )
+)]
+pub async fn propose_answer_override(
+    chain_id: u64,
+    address: String,
+    operator: Option<String>,
+    proposal: AnswerOverrideProposal,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+    let answer = match U256::from_dec_str(proposal.answer.as_str()) {
+        Ok(answer) => answer,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    match db::blocking(|| models::ActiveOracle::get(&mut db_connection, chain_id, address)) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+        Err(error) => {
+            tracing::error!("could not get active oracle: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    }
+
+    match db::blocking(|| {
+        models::AnswerOverride::propose(
+            &mut db_connection,
+            chain_id,
+            address,
+            answer,
+            proposal.reason,
+            operator.clone(),
+        )
+    }) {
+        Ok(answer_override) => {
+            tracing::info!(
+                "operator {} proposed answer override {} for oracle 0x{:x} on chain {}",
+                operator,
+                answer,
+                address,
+                chain_id
+            );
+            Ok(Box::new(reply::with_status(
+                reply::json(&AnswerOverride::from(answer_override)),
+                http::StatusCode::CREATED,
+            )))
+        }
+        Err(error) => {
+            tracing::error!("could not propose answer override: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+/// Approves an oracle's answer override.
+///
+/// Approves the pending manual answer override for an oracle, after which the answerer submits it. The approving operator must be different from the proposing one. Requires an operator api key as a bearer token.
+#[utoipa::path(
+    post,
+    path = "/overrides/{chain_id}/{address}/approval",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address.")
+    ),
+    responses(
+        (status = 200, description = "The answer override was approved.", body = AnswerOverride),
+        (status = 400, description = "The given oracle address is invalid."),
+        (status = 401, description = "No valid operator api key was given."),
+        (status = 403, description = "The answer override was proposed by the same operator."),
+        (status = 404, description = "No answer override is set for the given oracle."),
+        (status = 409, description = "The answer override was already approved."),
+        (status = 500, description = "The answer override could not be approved.")
+    )
+)]
+pub async fn approve_answer_override(
+    chain_id: u64,
+    address: String,
+    operator: Option<String>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let operator = match operator {
+        Some(operator) => operator,
+        None => return Ok(Box::new(http::StatusCode::UNAUTHORIZED)),
+    };
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    let mut answer_override =
+        match db::blocking(|| models::AnswerOverride::get(&mut db_connection, chain_id, address)) {
+            Ok(Some(answer_override)) => answer_override,
+            Ok(None) => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+            Err(error) => {
+                tracing::error!("could not get answer override: {:#}", error);
+                return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+            }
+        };
+    if answer_override.is_approved() {
+        return Ok(Box::new(http::StatusCode::CONFLICT));
+    }
+    if answer_override.proposed_by == operator {
+        return Ok(Box::new(http::StatusCode::FORBIDDEN));
+    }
+
+    match db::blocking(|| answer_override.approve(&mut db_connection, operator.clone())) {
+        Ok(()) => {
+            tracing::info!(
+                "operator {} approved answer override {} for oracle 0x{:x} on chain {}",
+                operator,
+                answer_override.answer.0,
+                address,
+                chain_id
+            );
+            Ok(Box::new(reply::json(&AnswerOverride::from(
+                answer_override,
+            ))))
+        }
+        Err(error) => {
+            tracing::error!("could not approve answer override: {:#}", error);
+            Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    commons::{API_DEFAULT_PAGE_SIZE, API_MAX_PAGE_SIZE},
+    db::pagination::{self, Pagination, SortOrder},
+};
+
+use super::oracles::OracleSummary;
+
+// shared by every list endpoint, the accepted sort fields being specific to each
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// How many items to return, 100 by default and up to 1000.
+    pub limit: Option<u32>,
+    /// How many items to skip, 0 by default.
+    pub offset: Option<u64>,
+    /// The field items are sorted by, depending on the endpoint.
+    pub sort: Option<String>,
+    /// Either `asc`, the default, or `desc`.
+    pub order: Option<String>,
+}
+
+impl PageQuery {
+    // none is returned for out of range limits and unknown sort fields or orders
+    pub fn pagination<S: FromStr + Default>(&self) -> Option<Pagination<S>> {
+        let limit = self.limit.unwrap_or(API_DEFAULT_PAGE_SIZE);
+        if !(1..=API_MAX_PAGE_SIZE).contains(&limit) {
+            return None;
+        }
+        Some(Pagination {
+            limit: i64::from(limit),
+            offset: i64::try_from(self.offset.unwrap_or_default()).ok()?,
+            sort: match self.sort.as_deref() {
+                Some(sort) => sort.parse().ok()?,
+                None => S::default(),
+            },
+            order: match self.order.as_deref() {
+                Some(order) => order.parse().ok()?,
+                None => SortOrder::default(),
+            },
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[aliases(OracleSummaryPage = Page<OracleSummary>)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // how many items match the request overall, 0 past the last page
+    pub total: u64,
+    pub limit: u64,
+    pub offset: u64,
+}
+
+impl<T> Page<T> {
+    pub fn new<R, S>(page: pagination::Page<R>, pagination: &Pagination<S>) -> Self
+    where
+        T: From<R>,
+    {
+        Self {
+            items: page.records.into_iter().map(T::from).collect(),
+            total: u64::try_from(page.total).unwrap_or_default(),
+            limit: u64::try_from(pagination.limit).unwrap_or_default(),
+            offset: u64::try_from(pagination.offset).unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    convert::Infallible,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::Address;
+use serde::Serialize;
+use utoipa::ToSchema;
+use warp::{get, http, path, reply, Filter, Rejection, Reply};
+
+use crate::{
+    db::{self, models, DbConnection},
+    defillama::DefiLlamaClient,
+    specification::{self, Specification},
+};
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotResponse {
+    pub path: String,
+    pub url: Option<String>,
+    pub body: String,
+    pub timestamp: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub id: i64,
+    pub chain_id: u64,
+    pub oracle_address: String,
+    pub specification: Specification,
+    pub answer: Option<String>,
+    pub timestamp: u64,
+    pub responses: Vec<SnapshotResponse>,
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotReplay {
+    pub snapshot_id: i64,
+    pub recorded_answer: Option<String>,
+    pub replayed_answer: Option<String>,
+    pub matches: bool,
+}
+
+pub fn handlers(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::GET)
+        .max_age(600);
+
+    let db_connection_pool_filter = warp::any().map(move || db_connection_pool.clone());
+
+    let get_snapshot = path!("snapshots" / i64)
+        .and(get())
+        .and(db_connection_pool_filter.clone())
+        .and_then(get_snapshot);
+
+    let replay_snapshot = path!("snapshots" / u64 / String / "replay")
+        .and(get())
+        .and(db_connection_pool_filter)
+        .and_then(replay_snapshot);
+
+    get_snapshot.or(replay_snapshot).with(cors)
+}
+
+/// Gets a recorded DefiLlama snapshot.
+///
+/// Returns the raw DefiLlama responses, along with the urls they were fetched from and when, that an oracle's answer was computed from. Oracles reference the snapshot they were answered with through its id.
+#[utoipa::path(
+    get,
+    path = "/snapshots/{id}",
+    params(
+        ("id" = i64, Path, description = "The snapshot's id.")
+    ),
+    responses(
+        (status = 200, description = "The snapshot was found.", body = Snapshot),
+        (status = 404, description = "No snapshot exists with the given id."),
+        (status = 500, description = "The snapshot could not be fetched.")
+    )
+)]
+pub async fn get_snapshot(
+    id: i64,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    let snapshot = match db::blocking(|| models::DefiLlamaSnapshot::get(&mut db_connection, id)) {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+        Err(error) => {
+            tracing::error!("could not get defillama snapshot: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+
+    let responses = match snapshot.recorded_responses() {
+        Ok(recorded_responses) => recorded_responses,
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+
+    Ok(Box::new(reply::json(&Snapshot {
+        id: snapshot.id,
+        chain_id: snapshot.chain_id as u64,
+        oracle_address: format!("0x{:x}", snapshot.oracle_address.0),
+        specification: snapshot.specification,
+        answer: snapshot.answer.map(|answer| answer.0.to_string()),
+        timestamp: to_unix_timestamp(*snapshot.timestamp),
+        responses: responses
+            .into_iter()
+            .map(|response| SnapshotResponse {
+                path: response.path,
+                url: response.url,
+                body: response.body,
+                timestamp: to_unix_timestamp(response.timestamp),
+            })
+            .collect(),
+    })))
+}
+
+/// Replays an oracle's answer computation.
+///
+/// Re-runs the answer computation for an oracle using its latest recorded DefiLlama snapshot instead of live data, and compares the result with the originally computed answer.
+#[utoipa::path(
+    get,
+    path = "/snapshots/{chain_id}/{address}/replay",
+    params(
+        ("chain_id" = u64, Path, description = "The oracle's chain id."),
+        ("address" = String, Path, description = "The oracle's address.")
+    ),
+    responses(
+        (status = 200, description = "The answer computation was replayed.", body = SnapshotReplay),
+        (status = 400, description = "The given oracle address is invalid."),
+        (status = 404, description = "No snapshot was recorded for the given oracle."),
+        (status = 500, description = "The snapshot could not be fetched or replayed.")
+    )
+)]
+pub async fn replay_snapshot(
+    chain_id: u64,
+    address: String,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let address = match Address::from_str(address.as_str()) {
+        Ok(address) => address,
+        Err(_) => return Ok(Box::new(http::StatusCode::BAD_REQUEST)),
+    };
+
+    let mut db_connection = match db::blocking(|| db_connection_pool.get()) {
+        Ok(db_connection) => db_connection,
+        Err(error) => {
+            tracing::error!("could not get new connection from pool: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    let snapshot = match db::blocking(|| {
+        models::DefiLlamaSnapshot::get_latest_for_oracle(&mut db_connection, chain_id, address)
+    }) {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return Ok(Box::new(http::StatusCode::NOT_FOUND)),
+        Err(error) => {
+            tracing::error!("could not get defillama snapshot: {:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+    drop(db_connection);
+
+    let recorded_responses = match snapshot.recorded_responses() {
+        Ok(recorded_responses) => recorded_responses,
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+        }
+    };
+
+    let recorded_answer = snapshot.answer.map(|answer| answer.0);
+    let replayed_answer = specification::answer(
+        &snapshot.specification,
+        &DefiLlamaClient::replay(recorded_responses),
+        None,
+    )
+    .await
+    .unwrap_or_else(|error| {
+        tracing::error!("could not replay snapshot answer: {:#}", error);
+        None
+    });
+
+    Ok(Box::new(reply::json(&SnapshotReplay {
+        snapshot_id: snapshot.id,
+        recorded_answer: recorded_answer.map(|answer| answer.to_string()),
+        replayed_answer: replayed_answer.map(|answer| answer.to_string()),
+        matches: recorded_answer == replayed_answer,
+    })))
+}
@@ -1,13 +1,13 @@
-use std::{convert::Infallible, sync::Arc};
+use std::convert::Infallible;
 
-use carrot_commons::http_client::HttpClient;
 use serde_json::Value;
 use warp::{body, http, path, post, reply, Filter, Rejection, Reply};
 
+use crate::defillama::DefiLlamaClient;
 use crate::specification::{self, Specification};
 
 pub fn handlers(
-    defillama_http_client: Arc<HttpClient>,
+    defillama: DefiLlamaClient,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
     let cors = warp::cors()
         .allow_any_origin()
@@ -15,16 +15,14 @@ pub fn handlers(
         .allow_header("Content-Type")
         .max_age(600);
 
-    let validate = path("specifications")
+    path("specifications")
         .and(path("validations"))
         .and(post())
         .and(path::end())
         .and(body::json())
-        .and(warp::any().map(move || defillama_http_client.clone()))
+        .and(warp::any().map(move || defillama.clone()))
         .and_then(validate_specification)
-        .with(cors);
-
-    validate
+        .with(cors)
 }
 
 /// Validates specifications.
@@ -41,12 +39,12 @@ pub fn handlers(
 )]
 pub async fn validate_specification(
     raw_specification: Value,
-    defillama_http_client: Arc<HttpClient>,
+    defillama: DefiLlamaClient,
 ) -> Result<impl Reply, Infallible> {
     match serde_json::from_value::<Specification>(raw_specification) {
         Ok(specification) => Ok(reply::with_status(
             reply::reply(),
-            if specification::validate(&specification, defillama_http_client).await {
+            if specification::validate(&specification, defillama).await {
                 http::StatusCode::NO_CONTENT
             } else {
                 http::StatusCode::BAD_REQUEST
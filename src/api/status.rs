@@ -0,0 +1,189 @@
+use std::{convert::Infallible, sync::Arc};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::{providers::Middleware, utils};
+use serde::Serialize;
+use tokio::time::timeout;
+use utoipa::ToSchema;
+use warp::{get, http, path, reply, Filter, Rejection, Reply};
+
+use crate::{
+    chains::Chains,
+    commons::HEALTH_CHECK_TIMEOUT,
+    db::{self, models, DbConnection},
+    logging::{last_chain_error, ChainError},
+};
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswererStatus {
+    pub address: String,
+    pub balance: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainStatus {
+    pub chain_id: u64,
+    pub last_processed_block: Option<u64>,
+    pub head_block: Option<u64>,
+    pub active_oracles: usize,
+    pub answerable_oracles: usize,
+    pub pending_txs: usize,
+    pub last_error: Option<ChainError>,
+    pub answerers: Vec<AnswererStatus>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    pub chains: Vec<ChainStatus>,
+}
+
+pub fn handlers(
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let cors = warp::cors()
+        .allow_any_origin()
+        .allow_method(http::Method::GET)
+        .max_age(600);
+
+    let with_chains = warp::any().map(move || chains.clone());
+    let with_db_connection_pool = warp::any().map(move || db_connection_pool.clone());
+
+    path!("status")
+        .and(get())
+        .and(with_chains)
+        .and(with_db_connection_pool)
+        .and_then(get_status)
+        .with(cors)
+}
+
+struct OracleCounts {
+    active: usize,
+    answerable: usize,
+    pending_txs: usize,
+}
+
+fn get_oracle_counts(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: u64,
+) -> anyhow::Result<OracleCounts> {
+    let mut db_connection = db_connection_pool.get()?;
+    Ok(OracleCounts {
+        active: models::ActiveOracle::get_all_for_chain_id(&mut db_connection, chain_id)?.len(),
+        answerable: models::ActiveOracle::get_all_answerable_for_chain_id(
+            &mut db_connection,
+            chain_id,
+        )?
+        .len(),
+        pending_txs: models::ActiveOracle::get_all_with_answer_tx_hash(
+            &mut db_connection,
+            chain_id,
+        )?
+        .len(),
+    })
+}
+
+async fn get_chain_status(
+    chains: &Chains,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: u64,
+) -> anyhow::Result<Option<ChainStatus>> {
+    let (listener, provider, answerer_keys) = match (
+        chains.listener(chain_id),
+        chains.provider(chain_id),
+        chains.answerer_keys(chain_id),
+    ) {
+        (Some(listener), Some(provider), Some(answerer_keys)) => {
+            (listener, provider, answerer_keys)
+        }
+        _ => return Ok(None),
+    };
+
+    let oracle_counts = db::blocking(|| get_oracle_counts(db_connection_pool, chain_id))?;
+
+    let mut answerers = Vec::with_capacity(answerer_keys.len());
+    for answerer_key in answerer_keys.iter() {
+        // monitored balances are already known, the others are fetched on the spot
+        let balance = match answerer_key
+            .balance()
+            .and_then(|answerer_balance| answerer_balance.get())
+        {
+            Some(balance) => Some(balance),
+            None => match timeout(
+                HEALTH_CHECK_TIMEOUT,
+                provider.get_balance(answerer_key.address(), None),
+            )
+            .await
+            {
+                Ok(Ok(balance)) => Some(balance),
+                Ok(Err(error)) => {
+                    tracing::warn!(
+                        "could not get balance of answerer 0x{:x} on chain {}: {:#}",
+                        answerer_key.address(),
+                        chain_id,
+                        error
+                    );
+                    None
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "timed out getting balance of answerer 0x{:x} on chain {}",
+                        answerer_key.address(),
+                        chain_id
+                    );
+                    None
+                }
+            },
+        };
+        answerers.push(AnswererStatus {
+            address: format!("0x{:x}", answerer_key.address()),
+            balance: balance.map(utils::format_ether),
+        });
+    }
+
+    Ok(Some(ChainStatus {
+        chain_id,
+        last_processed_block: listener.last_processed_block(),
+        head_block: listener.present_head(),
+        active_oracles: oracle_counts.active,
+        answerable_oracles: oracle_counts.answerable,
+        pending_txs: oracle_counts.pending_txs,
+        last_error: last_chain_error(chain_id),
+        answerers,
+    }))
+}
+
+/// Gets the service's status.
+///
+/// Gets a summary of every running chain: its latest processed and known head blocks, how many active oracles it has, how many of them can be answered right now and how many have an answer tx pending, the latest error logged by its tasks and its answerers' balances in ether.
+#[utoipa::path(
+    get,
+    path = "/status",
+    responses(
+        (status = 200, description = "The status of every running chain.", body = Status),
+        (status = 500, description = "The status could not be computed.")
+    )
+)]
+pub async fn get_status(
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> Result<Box<dyn Reply>, Infallible> {
+    let mut chains_status = Vec::new();
+    for chain_id in chains.chain_ids() {
+        // the chain might have been removed in the meantime
+        match get_chain_status(&chains, db_connection_pool.clone(), chain_id).await {
+            Ok(Some(chain_status)) => chains_status.push(chain_status),
+            Ok(None) => {}
+            Err(error) => {
+                tracing::error!("could not get status of chain {}: {:#}", chain_id, error);
+                return Ok(Box::new(http::StatusCode::INTERNAL_SERVER_ERROR));
+            }
+        }
+    }
+    Ok(Box::new(reply::json(&Status {
+        chains: chains_status,
+    })))
+}
@@ -0,0 +1,125 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use ethers::{
+    abi::Detokenize,
+    contract::ContractCall,
+    providers::{Http, Middleware, Provider},
+    types::{BlockId, BlockNumber, U64},
+};
+use tokio::sync::OnceCell;
+
+// archive nodes are only used to read historical state when answering, the latest
+// state rpc used for scanning and transacting is kept separate
+pub struct ArchiveNode {
+    provider: Arc<Provider<Http>>,
+}
+
+impl ArchiveNode {
+    pub fn new(provider: Provider<Http>) -> Self {
+        Self {
+            provider: Arc::new(provider),
+        }
+    }
+
+    // contracts used for historical reads must be bound to this provider
+    pub fn provider(&self) -> Arc<Provider<Http>> {
+        self.provider.clone()
+    }
+
+    // returns the latest block mined at or before the given timestamp
+    pub async fn block_at_timestamp(&self, timestamp: SystemTime) -> anyhow::Result<U64> {
+        let timestamp = timestamp
+            .duration_since(UNIX_EPOCH)
+            .context("could not get unix timestamp")?
+            .as_secs();
+
+        let mut low = U64::zero();
+        let mut high = self
+            .provider
+            .get_block_number()
+            .await
+            .context("could not get latest block number from archive node")?;
+        if self.get_block_timestamp(low).await? > timestamp {
+            anyhow::bail!("timestamp {} is before the genesis block", timestamp);
+        }
+        while low < high {
+            let middle = (low + high + 1) / 2;
+            if self.get_block_timestamp(middle).await? <= timestamp {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        Ok(low)
+    }
+
+    pub async fn call_at_block<D: Detokenize>(
+        &self,
+        call: ContractCall<Provider<Http>, D>,
+        block_number: U64,
+    ) -> anyhow::Result<D> {
+        call.block(BlockId::Number(BlockNumber::Number(block_number)))
+            .call()
+            .await
+            .context(format!(
+                "could not perform contract call at block {}",
+                block_number
+            ))
+    }
+
+    async fn get_block_timestamp(&self, block_number: U64) -> anyhow::Result<u64> {
+        Ok(self
+            .provider
+            .get_block(block_number)
+            .await
+            .context(format!(
+                "could not get block {} from archive node",
+                block_number
+            ))?
+            .context(format!("block {} not found on archive node", block_number))?
+            .timestamp
+            .as_u64())
+    }
+}
+
+// archive node access pinned to an oracle's measurement timestamp. the block is
+// only looked up when a handler actually needs it
+pub struct HistoricalState {
+    archive_node: Arc<ArchiveNode>,
+    timestamp: SystemTime,
+    block_number: OnceCell<U64>,
+}
+
+impl HistoricalState {
+    pub fn new(archive_node: Arc<ArchiveNode>, timestamp: SystemTime) -> Self {
+        Self {
+            archive_node,
+            timestamp,
+            block_number: OnceCell::new(),
+        }
+    }
+
+    pub fn archive_node(&self) -> &ArchiveNode {
+        &self.archive_node
+    }
+
+    pub async fn block_number(&self) -> anyhow::Result<U64> {
+        self.block_number
+            .get_or_try_init(|| self.archive_node.block_at_timestamp(self.timestamp))
+            .await
+            .copied()
+    }
+
+    pub async fn call<D: Detokenize>(
+        &self,
+        call: ContractCall<Provider<Http>, D>,
+    ) -> anyhow::Result<D> {
+        let block_number = self.block_number().await?;
+        self.archive_node.call_at_block(call, block_number).await
+    }
+}
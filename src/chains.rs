@@ -0,0 +1,797 @@
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use answerer_framework::scanner::ScanStore;
+use anyhow::Context;
+use carrot_commons::http_client::HttpClient;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::{
+    contract::EthEvent,
+    middleware::SignerMiddleware,
+    providers::{Http, Provider},
+    types::Filter,
+    utils,
+};
+use tokio::{
+    sync::{mpsc, oneshot, watch, Notify},
+    task::{AbortHandle, JoinSet},
+};
+use tracing::{info_span, Span};
+use tracing_futures::Instrument;
+
+use crate::{
+    alerts::{self, AlertKind},
+    answerer::{
+        answer_active_oracles,
+        balance::{monitor_answerer_balance, AnswererBalance},
+        finalizations::collect_finalized_oracles,
+        keys::{AnswererKey, AnswererKeys},
+        orphaned_txs::collect_orphaned_answer_txs,
+        proofs::ProofPublisher,
+        purge::purge_expired_oracles,
+        recovery::recover_in_flight_answer_txs,
+    },
+    archive::ArchiveNode,
+    commons::{
+        ChainConfig, BALANCE_CHECK_INTERVAL, CHECKPOINT_CONFIRMATION_BLOCKS,
+        EXPIRED_ORACLES_PURGE_INTERVAL, FINALIZED_ORACLES_CHECK_INTERVAL,
+        HEAD_LAG_STALENESS_WINDOW, HEAD_LAG_THRESHOLD_BLOCKS, HEARTBEAT_MIN_INTERVAL,
+        ORPHANED_ANSWER_TXS_CHECK_INTERVAL, ORPHANED_ANSWER_TX_THRESHOLD, PAST_LOGS_BLOCKS_RANGE,
+        PAST_LOGS_MAX_RPS, RECONCILIATION_INTERVAL, RECONCILIATION_MARGIN_BLOCKS,
+        RPC_CIRCUIT_BREAKER_COOLDOWN, RPC_CIRCUIT_BREAKER_THRESHOLD, RPC_FAILOVER_THRESHOLD,
+    },
+    contracts::factory::CreateTokenFilter,
+    db::{self, models, DbConnection},
+    defillama::DefiLlamaClient,
+    heartbeat::Heartbeat,
+    ipfs::IpfsFetcher,
+    listener::{
+        gaps::{backfill_new_factories, repair_block_gaps},
+        head_lag::monitor_head_lag,
+        leader::{keep_scanner_lease, LeaderElection},
+        past::scan_past_logs,
+        present::scan_present_logs,
+        reconciliation::reconcile_recent_logs,
+        Backfiller, DefiLlamaOracleDetector, Listener, PgScanStore,
+    },
+    quorum::QuorumReader,
+    rpc::FallbackHttp,
+    shutdown::ShutdownSignal,
+    signer::{build_answerer_signers, AnswererSigner},
+    vault::VaultClient,
+};
+
+const DEFAULT_LOGS_POLLING_INTERVAL_SECONDS: u64 = 30;
+
+type TaskResult = anyhow::Result<()>;
+
+// everything shared between chains that is needed to start their tasks, kept around
+// for the whole process lifetime so that chains can also be added at runtime
+pub struct ChainsContext {
+    pub dev_mode: bool,
+    pub dry_run: bool,
+    pub record_defillama_responses: bool,
+    pub persist_indexed_logs: bool,
+    // only set when running multiple replicas, otherwise every chain is always led
+    pub leader_election: Option<Arc<LeaderElection>>,
+    pub vault_client: Option<Arc<VaultClient>>,
+    pub db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    pub ipfs_fetcher: Arc<IpfsFetcher>,
+    pub data_manager_http_client: Arc<HttpClient>,
+    pub defillama: DefiLlamaClient,
+}
+
+pub struct RunningChain {
+    pub listener: Listener,
+    pub backfiller: Arc<Backfiller>,
+    pub provider: Arc<Provider<FallbackHttp>>,
+    pub answerer_keys: Arc<AnswererKeys>,
+    // wakes the answerer up to start an answering run right away
+    answering_trigger: Arc<Notify>,
+    // every task of the chain except for the answerer, which is stopped through the
+    // shutdown signal so that in-flight answers are completed
+    tasks: Vec<AbortHandle>,
+    shutdown_sender: watch::Sender<bool>,
+}
+
+impl RunningChain {
+    fn stop(&self) {
+        let _ = self.shutdown_sender.send(true);
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+    }
+}
+
+// sent to the main task, which owns the task sets every chain's tasks are spawned in
+pub struct ChainAddition {
+    pub chain_id: u64,
+    pub chain_config: Box<ChainConfig>,
+    pub result: oneshot::Sender<anyhow::Result<()>>,
+}
+
+// the running chains, shared between the main task and the api
+pub struct Chains {
+    running: RwLock<HashMap<u64, RunningChain>>,
+    additions: mpsc::Sender<ChainAddition>,
+}
+
+impl Chains {
+    pub fn new() -> (Self, mpsc::Receiver<ChainAddition>) {
+        let (additions, additions_receiver) = mpsc::channel(1);
+        (
+            Self {
+                running: RwLock::new(HashMap::new()),
+                additions,
+            },
+            additions_receiver,
+        )
+    }
+
+    pub fn listener(&self, chain_id: u64) -> Option<Listener> {
+        self.running
+            .read()
+            .unwrap() // this should never panic
+            .get(&chain_id)
+            .map(|chain| chain.listener.clone())
+    }
+
+    pub fn backfiller(&self, chain_id: u64) -> Option<Arc<Backfiller>> {
+        self.running
+            .read()
+            .unwrap() // this should never panic
+            .get(&chain_id)
+            .map(|chain| chain.backfiller.clone())
+    }
+
+    pub fn provider(&self, chain_id: u64) -> Option<Arc<Provider<FallbackHttp>>> {
+        self.running
+            .read()
+            .unwrap() // this should never panic
+            .get(&chain_id)
+            .map(|chain| chain.provider.clone())
+    }
+
+    pub fn answerer_keys(&self, chain_id: u64) -> Option<Arc<AnswererKeys>> {
+        self.running
+            .read()
+            .unwrap() // this should never panic
+            .get(&chain_id)
+            .map(|chain| chain.answerer_keys.clone())
+    }
+
+    // returns false if the chain isn't running
+    pub fn trigger_answering(&self, chain_id: u64) -> bool {
+        match self
+            .running
+            .read()
+            .unwrap() // this should never panic
+            .get(&chain_id)
+        {
+            Some(chain) => {
+                chain.answering_trigger.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, chain_id: u64) -> bool {
+        self.running
+            .read()
+            .unwrap() // this should never panic
+            .contains_key(&chain_id)
+    }
+
+    pub fn chain_ids(&self) -> Vec<u64> {
+        let mut chain_ids: Vec<_> = self
+            .running
+            .read()
+            .unwrap() // this should never panic
+            .keys()
+            .copied()
+            .collect();
+        chain_ids.sort();
+        chain_ids
+    }
+
+    pub fn insert(&self, chain_id: u64, chain: RunningChain) {
+        self.running
+            .write()
+            .unwrap() // this should never panic
+            .insert(chain_id, chain);
+    }
+
+    // waits for the main task to start the chain's tasks
+    pub async fn add(&self, chain_id: u64, chain_config: ChainConfig) -> anyhow::Result<()> {
+        let (result, result_receiver) = oneshot::channel();
+        self.additions
+            .send(ChainAddition {
+                chain_id,
+                chain_config: Box::new(chain_config),
+                result,
+            })
+            .await
+            .context("main task not running")?;
+        result_receiver.await.context("main task not running")?
+    }
+
+    // returns false if the chain wasn't running
+    pub fn remove(&self, chain_id: u64) -> bool {
+        match self
+            .running
+            .write()
+            .unwrap() // this should never panic
+            .remove(&chain_id)
+        {
+            Some(chain) => {
+                chain.stop();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // stops every answerer from starting new answering runs
+    pub fn trigger_shutdown(&self) {
+        for chain in self
+            .running
+            .read()
+            .unwrap() // this should never panic
+            .values()
+        {
+            let _ = chain.shutdown_sender.send(true);
+        }
+    }
+}
+
+// spawns all of the chain's tasks in the given task sets, keeping the answering one
+// apart so that in-flight answers can be waited for on shutdown
+pub async fn start_chain(
+    context: &ChainsContext,
+    chain_id: u64,
+    mut chain_config: ChainConfig,
+    join_set: &mut JoinSet<TaskResult>,
+    answering_tasks: &mut JoinSet<TaskResult>,
+) -> anyhow::Result<RunningChain> {
+    tracing::info!(
+        "setting up chain with id {} with rpc endpoint: {}",
+        chain_id,
+        chain_config.rpc_endpoint
+    );
+
+    if let Some(vault_client) = context.vault_client.as_ref() {
+        vault_client
+            .resolve_chain_config_secrets(chain_id, &mut chain_config)
+            .await
+            .context("could not resolve secrets from vault")?;
+    }
+
+    let factories_deployment_block = chain_config
+        .factories
+        .iter()
+        .map(|factory| factory.deployment_block)
+        .min()
+        .context(format!("no factories configured for chain {}", chain_id))?;
+    let stored_checkpoint_block_number =
+        db::blocking(|| get_checkpoint_block_number(chain_id, context.db_connection_pool.clone()))?;
+    let checkpoint_block_number =
+        stored_checkpoint_block_number.unwrap_or(factories_deployment_block);
+
+    let ChainClients {
+        provider,
+        signers,
+        archive_node,
+        quorum_reader,
+    } = connect_chain(chain_id, &chain_config).await?;
+    let signer = signers[0].clone();
+
+    let min_answerer_balance = chain_config
+        .min_answerer_balance
+        .map(utils::parse_ether)
+        .transpose()
+        .context(format!(
+            "could not parse minimum answerer balance for chain {}",
+            chain_id
+        ))?;
+
+    let mut tasks = Vec::new();
+    let mut answerer_keys = Vec::with_capacity(signers.len());
+    for signer in signers.into_iter() {
+        let answerer_balance = min_answerer_balance.map(|threshold| {
+            let answerer_balance = Arc::new(AnswererBalance::new(threshold));
+            tasks.push(
+                join_set.spawn(
+                    monitor_answerer_balance(
+                        chain_id,
+                        chain_config
+                            .balance_check_interval_seconds
+                            .map(Duration::from_secs)
+                            .unwrap_or(BALANCE_CHECK_INTERVAL),
+                        signer.clone(),
+                        answerer_balance.clone(),
+                    )
+                    .instrument(info_span!("balance-monitor", chain_id)),
+                ),
+            );
+            answerer_balance
+        });
+        answerer_keys.push(AnswererKey::new(signer, answerer_balance));
+    }
+    let answerer_keys = Arc::new(build_answerer_keys(answerer_keys));
+
+    tasks.push(
+        join_set.spawn(
+            recover_in_flight_answer_txs(
+                chain_id,
+                signer.clone(),
+                context.db_connection_pool.clone(),
+            )
+            .instrument(info_span!("in-flight-txs-recovery", chain_id)),
+        ),
+    );
+
+    tasks.push(
+        join_set.spawn(
+            collect_orphaned_answer_txs(
+                chain_id,
+                ORPHANED_ANSWER_TXS_CHECK_INTERVAL,
+                chain_config
+                    .orphaned_answer_tx_threshold_seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(ORPHANED_ANSWER_TX_THRESHOLD),
+                signer.clone(),
+                context.db_connection_pool.clone(),
+            )
+            .instrument(info_span!("orphaned-txs-collector", chain_id)),
+        ),
+    );
+
+    tasks.push(
+        join_set.spawn(
+            collect_finalized_oracles(
+                chain_id,
+                chain_config
+                    .finalized_oracles_check_interval_seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(FINALIZED_ORACLES_CHECK_INTERVAL),
+                signer.clone(),
+                chain_config.multicall_address,
+                quorum_reader.clone(),
+                context.db_connection_pool.clone(),
+            )
+            .instrument(info_span!("finalized-oracles-collector", chain_id)),
+        ),
+    );
+
+    tasks.push(
+        join_set.spawn(
+            purge_expired_oracles(
+                chain_id,
+                chain_config
+                    .expired_oracles_purge_interval_seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(EXPIRED_ORACLES_PURGE_INTERVAL),
+                context.db_connection_pool.clone(),
+            )
+            .instrument(info_span!("expired-oracles-purge", chain_id)),
+        ),
+    );
+
+    let oracles_acknowledged = Arc::new(Notify::new());
+
+    let detector = Arc::new(DefiLlamaOracleDetector {
+        chain_id,
+        template_id: chain_config.template_id,
+        multicall_address: chain_config.multicall_address,
+        signer: signer.clone(),
+        quorum_reader: quorum_reader.clone(),
+        db_connection_pool: context.db_connection_pool.clone(),
+        data_manager_http_client: context.data_manager_http_client.clone(),
+        ipfs_fetcher: context.ipfs_fetcher.clone(),
+        defillama: context.defillama.clone(),
+    });
+    let scan_store: Arc<dyn ScanStore> =
+        Arc::new(PgScanStore::new(context.db_connection_pool.clone()));
+    let listener = Listener::new(
+        chain_id,
+        chain_config
+            .checkpoint_confirmation_blocks
+            .unwrap_or(CHECKPOINT_CONFIRMATION_BLOCKS),
+        scan_store.clone(),
+        context.persist_indexed_logs,
+        detector,
+        oracles_acknowledged.clone(),
+    );
+    let events_filter = Filter::new()
+        .address(
+            chain_config
+                .factories
+                .iter()
+                .map(|factory| factory.address)
+                .collect::<Vec<_>>(),
+        )
+        .event(CreateTokenFilter::abi_signature().deref());
+
+    let logs_blocks_range = chain_config
+        .logs_blocks_range
+        .unwrap_or(PAST_LOGS_BLOCKS_RANGE);
+    let logs_max_rps = chain_config.logs_max_rps.unwrap_or(PAST_LOGS_MAX_RPS);
+    let backfiller = Arc::new(Backfiller::new(
+        chain_id,
+        listener.clone(),
+        provider.clone(),
+        events_filter.clone(),
+        logs_blocks_range,
+        logs_max_rps,
+    ));
+    if let Some(leader_election) = context.leader_election.clone() {
+        // tried right away so that the leader doesn't discard the past blocks it scans
+        // before the first renewal
+        let leading = leader_election
+            .try_acquire(chain_id, scan_store.as_ref())
+            .unwrap_or_else(|error| {
+                tracing::error!("{:#}", error);
+                false
+            });
+        if !leading {
+            tracing::info!(
+                "another instance leads scanning on chain {}, following",
+                chain_id
+            );
+        }
+        listener.set_leading(leading);
+        tasks.push(
+            join_set.spawn(
+                keep_scanner_lease(
+                    chain_id,
+                    checkpoint_block_number,
+                    leader_election,
+                    listener.clone(),
+                    backfiller.clone(),
+                )
+                .instrument(info_span!("scanner-lease", chain_id)),
+            ),
+        );
+    }
+    if !context.dev_mode {
+        // gaps are repaired by whoever leads at startup, and a follower taking over
+        // later catches up from the checkpoint instead
+        if listener.is_leading() {
+            let backfiller = backfiller.clone();
+            let scan_store = scan_store.clone();
+            let factories = chain_config
+                .factories
+                .iter()
+                .map(|factory| (factory.address, factory.deployment_block))
+                .collect();
+            let repair_gaps = chain_config.repair_block_gaps.unwrap_or(true);
+            // both run through the same backfiller, so one after the other
+            tasks.push(
+                join_set.spawn(
+                    async move {
+                        backfill_new_factories(
+                            chain_id,
+                            backfiller.clone(),
+                            scan_store.clone(),
+                            factories,
+                            stored_checkpoint_block_number,
+                        )
+                        .await?;
+                        if repair_gaps {
+                            repair_block_gaps(
+                                chain_id,
+                                backfiller,
+                                scan_store,
+                                factories_deployment_block,
+                                checkpoint_block_number,
+                            )
+                            .await?;
+                        }
+                        Ok(())
+                    }
+                    .instrument(info_span!("gap-repair", chain_id)),
+                ),
+            );
+        }
+        tasks.push(
+            join_set.spawn(
+                reconcile_recent_logs(
+                    listener.clone(),
+                    backfiller.clone(),
+                    chain_config
+                        .reconciliation_interval_seconds
+                        .map(Duration::from_secs)
+                        .unwrap_or(RECONCILIATION_INTERVAL),
+                    chain_config
+                        .reconciliation_margin_blocks
+                        .unwrap_or(RECONCILIATION_MARGIN_BLOCKS),
+                )
+                .instrument(info_span!("reconciliation", chain_id)),
+            ),
+        );
+        tasks.push(
+            join_set.spawn(
+                scan_past_logs(
+                    listener.clone(),
+                    provider.clone(),
+                    events_filter.clone(),
+                    checkpoint_block_number,
+                    logs_blocks_range,
+                    logs_max_rps,
+                    move |checkpoint_block, head| {
+                        alerts::raise(
+                            chain_id,
+                            AlertKind::CheckpointCorrupted {
+                                checkpoint_block,
+                                head,
+                            },
+                        )
+                    },
+                )
+                .instrument(info_span!("past-scanner", chain_id)),
+            ),
+        );
+    }
+
+    tasks.push(
+        join_set.spawn(
+            monitor_head_lag(
+                chain_id,
+                listener.clone(),
+                provider.clone(),
+                checkpoint_block_number,
+                chain_config
+                    .head_lag_threshold_blocks
+                    .unwrap_or(HEAD_LAG_THRESHOLD_BLOCKS),
+                chain_config
+                    .head_lag_staleness_window_seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(HEAD_LAG_STALENESS_WINDOW),
+            )
+            .instrument(info_span!("head-lag-monitor", chain_id)),
+        ),
+    );
+
+    let heartbeat_config = chain_config.heartbeat.clone().unwrap_or_default();
+    let heartbeat_min_interval = heartbeat_config
+        .min_interval_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(HEARTBEAT_MIN_INTERVAL);
+    let scan_heartbeat = start_heartbeat(
+        heartbeat_config.scan_url,
+        heartbeat_min_interval,
+        info_span!("scan-heartbeat", chain_id),
+        join_set,
+        &mut tasks,
+    )?;
+    let answer_heartbeat = start_heartbeat(
+        heartbeat_config.answer_url,
+        heartbeat_min_interval,
+        info_span!("answer-heartbeat", chain_id),
+        join_set,
+        &mut tasks,
+    )?;
+
+    tasks.push(
+        join_set.spawn(
+            scan_present_logs(
+                listener.clone(),
+                provider.clone(),
+                events_filter,
+                Duration::from_secs(
+                    chain_config
+                        .logs_polling_interval_seconds
+                        .unwrap_or(DEFAULT_LOGS_POLLING_INTERVAL_SECONDS),
+                ),
+                logs_blocks_range,
+                move || scan_heartbeat.beat(),
+            )
+            .instrument(info_span!("present-scanner", chain_id)),
+        ),
+    );
+
+    let answering_trigger = Arc::new(Notify::new());
+    let (shutdown_sender, shutdown) = ShutdownSignal::channel();
+    answering_tasks.spawn(
+        answer_active_oracles(
+            context.dev_mode,
+            context.dry_run,
+            context.record_defillama_responses,
+            chain_id,
+            chain_config,
+            answerer_keys.clone(),
+            archive_node,
+            quorum_reader,
+            oracles_acknowledged,
+            answering_trigger.clone(),
+            context.db_connection_pool.clone(),
+            context.defillama.clone(),
+            context.ipfs_fetcher.pins().then(|| {
+                Arc::new(ProofPublisher::new(
+                    context.ipfs_fetcher.clone(),
+                    context.data_manager_http_client.clone(),
+                    context.db_connection_pool.clone(),
+                ))
+            }),
+            answer_heartbeat,
+            shutdown,
+        )
+        .instrument(info_span!("answerer", chain_id)),
+    );
+
+    Ok(RunningChain {
+        listener,
+        backfiller,
+        provider,
+        answerer_keys,
+        answering_trigger,
+        tasks,
+        shutdown_sender,
+    })
+}
+
+// the chain's rpc clients, also used by the commands that act on a single chain
+pub struct ChainClients {
+    pub provider: Arc<Provider<FallbackHttp>>,
+    // one per answerer key, the primary one first
+    pub signers: Vec<Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>>,
+    pub archive_node: Option<Arc<ArchiveNode>>,
+    pub quorum_reader: Arc<QuorumReader>,
+}
+
+pub async fn connect_chain(
+    chain_id: u64,
+    chain_config: &ChainConfig,
+) -> anyhow::Result<ChainClients> {
+    let answerer_signers = build_answerer_signers(chain_id, chain_config)
+        .await
+        .context(format!(
+            "could not build answerer signers for chain {}",
+            chain_id
+        ))?;
+    connect_chain_with_signers(chain_id, chain_config, answerer_signers)
+}
+
+// same as connect_chain, but with the given signers in place of the configured
+// answerer keys
+pub fn connect_chain_with_signers(
+    chain_id: u64,
+    chain_config: &ChainConfig,
+    answerer_signers: Vec<AnswererSigner>,
+) -> anyhow::Result<ChainClients> {
+    let fallback_http = get_fallback_http(chain_id, chain_config)?;
+    let provider = Arc::new(Provider::new(fallback_http.clone()));
+    let signers: Vec<_> = answerer_signers
+        .into_iter()
+        .map(|answerer_signer| {
+            Arc::new(SignerMiddleware::new(
+                Provider::new(fallback_http.clone()),
+                answerer_signer,
+            ))
+        })
+        .collect();
+
+    let archive_node = match chain_config.archive_rpc_endpoint.clone() {
+        Some(archive_rpc_endpoint) => {
+            tracing::info!(
+                "using archive node for historical reads: {}",
+                archive_rpc_endpoint
+            );
+            Some(Arc::new(ArchiveNode::new(get_provider(
+                chain_id,
+                archive_rpc_endpoint,
+            )?)))
+        }
+        None => None,
+    };
+
+    let quorum_reader = match chain_config.quorum_rpc_endpoints.clone() {
+        Some(quorum_rpc_endpoints) => {
+            tracing::info!(
+                "confirming critical reads against {} quorum rpc endpoint(s)",
+                quorum_rpc_endpoints.len()
+            );
+            Arc::new(QuorumReader::new(quorum_rpc_endpoints)?)
+        }
+        None => Arc::new(QuorumReader::disabled()),
+    };
+
+    Ok(ChainClients {
+        provider,
+        signers,
+        archive_node,
+        quorum_reader,
+    })
+}
+
+pub fn build_answerer_keys(keys: Vec<AnswererKey>) -> AnswererKeys {
+    AnswererKeys::new(keys)
+}
+
+fn start_heartbeat(
+    url: Option<String>,
+    min_interval: Duration,
+    span: Span,
+    join_set: &mut JoinSet<TaskResult>,
+    tasks: &mut Vec<AbortHandle>,
+) -> anyhow::Result<Heartbeat> {
+    let url = match url {
+        Some(url) => url,
+        None => return Ok(Heartbeat::disabled()),
+    };
+    let (heartbeat, pinger) = Heartbeat::new(url, min_interval)?;
+    tasks.push(join_set.spawn(pinger.instrument(span)));
+    Ok(heartbeat)
+}
+
+fn get_checkpoint_block_number(
+    chain_id: u64,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<Option<u64>> {
+    let mut db_connection = db_connection_pool
+        .get()
+        .context("could not get database connection to get checkpoint block")?;
+
+    let checkpoint_block = models::Checkpoint::get_for_chain_id(&mut db_connection, chain_id)
+        .context("could not get checkpoint block")?;
+
+    Ok(checkpoint_block.map(|checkpoint| {
+        // realistically, the following should never happen
+        u64::try_from(checkpoint.block_number).unwrap_or_else(|_| {
+            panic!(
+                "could not convert checkpoint block number {} to unsigned integer",
+                checkpoint.block_number
+            )
+        })
+    }))
+}
+
+fn get_provider(chain_id: u64, rpc_url: String) -> anyhow::Result<Provider<Http>> {
+    Provider::<Http>::try_from(rpc_url)
+        .context(format!("could not get provider for chain {}", chain_id))
+}
+
+fn get_fallback_http(chain_id: u64, chain_config: &ChainConfig) -> anyhow::Result<FallbackHttp> {
+    let mut rpc_endpoints = vec![chain_config.rpc_endpoint.clone()];
+    rpc_endpoints.extend(
+        chain_config
+            .fallback_rpc_endpoints
+            .iter()
+            .flatten()
+            .cloned(),
+    );
+    let rpc_endpoints = rpc_endpoints
+        .into_iter()
+        .map(|rpc_endpoint| {
+            let max_rps = chain_config
+                .rpc_endpoints_max_rps
+                .as_ref()
+                .and_then(|rpc_endpoints_max_rps| rpc_endpoints_max_rps.get(&rpc_endpoint))
+                .copied()
+                .or(chain_config.rpc_max_rps);
+            (rpc_endpoint, max_rps)
+        })
+        .collect();
+    let fallback_http = FallbackHttp::new(
+        chain_id,
+        rpc_endpoints,
+        chain_config
+            .rpc_failover_threshold
+            .unwrap_or(RPC_FAILOVER_THRESHOLD),
+    )
+    .context(format!("could not get provider for chain {}", chain_id))?;
+    Ok(fallback_http.with_circuit_breaker(
+        chain_config
+            .rpc_circuit_breaker_threshold
+            .unwrap_or(RPC_CIRCUIT_BREAKER_THRESHOLD),
+        chain_config
+            .rpc_circuit_breaker_cooldown_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(RPC_CIRCUIT_BREAKER_COOLDOWN),
+    ))
+}
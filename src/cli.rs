@@ -0,0 +1,309 @@
+pub mod answer;
+pub mod list_oracles;
+pub mod reset_checkpoint;
+pub mod validate_config;
+
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::Address;
+
+use crate::{
+    commons::{Config, LEDGER_HD_PATH},
+    db::{self, DbConnection},
+};
+
+pub const USAGE: &str = "usage: defillama-answerer [COMMAND] [OPTIONS]
+
+commands:
+  serve            runs the service (default)
+  validate-config  checks the config against the configured chains and prints a report
+  list-oracles     prints the tracked oracles
+                   [--chain-id <id>] [--format table|json]
+  answer           computes and submits the answer of an oracle right away
+                   --chain-id <id> --address <address> [--ledger [--hd-path <path>]]
+  reset-checkpoint sets the block logs scanning resumes from, asking for confirmation
+                   --chain-id <id> --block <number> [--yes]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(anyhow::anyhow!("unknown output format {}", format)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Serve,
+    ValidateConfig,
+    ListOracles {
+        chain_id: Option<u64>,
+        format: OutputFormat,
+    },
+    Answer {
+        chain_id: u64,
+        address: Address,
+        // derivation path of the ledger key to sign with, in place of the
+        // configured answerer keys
+        ledger_hd_path: Option<String>,
+    },
+    ResetCheckpoint {
+        chain_id: u64,
+        block_number: u64,
+        assume_yes: bool,
+    },
+}
+
+impl Command {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let command = match args.next() {
+            Some(command) => command,
+            None => return Ok(Command::Serve),
+        };
+        let mut options = Options::parse(args)?;
+        let command = match command.as_str() {
+            "serve" => Command::Serve,
+            "validate-config" => Command::ValidateConfig,
+            "list-oracles" => Command::ListOracles {
+                chain_id: options.take("chain-id")?,
+                format: options.take("format")?.unwrap_or_default(),
+            },
+            "answer" => {
+                let ledger = options.flag("ledger")?;
+                let hd_path: Option<String> = options.take("hd-path")?;
+                if hd_path.is_some() && !ledger {
+                    anyhow::bail!("option --hd-path requires --ledger");
+                }
+                Command::Answer {
+                    chain_id: options.require("chain-id")?,
+                    address: options.require("address")?,
+                    ledger_hd_path: ledger
+                        .then(|| hd_path.unwrap_or_else(|| LEDGER_HD_PATH.to_owned())),
+                }
+            }
+            "reset-checkpoint" => Command::ResetCheckpoint {
+                chain_id: options.require("chain-id")?,
+                block_number: options.require("block")?,
+                assume_yes: options.flag("yes")?,
+            },
+            _ => anyhow::bail!("unknown command {}", command),
+        };
+        options.ensure_consumed()?;
+        Ok(command)
+    }
+
+    // commands other than serve print their output on stdout, so logs are kept apart
+    pub fn logs_to_stderr(&self) -> bool {
+        !matches!(self, Command::Serve)
+    }
+}
+
+// migrations are left to the service, commands expect the schema to be up to date
+pub fn connect_db(config: &Config) -> anyhow::Result<Pool<ConnectionManager<DbConnection>>> {
+    let db_pool_config = config.db_pool.clone().unwrap_or_default();
+    db::blocking(|| db::connect(&config.db_connection_string, &db_pool_config))
+}
+
+// the --name value options and --name flags given to a command
+struct Options {
+    values: HashMap<String, Option<String>>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut values = HashMap::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            let name = match arg.strip_prefix("--") {
+                Some(name) if !name.is_empty() => name.to_owned(),
+                _ => anyhow::bail!("unexpected argument {}", arg),
+            };
+            let value = args.next_if(|arg| !arg.starts_with("--"));
+            if values.insert(name, value).is_some() {
+                anyhow::bail!("option {} given more than once", arg);
+            }
+        }
+        Ok(Self { values })
+    }
+
+    fn take<T>(&mut self, name: &str) -> anyhow::Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Into<anyhow::Error>,
+    {
+        match self.values.remove(name) {
+            Some(Some(value)) => {
+                Ok(Some(value.parse().map_err(Into::into).context(format!(
+                    "invalid value {} for option --{}",
+                    value, name
+                ))?))
+            }
+            Some(None) => anyhow::bail!("missing value for option --{}", name),
+            None => Ok(None),
+        }
+    }
+
+    fn require<T>(&mut self, name: &str) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: Into<anyhow::Error>,
+    {
+        self.take(name)?
+            .context(format!("missing required option --{}", name))
+    }
+
+    fn flag(&mut self, name: &str) -> anyhow::Result<bool> {
+        match self.values.remove(name) {
+            Some(Some(value)) => anyhow::bail!("unexpected value {} for flag --{}", value, name),
+            Some(None) => Ok(true),
+            None => Ok(false),
+        }
+    }
+
+    fn ensure_consumed(&self) -> anyhow::Result<()> {
+        match self.values.keys().next() {
+            Some(name) => anyhow::bail!("unknown option --{}", name),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::Address;
+
+    use super::{Command, OutputFormat};
+
+    fn parse(args: &[&str]) -> anyhow::Result<Command> {
+        Command::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse(&[]).unwrap(), Command::Serve);
+        assert_eq!(parse(&["serve"]).unwrap(), Command::Serve);
+        assert_eq!(
+            parse(&["validate-config"]).unwrap(),
+            Command::ValidateConfig
+        );
+        assert_eq!(
+            parse(&["list-oracles"]).unwrap(),
+            Command::ListOracles {
+                chain_id: None,
+                format: OutputFormat::Table
+            }
+        );
+        assert_eq!(
+            parse(&["list-oracles", "--format", "json", "--chain-id", "100"]).unwrap(),
+            Command::ListOracles {
+                chain_id: Some(100),
+                format: OutputFormat::Json
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "answer",
+                "--chain-id",
+                "100",
+                "--address",
+                "0x0000000000000000000000000000000000000001"
+            ])
+            .unwrap(),
+            Command::Answer {
+                chain_id: 100,
+                address: Address::from_low_u64_be(1),
+                ledger_hd_path: None
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "answer",
+                "--chain-id",
+                "100",
+                "--address",
+                "0x0000000000000000000000000000000000000001",
+                "--ledger"
+            ])
+            .unwrap(),
+            Command::Answer {
+                chain_id: 100,
+                address: Address::from_low_u64_be(1),
+                ledger_hd_path: Some("m/44'/60'/0'/0/0".to_owned())
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "answer",
+                "--chain-id",
+                "100",
+                "--address",
+                "0x0000000000000000000000000000000000000001",
+                "--ledger",
+                "--hd-path",
+                "m/44'/60'/1'/0/0"
+            ])
+            .unwrap(),
+            Command::Answer {
+                chain_id: 100,
+                address: Address::from_low_u64_be(1),
+                ledger_hd_path: Some("m/44'/60'/1'/0/0".to_owned())
+            }
+        );
+        assert!(parse(&[
+            "answer",
+            "--chain-id",
+            "100",
+            "--address",
+            "0x0000000000000000000000000000000000000001",
+            "--hd-path",
+            "m/44'/60'/1'/0/0"
+        ])
+        .is_err());
+        assert!(parse(&["answer", "--chain-id", "100"]).is_err());
+        assert!(parse(&["answer", "--chain-id", "100", "--address", "0x01"]).is_err());
+        assert_eq!(
+            parse(&["reset-checkpoint", "--chain-id", "100", "--block", "42"]).unwrap(),
+            Command::ResetCheckpoint {
+                chain_id: 100,
+                block_number: 42,
+                assume_yes: false
+            }
+        );
+        assert_eq!(
+            parse(&[
+                "reset-checkpoint",
+                "--yes",
+                "--chain-id",
+                "100",
+                "--block",
+                "42"
+            ])
+            .unwrap(),
+            Command::ResetCheckpoint {
+                chain_id: 100,
+                block_number: 42,
+                assume_yes: true
+            }
+        );
+        assert!(parse(&["reset-checkpoint", "--chain-id", "100", "--block", "-1"]).is_err());
+        assert!(parse(&["list-oracles", "--format", "yaml"]).is_err());
+        assert!(parse(&["list-oracles", "--chain-id"]).is_err());
+        assert!(parse(&["list-oracles", "--chain-id", "1", "--chain-id", "2"]).is_err());
+        assert!(parse(&["foo"]).is_err());
+        assert!(parse(&["serve", "--foo"]).is_err());
+        assert!(parse(&["serve", "foo"]).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::Address;
+
+use crate::{
+    answerer::{answer_oracle, keys::AnswererKey},
+    chains::{build_answerer_keys, connect_chain, ChainClients},
+    commons::{ChainConfig, Config},
+    db::DbConnection,
+    defillama::DefiLlamaClient,
+};
+
+#[cfg(feature = "ledger")]
+use crate::signer::AnswererSigner;
+
+// signing through a ledger needs the binary to be built with the ledger feature
+#[cfg(feature = "ledger")]
+async fn connect_ledger(
+    chain_id: u64,
+    chain_config: &ChainConfig,
+    hd_path: &str,
+) -> anyhow::Result<ChainClients> {
+    let signer = AnswererSigner::from_ledger(chain_id, hd_path).await?;
+    crate::chains::connect_chain_with_signers(chain_id, chain_config, vec![signer])
+}
+
+#[cfg(not(feature = "ledger"))]
+async fn connect_ledger(
+    _chain_id: u64,
+    _chain_config: &ChainConfig,
+    _hd_path: &str,
+) -> anyhow::Result<ChainClients> {
+    anyhow::bail!("ledger signing is not supported, build with the ledger feature to enable it")
+}
+
+// balances aren't monitored here, the answer is submitted with whatever funds the
+// answerer keys have. with a ledger the device's key must be the oracle's answerer
+pub async fn answer(
+    config: &Config,
+    chain_id: u64,
+    address: Address,
+    ledger_hd_path: Option<String>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+) -> anyhow::Result<()> {
+    let chain_config = config
+        .chain_configs
+        .get(&chain_id)
+        .cloned()
+        .context(format!("chain {} is not configured", chain_id))?;
+    let ChainClients {
+        signers,
+        archive_node,
+        quorum_reader,
+        ..
+    } = match ledger_hd_path {
+        Some(hd_path) => connect_ledger(chain_id, &chain_config, &hd_path).await?,
+        None => connect_chain(chain_id, &chain_config).await?,
+    };
+    let answerer_keys = signers
+        .into_iter()
+        .map(|signer| AnswererKey::new(signer, None))
+        .collect();
+    let answerer_keys = Arc::new(build_answerer_keys(answerer_keys));
+
+    let dry_run = config.dry_run.unwrap_or(false);
+    if dry_run {
+        tracing::warn!("running in dry run mode, the answer will be computed but not submitted");
+    }
+    answer_oracle(
+        config.dev_mode.unwrap_or(false),
+        dry_run,
+        config.record_defillama_responses.unwrap_or(true),
+        chain_id,
+        address,
+        chain_config,
+        answerer_keys,
+        archive_node,
+        quorum_reader,
+        db_connection_pool,
+        defillama,
+    )
+    .await
+}
@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use serde::Serialize;
+
+use crate::{
+    answerer::diagnostics::next_action,
+    db::{
+        models::{self, RecordFilter},
+        DbConnection,
+    },
+};
+
+use super::OutputFormat;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleListing {
+    pub chain_id: u64,
+    pub address: String,
+    pub measurement_timestamp: u64,
+    pub expiration: Option<u64>,
+    pub state: String,
+    pub description: String,
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// the state is the next action the answerer will take on the oracle
+fn get_oracle_listings(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: Option<u64>,
+) -> anyhow::Result<Vec<OracleListing>> {
+    let mut db_connection = db_connection_pool.get()?;
+    let active_oracles = models::ActiveOracle::get_all_filtered(
+        &mut db_connection,
+        &RecordFilter {
+            chain_id,
+            limit: i64::MAX,
+            ..Default::default()
+        },
+    )?;
+
+    let mut answer_overrides = HashMap::new();
+    let mut answer_reviews = HashMap::new();
+    let mut chain_ids: Vec<_> = active_oracles
+        .iter()
+        .map(|active_oracle| active_oracle.chain_id as u64)
+        .collect();
+    chain_ids.sort();
+    chain_ids.dedup();
+    for chain_id in chain_ids {
+        for answer_override in
+            models::AnswerOverride::get_all_for_chain_id(&mut db_connection, chain_id)?
+        {
+            answer_overrides.insert(
+                (chain_id, answer_override.oracle_address.0),
+                answer_override,
+            );
+        }
+        for answer_review in
+            models::AnswerReview::get_all_for_chain_id(&mut db_connection, chain_id)?
+        {
+            answer_reviews.insert((chain_id, answer_review.oracle_address.0), answer_review);
+        }
+    }
+
+    let now = SystemTime::now();
+    Ok(active_oracles
+        .iter()
+        .map(|active_oracle| {
+            let key = (active_oracle.chain_id as u64, active_oracle.address.0);
+            let next_action = next_action(
+                active_oracle,
+                answer_overrides.get(&key),
+                answer_reviews.get(&key),
+                now,
+            );
+            OracleListing {
+                chain_id: active_oracle.chain_id as u64,
+                address: format!("0x{:x}", active_oracle.address.0),
+                measurement_timestamp: to_unix_timestamp(*active_oracle.measurement_timestamp),
+                expiration: active_oracle
+                    .expiration
+                    .map(|timestamp| to_unix_timestamp(*timestamp)),
+                state: next_action.kind().to_owned(),
+                description: next_action.description(),
+            }
+        })
+        .collect())
+}
+
+fn render_table(listings: &[OracleListing]) -> String {
+    let mut rows = vec![[
+        "CHAIN".to_owned(),
+        "ADDRESS".to_owned(),
+        "MEASUREMENT".to_owned(),
+        "EXPIRATION".to_owned(),
+        "STATE".to_owned(),
+    ]];
+    for listing in listings.iter() {
+        rows.push([
+            listing.chain_id.to_string(),
+            listing.address.clone(),
+            listing.measurement_timestamp.to_string(),
+            listing
+                .expiration
+                .map(|expiration| expiration.to_string())
+                .unwrap_or_else(|| "-".to_owned()),
+            listing.state.clone(),
+        ]);
+    }
+
+    let mut widths = [0; 5];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn list_oracles(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: Option<u64>,
+    format: OutputFormat,
+) -> anyhow::Result<String> {
+    let listings = get_oracle_listings(db_connection_pool, chain_id)?;
+    Ok(match format {
+        OutputFormat::Table => render_table(&listings),
+        OutputFormat::Json => serde_json::to_string_pretty(&listings)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{render_table, OracleListing};
+
+    #[test]
+    fn render_oracles_table() {
+        let listings = vec![
+            OracleListing {
+                chain_id: 100,
+                address: "0x01".to_owned(),
+                measurement_timestamp: 1_700_000_000,
+                expiration: Some(1_800_000_000),
+                state: "computeAnswer".to_owned(),
+                description: "computing and submitting the answer".to_owned(),
+            },
+            OracleListing {
+                chain_id: 11155111,
+                address: "0x02".to_owned(),
+                measurement_timestamp: 1_700_000_000,
+                expiration: None,
+                state: "waitForMeasurementTimestamp".to_owned(),
+                description: "waiting for the measurement timestamp 1700000000".to_owned(),
+            },
+        ];
+        assert_eq!(
+            render_table(&listings),
+            "CHAIN     ADDRESS  MEASUREMENT  EXPIRATION  STATE\n\
+             100       0x01     1700000000   1800000000  computeAnswer\n\
+             11155111  0x02     1700000000   -           waitForMeasurementTimestamp"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    env,
+    io::{BufRead, Write},
+    time::Duration,
+};
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::providers::{Http, Middleware, Provider};
+use tokio::time::timeout;
+
+use crate::{
+    commons::ChainConfig,
+    db::{models, DbConnection},
+};
+
+const HEAD_BLOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn fetch_head_block(chain_config: &ChainConfig) -> anyhow::Result<u64> {
+    let provider = Provider::<Http>::try_from(chain_config.rpc_endpoint.as_str())
+        .context("invalid rpc endpoint")?;
+    let head = timeout(HEAD_BLOCK_TIMEOUT, provider.get_block_number())
+        .await
+        .context("timed out fetching the head block")?
+        .context("could not fetch the head block")?;
+    Ok(head.as_u64())
+}
+
+// asks for a yes or no answer, anything other than yes counting as no
+fn confirm(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+) -> anyhow::Result<bool> {
+    write!(output, "{} [y/N] ", question)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// the running scanners keep moving the checkpoint forward, which is why the
+// operator is asked to stop the answerer first. advancing the checkpoint skips
+// blocks, so any oracle created in them is never acknowledged
+#[allow(clippy::too_many_arguments)]
+pub fn reset_checkpoint(
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    chain_id: u64,
+    chain_config: &ChainConfig,
+    block_number: u64,
+    head_block_number: u64,
+    assume_yes: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> anyhow::Result<String> {
+    let factories_deployment_block = chain_config
+        .factories
+        .iter()
+        .map(|factory| factory.deployment_block)
+        .min()
+        .context(format!("no factories configured for chain {}", chain_id))?;
+    if block_number < factories_deployment_block {
+        anyhow::bail!(
+            "block {} is before the factories deployment block {}",
+            block_number,
+            factories_deployment_block
+        );
+    }
+    if block_number > head_block_number {
+        anyhow::bail!(
+            "block {} is after the head block {}",
+            block_number,
+            head_block_number
+        );
+    }
+    let block_number_i64 = i64::try_from(block_number).context("block number too big")?;
+
+    let mut db_connection = db_connection_pool.get()?;
+    let current_block_number = models::Checkpoint::get_for_chain_id(&mut db_connection, chain_id)?
+        .map(|checkpoint| checkpoint.block_number as u64);
+    let change = match current_block_number {
+        Some(current) if current == block_number => {
+            return Ok(format!(
+                "checkpoint for chain {} already at block {}",
+                chain_id, block_number
+            ));
+        }
+        Some(current) if current > block_number => format!(
+            "rewinding checkpoint for chain {} from block {} to block {}, {} blocks will be rescanned",
+            chain_id,
+            current,
+            block_number,
+            current - block_number
+        ),
+        Some(current) => format!(
+            "advancing checkpoint for chain {} from block {} to block {}, {} blocks will be SKIPPED and oracles created in them never answered",
+            chain_id,
+            current,
+            block_number,
+            block_number - current
+        ),
+        None => format!(
+            "setting checkpoint for chain {} to block {}, no checkpoint stored yet",
+            chain_id, block_number
+        ),
+    };
+
+    if !assume_yes {
+        writeln!(output, "{}", change)?;
+        if !confirm(
+            input,
+            output,
+            "running answerer instances would overwrite the checkpoint, are they all stopped?",
+        )? || !confirm(input, output, "reset the checkpoint?")?
+        {
+            anyhow::bail!("aborted");
+        }
+    }
+
+    let actor = format!(
+        "{} (cli)",
+        env::var("USER").unwrap_or_else(|_| "unknown".to_owned())
+    );
+    models::Checkpoint::reset(&mut db_connection, chain_id, block_number_i64, &actor)?;
+    Ok(format!(
+        "checkpoint for chain {} reset to block {}",
+        chain_id, block_number
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::confirm;
+
+    #[test]
+    fn confirm_answers() {
+        for (answer, confirmed) in [
+            ("y\n", true),
+            ("YES\n", true),
+            (" yes \n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("", false),
+            ("yep\n", false),
+        ] {
+            let mut output = Vec::new();
+            assert_eq!(
+                confirm(&mut Cursor::new(answer), &mut output, "proceed?").unwrap(),
+                confirmed
+            );
+            assert_eq!(String::from_utf8(output).unwrap(), "proceed? [y/N] ");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    signers::Signer,
+    types::Address,
+};
+use serde::Serialize;
+use tokio::time::timeout;
+
+use crate::{
+    commons::{ChainConfig, Config},
+    signer::build_answerer_signers,
+};
+
+const RPC_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: String, result: anyhow::Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                ok: true,
+                detail,
+            },
+            Err(error) => Self {
+                name,
+                ok: false,
+                detail: format!("{:#}", error),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainReport {
+    pub chain_id: u64,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub valid: bool,
+    pub chains: Vec<ChainReport>,
+}
+
+async fn check_rpc_chain_id(chain_id: u64, rpc_endpoint: &str) -> anyhow::Result<String> {
+    let provider = Provider::<Http>::try_from(rpc_endpoint).context("invalid rpc endpoint")?;
+    let rpc_chain_id = timeout(RPC_CHECK_TIMEOUT, provider.get_chainid())
+        .await
+        .context("timed out")?
+        .context("could not get chain id")?;
+    if rpc_chain_id.as_u64() != chain_id {
+        anyhow::bail!("rpc is on chain {}", rpc_chain_id);
+    }
+    Ok(format!("rpc is on chain {}", rpc_chain_id))
+}
+
+async fn check_code(rpc_endpoint: &str, address: Address) -> anyhow::Result<String> {
+    let provider = Provider::<Http>::try_from(rpc_endpoint).context("invalid rpc endpoint")?;
+    let code = timeout(RPC_CHECK_TIMEOUT, provider.get_code(address, None))
+        .await
+        .context("timed out")?
+        .context("could not get code")?;
+    if code.is_empty() {
+        anyhow::bail!("no code at address");
+    }
+    Ok(format!("{} bytes of code", code.len()))
+}
+
+async fn check_chain(chain_id: u64, chain_config: &ChainConfig) -> ChainReport {
+    let mut checks = Vec::new();
+
+    let signers_check = build_answerer_signers(chain_id, chain_config)
+        .await
+        .map(|signers| {
+            let addresses: Vec<_> = signers
+                .iter()
+                .map(|signer| format!("0x{:x}", signer.address()))
+                .collect();
+            format!("answerers {}", addresses.join(", "))
+        });
+    checks.push(Check::new("answerer keys".to_owned(), signers_check));
+
+    let rpc_endpoints = [chain_config.rpc_endpoint.clone()]
+        .into_iter()
+        .chain(
+            chain_config
+                .fallback_rpc_endpoints
+                .iter()
+                .flatten()
+                .cloned(),
+        )
+        .chain(chain_config.quorum_rpc_endpoints.iter().flatten().cloned())
+        .chain(chain_config.archive_rpc_endpoint.clone());
+    for rpc_endpoint in rpc_endpoints {
+        checks.push(Check::new(
+            format!("rpc endpoint {}", rpc_endpoint),
+            check_rpc_chain_id(chain_id, &rpc_endpoint).await,
+        ));
+    }
+
+    if chain_config.factories.is_empty() {
+        checks.push(Check::new(
+            "factories".to_owned(),
+            Err(anyhow::anyhow!("no factories configured")),
+        ));
+    }
+    for factory in chain_config.factories.iter() {
+        checks.push(Check::new(
+            format!("factory 0x{:x}", factory.address),
+            check_code(&chain_config.rpc_endpoint, factory.address).await,
+        ));
+    }
+
+    ChainReport { chain_id, checks }
+}
+
+// checks every configured chain without starting anything, reporting all the
+// problems found instead of stopping at the first one
+pub async fn validate_config(config: &Config) -> Report {
+    let mut chain_ids: Vec<_> = config.chain_configs.keys().copied().collect();
+    chain_ids.sort();
+    let mut chains = Vec::with_capacity(chain_ids.len());
+    for chain_id in chain_ids {
+        chains.push(check_chain(chain_id, &config.chain_configs[&chain_id]).await);
+    }
+    Report {
+        valid: chains
+            .iter()
+            .all(|chain| chain.checks.iter().all(|check| check.ok)),
+        chains,
+    }
+}
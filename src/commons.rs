@@ -1,17 +1,97 @@
-use std::{collections::HashMap, net::Ipv4Addr, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::Ipv4Addr,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 use carrot_commons::http_client::HttpClient;
-use diesel::{
-    r2d2::{ConnectionManager, Pool},
-    PgConnection,
-};
-use ethers::types::Address;
-use serde::{Deserialize, Serialize};
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::alerts::AlertSeverity;
+use crate::db::DbConnection;
+use crate::defillama::DefiLlamaClient;
+
+pub const LEDGER_HD_PATH: &str = "m/44'/60'/0'/0/0";
 pub const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
 pub const ANSWERING_TASK_INTERVAL_SECONDS: Duration = Duration::from_secs(10);
 pub const FETCH_SPECIFICATION_JSON_MAX_ELAPSED_TIME: Duration = Duration::from_secs(6);
 pub const STORE_CID_MAX_ELAPSED_TIME: Duration = Duration::from_secs(60);
+pub const IPFS_PIN_TIMEOUT: Duration = Duration::from_secs(120);
+pub const IPFS_FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+pub const IPFS_MAX_DOCUMENT_SIZE: usize = 1_048_576;
+pub const ANSWER_COMPUTATION_TIMEOUT: Duration = Duration::from_secs(60);
+pub const ANSWER_RETRY_INITIAL_BACKOFF: Duration = Duration::from_secs(30);
+pub const ANSWER_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(3_600);
+pub const GAS_BUDGET_DAILY_WINDOW: Duration = Duration::from_secs(86_400);
+pub const GAS_BUDGET_WEEKLY_WINDOW: Duration = Duration::from_secs(604_800);
+pub const BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+pub const ANSWERING_CONCURRENCY: usize = 5;
+pub const ANSWER_CLAIM_DURATION: Duration = Duration::from_secs(600);
+pub const ORPHANED_ANSWER_TXS_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+pub const HEARTBEAT_MIN_INTERVAL: Duration = Duration::from_secs(60);
+pub const ORPHANED_ANSWER_TX_THRESHOLD: Duration = Duration::from_secs(1_800);
+pub const FINALIZED_ORACLES_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+pub const EXPIRED_ORACLES_PURGE_INTERVAL: Duration = Duration::from_secs(3_600);
+pub const REORG_CONFIRMATION_BLOCKS: u64 = 10;
+pub const CHECKPOINT_CONFIRMATION_BLOCKS: u64 = 10;
+pub const REORG_WATCH_POLLING_INTERVAL: Duration = Duration::from_secs(5);
+pub const REORG_WATCH_MAX_DURATION: Duration = Duration::from_secs(3_600);
+pub const ANOMALY_REFERENCE_AGE: Duration = Duration::from_secs(86_400);
+pub const SOURCE_MISSING_RECHECK_INTERVAL: Duration = Duration::from_secs(3_600);
+// the answer kpi token templates treat as invalid, used when no meaningful answer
+// can be given before the oracle expires
+pub const INVALID_ANSWER: U256 = U256::MAX;
+pub const ANSWER_TX_FEE_BUMP_PERCENTAGE: u64 = 20;
+pub const ANSWER_TX_MAX_RESUBMISSIONS: u32 = 3;
+pub const ANSWER_TX_REVERTS_ALERT_THRESHOLD: u32 = 3;
+pub const VAULT_TOKEN_RENEWAL_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+pub const PAST_LOGS_BLOCKS_RANGE: u64 = 5_000;
+pub const PAST_LOGS_MAX_RPS: u32 = 1;
+pub const RPC_FAILOVER_THRESHOLD: u32 = 3;
+pub const RPC_CIRCUIT_BREAKER_THRESHOLD: u32 = 10;
+pub const RPC_CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(60);
+pub const RECONCILIATION_INTERVAL: Duration = Duration::from_secs(3_600);
+pub const RECONCILIATION_MARGIN_BLOCKS: u64 = 10_000;
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(120);
+pub const LOG_FILE_MAX_SIZE_MB: u64 = 100;
+pub const LOG_FILE_MAX_FILES: usize = 5;
+pub const HEAD_LAG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+pub const HEAD_LAG_THRESHOLD_BLOCKS: u64 = 100;
+pub const HEAD_LAG_STALENESS_WINDOW: Duration = Duration::from_secs(600);
+pub const SCANNER_LEASE_DURATION: Duration = Duration::from_secs(60);
+pub const DB_POOL_MAX_SIZE: u32 = 10;
+pub const DB_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DB_CONNECT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+pub const EVENTS_CHANNEL_CAPACITY: usize = 1_024;
+pub const PG_NOTIFY_CHANNEL: &str = "oracle_events";
+pub const WEBHOOK_DELIVERY_MAX_ELAPSED_TIME: Duration = Duration::from_secs(60);
+pub const ALERTS_CHANNEL_CAPACITY: usize = 256;
+pub const ALERT_DELIVERY_MAX_ELAPSED_TIME: Duration = Duration::from_secs(120);
+pub const ALERTS_DEFAULT_MIN_SEVERITY: AlertSeverity = AlertSeverity::Warning;
+pub const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+pub const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+pub const PAGERDUTY_DEFAULT_MIN_SEVERITY: AlertSeverity = AlertSeverity::Critical;
+pub const GRAPHQL_DEFAULT_PAGE_SIZE: i64 = 100;
+pub const GRAPHQL_MAX_PAGE_SIZE: i64 = 1_000;
+pub const GRAPHQL_MAX_DEPTH: usize = 8;
+pub const GRAPHQL_MAX_COMPLEXITY: usize = 10_000;
+pub const GRPC_DEFAULT_PAGE_SIZE: u32 = 100;
+pub const GRPC_MAX_PAGE_SIZE: u32 = 1_000;
+pub const API_DEFAULT_PAGE_SIZE: u32 = 100;
+pub const API_MAX_PAGE_SIZE: u32 = 1_000;
+pub const DEFILLAMA_API_URL: &str = "https://api.llama.fi";
+pub const DEFILLAMA_COINS_URL: &str = "https://coins.llama.fi";
+pub const DEFILLAMA_STABLECOINS_URL: &str = "https://stablecoins.llama.fi";
+pub const DEFILLAMA_YIELDS_URL: &str = "https://yields.llama.fi";
+pub const DEFILLAMA_CACHE_TTL: Duration = Duration::from_secs(10);
+pub const DEFILLAMA_RETRY_MAX_ELAPSED_TIME: Duration = Duration::from_secs(20);
+pub const DEFILLAMA_RETRY_BUDGET: f64 = 20.0;
+pub const DEFILLAMA_RETRY_BUDGET_REFILL: f64 = 0.2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractConfig {
@@ -19,15 +99,208 @@ pub struct ContractConfig {
     pub deployment_block: u64,
 }
 
+// budgets are expressed in the chain's native currency (e.g. 0.5 for half an ether)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasBudgetConfig {
+    pub daily: Option<f64>,
+    pub weekly: Option<f64>,
+}
+
+// the median of the samples is used as the answer, which is rejected if the spread
+// between the samples exceeds the given percentage of the median
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerSamplingConfig {
+    pub samples: usize,
+    pub interval_seconds: u64,
+    pub max_spread_percentage: f64,
+}
+
+// answers deviating from the metric's value some time ago (a day by default) by more
+// than the given percentage are held for review instead of being submitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyDetectionConfig {
+    pub max_change_percentage: f64,
+    pub reference_age_seconds: Option<u64>,
+}
+
+// a private key is either given inline as hex or read from an encrypted json keystore,
+// whose password is taken from the given env variable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PrivateKeyConfig {
+    Raw(String),
+    Keystore {
+        keystore_path: PathBuf,
+        password_env: String,
+    },
+}
+
+// the key_id can be the key's id, arn or alias
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwsKmsKeyConfig {
+    pub key_id: String,
+    pub region: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptTimeoutPolicy {
+    Resubmit,
+    Clear,
+}
+
+// answer txs not mined within the timeout are either resubmitted with bumped fees, up
+// to the given number of times, or cleared so that the oracle is answered again later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptTimeoutConfig {
+    pub timeout_seconds: u64,
+    pub policy: ReceiptTimeoutPolicy,
+    pub fee_bump_percentage: Option<u64>,
+    pub max_resubmissions: Option<u32>,
+}
+
+// healthchecks pinged after every successful iteration of the logs scanning and
+// answering loops, at most once per interval, so that a stalled loop is noticed
+// even while the process itself is alive
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatConfig {
+    pub scan_url: Option<String>,
+    pub answer_url: Option<String>,
+    pub min_interval_seconds: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainConfig {
-    pub answerer_private_key: String,
+    // the built-in defaults of well known chains are applied unless set to false
+    pub preset: Option<bool>,
+    // exactly one of the private key and the aws kms key must be set
+    pub answerer_private_key: Option<PrivateKeyConfig>,
+    pub answerer_aws_kms_key: Option<AwsKmsKeyConfig>,
+    // keys of other answerer addresses, each used for the oracles whose answerer it is
+    #[serde(alias = "fallback_answerer_private_keys")]
+    pub additional_answerer_private_keys: Option<Vec<PrivateKeyConfig>>,
+    #[serde(alias = "fallback_answerer_aws_kms_keys")]
+    pub additional_answerer_aws_kms_keys: Option<Vec<AwsKmsKeyConfig>>,
     pub rpc_endpoint: String,
+    // used in order when the active endpoint keeps failing, see rpc_failover_threshold
+    pub fallback_rpc_endpoints: Option<Vec<String>>,
+    pub rpc_failover_threshold: Option<u32>,
+    // maximum requests per second sent to each of the main and fallback endpoints,
+    // unlimited by default. specific endpoints can be given their own limit
+    pub rpc_max_rps: Option<u32>,
+    pub rpc_endpoints_max_rps: Option<HashMap<String, u32>>,
+    // requests are paused for the cooldown after this many of them failed in a row
+    // on all endpoints
+    pub rpc_circuit_breaker_threshold: Option<u32>,
+    pub rpc_circuit_breaker_cooldown_seconds: Option<u64>,
+    // independent endpoints that must agree with the main one on the values deciding
+    // whether an oracle is answered, skipped or deleted
+    pub quorum_rpc_endpoints: Option<Vec<String>>,
+    // only needed by metrics reading on-chain state at the measurement timestamp
+    pub archive_rpc_endpoint: Option<String>,
+    // only needed on chains where multicall3 isn't deployed at its canonical address
+    pub multicall_address: Option<Address>,
     pub logs_blocks_range: Option<u64>,
+    // maximum logs requests per second sent while scanning past blocks or backfilling
+    pub logs_max_rps: Option<u32>,
     pub logs_polling_interval_seconds: Option<u64>,
+    // every reconciliation interval the last reconciliation margin blocks are scanned
+    // again, registering any oracle creation the scanners missed
+    pub reconciliation_interval_seconds: Option<u64>,
+    pub reconciliation_margin_blocks: Option<u64>,
+    // when set to false, gaps in the blocks scanned before the checkpoint aren't
+    // repaired on startup
+    pub repair_block_gaps: Option<bool>,
+    // indexing is reported as stale when the scanners lag behind the chain head by more
+    // than the threshold for longer than the staleness window
+    pub head_lag_threshold_blocks: Option<u64>,
+    pub head_lag_staleness_window_seconds: Option<u64>,
+    // the checkpoint trails the scanned block by this many blocks, so that blocks
+    // rewritten by a reorg are scanned again after a restart
+    pub checkpoint_confirmation_blocks: Option<u64>,
     pub answering_task_interval_seconds: Option<u64>,
+    pub answer_computation_timeout_seconds: Option<u64>,
+    pub answering_concurrency: Option<usize>,
+    pub answer_sampling: Option<AnswerSamplingConfig>,
+    pub anomaly_detection: Option<AnomalyDetectionConfig>,
+    // forces legacy (type 0) answer transactions on chains whose rpcs mishandle
+    // eip-1559 fields
+    pub legacy_transactions: Option<bool>,
+    pub reorg_confirmation_blocks: Option<u64>,
+    pub gas_budget: Option<GasBudgetConfig>,
+    pub receipt_timeout: Option<ReceiptTimeoutConfig>,
+    pub min_answerer_balance: Option<f64>,
+    pub balance_check_interval_seconds: Option<u64>,
+    pub orphaned_answer_tx_threshold_seconds: Option<u64>,
+    pub finalized_oracles_check_interval_seconds: Option<u64>,
+    pub expired_oracles_purge_interval_seconds: Option<u64>,
+    // when set, oracles whose data went missing from defillama are answered with
+    // their specification's fallback value this close to their expiration
+    pub source_missing_fallback_window_seconds: Option<u64>,
+    // when set, oracles whose answer can't be computed are answered with the
+    // template's invalid answer this close to their expiration
+    pub invalid_answer_window_seconds: Option<u64>,
+    pub native_token_coingecko_id: Option<String>,
+    pub heartbeat: Option<HeartbeatConfig>,
     pub template_id: u64,
-    pub factory: ContractConfig,
+    // logs from all the factories are scanned starting from the earliest deployment
+    #[serde(alias = "factory", deserialize_with = "deserialize_factories")]
+    pub factories: Vec<ContractConfig>,
+}
+
+// a single factory per chain used to be supported, so a lone one is still accepted
+fn deserialize_factories<'de, D>(deserializer: D) -> Result<Vec<ContractConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Factories {
+        One(ContractConfig),
+        Many(Vec<ContractConfig>),
+    }
+
+    Ok(match Factories::deserialize(deserializer)? {
+        Factories::One(factory) => vec![factory],
+        Factories::Many(factories) => factories,
+    })
+}
+
+// without a min idle value the pool keeps max size connections open, and statements
+// have no timeout unless given one. connect retries are extra attempts made on startup
+// while the database isn't reachable
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct DbPoolConfig {
+    pub max_size: Option<u32>,
+    pub min_idle: Option<u32>,
+    pub connection_timeout_seconds: Option<u64>,
+    pub statement_timeout_seconds: Option<u64>,
+    pub connect_retries: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Json,
+    Pretty,
+}
+
+// logs are also written to the file, which is rotated once it grows past the max size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFileConfig {
+    pub path: PathBuf,
+    pub max_size_mb: Option<u64>,
+    pub max_files: Option<usize>,
+}
+
+// targets map module paths to their own level, overriding the global one
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    pub format: Option<LogFormat>,
+    pub level: Option<String>,
+    pub targets: Option<HashMap<String, String>>,
+    pub file: Option<LogFileConfig>,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize)]
@@ -40,6 +313,22 @@ pub struct DataManagerConfig {
 pub struct ApiConfig {
     pub host: Ipv4Addr,
     pub port: u16,
+    // operator names mapped to their api keys, used to authenticate
+    // operator-only endpoints such as the answer overrides ones
+    #[serde(default)]
+    pub operators: HashMap<String, String>,
+    // exposes the read-only graphql endpoint, disabled by default
+    #[serde(default)]
+    pub graphql: bool,
+    // serves https instead of http when set
+    pub tls: Option<TlsConfig>,
+}
+
+// pem encoded certificate chain and private key, either rsa or pkcs8
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
 }
 
 impl Default for ApiConfig {
@@ -47,16 +336,189 @@ impl Default for ApiConfig {
         Self {
             host: Ipv4Addr::new(127, 0, 0, 1),
             port: 8080,
+            operators: HashMap::new(),
+            graphql: false,
+            tls: None,
         }
     }
 }
 
+// shared between every process using the same bucket name, so that replicas and
+// other services don't collectively exceed the allowed rate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedRateLimitConfig {
+    pub bucket: Option<String>,
+    pub requests_per_second: f64,
+    pub burst: Option<f64>,
+}
+
+// the urls default to defillama's public services, and can point to a self hosted
+// instance or a caching proxy instead. mirrors are base urls serving the same api as
+// api_url, tried in order when it fails. successful responses are reused for
+// cache_ttl_seconds and failed requests are retried for up to retry_max_elapsed_seconds,
+// 0 disables either
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DefiLlamaConfig {
+    pub api_url: Option<String>,
+    pub coins_url: Option<String>,
+    pub stablecoins_url: Option<String>,
+    pub yields_url: Option<String>,
+    pub cache_ttl_seconds: Option<u64>,
+    pub mirrors: Option<Vec<String>>,
+    pub retry_max_elapsed_seconds: Option<u64>,
+}
+
+// replicas sharing the same database elect a single scanning leader per chain. the
+// instance id defaults to the HOSTNAME env variable, which is the pod name on kubernetes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderElectionConfig {
+    pub instance_id: Option<String>,
+    pub lease_seconds: Option<u64>,
+}
+
+// the grpc server authenticates operators with the same api keys as the http api
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    pub host: Ipv4Addr,
+    pub port: u16,
+}
+
+// every oracle lifecycle event, or only the listed ones, is posted to the url as json,
+// signed with the secret so that receivers can verify where it comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub secret: String,
+    pub events: Option<Vec<String>>,
+}
+
+// every oracle lifecycle event, or only the listed ones, is sent as json on the
+// postgres channel through pg_notify
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PgNotifyConfig {
+    pub channel: Option<String>,
+    pub events: Option<Vec<String>>,
+}
+
+// alerts are posted through a slack incoming webhook, from the given severity on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+    pub min_severity: Option<AlertSeverity>,
+}
+
+// alerts are sent by a telegram bot to the given chat, either a numeric id or the
+// @username of a public channel, from the given severity on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
+    pub min_severity: Option<AlertSeverity>,
+}
+
+// alerts are posted through the discord webhook of the highest severity that's not
+// above theirs, so that e.g. info and critical alerts can go to different channels
+// while warnings end up with the info ones. alerts below every routed severity are
+// dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_urls: BTreeMap<AlertSeverity, String>,
+}
+
+// incidents are triggered through the events api v2 integration with the given
+// routing key, from the given severity on, and resolved once their condition clears
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PagerDutyConfig {
+    pub routing_key: String,
+    pub min_severity: Option<AlertSeverity>,
+}
+
+// conditions needing a human are sent to every configured sink on top of being logged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsConfig {
+    pub slack: Option<SlackConfig>,
+    pub telegram: Option<TelegramConfig>,
+    pub discord: Option<DiscordConfig>,
+    pub pagerduty: Option<PagerDutyConfig>,
+}
+
+// the token is read from the given env variable, VAULT_TOKEN by default, and
+// secrets from the given kv v2 mount, secret by default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultConfig {
+    pub address: String,
+    pub token_env: Option<String>,
+    pub mount: Option<String>,
+}
+
+// the endpoint is only needed to reach secrets manager through a proxy or a local
+// emulator, the credentials are found in the environment like for kms keys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AwsSecretsManagerConfig {
+    pub region: String,
+    pub endpoint: Option<String>,
+}
+
+// without an access token env variable, tokens are fetched from the metadata server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcpSecretManagerConfig {
+    pub access_token_env: Option<String>,
+    pub endpoint: Option<String>,
+}
+
+// when a refresh interval is set, resolved secrets are read again that often and the
+// service restarts once any of them was rotated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretManagersConfig {
+    pub aws: Option<AwsSecretsManagerConfig>,
+    pub gcp: Option<GcpSecretManagerConfig>,
+    pub refresh_interval_seconds: Option<u64>,
+}
+
+// a local anvil node with mock contracts and a demo oracle, only started in dev mode.
+// the demo specification is added to ipfs through the given kubo rpc api
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevnetConfig {
+    pub anvil_path: Option<String>,
+    pub port: Option<u16>,
+    pub chain_id: Option<u64>,
+    pub block_time_seconds: Option<u64>,
+    pub ipfs_api_endpoint: String,
+    pub demo_protocol: Option<String>,
+    pub demo_measurement_delay_seconds: Option<u64>,
+}
+
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct Config {
     pub db_connection_string: String,
+    pub db_pool: Option<DbPoolConfig>,
     pub ipfs_gateway_endpoint: String,
+    // raced against ipfs_gateway_endpoint when documents aren't on the data cdn
+    pub ipfs_gateway_endpoints: Option<Vec<String>>,
+    // kubo rpc api (or ipfs cluster proxy) specifications get pinned on
+    pub ipfs_pinning_endpoint: Option<String>,
+    pub ipfs_fetch_timeout_seconds: Option<u64>,
+    pub ipfs_max_document_size_bytes: Option<usize>,
+    // fetches specifications block by block, verifying them against their cid
+    pub ipfs_trustless_retrieval: Option<bool>,
     pub data_cdn_endpoint: String,
     pub dev_mode: Option<bool>,
+    pub devnet: Option<DevnetConfig>,
+    pub dry_run: Option<bool>,
+    pub record_defillama_responses: Option<bool>,
+    pub persist_indexed_logs: Option<bool>,
+    pub logging: Option<LoggingConfig>,
+    // how long in-flight answers are waited for after a sigterm or sigint
+    pub shutdown_timeout_seconds: Option<u64>,
+    pub defillama_shared_rate_limit: Option<SharedRateLimitConfig>,
+    pub defillama: Option<DefiLlamaConfig>,
+    pub leader_election: Option<LeaderElectionConfig>,
+    pub webhooks: Option<Vec<WebhookConfig>>,
+    pub pg_notify: Option<PgNotifyConfig>,
+    pub alerts: Option<AlertsConfig>,
+    pub grpc: Option<GrpcConfig>,
+    pub vault: Option<VaultConfig>,
+    pub secret_managers: Option<SecretManagersConfig>,
     pub data_manager: DataManagerConfig,
     pub api: ApiConfig,
     pub chain_configs: HashMap<u64, ChainConfig>,
@@ -70,9 +532,56 @@ pub struct ChainExecutionContext {
     pub template_id: u64,
     pub answerer_private_key: Arc<String>,
     pub ipfs_http_client: Arc<HttpClient>,
-    pub defillama_http_client: Arc<HttpClient>,
+    pub defillama: DefiLlamaClient,
     pub web3_storage_http_client: Option<Arc<HttpClient>>,
-    pub db_connection_pool: Pool<ConnectionManager<PgConnection>>,
+    pub db_connection_pool: Pool<ConnectionManager<DbConnection>>,
     pub factory_config: ContractConfig,
     pub dev_mode: bool,
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::ChainConfig;
+
+    fn chain_config(factories: serde_json::Value) -> ChainConfig {
+        let mut chain_config = json!({
+            "rpc_endpoint": "http://127.0.0.1:8545",
+            "answerer_private_key": format!("{:064x}", 1),
+            "template_id": 1,
+        });
+        chain_config
+            .as_object_mut()
+            .unwrap()
+            .extend(factories.as_object().unwrap().clone());
+        serde_json::from_value(chain_config).unwrap()
+    }
+
+    #[test]
+    fn deserialize_factories() {
+        let config = chain_config(json!({
+            "factory": {
+                "address": "0x0000000000000000000000000000000000000001",
+                "deployment_block": 10
+            }
+        }));
+        assert_eq!(config.factories.len(), 1);
+        assert_eq!(config.factories[0].deployment_block, 10);
+
+        let config = chain_config(json!({
+            "factories": [
+                {
+                    "address": "0x0000000000000000000000000000000000000001",
+                    "deployment_block": 10
+                },
+                {
+                    "address": "0x0000000000000000000000000000000000000002",
+                    "deployment_block": 5
+                }
+            ]
+        }));
+        assert_eq!(config.factories.len(), 2);
+        assert_eq!(config.factories[1].deployment_block, 5);
+    }
+}
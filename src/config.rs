@@ -0,0 +1,203 @@
+pub mod presets;
+
+use std::{env, path::PathBuf};
+
+use anyhow::Context;
+use carrot_commons::config::get_config;
+use serde_json::{Map, Value};
+
+use crate::commons::Config;
+
+use self::presets::apply_chain_preset;
+
+const ENV_OVERRIDES_PREFIX: &str = "DEFILLAMA_ANSWERER__";
+const ENV_OVERRIDES_SEPARATOR: &str = "__";
+
+// reads the config file, the one at the given path if any, and layers the
+// DEFILLAMA_ANSWERER__* env variables over it. the presets of well known chains
+// then fill in whatever their config leaves out
+pub fn load_config(alt_path: Option<PathBuf>) -> anyhow::Result<Config> {
+    let mut config: Value =
+        get_config("defillama-answerer", alt_path).context("could not read config file")?;
+    apply_env_overrides(&mut config, env::vars())?;
+    apply_chain_presets(&mut config);
+    serde_json::from_value(config).context("invalid config")
+}
+
+// chain ids that aren't numbers are left for deserialization to reject
+fn apply_chain_presets(config: &mut Value) {
+    let chain_configs = match config
+        .get_mut("chain_configs")
+        .and_then(Value::as_object_mut)
+    {
+        Some(chain_configs) => chain_configs,
+        None => return,
+    };
+    for (chain_id, chain_config) in chain_configs.iter_mut() {
+        if let Ok(chain_id) = chain_id.parse() {
+            apply_chain_preset(chain_id, chain_config);
+        }
+    }
+}
+
+// every variable maps to a field through its lowercased path, separated by double
+// underscores (e.g. DEFILLAMA_ANSWERER__CHAIN_CONFIGS__100__RPC_ENDPOINT). Values are
+// parsed as json when possible, and taken as strings otherwise
+fn apply_env_overrides(
+    config: &mut Value,
+    vars: impl Iterator<Item = (String, String)>,
+) -> anyhow::Result<()> {
+    // sorted so that a field is always overridden before its own fields
+    let mut overrides: Vec<_> = vars
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(ENV_OVERRIDES_PREFIX)?.to_lowercase();
+            Some((name, path, value))
+        })
+        .collect();
+    overrides.sort();
+
+    for (name, path, value) in overrides {
+        let mut field = &mut *config;
+        for segment in path.split(ENV_OVERRIDES_SEPARATOR) {
+            if segment.is_empty() {
+                anyhow::bail!("invalid config override {}", name);
+            }
+            // missing sections are created on the fly
+            if field.is_null() {
+                *field = Value::Object(Map::new());
+            }
+            field = match field {
+                Value::Object(fields) => fields.entry(segment).or_insert(Value::Null),
+                Value::Array(items) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| items.get_mut(index))
+                    .context(format!(
+                        "config override {} references a missing list item",
+                        name
+                    ))?,
+                _ => anyhow::bail!(
+                    "config override {} goes through a field that has no fields",
+                    name
+                ),
+            };
+        }
+        *field = serde_json::from_str(&value).unwrap_or(Value::String(value));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{apply_chain_presets, apply_env_overrides, load_config};
+
+    fn overrides(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn load_example_config() {
+        let config = load_config(Some(".config.example.yaml".into())).unwrap();
+        assert!(!config.chain_configs.is_empty());
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let mut config = json!({
+            "db_connection_string": "postgres://localhost",
+            "dry_run": false,
+            "api": { "host": "127.0.0.1", "port": 8080 },
+            "webhooks": [{ "url": "http://foo.bar" }],
+            "chain_configs": { "100": { "rpc_endpoint": "http://foo.bar" } },
+        });
+        apply_env_overrides(
+            &mut config,
+            overrides(&[
+                ("DEFILLAMA_ANSWERER__DRY_RUN", "true"),
+                ("DEFILLAMA_ANSWERER__API__PORT", "9090"),
+                ("DEFILLAMA_ANSWERER__DATA_MANAGER__API_KEY", "\"1234\""),
+                ("DEFILLAMA_ANSWERER__WEBHOOKS__0__URL", "http://bar.baz"),
+                (
+                    "DEFILLAMA_ANSWERER__CHAIN_CONFIGS__100__RPC_ENDPOINT",
+                    "http://bar.baz",
+                ),
+                ("DEFILLAMA_ANSWERER__CHAIN_CONFIGS__137__TEMPLATE_ID", "1"),
+                (
+                    "DEFILLAMA_ANSWERER__VAULT",
+                    "{\"address\": \"http://vault\"}",
+                ),
+                ("DEFILLAMA_ANSWERER__VAULT__MOUNT", "kv"),
+                ("OTHER__DRY_RUN", "false"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            json!({
+                "db_connection_string": "postgres://localhost",
+                "dry_run": true,
+                "data_manager": { "api_key": "1234" },
+                "api": { "host": "127.0.0.1", "port": 9090 },
+                "webhooks": [{ "url": "http://bar.baz" }],
+                "vault": { "address": "http://vault", "mount": "kv" },
+                "chain_configs": {
+                    "100": { "rpc_endpoint": "http://bar.baz" },
+                    "137": { "template_id": 1 },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn reject_invalid_overrides() {
+        let config = json!({ "dry_run": false, "webhooks": [] });
+        for name in [
+            "DEFILLAMA_ANSWERER__DRY_RUN__FOO",
+            "DEFILLAMA_ANSWERER__WEBHOOKS__0__URL",
+            "DEFILLAMA_ANSWERER__API____PORT",
+        ] {
+            assert!(apply_env_overrides(&mut config.clone(), overrides(&[(name, "1")])).is_err());
+        }
+    }
+
+    #[test]
+    fn apply_presets() {
+        let mut config = json!({
+            "chain_configs": {
+                "100": { "rpc_endpoint": "http://foo.bar", "logs_blocks_range": 1000 },
+                "11155111": {
+                    "rpc_endpoint": "http://foo.bar",
+                    "factory": { "address": "0x01", "deployment_block": 1 },
+                },
+                "534351": { "rpc_endpoint": "http://foo.bar", "preset": false },
+                "1337": { "rpc_endpoint": "http://foo.bar" },
+            },
+        });
+        apply_chain_presets(&mut config);
+
+        let gnosis = &config["chain_configs"]["100"];
+        assert_eq!(gnosis["logs_blocks_range"], 1000);
+        assert_eq!(gnosis["template_id"], 2);
+        assert_eq!(
+            gnosis["factories"][0]["address"],
+            "0xD503Bdcc3Cd38D3cEaBa1efA43EFCc03b7Fb1CbA"
+        );
+        let sepolia = &config["chain_configs"]["11155111"];
+        assert_eq!(sepolia["logs_blocks_range"], 2000);
+        assert!(sepolia.get("factories").is_none());
+        assert_eq!(
+            config["chain_configs"]["534351"],
+            json!({ "rpc_endpoint": "http://foo.bar", "preset": false })
+        );
+        assert_eq!(
+            config["chain_configs"]["1337"],
+            json!({ "rpc_endpoint": "http://foo.bar" })
+        );
+    }
+}
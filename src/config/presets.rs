@@ -0,0 +1,72 @@
+use serde_json::{json, Value};
+
+// built-in defaults for well known chains, so that their config only needs an rpc
+// endpoint and an answerer key. factories are only included for the chains on which
+// carrot is deployed, the others need them configured
+pub fn chain_preset(chain_id: u64) -> Option<Value> {
+    let preset = match chain_id {
+        // gnosis
+        100 => json!({
+            "logs_blocks_range": 5000,
+            "logs_polling_interval_seconds": 60,
+            "answering_task_interval_seconds": 10,
+            "checkpoint_confirmation_blocks": 10,
+            "reorg_confirmation_blocks": 10,
+            "legacy_transactions": false,
+            "template_id": 2,
+            "factories": [{
+                "address": "0xD503Bdcc3Cd38D3cEaBa1efA43EFCc03b7Fb1CbA",
+                "deployment_block": 28680516,
+            }],
+        }),
+        // sepolia
+        11155111 => json!({
+            "logs_blocks_range": 2000,
+            "logs_polling_interval_seconds": 60,
+            "answering_task_interval_seconds": 10,
+            "checkpoint_confirmation_blocks": 12,
+            "reorg_confirmation_blocks": 12,
+            "legacy_transactions": false,
+            "template_id": 2,
+            "factories": [{
+                "address": "0x44bBb970E534bCE4B42C5a34b15d5B049704417A",
+                "deployment_block": 3784913,
+            }],
+        }),
+        // scroll sepolia, blocks come every few seconds and are only reorged by the
+        // sequencer in rare cases
+        534351 => json!({
+            "logs_blocks_range": 2000,
+            "logs_polling_interval_seconds": 30,
+            "answering_task_interval_seconds": 10,
+            "checkpoint_confirmation_blocks": 5,
+            "reorg_confirmation_blocks": 5,
+            "legacy_transactions": false,
+        }),
+        _ => return None,
+    };
+    Some(preset)
+}
+
+// only fills in the fields missing from the chain's config, unless it opted out of
+// its preset with preset: false
+pub fn apply_chain_preset(chain_id: u64, chain_config: &mut Value) {
+    let fields = match chain_config.as_object_mut() {
+        Some(fields) => fields,
+        None => return,
+    };
+    if fields.get("preset") == Some(&Value::Bool(false)) {
+        return;
+    }
+    let preset = match chain_preset(chain_id) {
+        Some(Value::Object(preset)) => preset,
+        _ => return,
+    };
+    for (name, value) in preset {
+        // a lone factory given through the legacy key replaces the preset ones too
+        if name == "factories" && fields.contains_key("factory") {
+            continue;
+        }
+        fields.entry(name).or_insert(value);
+    }
+}
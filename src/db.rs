@@ -1,73 +1,114 @@
 pub mod models;
 pub mod schema;
 
-use std::{ops::Deref, time::Duration};
+pub use answerer_framework::db::{blocking, pagination};
+
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "postgres")]
+use self::postgres as backend;
+#[cfg(feature = "sqlite")]
+use self::sqlite as backend;
+
+pub use self::backend::{sql_types, DbConnection};
+
+use std::{
+    ops::Deref,
+    thread,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Context;
 use diesel::{
+    backend::Backend,
     deserialize::{self, FromSql},
-    pg::{Pg, PgConnection, PgValue},
-    prelude::*,
     r2d2::{ConnectionManager, Pool},
     serialize::{self, ToSql},
-    sql_types::{Bytea, Jsonb},
+    sql_types::Binary,
     AsExpression, Connection, FromSqlRow,
 };
 use ethers::types::{Address, H256, U256};
 
-use crate::specification::Specification;
+use crate::commons::{
+    DbPoolConfig, DB_CONNECTION_TIMEOUT, DB_CONNECT_RETRY_INTERVAL, DB_POOL_MAX_SIZE,
+};
 
-pub fn connect(url: &String) -> anyhow::Result<Pool<ConnectionManager<PgConnection>>> {
-    let db_connection_manager = ConnectionManager::<PgConnection>::new(url);
-    match Pool::builder()
-        .connection_timeout(Duration::from_secs(30))
-        .build(db_connection_manager)
-        .context("could not build connection pool to the database")
+#[cfg(all(feature = "postgres", feature = "sqlite"))]
+compile_error!("the postgres and sqlite features can't be enabled at the same time");
+
+#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+compile_error!("either the postgres or the sqlite feature must be enabled");
+
+pub type DbBackend = <DbConnection as Connection>::Backend;
+
+pub fn connect(
+    url: &String,
+    pool_config: &DbPoolConfig,
+) -> anyhow::Result<Pool<ConnectionManager<DbConnection>>> {
+    // checked upfront as r2d2 panics on these instead of returning an error
+    let max_size = pool_config.max_size.unwrap_or(DB_POOL_MAX_SIZE);
+    if max_size == 0 {
+        anyhow::bail!("the database pool max size must be greater than 0");
+    }
+    if pool_config
+        .min_idle
+        .is_some_and(|min_idle| min_idle > max_size)
     {
-        Ok(db_connection_pool) => Ok(db_connection_pool),
-        Err(error) => {
-            let parsed_url = reqwest::Url::parse(url).context(format!(
-                "could not parse database connection string {}",
-                url
-            ))?;
-
-            let database = parsed_url.path().chars().skip(1).collect::<String>();
-            let database = database.as_str();
-            let username = parsed_url.username();
-            tracing::error!(
-                "error connecting to database {}, trying to create it: {:#}",
-                database,
-                error
-            );
-
-            // connect to "postgres" database
-            let mut pg_db_parsed_url = parsed_url.clone();
-            pg_db_parsed_url.set_path("postgres");
-            let mut pg_db_connection = PgConnection::establish(pg_db_parsed_url.as_str())
-                .context("could not connect to admin postgres database")?;
-
-            // create database
-            diesel::sql_query(format!("CREATE DATABASE \"{}\";", database))
-                .execute(&mut pg_db_connection)
-                .context(format!("failed to create database {}", database))?;
-            diesel::sql_query(format!(
-                "GRANT ALL PRIVILEGES ON DATABASE \"{}\" TO \"{}\";",
-                database, username
-            ))
-            .execute(&mut pg_db_connection)
-            .context(format!(
-                "failed to grant privileges on database {} to user {}",
-                database, username
-            ))?;
-
-            // the database has been created at this point: use the og url to connect
-            connect(url)
+        anyhow::bail!(
+            "the database pool min idle connections can't be more than its max size {}",
+            max_size
+        );
+    }
+
+    let retries = pool_config.connect_retries.unwrap_or(0);
+    let mut attempt = 0;
+    loop {
+        match backend::connect_or_create(url, pool_config) {
+            Ok(db_connection_pool) => return Ok(db_connection_pool),
+            Err(error) if attempt < retries => {
+                attempt += 1;
+                tracing::warn!(
+                    "could not connect to database, retrying in {}s ({}/{}): {:#}",
+                    DB_CONNECT_RETRY_INTERVAL.as_secs(),
+                    attempt,
+                    retries,
+                    error
+                );
+                thread::sleep(DB_CONNECT_RETRY_INTERVAL);
+            }
+            Err(error) => return Err(error),
         }
     }
 }
 
+fn build_pool(
+    url: &String,
+    pool_config: &DbPoolConfig,
+) -> anyhow::Result<Pool<ConnectionManager<DbConnection>>> {
+    let max_size = pool_config.max_size.unwrap_or(DB_POOL_MAX_SIZE);
+    let db_connection_manager = ConnectionManager::<DbConnection>::new(url);
+    let mut builder = Pool::builder()
+        .max_size(max_size)
+        .min_idle(pool_config.min_idle)
+        .connection_timeout(
+            pool_config
+                .connection_timeout_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(DB_CONNECTION_TIMEOUT),
+        );
+    if let Some(customizer) = backend::connection_customizer(pool_config) {
+        builder = builder.connection_customizer(customizer);
+    }
+    builder
+        .build(db_connection_manager)
+        .context("could not build connection pool to the database")
+}
+
 #[derive(FromSqlRow, AsExpression, Debug, PartialEq, Clone, Copy)]
-#[diesel(sql_type = Bytea)]
+#[diesel(sql_type = Binary)]
 pub struct DbAddress(pub Address);
 
 impl Deref for DbAddress {
@@ -78,22 +119,21 @@ impl Deref for DbAddress {
     }
 }
 
-impl FromSql<Bytea, Pg> for DbAddress {
-    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
-        let value = <Vec<u8> as FromSql<Bytea, Pg>>::from_sql(bytes)?;
+impl FromSql<Binary, DbBackend> for DbAddress {
+    fn from_sql(bytes: <DbBackend as Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+        let value = <Vec<u8> as FromSql<Binary, DbBackend>>::from_sql(bytes)?;
         Ok(DbAddress(Address::from_slice(value.as_slice())))
     }
 }
 
-impl ToSql<Bytea, Pg> for DbAddress {
-    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
-        let value = self.0.as_bytes();
-        <&[u8] as ToSql<Bytea, Pg>>::to_sql(&value, &mut out.reborrow())
+impl ToSql<Binary, DbBackend> for DbAddress {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, DbBackend>) -> serialize::Result {
+        <[u8] as ToSql<Binary, DbBackend>>::to_sql(self.0.as_bytes(), out)
     }
 }
 
 #[derive(FromSqlRow, AsExpression, Debug, PartialEq, Clone, Copy)]
-#[diesel(sql_type = Bytea)]
+#[diesel(sql_type = Binary)]
 pub struct DbTxHash(pub H256);
 
 impl Deref for DbTxHash {
@@ -104,22 +144,24 @@ impl Deref for DbTxHash {
     }
 }
 
-impl FromSql<Bytea, Pg> for DbTxHash {
-    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
-        let value = <Vec<u8> as FromSql<Bytea, Pg>>::from_sql(bytes)?;
+impl FromSql<Binary, DbBackend> for DbTxHash {
+    fn from_sql(bytes: <DbBackend as Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+        let value = <Vec<u8> as FromSql<Binary, DbBackend>>::from_sql(bytes)?;
         Ok(DbTxHash(H256::from_slice(value.as_slice())))
     }
 }
 
-impl ToSql<Bytea, Pg> for DbTxHash {
-    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
-        let value = self.0.as_bytes();
-        <&[u8] as ToSql<Bytea, Pg>>::to_sql(&value, &mut out.reborrow())
+impl ToSql<Binary, DbBackend> for DbTxHash {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, DbBackend>) -> serialize::Result {
+        <[u8] as ToSql<Binary, DbBackend>>::to_sql(self.0.as_bytes(), out)
     }
 }
 
+// serialized as 32 big endian bytes, as even bigint is too small to store 18 decimals
+// formatted integers. writing it is specific to each backend, as the bytes don't
+// outlive the call
 #[derive(FromSqlRow, AsExpression, Debug, PartialEq)]
-#[diesel(sql_type = Bytea)]
+#[diesel(sql_type = Binary)]
 pub struct DbU256(pub U256);
 
 impl Deref for DbU256 {
@@ -130,31 +172,34 @@ impl Deref for DbU256 {
     }
 }
 
-impl FromSql<Bytea, Pg> for DbU256 {
-    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
-        let value = <Vec<u8> as FromSql<Bytea, Pg>>::from_sql(bytes)?;
+impl FromSql<Binary, DbBackend> for DbU256 {
+    fn from_sql(bytes: <DbBackend as Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+        let value = <Vec<u8> as FromSql<Binary, DbBackend>>::from_sql(bytes)?;
         Ok(DbU256(U256::from_big_endian(value.as_slice())))
     }
 }
 
-impl ToSql<Bytea, Pg> for DbU256 {
-    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
-        let mut value = [0u8; 32];
-        self.0.to_big_endian(&mut value);
-        <&[u8] as ToSql<Bytea, Pg>>::to_sql(&value.as_slice(), &mut out.reborrow())
-    }
-}
+// timestamps and json documents are read and written by each backend in its own way
+#[derive(FromSqlRow, AsExpression, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[diesel(sql_type = sql_types::Timestamp)]
+pub struct DbTimestamp(pub SystemTime);
 
-impl FromSql<Jsonb, Pg> for Specification {
-    fn from_sql(value: PgValue) -> deserialize::Result<Self> {
-        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(value)?;
-        Ok(serde_json::from_value(value)?)
+impl Deref for DbTimestamp {
+    type Target = SystemTime;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-impl ToSql<Jsonb, Pg> for Specification {
-    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
-        let value = serde_json::to_value(self)?;
-        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+#[derive(FromSqlRow, AsExpression, Debug, PartialEq, Clone)]
+#[diesel(sql_type = sql_types::Jsonb)]
+pub struct DbJson(pub serde_json::Value);
+
+impl Deref for DbJson {
+    type Target = serde_json::Value;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
@@ -1,36 +1,82 @@
-use std::time::SystemTime;
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::Context;
 use diesel::prelude::*;
-use ethers::types::{Address, H256, U256};
+use ethers::types::{Address, Log, TransactionReceipt, H256, U256, U64};
 
-use crate::specification::Specification;
+use crate::{defillama::RecordedResponse, specification::Specification};
 
 use super::{
+    pagination::{then_order_by, total_count, Page, Pagination},
     schema::{
         active_oracles::{self},
-        checkpoints,
+        answer_overrides, answer_reviews, answered_oracles, archived_oracles, audit_log,
+        cached_specifications, checkpoints, defillama_snapshots, dry_run_answers, feature_gates,
+        gas_spendings, indexed_logs, rate_limit_buckets, scanned_factories, scanned_ranges,
+        scanner_leases,
     },
-    DbAddress, DbTxHash, DbU256,
+    DbAddress, DbBackend, DbConnection, DbJson, DbTimestamp, DbTxHash, DbU256,
 };
 
+// used to page through records with optional filters, the time range applying to
+// each record's own reference timestamp
+#[derive(Debug, Clone, Default)]
+pub struct RecordFilter {
+    pub chain_id: Option<u64>,
+    pub oracle_address: Option<Address>,
+    pub from: Option<SystemTime>,
+    pub to: Option<SystemTime>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActiveOracleSort {
+    #[default]
+    MeasurementTimestamp,
+    Expiration,
+    AnswerAttempts,
+}
+
+impl FromStr for ActiveOracleSort {
+    type Err = anyhow::Error;
+
+    fn from_str(sort: &str) -> Result<Self, Self::Err> {
+        match sort {
+            "measurementTimestamp" => Ok(ActiveOracleSort::MeasurementTimestamp),
+            "expiration" => Ok(ActiveOracleSort::Expiration),
+            "answerAttempts" => Ok(ActiveOracleSort::AnswerAttempts),
+            _ => Err(anyhow::anyhow!("unknown sort field {}", sort)),
+        }
+    }
+}
+
 #[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
 #[diesel(treat_none_as_null = true)]
 #[diesel(table_name = active_oracles)]
-#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(check_for_backend(DbBackend))]
 pub struct ActiveOracle {
     pub address: DbAddress,
     pub chain_id: i32,
-    pub measurement_timestamp: SystemTime,
+    pub measurement_timestamp: DbTimestamp,
     pub specification: Specification,
-    pub expiration: Option<SystemTime>,
+    pub expiration: Option<DbTimestamp>,
     pub answer_tx_hash: Option<DbTxHash>,
     pub answer: Option<DbU256>,
+    pub answer_attempts: i32,
+    pub next_answer_attempt: Option<DbTimestamp>,
+    pub answer_tx_submitted_at: Option<DbTimestamp>,
+    pub source_missing_since: Option<DbTimestamp>,
+    // the snapshot of the defillama responses the stored answer was computed from
+    pub defillama_snapshot_id: Option<i64>,
 }
 
 impl ActiveOracle {
     pub fn create(
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         address: Address,
         chain_id: u64,
         measurement_timestamp: SystemTime,
@@ -40,11 +86,16 @@ impl ActiveOracle {
         let oracle = ActiveOracle {
             address: DbAddress(address),
             chain_id: i32::try_from(chain_id).unwrap(), // this should never panic
-            measurement_timestamp,
+            measurement_timestamp: DbTimestamp(measurement_timestamp),
             specification,
-            expiration: Some(expiration),
+            expiration: Some(DbTimestamp(expiration)),
             answer_tx_hash: None,
             answer: None,
+            answer_attempts: 0,
+            next_answer_attempt: None,
+            answer_tx_submitted_at: None,
+            source_missing_since: None,
+            defillama_snapshot_id: None,
         };
 
         diesel::insert_into(active_oracles::table)
@@ -57,35 +108,46 @@ impl ActiveOracle {
 
     pub fn update_answer_tx_hash(
         &mut self,
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         answer_tx_hash: H256,
     ) -> anyhow::Result<()> {
-        diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
-            .set(active_oracles::dsl::answer_tx_hash.eq(DbTxHash(answer_tx_hash)))
-            .execute(connection)
-            .context(format!(
-                "could not update active oracle 0x{:x} answer tx hash",
-                self.address.0
-            ))?;
+        // the submission timestamp is read back so that it has the database's precision
+        let answer_tx_submitted_at =
+            diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
+                .set((
+                    active_oracles::dsl::answer_tx_hash.eq(DbTxHash(answer_tx_hash)),
+                    active_oracles::dsl::answer_tx_submitted_at.eq(DbTimestamp(SystemTime::now())),
+                ))
+                .returning(active_oracles::dsl::answer_tx_submitted_at)
+                .get_result(connection)
+                .context(format!(
+                    "could not update active oracle 0x{:x} answer tx hash",
+                    self.address.0
+                ))?;
         self.answer_tx_hash = Some(DbTxHash(answer_tx_hash));
+        self.answer_tx_submitted_at = answer_tx_submitted_at;
         Ok(())
     }
 
-    pub fn delete_answer_tx_hash(&mut self, connection: &mut PgConnection) -> anyhow::Result<()> {
+    pub fn delete_answer_tx_hash(&mut self, connection: &mut DbConnection) -> anyhow::Result<()> {
         diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
-            .set(active_oracles::dsl::answer_tx_hash.eq(None::<DbTxHash>))
+            .set((
+                active_oracles::dsl::answer_tx_hash.eq(None::<DbTxHash>),
+                active_oracles::dsl::answer_tx_submitted_at.eq(None::<DbTimestamp>),
+            ))
             .execute(connection)
             .context(format!(
                 "could not delete active oracle 0x{:x} answer tx hash",
                 self.address.0
             ))?;
         self.answer_tx_hash = None;
+        self.answer_tx_submitted_at = None;
         Ok(())
     }
 
     pub fn update_answer(
         &mut self,
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         answer: U256,
     ) -> anyhow::Result<()> {
         diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
@@ -99,23 +161,44 @@ impl ActiveOracle {
         Ok(())
     }
 
-    pub fn delete_answer(&mut self, connection: &mut PgConnection) -> anyhow::Result<()> {
+    pub fn delete_answer(&mut self, connection: &mut DbConnection) -> anyhow::Result<()> {
         diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
-            .set(active_oracles::dsl::answer.eq(None::<DbU256>))
+            .set((
+                active_oracles::dsl::answer.eq(None::<DbU256>),
+                active_oracles::dsl::defillama_snapshot_id.eq(None::<i64>),
+            ))
             .execute(connection)
             .context(format!(
                 "could not delete active oracle 0x{:x} answer",
                 self.address.0
             ))?;
         self.answer = None;
+        self.defillama_snapshot_id = None;
+        Ok(())
+    }
+
+    pub fn update_defillama_snapshot_id(
+        &mut self,
+        connection: &mut DbConnection,
+        defillama_snapshot_id: i64,
+    ) -> anyhow::Result<()> {
+        diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
+            .set(active_oracles::dsl::defillama_snapshot_id.eq(defillama_snapshot_id))
+            .execute(connection)
+            .context(format!(
+                "could not update active oracle 0x{:x} defillama snapshot id",
+                self.address.0
+            ))?;
+        self.defillama_snapshot_id = Some(defillama_snapshot_id);
         Ok(())
     }
 
     pub fn update_expiration(
         &mut self,
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         expiration: SystemTime,
     ) -> anyhow::Result<()> {
+        let expiration = DbTimestamp(expiration);
         diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
             .set(active_oracles::dsl::expiration.eq(Some(expiration)))
             .execute(connection)
@@ -127,10 +210,132 @@ impl ActiveOracle {
         Ok(())
     }
 
+    pub fn schedule_answer_retry(
+        &mut self,
+        connection: &mut DbConnection,
+        next_answer_attempt: SystemTime,
+    ) -> anyhow::Result<()> {
+        let next_answer_attempt = DbTimestamp(next_answer_attempt);
+        let answer_attempts = self.answer_attempts + 1;
+        diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
+            .set((
+                active_oracles::dsl::answer_attempts.eq(answer_attempts),
+                active_oracles::dsl::next_answer_attempt.eq(Some(next_answer_attempt)),
+            ))
+            .execute(connection)
+            .context(format!(
+                "could not schedule active oracle 0x{:x} answer retry",
+                self.address.0
+            ))?;
+        self.answer_attempts = answer_attempts;
+        self.next_answer_attempt = Some(next_answer_attempt);
+        Ok(())
+    }
+
+    // makes the oracle answerable right away, while keeping track of past attempts
+    pub fn clear_answer_retry(&mut self, connection: &mut DbConnection) -> anyhow::Result<()> {
+        diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
+            .set(active_oracles::dsl::next_answer_attempt.eq(None::<DbTimestamp>))
+            .execute(connection)
+            .context(format!(
+                "could not clear active oracle 0x{:x} answer retry",
+                self.address.0
+            ))?;
+        self.next_answer_attempt = None;
+        Ok(())
+    }
+
+    // the first detection timestamp is kept, while the next check is pushed back
+    // every time the data is still missing
+    pub fn mark_source_missing(
+        &mut self,
+        connection: &mut DbConnection,
+        next_answer_attempt: SystemTime,
+    ) -> anyhow::Result<()> {
+        let next_answer_attempt = DbTimestamp(next_answer_attempt);
+        let source_missing_since = self
+            .source_missing_since
+            .unwrap_or_else(|| DbTimestamp(SystemTime::now()));
+        let source_missing_since =
+            diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
+                .set((
+                    active_oracles::dsl::source_missing_since.eq(Some(source_missing_since)),
+                    active_oracles::dsl::next_answer_attempt.eq(Some(next_answer_attempt)),
+                ))
+                .returning(active_oracles::dsl::source_missing_since)
+                .get_result(connection)
+                .context(format!(
+                    "could not mark active oracle 0x{:x} source as missing",
+                    self.address.0
+                ))?;
+        self.source_missing_since = source_missing_since;
+        self.next_answer_attempt = Some(next_answer_attempt);
+        Ok(())
+    }
+
+    pub fn clear_source_missing(&mut self, connection: &mut DbConnection) -> anyhow::Result<()> {
+        diesel::update(active_oracles::dsl::active_oracles.find((self.address, self.chain_id)))
+            .set(active_oracles::dsl::source_missing_since.eq(None::<DbTimestamp>))
+            .execute(connection)
+            .context(format!(
+                "could not clear active oracle 0x{:x} missing source",
+                self.address.0
+            ))?;
+        self.source_missing_since = None;
+        Ok(())
+    }
+
+    // claims are leases rather than locks so that a crashed answering task can't
+    // prevent the oracle from being answered forever. returns the claim's expiration
+    // if it was acquired, which is what releasing it later takes
+    pub fn claim(
+        &self,
+        connection: &mut DbConnection,
+        duration: Duration,
+    ) -> anyhow::Result<Option<SystemTime>> {
+        let now = SystemTime::now();
+        let claim_expiration = now + duration;
+        let updated = diesel::update(
+            active_oracles::dsl::active_oracles
+                .find((&self.address, &self.chain_id))
+                .filter(
+                    active_oracles::dsl::claim_expiration
+                        .is_null()
+                        .or(active_oracles::dsl::claim_expiration.lt(DbTimestamp(now))),
+                ),
+        )
+        .set(active_oracles::dsl::claim_expiration.eq(DbTimestamp(claim_expiration)))
+        .execute(connection)
+        .context(format!("could not claim oracle {}", self.address.0))?;
+        Ok((updated == 1).then_some(claim_expiration))
+    }
+
+    // releasing works by address as the model instance might have been consumed by
+    // then, for example after the oracle was answered and deleted. a claim held for
+    // longer than its duration might have been taken over by someone else in the
+    // meantime, so only the claim with the given expiration is released
+    pub fn release_claim(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        address: Address,
+        claim_expiration: SystemTime,
+    ) -> anyhow::Result<()> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        diesel::update(
+            active_oracles::dsl::active_oracles
+                .find((DbAddress(address), chain_id))
+                .filter(active_oracles::dsl::claim_expiration.eq(DbTimestamp(claim_expiration))),
+        )
+        .set(active_oracles::dsl::claim_expiration.eq(None::<DbTimestamp>))
+        .execute(connection)
+        .context(format!("could not release claim on oracle {}", address))?;
+        Ok(())
+    }
+
     // by getting ownership of self instead of a reference to it, we know that the active
     // oracle model instance will be dropped at the end of the function after having been
     // deleted from the db
-    pub fn delete(self, connection: &mut PgConnection) -> anyhow::Result<()> {
+    pub fn delete(self, connection: &mut DbConnection) -> anyhow::Result<()> {
         diesel::delete(active_oracles::dsl::active_oracles.find((&self.address, &self.chain_id)))
             .execute(connection)
             .context(format!(
@@ -140,25 +345,568 @@ impl ActiveOracle {
         Ok(())
     }
 
+    // like delete, but keeps a copy of the oracle in the archived oracles table
+    pub fn archive(
+        self,
+        connection: &mut DbConnection,
+        reason: ArchiveReason,
+    ) -> anyhow::Result<()> {
+        connection
+            .transaction(|connection| {
+                diesel::insert_into(archived_oracles::table)
+                    .values((
+                        archived_oracles::dsl::address.eq(&self.address),
+                        archived_oracles::dsl::chain_id.eq(self.chain_id),
+                        archived_oracles::dsl::measurement_timestamp.eq(self.measurement_timestamp),
+                        archived_oracles::dsl::specification.eq(&self.specification),
+                        archived_oracles::dsl::expiration.eq(self.expiration),
+                        archived_oracles::dsl::answer.eq(&self.answer),
+                        archived_oracles::dsl::answer_attempts.eq(self.answer_attempts),
+                        archived_oracles::dsl::reason.eq(reason.as_str()),
+                        archived_oracles::dsl::archived_at.eq(DbTimestamp(SystemTime::now())),
+                    ))
+                    .execute(connection)?;
+                diesel::delete(
+                    active_oracles::dsl::active_oracles.find((&self.address, &self.chain_id)),
+                )
+                .execute(connection)?;
+                diesel::QueryResult::Ok(())
+            })
+            .context(format!(
+                "could not archive {} oracle {}",
+                reason.as_str(),
+                self.address.0
+            ))
+    }
+
+    // archives an oracle an operator decided should never be answered, e.g. because
+    // its specification was maliciously crafted or its campaign was cancelled
+    pub fn remove(
+        self,
+        connection: &mut DbConnection,
+        removed_by: &str,
+        reason: &str,
+    ) -> anyhow::Result<()> {
+        let chain_id = self.chain_id as u64;
+        let address = self.address.0;
+        connection.transaction(|connection| {
+            self.archive(connection, ArchiveReason::Removed)?;
+            AuditLogEntry::create(
+                connection,
+                chain_id,
+                address,
+                AuditAction::OracleRemoved,
+                removed_by,
+                serde_json::json!({ "reason": reason }),
+            )?;
+            Ok(())
+        })
+    }
+
+    // like delete, but keeps a copy of the oracle along with its answer tx and what it
+    // cost in the answered oracles table, returning the copy's id. the receipt is
+    // missing when the tx was mined but its receipt couldn't be fetched
+    pub fn archive_answered(
+        self,
+        connection: &mut DbConnection,
+        tx_hash: H256,
+        receipt: Option<&TransactionReceipt>,
+        fee_usd: Option<f64>,
+    ) -> anyhow::Result<i64> {
+        let block_number = receipt
+            .and_then(|receipt| receipt.block_number)
+            .map(|block_number| block_number.as_u64() as i64);
+        let gas_used = receipt.and_then(|receipt| receipt.gas_used);
+        let effective_gas_price = receipt.and_then(|receipt| receipt.effective_gas_price);
+        let fee = gas_used
+            .zip(effective_gas_price)
+            .map(|(gas_used, effective_gas_price)| gas_used * effective_gas_price);
+        connection
+            .transaction(|connection| {
+                let id = diesel::insert_into(answered_oracles::table)
+                    .values((
+                        answered_oracles::dsl::address.eq(&self.address),
+                        answered_oracles::dsl::chain_id.eq(self.chain_id),
+                        answered_oracles::dsl::measurement_timestamp.eq(self.measurement_timestamp),
+                        answered_oracles::dsl::specification.eq(&self.specification),
+                        answered_oracles::dsl::expiration.eq(self.expiration),
+                        answered_oracles::dsl::answer.eq(&self.answer),
+                        answered_oracles::dsl::answer_attempts.eq(self.answer_attempts),
+                        answered_oracles::dsl::answer_tx_hash.eq(DbTxHash(tx_hash)),
+                        answered_oracles::dsl::answer_tx_submitted_at
+                            .eq(self.answer_tx_submitted_at),
+                        answered_oracles::dsl::block_number.eq(block_number),
+                        answered_oracles::dsl::gas_used.eq(gas_used.map(DbU256)),
+                        answered_oracles::dsl::effective_gas_price
+                            .eq(effective_gas_price.map(DbU256)),
+                        answered_oracles::dsl::fee.eq(fee.map(DbU256)),
+                        answered_oracles::dsl::fee_usd.eq(fee_usd),
+                        answered_oracles::dsl::answered_at.eq(DbTimestamp(SystemTime::now())),
+                        answered_oracles::dsl::defillama_snapshot_id.eq(self.defillama_snapshot_id),
+                    ))
+                    .returning(answered_oracles::dsl::id)
+                    .get_result::<i64>(connection)?;
+                diesel::delete(
+                    active_oracles::dsl::active_oracles.find((&self.address, &self.chain_id)),
+                )
+                .execute(connection)?;
+                diesel::QueryResult::Ok(id)
+            })
+            .context(format!(
+                "could not archive answered oracle {}",
+                self.address.0
+            ))
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        address: Address,
+    ) -> anyhow::Result<Option<ActiveOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(active_oracles::dsl::active_oracles
+            .find((DbAddress(address), chain_id))
+            .select(ActiveOracle::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<ActiveOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(active_oracles::table
+            .filter(active_oracles::dsl::chain_id.eq(chain_id))
+            .select(ActiveOracle::as_select())
+            .load(connection)?)
+    }
+
+    // the time range applies to the measurement timestamp
+    pub fn get_all_filtered(
+        connection: &mut DbConnection,
+        filter: &RecordFilter,
+    ) -> anyhow::Result<Vec<ActiveOracle>> {
+        let mut query = active_oracles::table
+            .select(ActiveOracle::as_select())
+            .into_boxed();
+        if let Some(chain_id) = filter.chain_id {
+            let chain_id = i32::try_from(chain_id).context("invalid chain id")?;
+            query = query.filter(active_oracles::dsl::chain_id.eq(chain_id));
+        }
+        if let Some(address) = filter.oracle_address {
+            query = query.filter(active_oracles::dsl::address.eq(DbAddress(address)));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(active_oracles::dsl::measurement_timestamp.ge(DbTimestamp(from)));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(active_oracles::dsl::measurement_timestamp.lt(DbTimestamp(to)));
+        }
+        Ok(query
+            .order((
+                active_oracles::dsl::measurement_timestamp.asc(),
+                active_oracles::dsl::chain_id.asc(),
+                active_oracles::dsl::address.asc(),
+            ))
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load(connection)?)
+    }
+
+    pub fn get_page_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        pagination: &Pagination<ActiveOracleSort>,
+    ) -> anyhow::Result<Page<ActiveOracle>> {
+        let chain_id = i32::try_from(chain_id).context("invalid chain id")?;
+        let query = active_oracles::table
+            .filter(active_oracles::dsl::chain_id.eq(chain_id))
+            .select((ActiveOracle::as_select(), total_count()))
+            .into_boxed();
+        let query = match pagination.sort {
+            ActiveOracleSort::MeasurementTimestamp => then_order_by(
+                query,
+                active_oracles::dsl::measurement_timestamp,
+                pagination.order,
+            ),
+            ActiveOracleSort::Expiration => {
+                then_order_by(query, active_oracles::dsl::expiration, pagination.order)
+            }
+            ActiveOracleSort::AnswerAttempts => then_order_by(
+                query,
+                active_oracles::dsl::answer_attempts,
+                pagination.order,
+            ),
+        };
+        // ties are broken by address so that pages are stable
+        Ok(Page::from_counted(
+            query
+                .then_order_by(active_oracles::dsl::address.asc())
+                .limit(pagination.limit)
+                .offset(pagination.offset)
+                .load(connection)?,
+        ))
+    }
+
+    // oracles with an answer tx are left to the answering task that submitted it
+    pub fn get_all_expired_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<ActiveOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(active_oracles::table
+            .filter(
+                active_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(active_oracles::dsl::expiration.lt(DbTimestamp(SystemTime::now())))
+                    .and(active_oracles::dsl::answer_tx_hash.is_null()),
+            )
+            .select(ActiveOracle::as_select())
+            .load(connection)?)
+    }
+
+    pub fn get_all_with_answer_tx_hash(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<ActiveOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(active_oracles::table
+            .filter(
+                active_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(active_oracles::dsl::answer_tx_hash.is_not_null()),
+            )
+            .select(ActiveOracle::as_select())
+            .load(connection)?)
+    }
+
+    pub fn get_all_with_answer_tx_submitted_before(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        before: SystemTime,
+    ) -> anyhow::Result<Vec<ActiveOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(active_oracles::table
+            .filter(
+                active_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(active_oracles::dsl::answer_tx_hash.is_not_null())
+                    .and(active_oracles::dsl::answer_tx_submitted_at.lt(DbTimestamp(before))),
+            )
+            .select(ActiveOracle::as_select())
+            .load(connection)?)
+    }
+
+    pub fn get_next_measurement_timestamp_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Option<SystemTime>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(active_oracles::table
+            .filter(
+                active_oracles::dsl::chain_id.eq(chain_id).and(
+                    active_oracles::dsl::measurement_timestamp.ge(DbTimestamp(SystemTime::now())),
+                ),
+            )
+            .select(diesel::dsl::min(active_oracles::dsl::measurement_timestamp))
+            .first::<Option<DbTimestamp>>(connection)?
+            .map(|measurement_timestamp| measurement_timestamp.0))
+    }
+
     pub fn get_all_answerable_for_chain_id(
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         chain_id: u64,
     ) -> anyhow::Result<Vec<ActiveOracle>> {
         let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        let now = DbTimestamp(SystemTime::now());
         Ok(active_oracles::table
             .filter(
                 active_oracles::dsl::chain_id
                     .eq(chain_id)
-                    .and(active_oracles::dsl::measurement_timestamp.lt(SystemTime::now())),
+                    .and(active_oracles::dsl::measurement_timestamp.lt(now))
+                    .and(
+                        active_oracles::dsl::next_answer_attempt
+                            .is_null()
+                            .or(active_oracles::dsl::next_answer_attempt.le(now)),
+                    ),
             )
             .select(ActiveOracle::as_select())
             .load(connection)?)
     }
+
+    // picks and claims the answerable oracle with the oldest measurement timestamp.
+    // rows locked by another instance picking at the same time are skipped instead of
+    // waited on, so that instances sharing the database never claim the same oracle.
+    // oracles in skipped are left alone, which lets an answering run handle each
+    // oracle at most once. the claim's expiration is returned along with the oracle
+    pub fn claim_next_answerable_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        duration: Duration,
+        skipped: &[Address],
+    ) -> anyhow::Result<Option<(ActiveOracle, SystemTime)>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        let skipped: Vec<DbAddress> = skipped.iter().copied().map(DbAddress).collect();
+        connection
+            .transaction(|connection| {
+                let now = DbTimestamp(SystemTime::now());
+                let query = active_oracles::table
+                    .filter(
+                        active_oracles::dsl::chain_id
+                            .eq(chain_id)
+                            .and(active_oracles::dsl::measurement_timestamp.lt(now))
+                            .and(
+                                active_oracles::dsl::next_answer_attempt
+                                    .is_null()
+                                    .or(active_oracles::dsl::next_answer_attempt.le(now)),
+                            )
+                            .and(
+                                active_oracles::dsl::claim_expiration
+                                    .is_null()
+                                    .or(active_oracles::dsl::claim_expiration.lt(now)),
+                            )
+                            .and(diesel::dsl::not(
+                                active_oracles::dsl::address.eq_any(&skipped),
+                            )),
+                    )
+                    .order(active_oracles::dsl::measurement_timestamp.asc())
+                    .select(ActiveOracle::as_select());
+                // sqlite has no row locks, as it only ever lets one writer in
+                #[cfg(feature = "postgres")]
+                let query = query.for_update().skip_locked();
+                let active_oracle = match query.first(connection).optional()? {
+                    Some(active_oracle) => active_oracle,
+                    None => return Ok(None),
+                };
+                let claim_expiration = now.0 + duration;
+                diesel::update(
+                    active_oracles::dsl::active_oracles
+                        .find((&active_oracle.address, &active_oracle.chain_id)),
+                )
+                .set(active_oracles::dsl::claim_expiration.eq(DbTimestamp(claim_expiration)))
+                .execute(connection)?;
+                Ok::<_, diesel::result::Error>(Some((active_oracle, claim_expiration)))
+            })
+            .context(format!(
+                "could not claim next answerable oracle on chain {}",
+                chain_id
+            ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveReason {
+    Expired,
+    Finalized,
+    Removed,
+}
+
+impl ArchiveReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchiveReason::Expired => "expired",
+            ArchiveReason::Finalized => "finalized",
+            ArchiveReason::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = archived_oracles)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct ArchivedOracle {
+    pub id: i64,
+    pub address: DbAddress,
+    pub chain_id: i32,
+    pub measurement_timestamp: DbTimestamp,
+    pub specification: Specification,
+    pub expiration: Option<DbTimestamp>,
+    pub answer: Option<DbU256>,
+    pub answer_attempts: i32,
+    pub reason: String,
+    pub archived_at: DbTimestamp,
+}
+
+impl ArchivedOracle {
+    pub fn get_all_for_oracle(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        address: Address,
+    ) -> anyhow::Result<Vec<ArchivedOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(archived_oracles::table
+            .filter(
+                archived_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(archived_oracles::dsl::address.eq(DbAddress(address))),
+            )
+            .order(archived_oracles::dsl::id.asc())
+            .select(ArchivedOracle::as_select())
+            .load(connection)?)
+    }
+
+    // the time range applies to the archival timestamp
+    pub fn get_all_filtered(
+        connection: &mut DbConnection,
+        filter: &RecordFilter,
+        reason: Option<&str>,
+    ) -> anyhow::Result<Vec<ArchivedOracle>> {
+        let mut query = archived_oracles::table
+            .select(ArchivedOracle::as_select())
+            .into_boxed();
+        if let Some(chain_id) = filter.chain_id {
+            let chain_id = i32::try_from(chain_id).context("invalid chain id")?;
+            query = query.filter(archived_oracles::dsl::chain_id.eq(chain_id));
+        }
+        if let Some(address) = filter.oracle_address {
+            query = query.filter(archived_oracles::dsl::address.eq(DbAddress(address)));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(archived_oracles::dsl::archived_at.ge(DbTimestamp(from)));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(archived_oracles::dsl::archived_at.lt(DbTimestamp(to)));
+        }
+        if let Some(reason) = reason {
+            query = query.filter(archived_oracles::dsl::reason.eq(reason.to_owned()));
+        }
+        Ok(query
+            .order(archived_oracles::dsl::id.asc())
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load(connection)?)
+    }
+
+    pub fn is_removed(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        address: Address,
+    ) -> anyhow::Result<bool> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(diesel::select(diesel::dsl::exists(
+            archived_oracles::table.filter(
+                archived_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(archived_oracles::dsl::address.eq(DbAddress(address)))
+                    .and(archived_oracles::dsl::reason.eq(ArchiveReason::Removed.as_str())),
+            ),
+        ))
+        .get_result(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = answered_oracles)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct AnsweredOracle {
+    pub id: i64,
+    pub address: DbAddress,
+    pub chain_id: i32,
+    pub measurement_timestamp: DbTimestamp,
+    pub specification: Specification,
+    pub expiration: Option<DbTimestamp>,
+    pub answer: Option<DbU256>,
+    pub answer_attempts: i32,
+    pub answer_tx_hash: DbTxHash,
+    pub answer_tx_submitted_at: Option<DbTimestamp>,
+    pub block_number: Option<i64>,
+    pub gas_used: Option<DbU256>,
+    pub effective_gas_price: Option<DbU256>,
+    pub fee: Option<DbU256>,
+    pub fee_usd: Option<f64>,
+    pub answered_at: DbTimestamp,
+    pub defillama_snapshot_id: Option<i64>,
+    pub proof_bundle_cid: Option<String>,
+}
+
+impl AnsweredOracle {
+    pub fn update_proof_bundle_cid(
+        connection: &mut DbConnection,
+        id: i64,
+        proof_bundle_cid: &str,
+    ) -> anyhow::Result<()> {
+        diesel::update(answered_oracles::table.find(id))
+            .set(answered_oracles::dsl::proof_bundle_cid.eq(proof_bundle_cid))
+            .execute(connection)
+            .context(format!(
+                "could not update proof bundle cid for answered oracle {}",
+                id
+            ))?;
+        Ok(())
+    }
+
+    pub fn get_all_for_oracle(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        address: Address,
+    ) -> anyhow::Result<Vec<AnsweredOracle>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(answered_oracles::table
+            .filter(
+                answered_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(answered_oracles::dsl::address.eq(DbAddress(address))),
+            )
+            .order(answered_oracles::dsl::id.asc())
+            .select(AnsweredOracle::as_select())
+            .load(connection)?)
+    }
+
+    // the time range applies to the answer timestamp
+    pub fn get_all_filtered(
+        connection: &mut DbConnection,
+        filter: &RecordFilter,
+    ) -> anyhow::Result<Vec<AnsweredOracle>> {
+        let mut query = answered_oracles::table
+            .select(AnsweredOracle::as_select())
+            .into_boxed();
+        if let Some(chain_id) = filter.chain_id {
+            let chain_id = i32::try_from(chain_id).context("invalid chain id")?;
+            query = query.filter(answered_oracles::dsl::chain_id.eq(chain_id));
+        }
+        if let Some(address) = filter.oracle_address {
+            query = query.filter(answered_oracles::dsl::address.eq(DbAddress(address)));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(answered_oracles::dsl::answered_at.ge(DbTimestamp(from)));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(answered_oracles::dsl::answered_at.lt(DbTimestamp(to)));
+        }
+        Ok(query
+            .order(answered_oracles::dsl::id.asc())
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load(connection)?)
+    }
+
+    // an answer tx reorged out never finalized the oracle, which is answered again
+    pub fn delete_for_answer_tx(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        tx_hash: H256,
+    ) -> anyhow::Result<()> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        diesel::delete(
+            answered_oracles::table.filter(
+                answered_oracles::dsl::chain_id
+                    .eq(chain_id)
+                    .and(answered_oracles::dsl::answer_tx_hash.eq(DbTxHash(tx_hash))),
+            ),
+        )
+        .execute(connection)
+        .context(format!(
+            "could not delete answered oracle with answer tx 0x{:x}",
+            tx_hash
+        ))?;
+        Ok(())
+    }
 }
 
 #[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
 #[diesel(table_name = checkpoints)]
-#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(check_for_backend(DbBackend))]
 pub struct Checkpoint {
     pub chain_id: i32,
     pub block_number: i64,
@@ -166,7 +914,7 @@ pub struct Checkpoint {
 
 impl Checkpoint {
     pub fn update(
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         chain_id: u64,
         block_number: i64,
     ) -> anyhow::Result<()> {
@@ -188,7 +936,7 @@ impl Checkpoint {
     }
 
     pub fn get_for_chain_id(
-        connection: &mut PgConnection,
+        connection: &mut DbConnection,
         chain_id: u64,
     ) -> anyhow::Result<Option<Checkpoint>> {
         let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
@@ -206,4 +954,1153 @@ impl Checkpoint {
             }
         }
     }
+
+    // sets the checkpoint on behalf of an operator, recording the previous block in
+    // the audit log
+    pub fn reset(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        block_number: i64,
+        actor: &str,
+    ) -> anyhow::Result<()> {
+        connection.transaction(|connection| {
+            let previous_block_number = Self::get_for_chain_id(connection, chain_id)?
+                .map(|checkpoint| checkpoint.block_number);
+            Self::update(connection, chain_id, block_number)?;
+            AuditLogEntry::create_for_chain(
+                connection,
+                chain_id,
+                AuditAction::CheckpointReset,
+                actor,
+                serde_json::json!({
+                    "from": previous_block_number,
+                    "to": block_number,
+                }),
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_all(connection: &mut DbConnection) -> anyhow::Result<Vec<Checkpoint>> {
+        Ok(checkpoints::dsl::checkpoints
+            .order(checkpoints::dsl::chain_id.asc())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
+#[diesel(table_name = gas_spendings)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct GasSpending {
+    pub chain_id: i32,
+    pub tx_hash: DbTxHash,
+    pub oracle_address: DbAddress,
+    pub fee: DbU256,
+    pub timestamp: DbTimestamp,
+    pub fee_usd: Option<f64>,
+    pub gas_used: Option<DbU256>,
+    pub effective_gas_price: Option<DbU256>,
+    pub kpi_token_address: Option<DbAddress>,
+}
+
+impl GasSpending {
+    pub fn create(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+        kpi_token_address: Option<Address>,
+        receipt: &TransactionReceipt,
+        fee_usd: Option<f64>,
+    ) -> anyhow::Result<GasSpending> {
+        let tx_hash = receipt.transaction_hash;
+        let (gas_used, effective_gas_price) = match (receipt.gas_used, receipt.effective_gas_price)
+        {
+            (Some(gas_used), Some(effective_gas_price)) => (gas_used, effective_gas_price),
+            _ => anyhow::bail!("missing gas data in receipt for tx 0x{:x}", tx_hash),
+        };
+        let gas_spending = GasSpending {
+            chain_id: i32::try_from(chain_id).unwrap(), // this should never panic
+            tx_hash: DbTxHash(tx_hash),
+            oracle_address: DbAddress(oracle_address),
+            fee: DbU256(gas_used.saturating_mul(effective_gas_price)),
+            timestamp: DbTimestamp(SystemTime::now()),
+            fee_usd,
+            gas_used: Some(DbU256(gas_used)),
+            effective_gas_price: Some(DbU256(effective_gas_price)),
+            kpi_token_address: kpi_token_address.map(DbAddress),
+        };
+
+        diesel::insert_into(gas_spendings::table)
+            .values(&gas_spending)
+            .execute(connection)
+            .context(format!(
+                "could not insert gas spending for tx 0x{:x} into database",
+                tx_hash
+            ))?;
+
+        Ok(gas_spending)
+    }
+
+    pub fn get_all_for_chain_id_between(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> anyhow::Result<Vec<GasSpending>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(gas_spendings::table
+            .filter(
+                gas_spendings::dsl::chain_id
+                    .eq(chain_id)
+                    .and(gas_spendings::dsl::timestamp.ge(DbTimestamp(from)))
+                    .and(gas_spendings::dsl::timestamp.lt(DbTimestamp(to))),
+            )
+            .order(gas_spendings::dsl::timestamp.asc())
+            .select(GasSpending::as_select())
+            .load(connection)?)
+    }
+
+    // the time range applies to the spending timestamp
+    pub fn get_all_filtered(
+        connection: &mut DbConnection,
+        filter: &RecordFilter,
+        kpi_token_address: Option<Address>,
+    ) -> anyhow::Result<Vec<GasSpending>> {
+        let mut query = gas_spendings::table
+            .select(GasSpending::as_select())
+            .into_boxed();
+        if let Some(chain_id) = filter.chain_id {
+            let chain_id = i32::try_from(chain_id).context("invalid chain id")?;
+            query = query.filter(gas_spendings::dsl::chain_id.eq(chain_id));
+        }
+        if let Some(address) = filter.oracle_address {
+            query = query.filter(gas_spendings::dsl::oracle_address.eq(DbAddress(address)));
+        }
+        if let Some(kpi_token_address) = kpi_token_address {
+            query = query
+                .filter(gas_spendings::dsl::kpi_token_address.eq(DbAddress(kpi_token_address)));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(gas_spendings::dsl::timestamp.ge(DbTimestamp(from)));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(gas_spendings::dsl::timestamp.lt(DbTimestamp(to)));
+        }
+        Ok(query
+            .order(gas_spendings::dsl::timestamp.asc())
+            .limit(filter.limit)
+            .offset(filter.offset)
+            .load(connection)?)
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        tx_hash: H256,
+    ) -> anyhow::Result<Option<GasSpending>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(gas_spendings::table
+            .find((chain_id, DbTxHash(tx_hash)))
+            .select(GasSpending::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    pub fn get_total_for_chain_id_since(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        since: SystemTime,
+    ) -> anyhow::Result<U256> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+
+        // fees are stored as raw bytes, so the sum has to happen here
+        let fees = gas_spendings::table
+            .filter(
+                gas_spendings::dsl::chain_id
+                    .eq(chain_id)
+                    .and(gas_spendings::dsl::timestamp.ge(DbTimestamp(since))),
+            )
+            .select(gas_spendings::dsl::fee)
+            .load::<DbU256>(connection)
+            .context("could not get gas spendings from database")?;
+        Ok(fees
+            .into_iter()
+            .fold(U256::zero(), |total, fee| total.saturating_add(fee.0)))
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
+#[diesel(table_name = dry_run_answers)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct DryRunAnswer {
+    pub chain_id: i32,
+    pub oracle_address: DbAddress,
+    pub answer: DbU256,
+    pub timestamp: DbTimestamp,
+}
+
+impl DryRunAnswer {
+    pub fn create(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+        answer: U256,
+    ) -> anyhow::Result<DryRunAnswer> {
+        let dry_run_answer = DryRunAnswer {
+            chain_id: i32::try_from(chain_id).unwrap(), // this should never panic
+            oracle_address: DbAddress(oracle_address),
+            answer: DbU256(answer),
+            timestamp: DbTimestamp(SystemTime::now()),
+        };
+
+        diesel::insert_into(dry_run_answers::table)
+            .values(&dry_run_answer)
+            .on_conflict((
+                dry_run_answers::dsl::chain_id,
+                dry_run_answers::dsl::oracle_address,
+            ))
+            .do_update()
+            .set((
+                dry_run_answers::dsl::answer.eq(&dry_run_answer.answer),
+                dry_run_answers::dsl::timestamp.eq(dry_run_answer.timestamp),
+            ))
+            .execute(connection)
+            .context(format!(
+                "could not insert dry run answer for oracle 0x{:x} into database",
+                oracle_address
+            ))?;
+
+        Ok(dry_run_answer)
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+    ) -> anyhow::Result<Option<DryRunAnswer>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        dry_run_answers::table
+            .find((chain_id, DbAddress(oracle_address)))
+            .select(DryRunAnswer::as_select())
+            .first(connection)
+            .optional()
+            .context(format!(
+                "could not get dry run answer for oracle 0x{:x}",
+                oracle_address
+            ))
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<DryRunAnswer>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(dry_run_answers::table
+            .filter(dry_run_answers::dsl::chain_id.eq(chain_id))
+            .select(DryRunAnswer::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
+#[diesel(table_name = feature_gates)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct FeatureGate {
+    pub chain_id: i32,
+    pub feature: String,
+    pub enabled: bool,
+}
+
+impl FeatureGate {
+    pub fn set(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        feature: &str,
+        enabled: bool,
+    ) -> anyhow::Result<()> {
+        let feature_gate = FeatureGate {
+            chain_id: i32::try_from(chain_id).unwrap(), // this should never panic
+            feature: feature.to_owned(),
+            enabled,
+        };
+
+        diesel::insert_into(feature_gates::table)
+            .values(&feature_gate)
+            .on_conflict((feature_gates::dsl::chain_id, feature_gates::dsl::feature))
+            .do_update()
+            .set(feature_gates::dsl::enabled.eq(enabled))
+            .execute(connection)
+            .context(format!(
+                "could not set feature gate {} for chain {}",
+                feature, chain_id
+            ))?;
+
+        Ok(())
+    }
+
+    pub fn delete(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        feature: &str,
+    ) -> anyhow::Result<()> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        diesel::delete(feature_gates::dsl::feature_gates.find((chain_id, feature)))
+            .execute(connection)
+            .context(format!(
+                "could not delete feature gate {} for chain {}",
+                feature, chain_id
+            ))?;
+        Ok(())
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<FeatureGate>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(feature_gates::table
+            .filter(feature_gates::dsl::chain_id.eq(chain_id))
+            .select(FeatureGate::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = defillama_snapshots)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct DefiLlamaSnapshot {
+    pub id: i64,
+    pub chain_id: i32,
+    pub oracle_address: DbAddress,
+    pub specification: Specification,
+    pub responses: DbJson,
+    pub answer: Option<DbU256>,
+    pub timestamp: DbTimestamp,
+}
+
+impl DefiLlamaSnapshot {
+    pub fn create(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+        specification: Specification,
+        responses: Vec<RecordedResponse>,
+        answer: Option<U256>,
+    ) -> anyhow::Result<DefiLlamaSnapshot> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        let responses = serde_json::to_value(responses)
+            .context("could not serialize recorded defillama responses")?;
+
+        diesel::insert_into(defillama_snapshots::table)
+            .values((
+                defillama_snapshots::dsl::chain_id.eq(chain_id),
+                defillama_snapshots::dsl::oracle_address.eq(DbAddress(oracle_address)),
+                defillama_snapshots::dsl::specification.eq(specification),
+                defillama_snapshots::dsl::responses.eq(DbJson(responses)),
+                defillama_snapshots::dsl::answer.eq(answer.map(DbU256)),
+                defillama_snapshots::dsl::timestamp.eq(DbTimestamp(SystemTime::now())),
+            ))
+            .returning(DefiLlamaSnapshot::as_returning())
+            .get_result(connection)
+            .context(format!(
+                "could not insert defillama snapshot for oracle 0x{:x} into database",
+                oracle_address
+            ))
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        id: i64,
+    ) -> anyhow::Result<Option<DefiLlamaSnapshot>> {
+        Ok(defillama_snapshots::table
+            .find(id)
+            .select(DefiLlamaSnapshot::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    pub fn get_latest_for_oracle(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+    ) -> anyhow::Result<Option<DefiLlamaSnapshot>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(defillama_snapshots::table
+            .filter(
+                defillama_snapshots::dsl::chain_id
+                    .eq(chain_id)
+                    .and(defillama_snapshots::dsl::oracle_address.eq(DbAddress(oracle_address))),
+            )
+            .order(defillama_snapshots::dsl::id.desc())
+            .select(DefiLlamaSnapshot::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    pub fn recorded_responses(&self) -> anyhow::Result<Vec<RecordedResponse>> {
+        serde_json::from_value(self.responses.0.clone()).context(format!(
+            "could not deserialize recorded defillama responses for snapshot {}",
+            self.id
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    AnswerOverrideProposed,
+    AnswerOverrideApproved,
+    AnswerOverrideApplied,
+    AnswerHeldForReview,
+    OracleRemoved,
+    CheckpointReset,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::AnswerOverrideProposed => "answer_override_proposed",
+            AuditAction::AnswerOverrideApproved => "answer_override_approved",
+            AuditAction::AnswerOverrideApplied => "answer_override_applied",
+            AuditAction::AnswerHeldForReview => "answer_held_for_review",
+            AuditAction::OracleRemoved => "oracle_removed",
+            AuditAction::CheckpointReset => "checkpoint_reset",
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = audit_log)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub chain_id: i32,
+    // missing for actions on the chain as a whole
+    pub oracle_address: Option<DbAddress>,
+    pub action: String,
+    pub actor: String,
+    pub details: DbJson,
+    pub timestamp: DbTimestamp,
+}
+
+impl AuditLogEntry {
+    pub fn create(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+        action: AuditAction,
+        actor: &str,
+        details: serde_json::Value,
+    ) -> anyhow::Result<AuditLogEntry> {
+        Self::insert(
+            connection,
+            chain_id,
+            Some(oracle_address),
+            action,
+            actor,
+            details,
+        )
+        .context(format!(
+            "could not insert {} audit log entry for oracle 0x{:x} into database",
+            action.as_str(),
+            oracle_address
+        ))
+    }
+
+    pub fn create_for_chain(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        action: AuditAction,
+        actor: &str,
+        details: serde_json::Value,
+    ) -> anyhow::Result<AuditLogEntry> {
+        Self::insert(connection, chain_id, None, action, actor, details).context(format!(
+            "could not insert {} audit log entry for chain {} into database",
+            action.as_str(),
+            chain_id
+        ))
+    }
+
+    fn insert(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Option<Address>,
+        action: AuditAction,
+        actor: &str,
+        details: serde_json::Value,
+    ) -> QueryResult<AuditLogEntry> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        diesel::insert_into(audit_log::table)
+            .values((
+                audit_log::dsl::chain_id.eq(chain_id),
+                audit_log::dsl::oracle_address.eq(oracle_address.map(DbAddress)),
+                audit_log::dsl::action.eq(action.as_str()),
+                audit_log::dsl::actor.eq(actor),
+                audit_log::dsl::details.eq(DbJson(details)),
+                audit_log::dsl::timestamp.eq(DbTimestamp(SystemTime::now())),
+            ))
+            .returning(AuditLogEntry::as_returning())
+            .get_result(connection)
+    }
+
+    pub fn get_all_for_chain(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<AuditLogEntry>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(audit_log::table
+            .filter(
+                audit_log::dsl::chain_id
+                    .eq(chain_id)
+                    .and(audit_log::dsl::oracle_address.is_null()),
+            )
+            .order(audit_log::dsl::id.asc())
+            .select(AuditLogEntry::as_select())
+            .load(connection)?)
+    }
+
+    pub fn get_all_for_oracle(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+    ) -> anyhow::Result<Vec<AuditLogEntry>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(audit_log::table
+            .filter(
+                audit_log::dsl::chain_id
+                    .eq(chain_id)
+                    .and(audit_log::dsl::oracle_address.eq(DbAddress(oracle_address))),
+            )
+            .order(audit_log::dsl::id.asc())
+            .select(AuditLogEntry::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(table_name = answer_overrides)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct AnswerOverride {
+    pub chain_id: i32,
+    pub oracle_address: DbAddress,
+    pub answer: DbU256,
+    pub reason: String,
+    pub proposed_by: String,
+    pub proposed_at: DbTimestamp,
+    pub approved_by: Option<String>,
+    pub approved_at: Option<DbTimestamp>,
+}
+
+impl AnswerOverride {
+    // proposing an override for an oracle that already has one replaces it,
+    // resetting any previous approval
+    pub fn propose(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+        answer: U256,
+        reason: String,
+        proposed_by: String,
+    ) -> anyhow::Result<AnswerOverride> {
+        let answer_override = AnswerOverride {
+            chain_id: i32::try_from(chain_id).unwrap(), // this should never panic
+            oracle_address: DbAddress(oracle_address),
+            answer: DbU256(answer),
+            reason,
+            proposed_by,
+            proposed_at: DbTimestamp(SystemTime::now()),
+            approved_by: None,
+            approved_at: None,
+        };
+
+        connection.transaction(|connection| {
+            diesel::insert_into(answer_overrides::table)
+                .values(&answer_override)
+                .on_conflict((
+                    answer_overrides::dsl::chain_id,
+                    answer_overrides::dsl::oracle_address,
+                ))
+                .do_update()
+                .set((
+                    answer_overrides::dsl::answer.eq(&answer_override.answer),
+                    answer_overrides::dsl::reason.eq(&answer_override.reason),
+                    answer_overrides::dsl::proposed_by.eq(&answer_override.proposed_by),
+                    answer_overrides::dsl::proposed_at.eq(answer_override.proposed_at),
+                    answer_overrides::dsl::approved_by.eq(None::<String>),
+                    answer_overrides::dsl::approved_at.eq(None::<DbTimestamp>),
+                ))
+                .execute(connection)
+                .context(format!(
+                    "could not insert answer override for oracle 0x{:x} into database",
+                    oracle_address
+                ))?;
+
+            AuditLogEntry::create(
+                connection,
+                chain_id,
+                oracle_address,
+                AuditAction::AnswerOverrideProposed,
+                &answer_override.proposed_by,
+                serde_json::json!({
+                    "answer": answer.to_string(),
+                    "reason": answer_override.reason,
+                }),
+            )?;
+
+            Ok(answer_override)
+        })
+    }
+
+    pub fn approve(
+        &mut self,
+        connection: &mut DbConnection,
+        approved_by: String,
+    ) -> anyhow::Result<()> {
+        if self.is_approved() {
+            anyhow::bail!("answer override already approved");
+        }
+        if self.proposed_by == approved_by {
+            anyhow::bail!(
+                "answer override must be approved by a different operator than the proposer"
+            );
+        }
+
+        // the checks above were made on this instance, so the approval only applies if
+        // the override wasn't replaced or approved in the meantime
+        let approved_at = DbTimestamp(SystemTime::now());
+        connection.transaction(|connection| {
+            let updated = diesel::update(
+                answer_overrides::dsl::answer_overrides
+                    .find((self.chain_id, &self.oracle_address))
+                    .filter(answer_overrides::dsl::answer.eq(&self.answer))
+                    .filter(answer_overrides::dsl::proposed_by.eq(&self.proposed_by))
+                    .filter(answer_overrides::dsl::proposed_at.eq(self.proposed_at))
+                    .filter(answer_overrides::dsl::approved_by.is_null()),
+            )
+            .set((
+                answer_overrides::dsl::approved_by.eq(&approved_by),
+                answer_overrides::dsl::approved_at.eq(approved_at),
+            ))
+            .execute(connection)
+            .context(format!(
+                "could not approve answer override for oracle 0x{:x}",
+                self.oracle_address.0
+            ))?;
+            if updated == 0 {
+                anyhow::bail!(
+                    "answer override for oracle 0x{:x} was replaced or approved in the meantime",
+                    self.oracle_address.0
+                );
+            }
+
+            AuditLogEntry::create(
+                connection,
+                self.chain_id as u64,
+                self.oracle_address.0,
+                AuditAction::AnswerOverrideApproved,
+                &approved_by,
+                serde_json::json!({ "answer": self.answer.0.to_string() }),
+            )?;
+
+            Ok::<_, anyhow::Error>(())
+        })?;
+
+        self.approved_by = Some(approved_by);
+        self.approved_at = Some(approved_at);
+        Ok(())
+    }
+
+    pub fn is_approved(&self) -> bool {
+        self.approved_by.is_some()
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+    ) -> anyhow::Result<Option<AnswerOverride>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(answer_overrides::dsl::answer_overrides
+            .find((chain_id, DbAddress(oracle_address)))
+            .select(AnswerOverride::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<AnswerOverride>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(answer_overrides::table
+            .filter(answer_overrides::dsl::chain_id.eq(chain_id))
+            .select(AnswerOverride::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, PartialEq)]
+#[diesel(table_name = answer_reviews)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct AnswerReview {
+    pub chain_id: i32,
+    pub oracle_address: DbAddress,
+    pub answer: DbU256,
+    pub reference_answer: DbU256,
+    pub created_at: DbTimestamp,
+}
+
+impl AnswerReview {
+    // holds an oracle's anomalous answer until an operator resolves it through an
+    // approved answer override
+    pub fn create(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+        answer: U256,
+        reference_answer: U256,
+    ) -> anyhow::Result<AnswerReview> {
+        let answer_review = AnswerReview {
+            chain_id: i32::try_from(chain_id).unwrap(), // this should never panic
+            oracle_address: DbAddress(oracle_address),
+            answer: DbU256(answer),
+            reference_answer: DbU256(reference_answer),
+            created_at: DbTimestamp(SystemTime::now()),
+        };
+
+        connection.transaction(|connection| {
+            diesel::insert_into(answer_reviews::table)
+                .values(&answer_review)
+                .on_conflict((
+                    answer_reviews::dsl::chain_id,
+                    answer_reviews::dsl::oracle_address,
+                ))
+                .do_update()
+                .set((
+                    answer_reviews::dsl::answer.eq(&answer_review.answer),
+                    answer_reviews::dsl::reference_answer.eq(&answer_review.reference_answer),
+                    answer_reviews::dsl::created_at.eq(answer_review.created_at),
+                ))
+                .execute(connection)
+                .context(format!(
+                    "could not insert answer review for oracle 0x{:x} into database",
+                    oracle_address
+                ))?;
+            AuditLogEntry::create(
+                connection,
+                chain_id,
+                oracle_address,
+                AuditAction::AnswerHeldForReview,
+                "answerer",
+                serde_json::json!({
+                    "answer": answer.to_string(),
+                    "reference_answer": reference_answer.to_string(),
+                }),
+            )?;
+            Ok(answer_review)
+        })
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        oracle_address: Address,
+    ) -> anyhow::Result<Option<AnswerReview>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(answer_reviews::table
+            .filter(
+                answer_reviews::dsl::chain_id
+                    .eq(chain_id)
+                    .and(answer_reviews::dsl::oracle_address.eq(DbAddress(oracle_address))),
+            )
+            .select(AnswerReview::as_select())
+            .first(connection)
+            .optional()?)
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<AnswerReview>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(answer_reviews::table
+            .filter(answer_reviews::dsl::chain_id.eq(chain_id))
+            .select(AnswerReview::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = rate_limit_buckets)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct RateLimitBucket {
+    pub name: String,
+    pub tokens: f64,
+    pub updated_at: DbTimestamp,
+}
+
+impl RateLimitBucket {
+    // atomically refills the bucket based on the time elapsed since its last update
+    // and takes a token from it if available. the database clock is used so that
+    // the bucket can be shared between hosts with skewed clocks
+    pub fn try_acquire(
+        connection: &mut DbConnection,
+        name: &str,
+        capacity: f64,
+        refill_per_second: f64,
+    ) -> anyhow::Result<bool> {
+        #[cfg(feature = "postgres")]
+        let acquired = diesel::sql_query(
+            "INSERT INTO rate_limit_buckets AS bucket (name, tokens, updated_at)
+            VALUES ($1, $2 - 1, timezone('utc', clock_timestamp()))
+            ON CONFLICT (name) DO UPDATE SET
+                tokens = LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM timezone('utc', clock_timestamp()) - bucket.updated_at)::FLOAT8 * $3) - 1,
+                updated_at = timezone('utc', clock_timestamp())
+            WHERE LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM timezone('utc', clock_timestamp()) - bucket.updated_at)::FLOAT8 * $3) >= 1",
+        )
+        .bind::<diesel::sql_types::Text, _>(name)
+        .bind::<diesel::sql_types::Double, _>(capacity)
+        .bind::<diesel::sql_types::Double, _>(refill_per_second);
+        // a sqlite database can't be shared between hosts, so the local clock is used.
+        // timestamps are stored in microseconds
+        #[cfg(feature = "sqlite")]
+        let acquired = diesel::sql_query(
+            "INSERT INTO rate_limit_buckets AS bucket (name, tokens, updated_at)
+            VALUES (?1, ?2 - 1, ?4)
+            ON CONFLICT (name) DO UPDATE SET
+                tokens = MIN(?2, bucket.tokens + (?4 - bucket.updated_at) / 1000000.0 * ?3) - 1,
+                updated_at = ?4
+            WHERE MIN(?2, bucket.tokens + (?4 - bucket.updated_at) / 1000000.0 * ?3) >= 1",
+        )
+        .bind::<diesel::sql_types::Text, _>(name)
+        .bind::<diesel::sql_types::Double, _>(capacity)
+        .bind::<diesel::sql_types::Double, _>(refill_per_second)
+        .bind::<super::sql_types::Timestamp, _>(DbTimestamp(SystemTime::now()));
+        let acquired = acquired.execute(connection).context(format!(
+            "could not acquire token from rate limit bucket {}",
+            name
+        ))?;
+
+        Ok(acquired > 0)
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        name: &str,
+    ) -> anyhow::Result<Option<RateLimitBucket>> {
+        Ok(rate_limit_buckets::table
+            .find(name)
+            .select(RateLimitBucket::as_select())
+            .first(connection)
+            .optional()?)
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = indexed_logs)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct IndexedLog {
+    pub id: i64,
+    pub chain_id: i32,
+    pub block_number: i64,
+    pub block_hash: Option<DbTxHash>,
+    pub tx_hash: DbTxHash,
+    pub log_index: i64,
+    pub address: DbAddress,
+    pub topics: DbJson,
+    pub data: Vec<u8>,
+    pub indexed_at: DbTimestamp,
+}
+
+impl IndexedLog {
+    // logs scanned again (e.g. after a restart or during a backfill) are only stored once
+    pub fn create(connection: &mut DbConnection, chain_id: u64, log: &Log) -> anyhow::Result<()> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        let (block_number, tx_hash, log_index) =
+            match (log.block_number, log.transaction_hash, log.log_index) {
+                (Some(block_number), Some(tx_hash), Some(log_index)) => {
+                    (block_number, tx_hash, log_index)
+                }
+                _ => anyhow::bail!("cannot index pending log {:?}", log),
+            };
+        let topics = serde_json::to_value(&log.topics).context("could not serialize log topics")?;
+
+        diesel::insert_into(indexed_logs::table)
+            .values((
+                indexed_logs::dsl::chain_id.eq(chain_id),
+                indexed_logs::dsl::block_number.eq(block_number.as_u64() as i64),
+                indexed_logs::dsl::block_hash.eq(log.block_hash.map(DbTxHash)),
+                indexed_logs::dsl::tx_hash.eq(DbTxHash(tx_hash)),
+                indexed_logs::dsl::log_index.eq(log_index.as_u64() as i64),
+                indexed_logs::dsl::address.eq(DbAddress(log.address)),
+                indexed_logs::dsl::topics.eq(DbJson(topics)),
+                indexed_logs::dsl::data.eq(log.data.to_vec()),
+                indexed_logs::dsl::indexed_at.eq(DbTimestamp(SystemTime::now())),
+            ))
+            .on_conflict_do_nothing()
+            .execute(connection)
+            .context(format!(
+                "could not insert log {} of tx 0x{:x} into database",
+                log_index, tx_hash
+            ))?;
+
+        Ok(())
+    }
+
+    pub fn get_all_for_chain_id_between(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<IndexedLog>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(indexed_logs::table
+            .filter(
+                indexed_logs::dsl::chain_id
+                    .eq(chain_id)
+                    .and(indexed_logs::dsl::block_number.ge(from_block as i64))
+                    .and(indexed_logs::dsl::block_number.le(to_block as i64)),
+            )
+            .order((
+                indexed_logs::dsl::block_number.asc(),
+                indexed_logs::dsl::log_index.asc(),
+            ))
+            .select(IndexedLog::as_select())
+            .load(connection)?)
+    }
+
+    pub fn to_log(&self) -> anyhow::Result<Log> {
+        Ok(Log {
+            address: self.address.0,
+            topics: serde_json::from_value(self.topics.0.clone()).context(format!(
+                "could not deserialize topics of indexed log {}",
+                self.id
+            ))?,
+            data: self.data.clone().into(),
+            block_hash: self.block_hash.map(|block_hash| block_hash.0),
+            block_number: Some(U64::from(self.block_number as u64)),
+            transaction_hash: Some(self.tx_hash.0),
+            log_index: Some(U256::from(self.log_index as u64)),
+            ..Default::default()
+        })
+    }
+}
+
+// factories whose logs before the checkpoint were scanned, so that factories added
+// to a chain later on can be told apart and scanned from their own deployment
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = scanned_factories)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct ScannedFactory {
+    pub chain_id: i32,
+    pub address: DbAddress,
+}
+
+impl ScannedFactory {
+    pub fn record(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        address: Address,
+    ) -> anyhow::Result<()> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        diesel::insert_into(scanned_factories::table)
+            .values((
+                scanned_factories::dsl::chain_id.eq(chain_id),
+                scanned_factories::dsl::address.eq(DbAddress(address)),
+            ))
+            .on_conflict_do_nothing()
+            .execute(connection)
+            .context(format!("could not record scanned factory 0x{:x}", address))?;
+        Ok(())
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<ScannedFactory>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(scanned_factories::table
+            .filter(scanned_factories::dsl::chain_id.eq(chain_id))
+            .select(ScannedFactory::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = scanned_ranges)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct ScannedRange {
+    pub chain_id: i32,
+    pub from_block: i64,
+    pub to_block: i64,
+}
+
+impl ScannedRange {
+    // the recorded range is merged with every overlapping or adjacent one, so that a
+    // chain scanned without interruptions is described by a single row. concurrent
+    // scanners might still leave overlapping rows behind, which are merged the next
+    // time a range touching them is recorded
+    pub fn record(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<()> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        connection
+            .transaction(|connection| {
+                let touching = scanned_ranges::table.filter(
+                    scanned_ranges::dsl::chain_id
+                        .eq(chain_id)
+                        .and(scanned_ranges::dsl::from_block.le(to_block as i64 + 1))
+                        .and(scanned_ranges::dsl::to_block.ge(from_block as i64 - 1)),
+                );
+                let (from_block, to_block) = touching
+                    .select(ScannedRange::as_select())
+                    .load(connection)?
+                    .into_iter()
+                    .fold((from_block as i64, to_block as i64), |(from, to), range| {
+                        (from.min(range.from_block), to.max(range.to_block))
+                    });
+                diesel::delete(touching).execute(connection)?;
+                diesel::insert_into(scanned_ranges::table)
+                    .values((
+                        scanned_ranges::dsl::chain_id.eq(chain_id),
+                        scanned_ranges::dsl::from_block.eq(from_block),
+                        scanned_ranges::dsl::to_block.eq(to_block),
+                    ))
+                    .on_conflict_do_nothing()
+                    .execute(connection)?;
+                Ok::<_, diesel::result::Error>(())
+            })
+            .context(format!(
+                "could not record scanned range from block {} to {}",
+                from_block, to_block
+            ))?;
+
+        Ok(())
+    }
+
+    pub fn get_all_for_chain_id(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Vec<ScannedRange>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(scanned_ranges::table
+            .filter(scanned_ranges::dsl::chain_id.eq(chain_id))
+            .order(scanned_ranges::dsl::from_block.asc())
+            .select(ScannedRange::as_select())
+            .load(connection)?)
+    }
+}
+
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = scanner_leases)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct ScannerLease {
+    pub chain_id: i32,
+    pub holder: String,
+    pub expires_at: DbTimestamp,
+}
+
+impl ScannerLease {
+    // takes or renews the chain's lease, which only succeeds if it's free, expired or
+    // already held by the given holder. the database clock is used so that instances
+    // on hosts with skewed clocks agree on when a lease expires
+    pub fn try_acquire(
+        connection: &mut DbConnection,
+        chain_id: u64,
+        holder: &str,
+        duration: Duration,
+    ) -> anyhow::Result<bool> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        #[cfg(feature = "postgres")]
+        let acquired = diesel::sql_query(
+            "INSERT INTO scanner_leases AS lease (chain_id, holder, expires_at)
+            VALUES ($1, $2, timezone('utc', clock_timestamp()) + make_interval(secs => $3))
+            ON CONFLICT (chain_id) DO UPDATE SET
+                holder = EXCLUDED.holder,
+                expires_at = EXCLUDED.expires_at
+            WHERE lease.holder = EXCLUDED.holder OR lease.expires_at < timezone('utc', clock_timestamp())",
+        )
+        .bind::<diesel::sql_types::Integer, _>(chain_id)
+        .bind::<diesel::sql_types::Text, _>(holder)
+        .bind::<diesel::sql_types::Double, _>(duration.as_secs_f64());
+        // a sqlite database can't be shared between hosts, so the local clock is used
+        #[cfg(feature = "sqlite")]
+        let acquired = {
+            let now = SystemTime::now();
+            diesel::sql_query(
+                "INSERT INTO scanner_leases AS lease (chain_id, holder, expires_at)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT (chain_id) DO UPDATE SET
+                    holder = excluded.holder,
+                    expires_at = excluded.expires_at
+                WHERE lease.holder = excluded.holder OR lease.expires_at < ?4",
+            )
+            .bind::<diesel::sql_types::Integer, _>(chain_id)
+            .bind::<diesel::sql_types::Text, _>(holder)
+            .bind::<super::sql_types::Timestamp, _>(DbTimestamp(now + duration))
+            .bind::<super::sql_types::Timestamp, _>(DbTimestamp(now))
+        };
+        let acquired = acquired.execute(connection).context(format!(
+            "could not acquire scanner lease for chain {}",
+            chain_id
+        ))?;
+
+        Ok(acquired > 0)
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        chain_id: u64,
+    ) -> anyhow::Result<Option<ScannerLease>> {
+        let chain_id = i32::try_from(chain_id).unwrap(); // this should never panic
+        Ok(scanner_leases::table
+            .find(chain_id)
+            .select(ScannerLease::as_select())
+            .first(connection)
+            .optional()?)
+    }
+}
+
+// specification documents are immutable, so once fetched they're kept around by cid
+// to spare restarts and rescans from fetching them again
+#[derive(Queryable, Selectable, Debug, PartialEq)]
+#[diesel(table_name = cached_specifications)]
+#[diesel(check_for_backend(DbBackend))]
+pub struct CachedSpecification {
+    pub cid: String,
+    pub specification: Specification,
+    pub fetched_at: DbTimestamp,
+}
+
+impl CachedSpecification {
+    pub fn create(
+        connection: &mut DbConnection,
+        cid: &str,
+        specification: Specification,
+    ) -> anyhow::Result<()> {
+        diesel::insert_into(cached_specifications::table)
+            .values((
+                cached_specifications::dsl::cid.eq(cid),
+                cached_specifications::dsl::specification.eq(specification),
+                cached_specifications::dsl::fetched_at.eq(DbTimestamp(SystemTime::now())),
+            ))
+            .on_conflict_do_nothing()
+            .execute(connection)
+            .context(format!("could not cache specification with cid {}", cid))?;
+        Ok(())
+    }
+
+    pub fn get(
+        connection: &mut DbConnection,
+        cid: &str,
+    ) -> anyhow::Result<Option<CachedSpecification>> {
+        Ok(cached_specifications::table
+            .find(cid)
+            .select(CachedSpecification::as_select())
+            .first(connection)
+            .optional()?)
+    }
 }
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use diesel::{
+    deserialize::{self, FromSql},
+    pg::{Pg, PgValue},
+    prelude::*,
+    r2d2::{self, ConnectionManager, CustomizeConnection, Pool},
+    serialize::{self, ToSql},
+    sql_types::{Binary, Jsonb, Timestamp},
+};
+
+use crate::{commons::DbPoolConfig, specification::Specification};
+
+use std::time::SystemTime;
+
+use super::{build_pool, DbJson, DbTimestamp, DbU256};
+
+pub type DbConnection = PgConnection;
+
+pub mod sql_types {
+    pub use diesel::sql_types::{Jsonb, Timestamp};
+}
+
+// applied to every connection as it's checked out of the pool, so that a stuck query
+// can't hold on to one of the pool's connections forever
+#[derive(Debug)]
+struct StatementTimeout(Duration);
+
+impl CustomizeConnection<PgConnection, r2d2::Error> for StatementTimeout {
+    fn on_acquire(&self, connection: &mut PgConnection) -> Result<(), r2d2::Error> {
+        diesel::sql_query(format!("SET statement_timeout = {}", self.0.as_millis()))
+            .execute(connection)
+            .map_err(r2d2::Error::QueryError)?;
+        Ok(())
+    }
+}
+
+pub fn connection_customizer(
+    pool_config: &DbPoolConfig,
+) -> Option<Box<dyn CustomizeConnection<PgConnection, r2d2::Error>>> {
+    pool_config
+        .statement_timeout_seconds
+        .map(|statement_timeout_seconds| {
+            Box::new(StatementTimeout(Duration::from_secs(
+                statement_timeout_seconds,
+            ))) as _
+        })
+}
+
+pub fn connect_or_create(
+    url: &String,
+    pool_config: &DbPoolConfig,
+) -> anyhow::Result<Pool<ConnectionManager<PgConnection>>> {
+    match build_pool(url, pool_config) {
+        Ok(db_connection_pool) => Ok(db_connection_pool),
+        Err(error) => {
+            let parsed_url = reqwest::Url::parse(url).context(format!(
+                "could not parse database connection string {}",
+                url
+            ))?;
+
+            let database = parsed_url.path().chars().skip(1).collect::<String>();
+            let database = database.as_str();
+            let username = parsed_url.username();
+            tracing::error!(
+                "error connecting to database {}, trying to create it: {:#}",
+                database,
+                error
+            );
+
+            // connect to "postgres" database
+            let mut pg_db_parsed_url = parsed_url.clone();
+            pg_db_parsed_url.set_path("postgres");
+            let mut pg_db_connection = PgConnection::establish(pg_db_parsed_url.as_str())
+                .context("could not connect to admin postgres database")?;
+
+            // create database
+            diesel::sql_query(format!("CREATE DATABASE \"{}\";", database))
+                .execute(&mut pg_db_connection)
+                .context(format!("failed to create database {}", database))?;
+            diesel::sql_query(format!(
+                "GRANT ALL PRIVILEGES ON DATABASE \"{}\" TO \"{}\";",
+                database, username
+            ))
+            .execute(&mut pg_db_connection)
+            .context(format!(
+                "failed to grant privileges on database {} to user {}",
+                database, username
+            ))?;
+
+            // the database has been created at this point: use the og url to connect
+            build_pool(url, pool_config)
+        }
+    }
+}
+
+impl ToSql<Binary, Pg> for DbU256 {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
+        let mut value = [0u8; 32];
+        self.0.to_big_endian(&mut value);
+        <&[u8] as ToSql<Binary, Pg>>::to_sql(&value.as_slice(), &mut out.reborrow())
+    }
+}
+
+impl FromSql<Jsonb, Pg> for Specification {
+    fn from_sql(value: PgValue) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for Specification {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
+
+impl FromSql<Timestamp, Pg> for DbTimestamp {
+    fn from_sql(value: PgValue) -> deserialize::Result<Self> {
+        Ok(DbTimestamp(
+            <SystemTime as FromSql<Timestamp, Pg>>::from_sql(value)?,
+        ))
+    }
+}
+
+impl ToSql<Timestamp, Pg> for DbTimestamp {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
+        <SystemTime as ToSql<Timestamp, Pg>>::to_sql(&self.0, out)
+    }
+}
+
+impl FromSql<Jsonb, Pg> for DbJson {
+    fn from_sql(value: PgValue) -> deserialize::Result<Self> {
+        Ok(DbJson(<serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(
+            value,
+        )?))
+    }
+}
+
+impl ToSql<Jsonb, Pg> for DbJson {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Pg>) -> serialize::Result {
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&self.0, out)
+    }
+}
@@ -1,25 +1,267 @@
 // @generated automatically by Diesel CLI.
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
     active_oracles (address, chain_id) {
-        address -> Bytea,
+        address -> Binary,
+        chain_id -> Int4,
+        measurement_timestamp -> Timestamp,
+        specification -> Jsonb,
+        answer_tx_hash -> Nullable<Binary>,
+        answer -> Nullable<Binary>,
+        expiration -> Nullable<Timestamp>,
+        answer_attempts -> Int4,
+        next_answer_attempt -> Nullable<Timestamp>,
+        claim_expiration -> Nullable<Timestamp>,
+        answer_tx_submitted_at -> Nullable<Timestamp>,
+        source_missing_since -> Nullable<Timestamp>,
+        defillama_snapshot_id -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    answer_overrides (chain_id, oracle_address) {
+        chain_id -> Int4,
+        oracle_address -> Binary,
+        answer -> Binary,
+        reason -> Text,
+        proposed_by -> Text,
+        proposed_at -> Timestamp,
+        approved_by -> Nullable<Text>,
+        approved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    answer_reviews (chain_id, oracle_address) {
+        chain_id -> Int4,
+        oracle_address -> Binary,
+        answer -> Binary,
+        reference_answer -> Binary,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    answered_oracles (id) {
+        id -> Int8,
+        address -> Binary,
+        chain_id -> Int4,
+        measurement_timestamp -> Timestamp,
+        specification -> Jsonb,
+        expiration -> Nullable<Timestamp>,
+        answer -> Nullable<Binary>,
+        answer_attempts -> Int4,
+        answer_tx_hash -> Binary,
+        answer_tx_submitted_at -> Nullable<Timestamp>,
+        block_number -> Nullable<Int8>,
+        gas_used -> Nullable<Binary>,
+        effective_gas_price -> Nullable<Binary>,
+        fee -> Nullable<Binary>,
+        fee_usd -> Nullable<Float8>,
+        answered_at -> Timestamp,
+        defillama_snapshot_id -> Nullable<Int8>,
+        proof_bundle_cid -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    archived_oracles (id) {
+        id -> Int8,
+        address -> Binary,
         chain_id -> Int4,
         measurement_timestamp -> Timestamp,
         specification -> Jsonb,
-        answer_tx_hash -> Nullable<Bytea>,
-        answer -> Nullable<Bytea>,
         expiration -> Nullable<Timestamp>,
+        answer -> Nullable<Binary>,
+        answer_attempts -> Int4,
+        reason -> Text,
+        archived_at -> Timestamp,
     }
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    audit_log (id) {
+        id -> Int8,
+        chain_id -> Int4,
+        oracle_address -> Nullable<Binary>,
+        action -> Text,
+        actor -> Text,
+        details -> Jsonb,
+        timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    cached_specifications (cid) {
+        cid -> Text,
+        specification -> Jsonb,
+        fetched_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
     checkpoints (chain_id) {
         chain_id -> Int4,
         block_number -> Int8,
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    defillama_snapshots (id) {
+        id -> Int8,
+        chain_id -> Int4,
+        oracle_address -> Binary,
+        specification -> Jsonb,
+        responses -> Jsonb,
+        answer -> Nullable<Binary>,
+        timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    dry_run_answers (chain_id, oracle_address) {
+        chain_id -> Int4,
+        oracle_address -> Binary,
+        answer -> Binary,
+        timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    feature_gates (chain_id, feature) {
+        chain_id -> Int4,
+        feature -> Text,
+        enabled -> Bool,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    gas_spendings (chain_id, tx_hash) {
+        chain_id -> Int4,
+        tx_hash -> Binary,
+        oracle_address -> Binary,
+        fee -> Binary,
+        timestamp -> Timestamp,
+        fee_usd -> Nullable<Float8>,
+        gas_used -> Nullable<Binary>,
+        effective_gas_price -> Nullable<Binary>,
+        kpi_token_address -> Nullable<Binary>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    indexed_logs (id) {
+        id -> Int8,
+        chain_id -> Int4,
+        block_number -> Int8,
+        block_hash -> Nullable<Binary>,
+        tx_hash -> Binary,
+        log_index -> Int8,
+        address -> Binary,
+        topics -> Jsonb,
+        data -> Binary,
+        indexed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    rate_limit_buckets (name) {
+        name -> Text,
+        tokens -> Float8,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    scanned_factories (chain_id, address) {
+        chain_id -> Int4,
+        address -> Binary,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    scanned_ranges (chain_id, from_block) {
+        chain_id -> Int4,
+        from_block -> Int8,
+        to_block -> Int8,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::sql_types::{Jsonb, Timestamp};
+
+    scanner_leases (chain_id) {
+        chain_id -> Int4,
+        holder -> Text,
+        expires_at -> Timestamp,
+    }
+}
+
 diesel::allow_tables_to_appear_in_same_query!(
     active_oracles,
+    answer_overrides,
+    answer_reviews,
+    answered_oracles,
+    archived_oracles,
+    audit_log,
+    cached_specifications,
     checkpoints,
+    defillama_snapshots,
+    dry_run_answers,
+    feature_gates,
+    gas_spendings,
+    indexed_logs,
+    rate_limit_buckets,
+    scanned_factories,
+    scanned_ranges,
+    scanner_leases,
 );
@@ -0,0 +1,153 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use diesel::{
+    connection::SimpleConnection,
+    deserialize::{self, FromSql},
+    prelude::*,
+    r2d2::{self, ConnectionManager, CustomizeConnection, Pool},
+    serialize::{self, IsNull, ToSql},
+    sql_types::{BigInt, Binary, Text},
+    sqlite::{Sqlite, SqliteValue},
+};
+
+use crate::{
+    commons::{DbPoolConfig, DB_CONNECTION_TIMEOUT},
+    specification::Specification,
+};
+
+use super::{build_pool, DbJson, DbTimestamp, DbU256};
+
+pub type DbConnection = SqliteConnection;
+
+// sqlite has no json nor timestamp types: json documents are stored as text, and
+// timestamps as the microseconds elapsed since the unix epoch
+pub mod sql_types {
+    use diesel::{
+        query_builder::QueryId,
+        sql_types::{ops, BigInt, SqlOrd},
+        SqlType,
+    };
+
+    #[derive(SqlType, QueryId)]
+    #[diesel(sqlite_type(name = "Text"))]
+    pub struct Jsonb;
+
+    #[derive(SqlType, QueryId)]
+    #[diesel(sqlite_type(name = "Long"))]
+    pub struct Timestamp;
+
+    impl SqlOrd for Timestamp {}
+
+    // shifting a timestamp takes microseconds
+    impl ops::Add for Timestamp {
+        type Rhs = BigInt;
+        type Output = Timestamp;
+    }
+
+    impl ops::Sub for Timestamp {
+        type Rhs = BigInt;
+        type Output = Timestamp;
+    }
+}
+
+use self::sql_types::{Jsonb, Timestamp};
+
+// writers wait for each other instead of failing right away, and readers aren't
+// blocked by them thanks to the write-ahead log
+#[derive(Debug)]
+struct Pragmas {
+    busy_timeout: Duration,
+}
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for Pragmas {
+    fn on_acquire(&self, connection: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        connection
+            .batch_execute(&format!(
+                "PRAGMA busy_timeout = {}; PRAGMA journal_mode = WAL;",
+                self.busy_timeout.as_millis()
+            ))
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+pub fn connection_customizer(
+    pool_config: &DbPoolConfig,
+) -> Option<Box<dyn CustomizeConnection<SqliteConnection, r2d2::Error>>> {
+    Some(Box::new(Pragmas {
+        busy_timeout: pool_config
+            .connection_timeout_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DB_CONNECTION_TIMEOUT),
+    }))
+}
+
+// the database file is created on the first connection
+pub fn connect_or_create(
+    url: &String,
+    pool_config: &DbPoolConfig,
+) -> anyhow::Result<Pool<ConnectionManager<SqliteConnection>>> {
+    if pool_config.statement_timeout_seconds.is_some() {
+        tracing::warn!("statement timeouts aren't supported by sqlite, ignoring them");
+    }
+    build_pool(url, pool_config)
+}
+
+impl ToSql<Binary, Sqlite> for DbU256 {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Sqlite>) -> serialize::Result {
+        let mut value = [0u8; 32];
+        self.0.to_big_endian(&mut value);
+        out.set_value(value.to_vec());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Timestamp, Sqlite> for DbTimestamp {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let microseconds = <i64 as FromSql<BigInt, Sqlite>>::from_sql(value)?;
+        let elapsed = Duration::from_micros(microseconds.unsigned_abs());
+        Ok(DbTimestamp(if microseconds < 0 {
+            UNIX_EPOCH - elapsed
+        } else {
+            UNIX_EPOCH + elapsed
+        }))
+    }
+}
+
+impl ToSql<Timestamp, Sqlite> for DbTimestamp {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Sqlite>) -> serialize::Result {
+        let microseconds = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => i64::try_from(elapsed.as_micros())?,
+            Err(error) => -i64::try_from(error.duration().as_micros())?,
+        };
+        out.set_value(microseconds);
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Jsonb, Sqlite> for DbJson {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(DbJson(serde_json::from_str(&value)?))
+    }
+}
+
+impl ToSql<Jsonb, Sqlite> for DbJson {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(serde_json::to_string(&self.0)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Jsonb, Sqlite> for Specification {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(serde_json::from_str(&value)?)
+    }
+}
+
+impl ToSql<Jsonb, Sqlite> for Specification {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(serde_json::to_string(self)?);
+        Ok(IsNull::No)
+    }
+}
@@ -0,0 +1,1008 @@
+pub mod models;
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Context;
+use backoff::{future::retry_notify, ExponentialBackoffBuilder};
+use carrot_commons::http_client::HttpClient;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::OnceCell;
+
+use crate::{
+    commons::{DEFILLAMA_RETRY_BUDGET, DEFILLAMA_RETRY_BUDGET_REFILL},
+    metrics, rate_limiter,
+};
+
+use self::models::{
+    Chain, CoinPrice, CoinPrices, FeesSummary, Stablecoin, Stablecoins, TvlPoint, YieldPool,
+    YieldPools,
+};
+
+// returned when defillama doesn't know about the requested data anymore, which
+// usually means that the related protocol has been delisted
+#[derive(Debug)]
+pub struct SourceMissing {
+    pub path: String,
+}
+
+impl Display for SourceMissing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} not found on defillama", self.path)
+    }
+}
+
+impl std::error::Error for SourceMissing {}
+
+pub fn is_source_missing(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<SourceMissing>())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedResponse {
+    pub path: String,
+    // the full url the response was fetched from, missing from older snapshots
+    #[serde(default)]
+    pub url: Option<String>,
+    pub body: String,
+    pub timestamp: SystemTime,
+}
+
+// defillama serves its data from a few separate hosts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefiLlamaService {
+    Api,
+    Coins,
+    Stablecoins,
+    Yields,
+}
+
+impl DefiLlamaService {
+    // responses from the main api are recorded under their plain path, which is
+    // what older snapshots contain
+    fn recorded_path(&self, path: &str) -> String {
+        match self {
+            DefiLlamaService::Api => path.to_owned(),
+            DefiLlamaService::Coins => format!("coins:{}", path),
+            DefiLlamaService::Stablecoins => format!("stablecoins:{}", path),
+            DefiLlamaService::Yields => format!("yields:{}", path),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FetchedResponse {
+    url: String,
+    body: String,
+    timestamp: SystemTime,
+}
+
+// errors are shared by every coalesced lookup, so they're kept as plain messages
+#[derive(Debug, Clone)]
+struct CoalescedError {
+    missing: bool,
+    message: String,
+}
+
+type InFlight = Arc<OnceCell<Result<FetchedResponse, CoalescedError>>>;
+
+struct CachedResponse {
+    response: FetchedResponse,
+    cached_at: Instant,
+}
+
+// a service's base urls, requests go to the active one and fail over to the others
+// in order. the one that last answered stays active, like rpc endpoints do
+struct Endpoints {
+    http_clients: Vec<Arc<HttpClient>>,
+    active: AtomicUsize,
+}
+
+impl Endpoints {
+    fn new(http_client: Arc<HttpClient>) -> Self {
+        Self {
+            http_clients: vec![http_client],
+            active: AtomicUsize::new(0),
+        }
+    }
+}
+
+// the http clients of every defillama service, shared by the whole process.
+// successful responses are cached for a short while so that many oracles and
+// validations referencing the same protocol don't each use up the rate limit
+pub struct DefiLlamaHttp {
+    api: Endpoints,
+    coins: Endpoints,
+    stablecoins: Endpoints,
+    yields: Endpoints,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<(DefiLlamaService, String), CachedResponse>>,
+    retry_max_elapsed_time: Duration,
+    retry_budget: RetryBudget,
+    in_flight: Mutex<HashMap<(DefiLlamaService, String), InFlight>>,
+}
+
+impl DefiLlamaHttp {
+    pub fn new(
+        api: Arc<HttpClient>,
+        coins: Arc<HttpClient>,
+        stablecoins: Arc<HttpClient>,
+        yields: Arc<HttpClient>,
+    ) -> Self {
+        Self {
+            api: Endpoints::new(api),
+            coins: Endpoints::new(coins),
+            stablecoins: Endpoints::new(stablecoins),
+            yields: Endpoints::new(yields),
+            cache_ttl: Duration::ZERO,
+            cache: Mutex::new(HashMap::new()),
+            retry_max_elapsed_time: Duration::ZERO,
+            retry_budget: RetryBudget::new(),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // a zero ttl disables caching
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    // failed requests are retried with a jittered exponential backoff until the
+    // given time elapses, a zero duration disables retries
+    pub fn with_retries(mut self, retry_max_elapsed_time: Duration) -> Self {
+        self.retry_max_elapsed_time = retry_max_elapsed_time;
+        self
+    }
+
+    // mirrors are tried in the given order when the service's main url fails
+    pub fn with_mirrors(
+        mut self,
+        service: DefiLlamaService,
+        mirrors: Vec<Arc<HttpClient>>,
+    ) -> Self {
+        match service {
+            DefiLlamaService::Api => &mut self.api,
+            DefiLlamaService::Coins => &mut self.coins,
+            DefiLlamaService::Stablecoins => &mut self.stablecoins,
+            DefiLlamaService::Yields => &mut self.yields,
+        }
+        .http_clients
+        .extend(mirrors);
+        self
+    }
+
+    fn endpoints(&self, service: DefiLlamaService) -> &Endpoints {
+        match service {
+            DefiLlamaService::Api => &self.api,
+            DefiLlamaService::Coins => &self.coins,
+            DefiLlamaService::Stablecoins => &self.stablecoins,
+            DefiLlamaService::Yields => &self.yields,
+        }
+    }
+
+    fn cached(&self, service: DefiLlamaService, path: &str) -> Option<FetchedResponse> {
+        if self.cache_ttl.is_zero() {
+            return None;
+        }
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(&(service, path.to_owned()))
+            .filter(|cached| cached.cached_at.elapsed() < self.cache_ttl)
+            .map(|cached| cached.response.clone())
+    }
+
+    fn cache(&self, service: DefiLlamaService, path: String, response: FetchedResponse) {
+        if self.cache_ttl.is_zero() {
+            return;
+        }
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, cached| cached.cached_at.elapsed() < self.cache_ttl);
+        cache.insert(
+            (service, path),
+            CachedResponse {
+                response,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    async fn fetch(
+        &self,
+        service: DefiLlamaService,
+        path: String,
+    ) -> anyhow::Result<FetchedResponse> {
+        if let Some(response) = self.cached(service, &path) {
+            tracing::trace!("using cached defillama response for {}", path);
+            return Ok(response);
+        }
+
+        // concurrent lookups of the same path share a single request and its result
+        let key = (service, path.clone());
+        let in_flight = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let result = in_flight
+            .get_or_init(|| async {
+                self.fetch_uncached(service, path.clone())
+                    .await
+                    .map_err(|error| CoalescedError {
+                        missing: is_source_missing(&error),
+                        message: format!("{:#}", error),
+                    })
+            })
+            .await
+            .clone();
+        let mut in_flights = self.in_flight.lock().unwrap();
+        if in_flights
+            .get(&key)
+            .is_some_and(|current| Arc::ptr_eq(current, &in_flight))
+        {
+            in_flights.remove(&key);
+        }
+        drop(in_flights);
+
+        result.map_err(|error| {
+            if error.missing {
+                SourceMissing { path }.into()
+            } else {
+                anyhow::anyhow!(error.message)
+            }
+        })
+    }
+
+    async fn fetch_uncached(
+        &self,
+        service: DefiLlamaService,
+        path: String,
+    ) -> anyhow::Result<FetchedResponse> {
+        let backoff = ExponentialBackoffBuilder::new()
+            .with_max_elapsed_time(Some(self.retry_max_elapsed_time))
+            .build();
+        let response = retry_notify(
+            backoff,
+            || async {
+                self.fetch_from_endpoints(service, path.clone())
+                    .await
+                    .map_err(|error| {
+                        if is_permanent(&error) || self.retry_max_elapsed_time.is_zero() {
+                            backoff::Error::permanent(error)
+                        } else if !self.retry_budget.withdraw() {
+                            backoff::Error::permanent(
+                                error.context("defillama retry budget exhausted"),
+                            )
+                        } else {
+                            backoff::Error::transient(error)
+                        }
+                    })
+            },
+            |error, delay: Duration| {
+                tracing::warn!(
+                    "{:#}, retrying defillama request in {}ms",
+                    error,
+                    delay.as_millis()
+                );
+            },
+        )
+        .await?;
+        self.retry_budget.deposit();
+        self.cache(service, path, response.clone());
+        Ok(response)
+    }
+
+    async fn fetch_from_endpoints(
+        &self,
+        service: DefiLlamaService,
+        path: String,
+    ) -> anyhow::Result<FetchedResponse> {
+        let endpoints = self.endpoints(service);
+        let active = endpoints.active.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for offset in 0..endpoints.http_clients.len() {
+            let index = (active + offset) % endpoints.http_clients.len();
+            match execute(&endpoints.http_clients[index], path.clone()).await {
+                Ok((url, body)) => {
+                    if index != active {
+                        tracing::warn!("defillama requests now go to {}", url);
+                        endpoints.active.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(FetchedResponse {
+                        url,
+                        body,
+                        timestamp: SystemTime::now(),
+                    });
+                }
+                // mirrors serve the same data, so they wouldn't know about it either
+                Err(error) if is_source_missing(&error) => return Err(error),
+                Err(error) => {
+                    if offset + 1 < endpoints.http_clients.len() {
+                        tracing::warn!("{:#}, trying the next defillama url", error);
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.unwrap())
+    }
+}
+
+// retries are only allowed while the process wide budget lasts, so that a defillama
+// outage doesn't multiply the requests sent to it. each retry takes a token, and each
+// successful request gives back a fraction of one
+struct RetryBudget {
+    tokens: Mutex<f64>,
+}
+
+impl RetryBudget {
+    fn new() -> Self {
+        Self {
+            tokens: Mutex::new(DEFILLAMA_RETRY_BUDGET),
+        }
+    }
+
+    fn withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens < 1.0 {
+            return false;
+        }
+        *tokens -= 1.0;
+        true
+    }
+
+    fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + DEFILLAMA_RETRY_BUDGET_REFILL).min(DEFILLAMA_RETRY_BUDGET);
+    }
+}
+
+#[derive(Debug)]
+struct UnexpectedStatus {
+    status: StatusCode,
+    path: String,
+    body: String,
+}
+
+impl Display for UnexpectedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unexpected status {} for {}: {}",
+            self.status, self.path, self.body
+        )
+    }
+}
+
+impl std::error::Error for UnexpectedStatus {}
+
+// client errors won't go away by retrying, except for rate limiting. server errors,
+// timeouts and connection errors are instead considered transient
+fn is_permanent(error: &anyhow::Error) -> bool {
+    is_source_missing(error)
+        || error.chain().any(|cause| {
+            cause
+                .downcast_ref::<UnexpectedStatus>()
+                .is_some_and(|unexpected| {
+                    unexpected.status.is_client_error()
+                        && unexpected.status != StatusCode::TOO_MANY_REQUESTS
+                })
+        })
+}
+
+async fn execute(http_client: &HttpClient, path: String) -> anyhow::Result<(String, String)> {
+    let waiting_since = Instant::now();
+    rate_limiter::defillama_until_ready().await;
+    // relative paths keep any path prefix in the base url, e.g. an api key. the http
+    // client also waits for the per process rate limiter before returning the request
+    let request = http_client
+        .request(Method::GET, path.trim_start_matches('/'))
+        .await?;
+    metrics::observe_defillama_rate_limiter_wait(waiting_since.elapsed());
+
+    // time spent waiting for the rate limiters isn't defillama's latency
+    let start = Instant::now();
+    let result = send(request, path.clone()).await;
+    let outcome = match result.as_ref() {
+        Ok(_) => "success",
+        Err(error) if is_source_missing(error) => "missing",
+        Err(_) => "error",
+    };
+    metrics::observe_defillama_request(&path, outcome, start.elapsed());
+    result
+}
+
+async fn send(request: RequestBuilder, path: String) -> anyhow::Result<(String, String)> {
+    let (client, request) = request.build_split();
+    let request = request.context(format!("could not build request for {}", path))?;
+    let url = request.url().to_string();
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(error) => {
+            let status = if error.is_timeout() {
+                "timeout"
+            } else {
+                "connection"
+            };
+            metrics::record_defillama_response(&path, status);
+            return Err(anyhow::Error::from(error).context(format!("could not get {}", path)));
+        }
+    };
+    let status = response.status();
+    metrics::record_defillama_response(&path, status.as_str());
+    let body = response
+        .text()
+        .await
+        .context(format!("could not get text response for {}", path))?;
+    if status.is_success() {
+        return Ok((url, body));
+    }
+
+    if status == StatusCode::NOT_FOUND || body.to_lowercase().contains("not found") {
+        return Err(SourceMissing { path }.into());
+    }
+    Err(UnexpectedStatus { status, path, body }.into())
+}
+
+#[derive(Clone)]
+enum Source {
+    Live(Arc<DefiLlamaHttp>),
+    Recording(Arc<DefiLlamaHttp>, Arc<Mutex<Vec<RecordedResponse>>>),
+    Replay(Arc<HashMap<String, String>>),
+}
+
+// typed access to defillama, used by the specification handlers and everything else
+// reading from it. recording clients keep track of every response so that an answer
+// computation can later be deterministically replayed from the archive
+#[derive(Clone)]
+pub struct DefiLlamaClient {
+    source: Source,
+}
+
+impl DefiLlamaClient {
+    pub fn live(http: Arc<DefiLlamaHttp>) -> Self {
+        Self {
+            source: Source::Live(http),
+        }
+    }
+
+    // a live client sending requests for every service to the given mock server
+    #[cfg(test)]
+    pub fn mock(uri: String) -> Self {
+        let http_client = Arc::new(
+            HttpClient::builder(uri, crate::commons::HTTP_TIMEOUT)
+                .build()
+                .unwrap(),
+        );
+        Self::live(Arc::new(DefiLlamaHttp::new(
+            http_client.clone(),
+            http_client.clone(),
+            http_client.clone(),
+            http_client,
+        )))
+    }
+
+    // a client sharing this one's http clients, recording what it fetches
+    pub fn recording(&self) -> Self {
+        let source = match &self.source {
+            Source::Live(http) | Source::Recording(http, _) => {
+                Source::Recording(http.clone(), Arc::new(Mutex::new(Vec::new())))
+            }
+            Source::Replay(responses) => Source::Replay(responses.clone()),
+        };
+        Self { source }
+    }
+
+    pub fn replay(responses: Vec<RecordedResponse>) -> Self {
+        Self {
+            source: Source::Replay(Arc::new(
+                responses
+                    .into_iter()
+                    .map(|response| (response.path, response.body))
+                    .collect(),
+            )),
+        }
+    }
+
+    pub fn recorded_responses(&self) -> Vec<RecordedResponse> {
+        match &self.source {
+            Source::Recording(_, responses) => responses.lock().unwrap().clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub async fn get(&self, service: DefiLlamaService, path: String) -> anyhow::Result<String> {
+        match &self.source {
+            Source::Live(http) => http
+                .fetch(service, path)
+                .await
+                .map(|response| response.body),
+            Source::Recording(http, responses) => {
+                let response = http.fetch(service, path.clone()).await?;
+                responses.lock().unwrap().push(RecordedResponse {
+                    path: service.recorded_path(&path),
+                    url: Some(response.url),
+                    body: response.body.clone(),
+                    timestamp: response.timestamp,
+                });
+                Ok(response.body)
+            }
+            Source::Replay(responses) => {
+                let path = service.recorded_path(&path);
+                responses
+                    .get(&path)
+                    .cloned()
+                    .context(format!("no recorded response for path {}", path))
+            }
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        service: DefiLlamaService,
+        path: String,
+    ) -> anyhow::Result<T> {
+        let raw = self.get(service, path.clone()).await?;
+        serde_json::from_str(&raw).context(format!("could not deserialize response for {}", path))
+    }
+
+    // any response, whatever its status, means that defillama can be reached
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        if let Source::Live(http) | Source::Recording(http, _) = &self.source {
+            let active = http.api.active.load(Ordering::Relaxed);
+            http.api.http_clients[active]
+                .request(Method::GET, "")
+                .await?
+                .send()
+                .await?;
+        }
+        Ok(())
+    }
+
+    // the tvl is returned as the number defillama sent, so that it can be scaled
+    // without going through a lossy numeric type
+    pub async fn protocol_tvl(&self, protocol: &str) -> anyhow::Result<String> {
+        let raw = self
+            .get(DefiLlamaService::Api, format!("/tvl/{protocol}"))
+            .await
+            .context(format!(
+                "could not get current tvl for protocol {}",
+                protocol
+            ))?;
+        Ok(raw.trim().to_owned())
+    }
+
+    pub async fn historical_protocol_tvl(&self, protocol: &str) -> anyhow::Result<Vec<TvlPoint>> {
+        let protocol_response: models::Protocol = self
+            .get_json(DefiLlamaService::Api, format!("/protocol/{protocol}"))
+            .await
+            .context(format!(
+                "could not get historical tvl for protocol {}",
+                protocol
+            ))?;
+        Ok(protocol_response.tvl)
+    }
+
+    pub async fn chains(&self) -> anyhow::Result<Vec<Chain>> {
+        self.get_json(DefiLlamaService::Api, "/v2/chains".to_owned())
+            .await
+            .context("could not get chains")
+    }
+
+    pub async fn protocol_fees(&self, protocol: &str) -> anyhow::Result<FeesSummary> {
+        self.get_json(DefiLlamaService::Api, format!("/summary/fees/{protocol}"))
+            .await
+            .context(format!("could not get fees for protocol {}", protocol))
+    }
+
+    // coins are given as {chain}:{address} or coingecko:{id}
+    pub async fn current_prices(
+        &self,
+        coins: &[String],
+    ) -> anyhow::Result<HashMap<String, CoinPrice>> {
+        let prices: CoinPrices = self
+            .get_json(
+                DefiLlamaService::Coins,
+                format!("/prices/current/{}", coins.join(",")),
+            )
+            .await
+            .context(format!(
+                "could not get current prices for {}",
+                coins.join(",")
+            ))?;
+        Ok(prices.coins)
+    }
+
+    pub async fn stablecoins(&self) -> anyhow::Result<Vec<Stablecoin>> {
+        let stablecoins: Stablecoins = self
+            .get_json(DefiLlamaService::Stablecoins, "/stablecoins".to_owned())
+            .await
+            .context("could not get stablecoins")?;
+        Ok(stablecoins.pegged_assets)
+    }
+
+    pub async fn yield_pools(&self) -> anyhow::Result<Vec<YieldPool>> {
+        let pools: YieldPools = self
+            .get_json(DefiLlamaService::Yields, "/pools".to_owned())
+            .await
+            .context("could not get yield pools")?;
+        Ok(pools.data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use carrot_commons::http_client::HttpClient;
+    use futures_util::future::join_all;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::commons::{DEFILLAMA_RETRY_BUDGET, DEFILLAMA_RETRY_BUDGET_REFILL, HTTP_TIMEOUT};
+
+    use super::{is_source_missing, DefiLlamaClient, DefiLlamaHttp, DefiLlamaService, RetryBudget};
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let defillama_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("1234.5678"))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/prices/current/coingecko:xdai"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "coins": { "coingecko:xdai": { "price": 1.0 } }
+            })))
+            .mount(&defillama_mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/pools"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "success",
+                "data": [{
+                    "pool": "747c1d2a-c668-4682-b9f9-296708a3dd90",
+                    "chain": "Ethereum",
+                    "project": "lido",
+                    "symbol": "STETH",
+                    "tvlUsd": 1.5e10,
+                    "apy": 3.2
+                }]
+            })))
+            .mount(&defillama_mock_server)
+            .await;
+
+        let client = DefiLlamaClient::mock(defillama_mock_server.uri()).recording();
+        assert_eq!(client.protocol_tvl("foo").await.unwrap(), "1234.5678");
+        assert_eq!(
+            client
+                .current_prices(&["coingecko:xdai".to_owned()])
+                .await
+                .unwrap()["coingecko:xdai"]
+                .price,
+            1.0
+        );
+
+        assert_eq!(client.yield_pools().await.unwrap()[0].project, "lido");
+
+        let recorded_responses = client.recorded_responses();
+        assert_eq!(recorded_responses.len(), 3);
+        assert_eq!(recorded_responses[0].path, "/tvl/foo");
+        assert_eq!(
+            recorded_responses[0].url,
+            Some(format!("{}/tvl/foo", defillama_mock_server.uri()))
+        );
+        assert_eq!(recorded_responses[0].body, "1234.5678");
+        assert_eq!(
+            recorded_responses[1].path,
+            "coins:/prices/current/coingecko:xdai"
+        );
+        assert_eq!(recorded_responses[2].path, "yields:/pools");
+
+        // the replay doesn't hit the server anymore
+        defillama_mock_server.reset().await;
+        let replay = DefiLlamaClient::replay(recorded_responses);
+        assert_eq!(replay.protocol_tvl("foo").await.unwrap(), "1234.5678");
+        assert!(replay
+            .current_prices(&["coingecko:xdai".to_owned()])
+            .await
+            .is_ok());
+        assert!(replay.protocol_tvl("bar").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn cache() {
+        let defillama_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("1234.5678"))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/bar"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&defillama_mock_server)
+            .await;
+
+        let http_client = Arc::new(
+            HttpClient::builder(defillama_mock_server.uri(), HTTP_TIMEOUT)
+                .build()
+                .unwrap(),
+        );
+        let client = DefiLlamaClient::live(Arc::new(
+            DefiLlamaHttp::new(
+                http_client.clone(),
+                http_client.clone(),
+                http_client.clone(),
+                http_client,
+            )
+            .with_cache_ttl(Duration::from_millis(500)),
+        ));
+
+        // errors aren't cached, successful responses are until they expire
+        for _ in 0..3 {
+            assert!(client.protocol_tvl("foo").await.is_ok());
+            assert!(client.protocol_tvl("bar").await.is_err());
+        }
+        let requests = defillama_mock_server.received_requests().await.unwrap();
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| request.url.path() == "/tvl/foo")
+                .count(),
+            1
+        );
+        assert_eq!(requests.len(), 4);
+
+        // recording clients share the cache, recording the cached response
+        let recording = client.recording();
+        assert!(recording.protocol_tvl("foo").await.is_ok());
+        assert_eq!(recording.recorded_responses().len(), 1);
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert!(client.protocol_tvl("foo").await.is_ok());
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn mirrors() {
+        let defillama_mock_server = MockServer::start().await;
+        let mirror_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/bar"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/key/tvl/foo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("1234.5678"))
+            .mount(&mirror_mock_server)
+            .await;
+
+        let http_client = |base_url: String| {
+            Arc::new(HttpClient::builder(base_url, HTTP_TIMEOUT).build().unwrap())
+        };
+        let primary = http_client(defillama_mock_server.uri());
+        let client = DefiLlamaClient::live(Arc::new(
+            DefiLlamaHttp::new(primary.clone(), primary.clone(), primary.clone(), primary)
+                .with_mirrors(
+                    DefiLlamaService::Api,
+                    vec![http_client(format!("{}/key/", mirror_mock_server.uri()))],
+                ),
+        ));
+
+        // missing data isn't looked up on mirrors
+        let error = client.protocol_tvl("bar").await.unwrap_err();
+        assert!(is_source_missing(&error));
+        assert!(mirror_mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty());
+
+        // the mirror stays active once the main url fails
+        let recording = client.recording();
+        for _ in 0..2 {
+            assert_eq!(recording.protocol_tvl("foo").await.unwrap(), "1234.5678");
+        }
+        assert_eq!(
+            recording.recorded_responses()[0].url,
+            Some(format!("{}/key/tvl/foo", mirror_mock_server.uri()))
+        );
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            mirror_mock_server.received_requests().await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn retries() {
+        let defillama_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("1234.5678"))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/bar"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&defillama_mock_server)
+            .await;
+
+        let http_client = Arc::new(
+            HttpClient::builder(defillama_mock_server.uri(), HTTP_TIMEOUT)
+                .build()
+                .unwrap(),
+        );
+        let client = DefiLlamaClient::live(Arc::new(
+            DefiLlamaHttp::new(
+                http_client.clone(),
+                http_client.clone(),
+                http_client.clone(),
+                http_client,
+            )
+            .with_retries(Duration::from_secs(5)),
+        ));
+
+        assert_eq!(client.protocol_tvl("foo").await.unwrap(), "1234.5678");
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // client errors are permanent
+        assert!(client.protocol_tvl("bar").await.is_err());
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn retry_budget() {
+        let budget = RetryBudget::new();
+        for _ in 0..DEFILLAMA_RETRY_BUDGET as usize {
+            assert!(budget.withdraw());
+        }
+        assert!(!budget.withdraw());
+
+        // a retry is given back every few successful requests
+        for _ in 0..(1.0 / DEFILLAMA_RETRY_BUDGET_REFILL).round() as usize {
+            budget.deposit();
+        }
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+    }
+
+    #[tokio::test]
+    async fn coalescing() {
+        let defillama_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("1234.5678")
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/bar"))
+            .respond_with(ResponseTemplate::new(404).set_delay(Duration::from_millis(200)))
+            .mount(&defillama_mock_server)
+            .await;
+
+        let client = DefiLlamaClient::mock(defillama_mock_server.uri());
+        let tvls = join_all((0..5).map(|_| client.protocol_tvl("foo"))).await;
+        assert!(tvls.into_iter().all(|tvl| tvl.unwrap() == "1234.5678"));
+        let errors = join_all((0..5).map(|_| client.protocol_tvl("bar"))).await;
+        assert!(errors
+            .into_iter()
+            .all(|error| is_source_missing(&error.unwrap_err())));
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // lookups after the shared one completed send a new request
+        assert!(client.protocol_tvl("foo").await.is_ok());
+        assert_eq!(
+            defillama_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn source_missing() {
+        let defillama_mock_server = MockServer::start().await;
+        let client = DefiLlamaClient::mock(defillama_mock_server.uri());
+        Mock::given(method("GET"))
+            .and(path("/tvl/foo"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/bar"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Protocol not found"))
+            .mount(&defillama_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/tvl/baz"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&defillama_mock_server)
+            .await;
+
+        for (protocol, missing) in [("foo", true), ("bar", true), ("baz", false)] {
+            let error = client
+                .get(DefiLlamaService::Api, format!("/tvl/{protocol}"))
+                .await
+                .unwrap_err();
+            assert_eq!(is_source_missing(&error), missing);
+        }
+    }
+}
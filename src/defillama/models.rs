@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+// only the fields the answerer cares about are modeled, defillama returns a lot more
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TvlPoint {
+    pub date: u64,
+    #[serde(rename = "totalLiquidityUSD")]
+    pub total_liquidity_usd: f64,
+}
+
+// returned by /protocol/{protocol}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Protocol {
+    #[serde(default)]
+    pub name: String,
+    pub tvl: Vec<TvlPoint>,
+}
+
+// returned by /v2/chains
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Chain {
+    pub name: String,
+    pub tvl: f64,
+    pub token_symbol: Option<String>,
+    pub chain_id: Option<u64>,
+    #[serde(rename = "gecko_id")]
+    pub gecko_id: Option<String>,
+}
+
+// returned by /summary/fees/{protocol}, the chart holds daily (timestamp, fees) pairs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeesSummary {
+    #[serde(default)]
+    pub name: String,
+    pub total24h: Option<f64>,
+    pub total7d: Option<f64>,
+    pub total30d: Option<f64>,
+    pub total_all_time: Option<f64>,
+    #[serde(default)]
+    pub total_data_chart: Vec<(u64, f64)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CoinPrice {
+    pub price: f64,
+    pub symbol: Option<String>,
+    pub timestamp: Option<u64>,
+    pub confidence: Option<f64>,
+    pub decimals: Option<u8>,
+}
+
+// returned by the coins service's /prices/current/{coins}, keyed by coin
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CoinPrices {
+    pub coins: HashMap<String, CoinPrice>,
+}
+
+// circulating amounts are keyed by peg, e.g. peggedUSD
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Stablecoin {
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    pub peg_type: String,
+    #[serde(default)]
+    pub circulating: HashMap<String, f64>,
+}
+
+// returned by the stablecoins service's /stablecoins
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Stablecoins {
+    pub pegged_assets: Vec<Stablecoin>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct YieldPool {
+    pub pool: String,
+    pub chain: String,
+    pub project: String,
+    pub symbol: String,
+    pub tvl_usd: f64,
+    pub apy: Option<f64>,
+}
+
+// returned by the yields service's /pools
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct YieldPools {
+    pub data: Vec<YieldPool>,
+}
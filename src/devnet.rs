@@ -0,0 +1,349 @@
+pub mod mocks;
+
+use std::{
+    process::Stdio,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use carrot_commons::http_client::HttpClient;
+use ethers::{
+    abi::{self, AbiEncode, Token},
+    contract::{EthCall, EthEvent},
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, TransactionRequest, U256},
+};
+use reqwest::{header::CONTENT_TYPE, Method};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{
+    process::{Child, Command},
+    time::{sleep, Instant},
+};
+
+use crate::{
+    commons::{ChainConfig, DevnetConfig, HTTP_TIMEOUT},
+    contracts::{
+        defi_llama_oracle::{
+            AnswererCall, AnswererReturn, FinalizeCall, FinalizedCall, FinalizedReturn,
+            KpiTokenCall, KpiTokenReturn, MeasurementTimestampCall, MeasurementTimestampReturn,
+            SpecificationCall, SpecificationReturn, TemplateCall, TemplateReturn,
+        },
+        factory::CreateTokenFilter,
+        kpi_token::{ExpirationCall, ExpirationReturn, OraclesCall, OraclesReturn},
+        shared_types::Template,
+    },
+    specification::{handlers::tvl::TvlPayload, Specification},
+};
+
+use self::mocks::{
+    emitter_runtime, stub_flag_storage, stub_return_storage, MULTICALL_RUNTIME, STUB_RUNTIME,
+};
+
+const DEFAULT_ANVIL_PATH: &str = "anvil";
+const DEFAULT_PORT: u16 = 8545;
+const DEFAULT_CHAIN_ID: u64 = 31337;
+const DEFAULT_BLOCK_TIME_SECONDS: u64 = 1;
+const DEFAULT_DEMO_PROTOCOL: &str = "uniswap";
+const DEFAULT_DEMO_MEASUREMENT_DELAY: Duration = Duration::from_secs(120);
+const ANVIL_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+// the demo token is created once the chain's scanners are watching new blocks
+const DEMO_ORACLE_CREATION_DELAY: Duration = Duration::from_secs(15);
+const DEMO_KPI_TOKEN_DURATION: Duration = Duration::from_secs(86_400);
+const DEMO_TEMPLATE_ID: u64 = 1;
+
+// first account of anvil's default mnemonic, funded at genesis
+const ANVIL_PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcad997c52c4ce3ff80";
+
+// the factory and multicall mocks are placed at fixed addresses, while every demo
+// token and oracle gets fresh ones so that they're not mistaken for the ones created
+// by a previous run, which are still in the database
+const FACTORY_ADDRESS: u64 = 0xfac7;
+const MULTICALL_ADDRESS: u64 = 0xca11;
+
+#[derive(Deserialize)]
+struct IpfsAddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+// a local anvil node with mock carrot contracts, killed when dropped
+pub struct Devnet {
+    _anvil: Child,
+    chain_id: u64,
+    rpc_endpoint: String,
+    provider: Provider<Http>,
+    account: Address,
+    ipfs_api_http_client: HttpClient,
+    demo_protocol: String,
+    demo_measurement_delay: Duration,
+}
+
+impl Devnet {
+    pub async fn start(config: DevnetConfig) -> anyhow::Result<Self> {
+        let port = config.port.unwrap_or(DEFAULT_PORT);
+        let chain_id = config.chain_id.unwrap_or(DEFAULT_CHAIN_ID);
+        let anvil_path = config
+            .anvil_path
+            .unwrap_or_else(|| DEFAULT_ANVIL_PATH.to_owned());
+        tracing::info!("starting anvil on port {} with chain id {}", port, chain_id);
+        // answers are submitted from the impersonated expected answerer in dev mode
+        let anvil = Command::new(&anvil_path)
+            .arg("--port")
+            .arg(port.to_string())
+            .arg("--chain-id")
+            .arg(chain_id.to_string())
+            .arg("--block-time")
+            .arg(
+                config
+                    .block_time_seconds
+                    .unwrap_or(DEFAULT_BLOCK_TIME_SECONDS)
+                    .to_string(),
+            )
+            .arg("--auto-impersonate")
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context(format!("could not start anvil at {}", anvil_path))?;
+
+        let rpc_endpoint = format!("http://127.0.0.1:{}", port);
+        let provider = Provider::<Http>::try_from(rpc_endpoint.as_str())
+            .context("could not create anvil provider")?;
+        let started_at = Instant::now();
+        loop {
+            match provider.get_chainid().await {
+                Ok(node_chain_id) if node_chain_id.as_u64() == chain_id => break,
+                Ok(node_chain_id) => {
+                    anyhow::bail!(
+                        "the node on port {} has chain id {}, is another one already running?",
+                        port,
+                        node_chain_id
+                    );
+                }
+                Err(error) if started_at.elapsed() > ANVIL_STARTUP_TIMEOUT => {
+                    return Err(error).context("anvil didn't start in time");
+                }
+                Err(_) => sleep(Duration::from_millis(200)).await,
+            }
+        }
+
+        let account = LocalWallet::from_str(ANVIL_PRIVATE_KEY)?.address();
+        let ipfs_api_http_client =
+            HttpClient::builder(config.ipfs_api_endpoint, HTTP_TIMEOUT).build()?;
+        let devnet = Self {
+            _anvil: anvil,
+            chain_id,
+            rpc_endpoint,
+            provider,
+            account,
+            ipfs_api_http_client,
+            demo_protocol: config
+                .demo_protocol
+                .unwrap_or_else(|| DEFAULT_DEMO_PROTOCOL.to_owned()),
+            demo_measurement_delay: config
+                .demo_measurement_delay_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_DEMO_MEASUREMENT_DELAY),
+        };
+
+        devnet
+            .set_code(
+                Address::from_low_u64_be(FACTORY_ADDRESS),
+                emitter_runtime(CreateTokenFilter::signature()),
+            )
+            .await
+            .context("could not deploy the mock factory")?;
+        devnet
+            .set_code(
+                Address::from_low_u64_be(MULTICALL_ADDRESS),
+                Bytes::from_str(MULTICALL_RUNTIME)?,
+            )
+            .await
+            .context("could not deploy the mock multicall")?;
+        tracing::info!("anvil devnet running at {}", devnet.rpc_endpoint);
+
+        Ok(devnet)
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    // the factory is "deployed" at genesis, and the anvil account answers
+    pub fn chain_config(&self) -> anyhow::Result<ChainConfig> {
+        serde_json::from_value(json!({
+            "preset": false,
+            "answerer_private_key": ANVIL_PRIVATE_KEY,
+            "rpc_endpoint": self.rpc_endpoint,
+            "logs_polling_interval_seconds": 1,
+            "multicall_address": Address::from_low_u64_be(MULTICALL_ADDRESS),
+            "template_id": DEMO_TEMPLATE_ID,
+            "factory": {
+                "address": Address::from_low_u64_be(FACTORY_ADDRESS),
+                "deployment_block": 0,
+            },
+        }))
+        .context("could not build the devnet chain config")
+    }
+
+    // deploys a kpi token with a single oracle measuring the demo protocol's tvl and
+    // emits its creation log from the factory, as the real factory would
+    pub async fn create_demo_oracle(self: Arc<Self>) -> anyhow::Result<()> {
+        sleep(DEMO_ORACLE_CREATION_DELAY).await;
+
+        let specification = Specification::Tvl(TvlPayload {
+            protocol: self.demo_protocol.clone(),
+            fallback: None,
+        });
+        let specification_cid = self
+            .add_to_ipfs(serde_json::to_vec(&specification)?)
+            .await
+            .context("could not add the demo specification to ipfs")?;
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        let kpi_token = Address::random();
+        let oracle = Address::random();
+        self.deploy_stub(
+            kpi_token,
+            vec![
+                (
+                    OraclesCall::selector(),
+                    OraclesReturn(vec![oracle]).encode(),
+                ),
+                (
+                    ExpirationCall::selector(),
+                    ExpirationReturn(U256::from((now + DEMO_KPI_TOKEN_DURATION).as_secs()))
+                        .encode(),
+                ),
+            ],
+            None,
+        )
+        .await
+        .context("could not deploy the demo kpi token")?;
+        self.deploy_stub(
+            oracle,
+            vec![
+                (FinalizedCall::selector(), FinalizedReturn(false).encode()),
+                (
+                    TemplateCall::selector(),
+                    TemplateReturn(Template {
+                        addrezz: Address::zero(),
+                        version: 1,
+                        id: U256::from(DEMO_TEMPLATE_ID),
+                        specification: String::new(),
+                    })
+                    .encode(),
+                ),
+                (
+                    SpecificationCall::selector(),
+                    SpecificationReturn(specification_cid.clone()).encode(),
+                ),
+                (
+                    MeasurementTimestampCall::selector(),
+                    MeasurementTimestampReturn(U256::from(
+                        (now + self.demo_measurement_delay).as_secs(),
+                    ))
+                    .encode(),
+                ),
+                (
+                    AnswererCall::selector(),
+                    AnswererReturn(self.account).encode(),
+                ),
+                (KpiTokenCall::selector(), KpiTokenReturn(kpi_token).encode()),
+            ],
+            Some((FinalizeCall::selector(), FinalizedCall::selector())),
+        )
+        .await
+        .context("could not deploy the demo oracle")?;
+
+        let mut data = vec![0u8; 4];
+        data.extend(abi::encode(&[Token::Address(kpi_token)]));
+        let receipt = self
+            .provider
+            .send_transaction(
+                TransactionRequest::new()
+                    .from(self.account)
+                    .to(Address::from_low_u64_be(FACTORY_ADDRESS))
+                    .data(data),
+                None,
+            )
+            .await
+            .context("could not send the demo kpi token creation transaction")?
+            .await
+            .context("could not get the demo kpi token creation receipt")?
+            .context("the demo kpi token creation transaction was dropped")?;
+
+        tracing::info!(
+            "created demo oracle 0x{:x} measuring the tvl of {} in {}s, specification {}, block {}",
+            oracle,
+            self.demo_protocol,
+            self.demo_measurement_delay.as_secs(),
+            specification_cid,
+            receipt.block_number.unwrap_or_default()
+        );
+
+        Ok(())
+    }
+
+    async fn deploy_stub(
+        &self,
+        address: Address,
+        outputs: Vec<([u8; 4], Vec<u8>)>,
+        flag: Option<([u8; 4], [u8; 4])>,
+    ) -> anyhow::Result<()> {
+        self.set_code(address, Bytes::from_str(STUB_RUNTIME)?)
+            .await?;
+        let mut storage = Vec::new();
+        for (selector, output) in outputs.iter() {
+            storage.extend(stub_return_storage(*selector, output));
+        }
+        if let Some((trigger_selector, flag_selector)) = flag {
+            storage.push(stub_flag_storage(trigger_selector, flag_selector));
+        }
+        for (slot, value) in storage.into_iter() {
+            self.provider
+                .request::<_, bool>("anvil_setStorageAt", (address, slot, value))
+                .await
+                .context(format!("could not set storage of 0x{:x}", address))?;
+        }
+        Ok(())
+    }
+
+    async fn set_code(&self, address: Address, code: Bytes) -> anyhow::Result<()> {
+        self.provider
+            .request::<_, ()>("anvil_setCode", (address, code))
+            .await
+            .context(format!("could not set code of 0x{:x}", address))
+    }
+
+    // adds the content through the kubo rpc api, which only accepts multipart uploads
+    async fn add_to_ipfs(&self, content: Vec<u8>) -> anyhow::Result<String> {
+        let boundary = "defillama-answerer-devnet";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"specification.json\"\r\nContent-Type: application/json\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend(content);
+        body.extend(format!("\r\n--{boundary}--\r\n").into_bytes());
+
+        let response = self
+            .ipfs_api_http_client
+            .request(Method::POST, "/api/v0/add")
+            .await?
+            .query(&[("cid-version", "1")])
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<IpfsAddResponse>()
+            .await?;
+        Ok(response.hash)
+    }
+}
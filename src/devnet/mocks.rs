@@ -0,0 +1,90 @@
+use ethers::types::{Bytes, H256, U256};
+
+// the mocks are hand assembled so that no solidity toolchain is needed to run the
+// devnet. none of them checks its caller, they're only meant for a local anvil node
+
+// returns, for the called selector, the abi encoded output stored with
+// `stub_return_storage`. calls to selectors without a stored output succeed without
+// returning anything, and set the output configured with `stub_flag_storage` to true.
+// storage is laid out per selector, with the output's length at `selector << 32` and
+// its words right after it
+pub const STUB_RUNTIME: &str = "60003560e01c60201b805480156100345760005b8181101561002f578060051c8301600101548152602001610013565b506000f35b5063ffffffff0154801561004e5760201b60010160019055005b00";
+
+// implements multicall3's `aggregate3`, reverting when a call that isn't allowed to
+// fail does
+pub const MULTICALL_RUNTIME: &str = "6004356004018035602052602001600052602060805260205160a05260205160051b60c0016060525b60205160405110156100d5576000518060405160051b0135018060400135810180358082602001606051606001376000600082606051606001600087355af1808460200135176100785760006000fd5b606051526040606051602001525050503d601f01601f191660008160605160400101523d60006060516060013e3d6060516040015260c06060510360405160051b60c0015260605101606001606052604051600101604052610028565b6080606051036080f3";
+
+// emits a log with the given topic on any call, using the first calldata word after
+// the selector as the log's data
+pub fn emitter_runtime(topic: H256) -> Bytes {
+    let mut runtime = vec![0x60, 0x20, 0x60, 0x04, 0x60, 0x00, 0x37, 0x7f];
+    runtime.extend_from_slice(topic.as_bytes());
+    runtime.extend_from_slice(&[0x60, 0x20, 0x60, 0x00, 0xa1, 0x00]);
+    runtime.into()
+}
+
+fn selector_slot(selector: [u8; 4]) -> U256 {
+    U256::from(u32::from_be_bytes(selector)) << 32
+}
+
+// storage slots and values making a stub return the given abi encoded output
+pub fn stub_return_storage(selector: [u8; 4], output: &[u8]) -> Vec<(U256, H256)> {
+    let base_slot = selector_slot(selector);
+    let mut storage = vec![(base_slot, H256::from_low_u64_be(output.len() as u64))];
+    for (index, word) in output.chunks(32).enumerate() {
+        let mut value = [0u8; 32];
+        value[..word.len()].copy_from_slice(word);
+        storage.push((base_slot + index + 1, H256(value)));
+    }
+    storage
+}
+
+// storage slot and value making a call to the trigger selector set the flag
+// selector's output to true
+pub fn stub_flag_storage(trigger_selector: [u8; 4], flag_selector: [u8; 4]) -> (U256, H256) {
+    (
+        selector_slot(trigger_selector) + u32::MAX,
+        H256::from_low_u64_be(u32::from_be_bytes(flag_selector) as u64),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::{
+        abi::AbiEncode,
+        types::{H256, U256},
+    };
+
+    use super::{emitter_runtime, stub_flag_storage, stub_return_storage};
+
+    #[test]
+    fn stub_storage() {
+        let output = "bafkreia".repeat(5).encode();
+        let storage = stub_return_storage([0x53, 0x10, 0x09, 0x33], &output);
+        let base_slot = U256::from(0x53100933u64) << 32;
+        assert_eq!(storage.len(), 1 + output.len() / 32);
+        assert_eq!(
+            storage[0],
+            (base_slot, H256::from_low_u64_be(output.len() as u64))
+        );
+        for (index, (slot, value)) in storage.into_iter().skip(1).enumerate() {
+            assert_eq!(slot, base_slot + index + 1);
+            assert_eq!(value.as_bytes(), &output[index * 32..(index + 1) * 32]);
+        }
+
+        assert_eq!(
+            stub_flag_storage([0x05, 0x26, 0x1a, 0xea], [0xb3, 0xf0, 0x5b, 0x97]),
+            (
+                (U256::from(0x05261aeau64) << 32) + u32::MAX,
+                H256::from_low_u64_be(0xb3f05b97)
+            )
+        );
+    }
+
+    #[test]
+    fn emitter() {
+        let runtime = emitter_runtime(H256::repeat_byte(0xab));
+        assert_eq!(runtime.len(), 46);
+        assert_eq!(&runtime[8..40], H256::repeat_byte(0xab).as_bytes());
+    }
+}
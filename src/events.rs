@@ -0,0 +1,118 @@
+use std::{
+    sync::LazyLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::types::Address;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+use crate::commons::EVENTS_CHANNEL_CAPACITY;
+
+// every oracle lifecycle event goes through here, and whoever is interested (e.g.
+// webhooks) subscribes to it. events are dropped when nobody is listening
+static EVENTS: LazyLock<broadcast::Sender<OracleEvent>> =
+    LazyLock::new(|| broadcast::channel(EVENTS_CHANNEL_CAPACITY).0);
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum OracleEventKind {
+    Detected,
+    #[serde(rename_all = "camelCase")]
+    AnswerComputed {
+        answer: String,
+        defillama_snapshot_id: Option<i64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    TxSubmitted {
+        tx_hash: String,
+    },
+    // the tx hash is missing when the oracle was finalized by someone else
+    #[serde(rename_all = "camelCase")]
+    Finalized {
+        tx_hash: Option<String>,
+    },
+    Failed {
+        reason: String,
+    },
+    Expired,
+    Removed {
+        reason: String,
+    },
+}
+
+impl OracleEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OracleEventKind::Detected => "detected",
+            OracleEventKind::AnswerComputed { .. } => "answerComputed",
+            OracleEventKind::TxSubmitted { .. } => "txSubmitted",
+            OracleEventKind::Finalized { .. } => "finalized",
+            OracleEventKind::Failed { .. } => "failed",
+            OracleEventKind::Expired => "expired",
+            OracleEventKind::Removed { .. } => "removed",
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OracleEvent {
+    pub chain_id: u64,
+    pub oracle_address: String,
+    pub timestamp: u64,
+    #[serde(flatten)]
+    pub kind: OracleEventKind,
+}
+
+pub fn emit(chain_id: u64, oracle_address: Address, kind: OracleEventKind) {
+    let event = OracleEvent {
+        chain_id,
+        oracle_address: format!("0x{:x}", oracle_address),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default(),
+        kind,
+    };
+    // sending only fails when there are no subscribers
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<OracleEvent> {
+    EVENTS.subscribe()
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::Address;
+
+    use super::{emit, subscribe, OracleEventKind};
+
+    #[tokio::test]
+    async fn emit_and_subscribe() {
+        // no subscribers yet, the event is dropped
+        emit(1, Address::zero(), OracleEventKind::Detected);
+
+        let mut events = subscribe();
+        emit(
+            100,
+            Address::zero(),
+            OracleEventKind::TxSubmitted {
+                tx_hash: "0x01".to_owned(),
+            },
+        );
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.chain_id, 100);
+        assert_eq!(event.kind.name(), "txSubmitted");
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "txSubmitted");
+        assert_eq!(json["txHash"], "0x01");
+        assert_eq!(
+            json["oracleAddress"],
+            "0x0000000000000000000000000000000000000000"
+        );
+    }
+}
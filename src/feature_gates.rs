@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::db::{models, DbConnection};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    AnswerRetryBackoff,
+    ScheduledAnswering,
+}
+
+impl Feature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feature::AnswerRetryBackoff => "answer_retry_backoff",
+            Feature::ScheduledAnswering => "scheduled_answering",
+        }
+    }
+
+    // whether the feature is active on chains without an explicit gate. features
+    // enabled by default can be rolled back per chain, while features disabled by
+    // default can be rolled out gradually
+    pub fn default_enabled(&self) -> bool {
+        match self {
+            Feature::AnswerRetryBackoff => true,
+            Feature::ScheduledAnswering => true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FeatureGates {
+    gates: HashMap<String, bool>,
+}
+
+impl FeatureGates {
+    pub fn load(connection: &mut DbConnection, chain_id: u64) -> anyhow::Result<Self> {
+        Ok(models::FeatureGate::get_all_for_chain_id(connection, chain_id)?.into())
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.gates
+            .get(feature.as_str())
+            .copied()
+            .unwrap_or_else(|| feature.default_enabled())
+    }
+}
+
+impl From<Vec<models::FeatureGate>> for FeatureGates {
+    fn from(feature_gates: Vec<models::FeatureGate>) -> Self {
+        Self {
+            gates: feature_gates
+                .into_iter()
+                .map(|feature_gate| (feature_gate.feature, feature_gate.enabled))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::db::models;
+
+    use super::{Feature, FeatureGates};
+
+    #[test]
+    fn is_enabled() {
+        // no gates, defaults apply
+        let feature_gates = FeatureGates::default();
+        assert_eq!(
+            feature_gates.is_enabled(Feature::AnswerRetryBackoff),
+            Feature::AnswerRetryBackoff.default_enabled()
+        );
+
+        // explicit gates override the defaults
+        for enabled in [true, false] {
+            let feature_gates = FeatureGates::from(vec![models::FeatureGate {
+                chain_id: 100,
+                feature: Feature::AnswerRetryBackoff.as_str().to_owned(),
+                enabled,
+            }]);
+            assert_eq!(
+                feature_gates.is_enabled(Feature::AnswerRetryBackoff),
+                enabled
+            );
+        }
+
+        // unknown gates are ignored
+        let feature_gates = FeatureGates::from(vec![models::FeatureGate {
+            chain_id: 100,
+            feature: "foo".to_owned(),
+            enabled: false,
+        }]);
+        assert_eq!(
+            feature_gates.is_enabled(Feature::AnswerRetryBackoff),
+            Feature::AnswerRetryBackoff.default_enabled()
+        );
+    }
+}
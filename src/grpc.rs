@@ -0,0 +1,287 @@
+pub mod proto {
+    tonic::include_proto!("answerer.v1");
+}
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use diesel::r2d2::{ConnectionManager, Pool};
+use ethers::types::Address;
+use tonic::{metadata::MetadataMap, transport::Server, Request, Response, Status};
+
+use crate::{
+    chains::Chains,
+    commons::{GrpcConfig, GRPC_DEFAULT_PAGE_SIZE, GRPC_MAX_PAGE_SIZE},
+    db::{
+        self,
+        models::{self, RecordFilter},
+        DbConnection,
+    },
+    defillama::DefiLlamaClient,
+    specification::{self, handlers::tvl::TvlPayload, Specification},
+};
+
+use self::proto::{
+    answerer_server::{Answerer, AnswererServer},
+    specification::Metric,
+    ListOraclesRequest, ListOraclesResponse, PreviewAnswerRequest, PreviewAnswerResponse,
+    TriggerAnswerRequest, TriggerAnswerResponse, ValidateSpecificationRequest,
+    ValidateSpecificationResponse,
+};
+
+impl TryFrom<Option<proto::Specification>> for Specification {
+    type Error = Status;
+
+    fn try_from(specification: Option<proto::Specification>) -> Result<Self, Self::Error> {
+        match specification.and_then(|specification| specification.metric) {
+            Some(Metric::Tvl(payload)) => Ok(Specification::Tvl(TvlPayload {
+                protocol: payload.protocol,
+                fallback: payload.fallback,
+            })),
+            None => Err(Status::invalid_argument("missing specification metric")),
+        }
+    }
+}
+
+impl From<Specification> for proto::Specification {
+    fn from(specification: Specification) -> Self {
+        let metric = match specification {
+            Specification::Tvl(payload) => Metric::Tvl(proto::TvlPayload {
+                protocol: payload.protocol,
+                fallback: payload.fallback,
+            }),
+        };
+        Self {
+            metric: Some(metric),
+        }
+    }
+}
+
+fn to_unix_timestamp(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+impl From<models::ActiveOracle> for proto::Oracle {
+    fn from(oracle: models::ActiveOracle) -> Self {
+        Self {
+            chain_id: oracle.chain_id as u64,
+            address: format!("0x{:x}", oracle.address.0),
+            specification: Some(oracle.specification.into()),
+            measurement_timestamp: to_unix_timestamp(*oracle.measurement_timestamp),
+            expiration: oracle
+                .expiration
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+            answer: oracle.answer.map(|answer| answer.0.to_string()),
+            answer_tx_hash: oracle
+                .answer_tx_hash
+                .map(|answer_tx_hash| format!("0x{:x}", answer_tx_hash.0)),
+            answer_attempts: u32::try_from(oracle.answer_attempts).unwrap_or_default(),
+            next_answer_attempt: oracle
+                .next_answer_attempt
+                .map(|timestamp| to_unix_timestamp(*timestamp)),
+        }
+    }
+}
+
+pub struct AnswererService {
+    // operator names mapped to their api keys, shared with the http api
+    operators: HashMap<String, String>,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+}
+
+impl AnswererService {
+    // resolves the operator name associated with the api key passed as a bearer token
+    fn operator(&self, metadata: &MetadataMap) -> Option<String> {
+        let api_key = metadata
+            .get("authorization")?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")?;
+        self.operators
+            .iter()
+            .find(|(_, operator_api_key)| operator_api_key.as_str() == api_key)
+            .map(|(operator, _)| operator.clone())
+    }
+}
+
+#[tonic::async_trait]
+impl Answerer for AnswererService {
+    async fn validate_specification(
+        &self,
+        request: Request<ValidateSpecificationRequest>,
+    ) -> Result<Response<ValidateSpecificationResponse>, Status> {
+        let specification = Specification::try_from(request.into_inner().specification)?;
+        let valid = specification::validate(&specification, self.defillama.clone()).await;
+        Ok(Response::new(ValidateSpecificationResponse { valid }))
+    }
+
+    async fn preview_answer(
+        &self,
+        request: Request<PreviewAnswerRequest>,
+    ) -> Result<Response<PreviewAnswerResponse>, Status> {
+        let specification = Specification::try_from(request.into_inner().specification)?;
+        if !specification::validate(&specification, self.defillama.clone()).await {
+            return Err(Status::invalid_argument("invalid specification"));
+        }
+        let answer = specification::answer(&specification, &self.defillama.recording(), None)
+            .await
+            .map_err(|error| {
+                tracing::error!("could not preview answer: {:#}", error);
+                Status::unavailable("could not compute answer")
+            })?;
+        Ok(Response::new(PreviewAnswerResponse {
+            answer: answer.map(|answer| answer.to_string()),
+        }))
+    }
+
+    async fn list_oracles(
+        &self,
+        request: Request<ListOraclesRequest>,
+    ) -> Result<Response<ListOraclesResponse>, Status> {
+        let request = request.into_inner();
+        let limit = request.limit.unwrap_or(GRPC_DEFAULT_PAGE_SIZE);
+        if !(1..=GRPC_MAX_PAGE_SIZE).contains(&limit) {
+            return Err(Status::invalid_argument(format!(
+                "limit must be between 1 and {}",
+                GRPC_MAX_PAGE_SIZE
+            )));
+        }
+        let filter = RecordFilter {
+            chain_id: request.chain_id,
+            limit: i64::from(limit),
+            offset: i64::try_from(request.offset)
+                .map_err(|_| Status::invalid_argument("offset is too big"))?,
+            ..Default::default()
+        };
+
+        let oracles = db::blocking(|| {
+            let mut db_connection = self
+                .db_connection_pool
+                .get()
+                .context("could not get new connection from pool")?;
+            models::ActiveOracle::get_all_filtered(&mut db_connection, &filter)
+        })
+        .map_err(|error| {
+            tracing::error!("could not list oracles: {:#}", error);
+            Status::internal("could not list oracles")
+        })?;
+        Ok(Response::new(ListOraclesResponse {
+            oracles: oracles.into_iter().map(proto::Oracle::from).collect(),
+        }))
+    }
+
+    async fn trigger_answer(
+        &self,
+        request: Request<TriggerAnswerRequest>,
+    ) -> Result<Response<TriggerAnswerResponse>, Status> {
+        let operator = self
+            .operator(request.metadata())
+            .ok_or_else(|| Status::unauthenticated("invalid or missing api key"))?;
+        let request = request.into_inner();
+        let chain_id = request.chain_id;
+        if !self.chains.contains(chain_id) {
+            return Err(Status::not_found(format!(
+                "chain {} is not running",
+                chain_id
+            )));
+        }
+
+        if let Some(oracle_address) = request.oracle_address {
+            let address = Address::from_str(oracle_address.as_str())
+                .map_err(|_| Status::invalid_argument("invalid oracle address"))?;
+            let found = db::blocking(|| {
+                let mut db_connection = self
+                    .db_connection_pool
+                    .get()
+                    .context("could not get new connection from pool")?;
+                match models::ActiveOracle::get(&mut db_connection, chain_id, address)? {
+                    Some(mut active_oracle) => {
+                        active_oracle.clear_answer_retry(&mut db_connection)?;
+                        anyhow::Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            })
+            .map_err(|error| {
+                tracing::error!("{:#}", error);
+                Status::internal("could not clear the oracle's answer retry")
+            })?;
+            if !found {
+                return Err(Status::not_found(format!(
+                    "no active oracle 0x{:x} on chain {}",
+                    address, chain_id
+                )));
+            }
+        }
+
+        // the chain might have been removed in the meantime
+        if !self.chains.trigger_answering(chain_id) {
+            return Err(Status::not_found(format!(
+                "chain {} is not running",
+                chain_id
+            )));
+        }
+        tracing::info!(
+            "operator {} triggered an answering run on chain {}",
+            operator,
+            chain_id
+        );
+        Ok(Response::new(TriggerAnswerResponse {}))
+    }
+}
+
+pub async fn serve(
+    config: GrpcConfig,
+    operators: HashMap<String, String>,
+    chains: Arc<Chains>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    defillama: DefiLlamaClient,
+) -> anyhow::Result<()> {
+    let address = SocketAddr::from((config.host, config.port));
+    tracing::info!("serving grpc on {}", address);
+    Server::builder()
+        .add_service(AnswererServer::new(AnswererService {
+            operators,
+            chains,
+            db_connection_pool,
+            defillama,
+        }))
+        .serve(address)
+        .await
+        .context(format!("could not serve grpc on {}", address))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::specification::{handlers::tvl::TvlPayload, Specification};
+
+    use super::proto;
+
+    #[test]
+    fn convert_specification() {
+        let specification = Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: Some("10".to_owned()),
+        });
+        let converted: proto::Specification = specification.clone().into();
+        assert_eq!(
+            Specification::try_from(Some(converted)).unwrap(),
+            specification
+        );
+
+        // a specification without a metric can't be converted
+        assert!(Specification::try_from(Some(proto::Specification { metric: None })).is_err());
+        assert!(Specification::try_from(None).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use reqwest::Client;
+use tokio::{sync::Notify, time::sleep};
+
+use crate::commons::HTTP_TIMEOUT;
+
+// signals that a loop completed an iteration successfully. beats are coalesced, so
+// the healthcheck is pinged at most once per interval however often the loop runs,
+// and a stalled loop shows up as missing pings even if the process is alive
+#[derive(Clone, Default)]
+pub struct Heartbeat {
+    beats: Option<Arc<Notify>>,
+}
+
+impl Heartbeat {
+    // returns the heartbeat together with the task pinging the url on its beats
+    pub fn new(
+        url: String,
+        min_interval: Duration,
+    ) -> anyhow::Result<(Self, impl Future<Output = anyhow::Result<()>>)> {
+        let client = Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .context("could not build heartbeat http client")?;
+        let beats = Arc::new(Notify::new());
+        let heartbeat = Self {
+            beats: Some(beats.clone()),
+        };
+        Ok((heartbeat, ping_on_beats(client, url, min_interval, beats)))
+    }
+
+    // a heartbeat that isn't configured doesn't do anything
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn beat(&self) {
+        if let Some(beats) = self.beats.as_ref() {
+            beats.notify_one();
+        }
+    }
+}
+
+async fn ping_on_beats(
+    client: Client,
+    url: String,
+    min_interval: Duration,
+    beats: Arc<Notify>,
+) -> anyhow::Result<()> {
+    loop {
+        beats.notified().await;
+        // a failed ping is not retried, as the next beat pings again anyway
+        if let Err(error) = client
+            .get(url.as_str())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| error.without_url())
+        {
+            tracing::warn!("could not ping heartbeat url: {:#}", error);
+        }
+        sleep(min_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tokio::time::sleep;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::Heartbeat;
+
+    #[tokio::test]
+    async fn coalesce_beats() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ping/foo"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let (heartbeat, pinger) = Heartbeat::new(
+            format!("{}/ping/foo", mock_server.uri()),
+            Duration::from_millis(500),
+        )
+        .unwrap();
+        let pinger = tokio::spawn(pinger);
+        for _ in 0..5 {
+            heartbeat.beat();
+        }
+        sleep(Duration::from_millis(100)).await;
+        // beats during the interval result in a single ping once it's over
+        heartbeat.beat();
+        heartbeat.beat();
+        sleep(Duration::from_millis(800)).await;
+        pinger.abort();
+
+        Heartbeat::disabled().beat();
+    }
+}
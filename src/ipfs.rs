@@ -0,0 +1,643 @@
+use std::{
+    fmt::{self, Display},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use backoff::{future::retry, ExponentialBackoff};
+use carrot_commons::http_client::HttpClient;
+use futures_util::future::select_ok;
+use reqwest::{
+    multipart::{Form, Part},
+    Method, Response,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::time::timeout;
+
+use crate::commons::{IPFS_FETCH_TIMEOUT, IPFS_MAX_DOCUMENT_SIZE};
+
+use self::trustless::{decode_block, Cid};
+
+mod trustless;
+
+// returned when a document could be fetched but isn't what was expected. documents
+// are immutable, so fetching them again wouldn't help
+#[derive(Debug)]
+pub struct InvalidDocument {
+    pub cid: String,
+}
+
+impl Display for InvalidDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid document with cid {}", self.cid)
+    }
+}
+
+impl std::error::Error for InvalidDocument {}
+
+pub fn is_invalid_document(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<InvalidDocument>().is_some()
+}
+
+#[derive(Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+// fetches documents by cid from the data cdn, falling back to racing all the
+// configured ipfs gateways against each other, so that a single gateway's outage
+// doesn't make new oracles invisible
+pub struct IpfsFetcher {
+    data_cdn: Arc<HttpClient>,
+    gateways: Vec<Arc<HttpClient>>,
+    pinning_node: Option<Arc<HttpClient>>,
+    fetch_timeout: Duration,
+    max_document_size: usize,
+    trustless: bool,
+}
+
+impl IpfsFetcher {
+    pub fn new(data_cdn: Arc<HttpClient>, gateways: Vec<Arc<HttpClient>>) -> Self {
+        Self {
+            data_cdn,
+            gateways,
+            pinning_node: None,
+            fetch_timeout: IPFS_FETCH_TIMEOUT,
+            max_document_size: IPFS_MAX_DOCUMENT_SIZE,
+            trustless: false,
+        }
+    }
+
+    // every block is fetched raw from the gateways and checked against its cid before
+    // being used, so that neither the gateways nor the data cdn, which doesn't serve
+    // blocks and is skipped, need to be trusted with documents' contents
+    pub fn with_trustless_retrieval(mut self, trustless: bool) -> Self {
+        self.trustless = trustless;
+        self
+    }
+
+    // cids are chosen by whoever creates an oracle, so a single fetch can neither
+    // take longer than the timeout nor buffer more than the maximum document size
+    pub fn with_limits(mut self, fetch_timeout: Duration, max_document_size: usize) -> Self {
+        self.fetch_timeout = fetch_timeout;
+        self.max_document_size = max_document_size;
+        self
+    }
+
+    // documents are additionally pinned on the given node's kubo rpc api, so that
+    // an independent copy of what answers were based on is retained
+    pub fn with_pinning_node(mut self, pinning_node: Arc<HttpClient>) -> Self {
+        self.pinning_node = Some(pinning_node);
+        self
+    }
+
+    pub fn pins(&self) -> bool {
+        self.pinning_node.is_some()
+    }
+
+    pub async fn fetch_json<J: DeserializeOwned>(&self, cid: &str) -> anyhow::Result<J> {
+        if self.trustless {
+            return parse(cid, &self.fetch_verified(cid).await?);
+        }
+        let cid = cid.to_lowercase();
+
+        match self.fetch(&self.data_cdn, &cid, &cid, "the data cdn").await {
+            Ok(raw) => return parse(&cid, &raw),
+            // the document would be just as big anywhere else
+            Err(error) if is_invalid_document(&error) => return Err(error),
+            Err(error) => tracing::debug!("{:#}, falling back to ipfs gateways", error),
+        }
+
+        if self.gateways.is_empty() {
+            anyhow::bail!("no ipfs gateway to fetch {} from", cid);
+        }
+        let path = format!("/ipfs/{cid}");
+        let (raw, _) = select_ok(
+            self.gateways
+                .iter()
+                .map(|gateway| Box::pin(self.fetch(gateway, &path, &cid, "ipfs gateway"))),
+        )
+        .await
+        .context(format!("could not fetch {} from any ipfs gateway", cid))?;
+        parse(&cid, &raw)
+    }
+
+    pub async fn fetch_json_with_retry<J: DeserializeOwned>(
+        &self,
+        cid: &str,
+        backoff: ExponentialBackoff,
+    ) -> anyhow::Result<J> {
+        retry(backoff, || async {
+            self.fetch_json(cid).await.map_err(|error| {
+                if is_invalid_document(&error) {
+                    backoff::Error::permanent(error)
+                } else {
+                    backoff::Error::transient(error)
+                }
+            })
+        })
+        .await
+    }
+
+    pub async fn pin(&self, cid: &str) -> anyhow::Result<()> {
+        let pinning_node = match &self.pinning_node {
+            Some(pinning_node) => pinning_node,
+            None => return Ok(()),
+        };
+        pinning_node
+            .request(Method::POST, "api/v0/pin/add")
+            .await?
+            .query(&[("arg", cid)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context(format!("could not pin {} on the ipfs node", cid))?;
+        Ok(())
+    }
+
+    pub async fn pin_with_retry(
+        &self,
+        cid: &str,
+        backoff: ExponentialBackoff,
+    ) -> anyhow::Result<()> {
+        retry(backoff, || async {
+            self.pin(cid).await.map_err(backoff::Error::transient)
+        })
+        .await
+    }
+
+    // uploads the document to the pinning node, which pins it, returning its cid
+    pub async fn add_json<T: Serialize>(&self, name: &str, document: &T) -> anyhow::Result<String> {
+        let pinning_node = self
+            .pinning_node
+            .as_ref()
+            .context("no ipfs node to add documents to")?;
+        let document =
+            serde_json::to_vec(document).context(format!("could not serialize {}", name))?;
+        let form = Form::new().part(
+            "file",
+            Part::bytes(document)
+                .file_name(name.to_owned())
+                .mime_str("application/json")?,
+        );
+        let response = pinning_node
+            .request(Method::POST, "api/v0/add")
+            .await?
+            .query(&[("cid-version", "1"), ("pin", "true")])
+            .multipart(form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context(format!("could not add {} to the ipfs node", name))?
+            .json::<AddResponse>()
+            .await
+            .context(format!(
+                "could not read the cid of {} from the ipfs node",
+                name
+            ))?;
+        Ok(response.hash)
+    }
+
+    async fn fetch_verified(&self, raw_cid: &str) -> anyhow::Result<Vec<u8>> {
+        let root = Cid::parse(raw_cid)
+            .context(InvalidDocument {
+                cid: raw_cid.to_owned(),
+            })
+            .context(format!("cannot verify {}", raw_cid))?;
+
+        // blocks are walked depth first, which is the order their data is laid out in
+        let mut document = Vec::new();
+        let mut fetched = 0;
+        let mut pending = vec![root];
+        while let Some(cid) = pending.pop() {
+            let block = self.fetch_block(&cid).await?;
+            fetched += block.len();
+            if fetched > self.max_document_size {
+                return Err(anyhow::Error::new(InvalidDocument {
+                    cid: raw_cid.to_owned(),
+                })
+                .context(format!(
+                    "document is larger than {} bytes",
+                    self.max_document_size
+                )));
+            }
+            let block = decode_block(&cid, block)?;
+            document.extend(block.data);
+            pending.extend(block.children.into_iter().rev());
+        }
+        Ok(document)
+    }
+
+    async fn fetch_block(&self, cid: &Cid) -> anyhow::Result<Vec<u8>> {
+        if self.gateways.is_empty() {
+            anyhow::bail!("no ipfs gateway to fetch {} from", cid);
+        }
+        let raw_cid = cid.to_string();
+        let path = format!("/ipfs/{raw_cid}?format=raw");
+        let (block, _) = select_ok(self.gateways.iter().map(|gateway| {
+            Box::pin(async {
+                let block = self
+                    .fetch(gateway, &path, &raw_cid, "ipfs gateway")
+                    .await
+                    // a gateway's unverified response can't make a document invalid
+                    .map_err(|error| anyhow::anyhow!("{:#}", error))?;
+                cid.verify(&block)?;
+                anyhow::Ok(block)
+            })
+        }))
+        .await
+        .context(format!(
+            "could not fetch verified block {} from any ipfs gateway",
+            raw_cid
+        ))?;
+        Ok(block)
+    }
+
+    async fn fetch(
+        &self,
+        http_client: &HttpClient,
+        path: &str,
+        cid: &str,
+        source: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        timeout(self.fetch_timeout, async {
+            let response = http_client
+                .request(Method::GET, path)
+                .await?
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .context(format!("could not fetch {} from {}", cid, source))?;
+            read_limited(response, cid, self.max_document_size)
+                .await
+                .context(format!("could not read {} from {}", cid, source))
+        })
+        .await
+        .context(format!("timed out fetching {} from {}", cid, source))?
+    }
+}
+
+// the body is read in chunks so that oversized documents are given up on as soon as
+// they exceed the maximum size, instead of being buffered whole
+async fn read_limited(
+    mut response: Response,
+    cid: &str,
+    max_size: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let too_large = || {
+        anyhow::Error::new(InvalidDocument {
+            cid: cid.to_owned(),
+        })
+        .context(format!("document is larger than {} bytes", max_size))
+    };
+    if response
+        .content_length()
+        .is_some_and(|content_length| content_length > max_size as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_size {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+fn parse<J: DeserializeOwned>(cid: &str, raw: &[u8]) -> anyhow::Result<J> {
+    serde_json::from_slice(raw)
+        .context(InvalidDocument {
+            cid: cid.to_owned(),
+        })
+        .context(format!("could not deserialize {}", cid))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use backoff::ExponentialBackoffBuilder;
+    use carrot_commons::http_client::HttpClient;
+    use prost::Message;
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::commons::HTTP_TIMEOUT;
+
+    use super::{
+        is_invalid_document,
+        trustless::{Cid, PbLink, PbNode, UnixFsData, DAG_PB_CODEC, RAW_CODEC, UNIXFS_FILE},
+        IpfsFetcher,
+    };
+
+    fn http_client(mock_server: &MockServer) -> Arc<HttpClient> {
+        Arc::new(
+            HttpClient::builder(mock_server.uri(), HTTP_TIMEOUT)
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn gateway_fallback() {
+        let cdn_mock_server = MockServer::start().await;
+        let down_gateway_mock_server = MockServer::start().await;
+        let slow_gateway_mock_server = MockServer::start().await;
+        let gateway_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&cdn_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&down_gateway_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ipfs/bafkfoo"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"slow":true}"#)
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&slow_gateway_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ipfs/bafkfoo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"slow":false}"#))
+            .mount(&gateway_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ipfs/bafkbar"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+            .mount(&gateway_mock_server)
+            .await;
+
+        let fetcher = IpfsFetcher::new(
+            http_client(&cdn_mock_server),
+            vec![
+                http_client(&down_gateway_mock_server),
+                http_client(&slow_gateway_mock_server),
+                http_client(&gateway_mock_server),
+            ],
+        );
+
+        // the fastest gateway wins, and cids are lowercased
+        let document: Value = fetcher.fetch_json("BAFKFOO").await.unwrap();
+        assert_eq!(document["slow"], false);
+
+        let error = fetcher
+            .fetch_json_with_retry::<Value>(
+                "bafkbar",
+                ExponentialBackoffBuilder::new()
+                    .with_max_elapsed_time(Some(Duration::from_secs(5)))
+                    .build(),
+            )
+            .await
+            .unwrap_err();
+        assert!(is_invalid_document(&error));
+        assert_eq!(
+            gateway_mock_server
+                .received_requests()
+                .await
+                .unwrap()
+                .iter()
+                .filter(|request| request.url.path() == "/ipfs/bafkbar")
+                .count(),
+            1
+        );
+
+        let error = fetcher.fetch_json::<Value>("bafkbaz").await.unwrap_err();
+        assert!(!is_invalid_document(&error));
+    }
+
+    #[tokio::test]
+    async fn cdn_first() {
+        let cdn_mock_server = MockServer::start().await;
+        let gateway_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/bafkfoo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"foo":1}"#))
+            .mount(&cdn_mock_server)
+            .await;
+
+        let fetcher = IpfsFetcher::new(
+            http_client(&cdn_mock_server),
+            vec![http_client(&gateway_mock_server)],
+        );
+        let document: Value = fetcher.fetch_json("bafkfoo").await.unwrap();
+        assert_eq!(document["foo"], 1);
+        assert!(gateway_mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn limits() {
+        let cdn_mock_server = MockServer::start().await;
+        let gateway_mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/bafkfoo"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!(r#"{{"foo":"{}"}}"#, "a".repeat(2_048))),
+            )
+            .mount(&cdn_mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ipfs/bafkbar"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"foo":1}"#)
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&gateway_mock_server)
+            .await;
+
+        let fetcher = IpfsFetcher::new(
+            http_client(&cdn_mock_server),
+            vec![http_client(&gateway_mock_server)],
+        )
+        .with_limits(Duration::from_millis(200), 1_024);
+
+        // oversized documents are never fetched again, not even from gateways
+        let error = fetcher.fetch_json::<Value>("bafkfoo").await.unwrap_err();
+        assert!(is_invalid_document(&error));
+        assert!(gateway_mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty());
+
+        // timeouts are transient
+        let error = fetcher.fetch_json::<Value>("bafkbar").await.unwrap_err();
+        assert!(!is_invalid_document(&error));
+    }
+
+    #[tokio::test]
+    async fn trustless_retrieval() {
+        let cdn_mock_server = MockServer::start().await;
+        let lying_gateway_mock_server = MockServer::start().await;
+        let gateway_mock_server = MockServer::start().await;
+
+        // a dag-pb root holding the document's start and linking to a raw leaf with
+        // the rest of it
+        let leaf = br#""bar"}"#;
+        let leaf_cid = Cid::new_v1(RAW_CODEC, leaf);
+        let root = PbNode {
+            links: vec![PbLink {
+                hash: Some(leaf_cid.to_bytes()),
+                name: Some(String::new()),
+                tsize: Some(leaf.len() as u64),
+            }],
+            data: Some(
+                UnixFsData {
+                    r#type: Some(UNIXFS_FILE),
+                    data: Some(br#"{"foo":"#.to_vec()),
+                    filesize: Some(13),
+                    blocksizes: vec![leaf.len() as u64],
+                }
+                .encode_to_vec(),
+            ),
+        }
+        .encode_to_vec();
+        let root_cid = Cid::new_v1(DAG_PB_CODEC, &root);
+
+        for (cid, block) in [(&root_cid, root.clone()), (&leaf_cid, leaf.to_vec())] {
+            Mock::given(method("GET"))
+                .and(path(format!("/ipfs/{}", cid)))
+                .and(query_param("format", "raw"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(block))
+                .mount(&gateway_mock_server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(br#"{"foo":"baz"}"#.to_vec()))
+            .mount(&lying_gateway_mock_server)
+            .await;
+
+        let fetcher = IpfsFetcher::new(
+            http_client(&cdn_mock_server),
+            vec![
+                http_client(&lying_gateway_mock_server),
+                http_client(&gateway_mock_server),
+            ],
+        )
+        .with_trustless_retrieval(true);
+        let document: Value = fetcher.fetch_json(&root_cid.to_string()).await.unwrap();
+        assert_eq!(document["foo"], "bar");
+        assert!(cdn_mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .is_empty());
+
+        // with only the lying gateway nothing can be verified, which isn't permanent
+        let fetcher = IpfsFetcher::new(
+            http_client(&cdn_mock_server),
+            vec![http_client(&lying_gateway_mock_server)],
+        )
+        .with_trustless_retrieval(true);
+        let error = fetcher
+            .fetch_json::<Value>(&root_cid.to_string())
+            .await
+            .unwrap_err();
+        assert!(!is_invalid_document(&error));
+
+        // documents bigger than the limit are given up on while walking their blocks
+        let fetcher = IpfsFetcher::new(
+            http_client(&cdn_mock_server),
+            vec![http_client(&gateway_mock_server)],
+        )
+        .with_trustless_retrieval(true)
+        .with_limits(Duration::from_secs(5), root.len() + 1);
+        let error = fetcher
+            .fetch_json::<Value>(&root_cid.to_string())
+            .await
+            .unwrap_err();
+        assert!(is_invalid_document(&error));
+    }
+
+    #[tokio::test]
+    async fn pin() {
+        let cdn_mock_server = MockServer::start().await;
+        let node_mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v0/pin/add"))
+            .and(query_param("arg", "bafkfoo"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&node_mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v0/pin/add"))
+            .and(query_param("arg", "bafkfoo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"Pins":["bafkfoo"]}"#))
+            .mount(&node_mock_server)
+            .await;
+
+        // without a pinning node, pinning is a no-op
+        let fetcher = IpfsFetcher::new(http_client(&cdn_mock_server), vec![]);
+        assert!(!fetcher.pins());
+        fetcher.pin("bafkfoo").await.unwrap();
+
+        let fetcher = IpfsFetcher::new(http_client(&cdn_mock_server), vec![])
+            .with_pinning_node(http_client(&node_mock_server));
+        assert!(fetcher.pins());
+        fetcher
+            .pin_with_retry(
+                "bafkfoo",
+                ExponentialBackoffBuilder::new()
+                    .with_initial_interval(Duration::from_millis(10))
+                    .with_max_elapsed_time(Some(Duration::from_secs(5)))
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(node_mock_server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn add_json() {
+        let cdn_mock_server = MockServer::start().await;
+        let node_mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v0/add"))
+            .and(query_param("cid-version", "1"))
+            .and(query_param("pin", "true"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"Name":"foo.json","Hash":"bafkfoo","Size":"13"}"#),
+            )
+            .mount(&node_mock_server)
+            .await;
+
+        let fetcher = IpfsFetcher::new(http_client(&cdn_mock_server), vec![]);
+        assert!(fetcher
+            .add_json("foo.json", &json!({"foo": 1}))
+            .await
+            .is_err());
+
+        let fetcher = fetcher.with_pinning_node(http_client(&node_mock_server));
+        assert_eq!(
+            fetcher
+                .add_json("foo.json", &json!({"foo": 1}))
+                .await
+                .unwrap(),
+            "bafkfoo"
+        );
+        let requests = node_mock_server.received_requests().await.unwrap();
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains(r#"filename="foo.json""#));
+        assert!(body.contains(r#"{"foo":1}"#));
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt::{self, Display};
+
+use anyhow::Context;
+use data_encoding::BASE32_NOPAD;
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use super::InvalidDocument;
+
+pub(super) const RAW_CODEC: u64 = 0x55;
+pub(super) const DAG_PB_CODEC: u64 = 0x70;
+const SHA2_256_CODE: u64 = 0x12;
+const SHA2_256_LENGTH: u64 = 32;
+const UNIXFS_RAW: i32 = 0;
+pub(super) const UNIXFS_FILE: i32 = 2;
+
+// only what's needed to verify blocks hashed with sha2-256, which is what every
+// common ipfs implementation produces by default
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cid {
+    version: u64,
+    codec: u64,
+    digest: Vec<u8>,
+}
+
+impl Cid {
+    #[cfg(test)]
+    pub fn new_v1(codec: u64, block: &[u8]) -> Self {
+        Self {
+            version: 1,
+            codec,
+            digest: Sha256::digest(block).to_vec(),
+        }
+    }
+
+    pub fn parse(raw: &str) -> anyhow::Result<Self> {
+        // cidv0s are bare base58btc encoded multihashes
+        if raw.len() == 46 && raw.starts_with("Qm") {
+            let bytes = bs58::decode(raw)
+                .into_vec()
+                .context(format!("invalid cid {}", raw))?;
+            return Self::from_bytes(&bytes).context(format!("invalid cid {}", raw));
+        }
+
+        let bytes = match raw.chars().next() {
+            Some('b') => BASE32_NOPAD
+                .decode(raw[1..].to_uppercase().as_bytes())
+                .context(format!("invalid cid {}", raw))?,
+            Some('z') => bs58::decode(&raw[1..])
+                .into_vec()
+                .context(format!("invalid cid {}", raw))?,
+            _ => anyhow::bail!("unsupported multibase for cid {}", raw),
+        };
+        Self::from_bytes(&bytes).context(format!("invalid cid {}", raw))
+    }
+
+    // links in dag-pb nodes hold cids in their binary form
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut cursor = bytes;
+        let (version, codec) = if cursor.starts_with(&[SHA2_256_CODE as u8, SHA2_256_LENGTH as u8])
+        {
+            (0, DAG_PB_CODEC)
+        } else {
+            let version = read_varint(&mut cursor)?;
+            if version != 1 {
+                anyhow::bail!("unsupported cid version {}", version);
+            }
+            (version, read_varint(&mut cursor)?)
+        };
+        if codec != RAW_CODEC && codec != DAG_PB_CODEC {
+            anyhow::bail!("unsupported codec 0x{:x}", codec);
+        }
+
+        let hash_code = read_varint(&mut cursor)?;
+        let hash_length = read_varint(&mut cursor)?;
+        if hash_code != SHA2_256_CODE || hash_length != SHA2_256_LENGTH {
+            anyhow::bail!("unsupported multihash 0x{:x}", hash_code);
+        }
+        if cursor.len() != SHA2_256_LENGTH as usize {
+            anyhow::bail!("invalid multihash length");
+        }
+        Ok(Self {
+            version,
+            codec,
+            digest: cursor.to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if self.version != 0 {
+            write_varint(&mut bytes, self.version);
+            write_varint(&mut bytes, self.codec);
+        }
+        write_varint(&mut bytes, SHA2_256_CODE);
+        write_varint(&mut bytes, SHA2_256_LENGTH);
+        bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+
+    pub fn verify(&self, block: &[u8]) -> anyhow::Result<()> {
+        if Sha256::digest(block).as_slice() != self.digest.as_slice() {
+            anyhow::bail!("block doesn't match cid {}", self);
+        }
+        Ok(())
+    }
+}
+
+impl Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.version == 0 {
+            return write!(f, "{}", bs58::encode(self.to_bytes()).into_string());
+        }
+        write!(
+            f,
+            "b{}",
+            BASE32_NOPAD.encode(&self.to_bytes()).to_lowercase()
+        )
+    }
+}
+
+fn read_varint(cursor: &mut &[u8]) -> anyhow::Result<u64> {
+    let mut value = 0u64;
+    for (index, byte) in cursor.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            *cursor = &cursor[index + 1..];
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("invalid varint")
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PbLink {
+    #[prost(bytes = "vec", optional, tag = "1")]
+    pub hash: Option<Vec<u8>>,
+    #[prost(string, optional, tag = "2")]
+    pub name: Option<String>,
+    #[prost(uint64, optional, tag = "3")]
+    pub tsize: Option<u64>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PbNode {
+    #[prost(message, repeated, tag = "2")]
+    pub links: Vec<PbLink>,
+    #[prost(bytes = "vec", optional, tag = "1")]
+    pub data: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct UnixFsData {
+    #[prost(int32, optional, tag = "1")]
+    pub r#type: Option<i32>,
+    #[prost(bytes = "vec", optional, tag = "2")]
+    pub data: Option<Vec<u8>>,
+    #[prost(uint64, optional, tag = "3")]
+    pub filesize: Option<u64>,
+    #[prost(uint64, repeated, tag = "4")]
+    pub blocksizes: Vec<u64>,
+}
+
+// what a verified block contributes to the file: its own bytes, followed by the
+// ones of its children in order
+pub struct DecodedBlock {
+    pub data: Vec<u8>,
+    pub children: Vec<Cid>,
+}
+
+// only called on blocks that were verified against their cid, so anything that
+// can't be decoded is the document's fault and not the gateway's
+pub fn decode_block(cid: &Cid, block: Vec<u8>) -> anyhow::Result<DecodedBlock> {
+    if cid.codec == RAW_CODEC {
+        return Ok(DecodedBlock {
+            data: block,
+            children: Vec::new(),
+        });
+    }
+
+    let invalid = || InvalidDocument {
+        cid: cid.to_string(),
+    };
+    let node = PbNode::decode(block.as_slice())
+        .context(invalid())
+        .context("could not decode dag-pb node")?;
+    let unixfs = UnixFsData::decode(node.data.unwrap_or_default().as_slice())
+        .context(invalid())
+        .context("could not decode unixfs data")?;
+    let unixfs_type = unixfs.r#type.unwrap_or_default();
+    if unixfs_type != UNIXFS_FILE && unixfs_type != UNIXFS_RAW {
+        return Err(anyhow::Error::new(invalid()).context("not a unixfs file"));
+    }
+    let children = node
+        .links
+        .into_iter()
+        .map(|link| Cid::from_bytes(&link.hash.unwrap_or_default()))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .context(invalid())
+        .context("invalid link")?;
+    Ok(DecodedBlock {
+        data: unixfs.data.unwrap_or_default(),
+        children,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use prost::Message;
+
+    use super::{
+        decode_block, Cid, PbLink, PbNode, UnixFsData, DAG_PB_CODEC, RAW_CODEC, UNIXFS_FILE,
+    };
+
+    #[test]
+    fn cid() {
+        // the empty directory, in both versions
+        let v0 = Cid::parse("QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn").unwrap();
+        let v1 = Cid::parse("bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354").unwrap();
+        assert_eq!(v0.codec, DAG_PB_CODEC);
+        assert_eq!(v0.digest, v1.digest);
+        assert_eq!(
+            v0.to_string(),
+            "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn"
+        );
+        assert_eq!(
+            v1.to_string(),
+            "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354"
+        );
+
+        let raw = Cid::new_v1(RAW_CODEC, b"foo");
+        assert_eq!(Cid::parse(&raw.to_string()).unwrap(), raw);
+        raw.verify(b"foo").unwrap();
+        assert!(raw.verify(b"bar").is_err());
+
+        assert!(Cid::parse("foo").is_err());
+        assert!(Cid::parse("bafkfoo").is_err());
+    }
+
+    #[test]
+    fn dag_pb() {
+        let leaf = Cid::new_v1(RAW_CODEC, b"bar");
+        let node = PbNode {
+            links: vec![PbLink {
+                hash: Some(leaf.to_bytes()),
+                name: Some(String::new()),
+                tsize: Some(3),
+            }],
+            data: Some(
+                UnixFsData {
+                    r#type: Some(UNIXFS_FILE),
+                    data: Some(b"foo".to_vec()),
+                    filesize: Some(6),
+                    blocksizes: vec![3],
+                }
+                .encode_to_vec(),
+            ),
+        }
+        .encode_to_vec();
+        let cid = Cid::new_v1(DAG_PB_CODEC, &node);
+
+        let decoded = decode_block(&cid, node).unwrap();
+        assert_eq!(decoded.data, b"foo");
+        assert_eq!(decoded.children, vec![leaf]);
+
+        assert!(decode_block(&cid, b"\xff\xff".to_vec()).is_err());
+    }
+}
@@ -1,132 +1,441 @@
+pub mod alerts;
 pub mod answerer;
 pub mod api;
+pub mod archive;
+pub mod chains;
+pub mod cli;
 pub mod commons;
+pub mod config;
 pub mod contracts;
 pub mod db;
+pub mod defillama;
+pub mod devnet;
+pub mod events;
+pub mod feature_gates;
+pub mod grpc;
+pub mod heartbeat;
+pub mod ipfs;
 pub mod listener;
+pub mod logging;
+pub mod metrics;
+#[cfg(feature = "postgres")]
+pub mod pg_notify;
+pub mod rate_limiter;
+pub mod rpc;
+pub mod secrets;
+pub mod signer;
 pub mod specification;
+pub mod vault;
+pub mod webhooks;
+
+pub use answerer_framework::{quorum, shutdown};
 
 use std::{
-    env, num::NonZeroU32, ops::Deref, path::PathBuf, process::exit, sync::Arc, time::Duration,
+    collections::HashMap, env, future::pending, io, num::NonZeroU32, path::PathBuf, process::exit,
+    sync::Arc, time::Duration,
 };
 
 use anyhow::Context;
-use carrot_commons::{config::get_config, http_client::HttpClient};
-use diesel::{
-    r2d2::{ConnectionManager, Pool},
-    PgConnection,
-};
-use ethers::{
-    contract::EthEvent,
-    middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::{LocalWallet, Signer},
-    types::Filter,
-};
+use carrot_commons::http_client::HttpClient;
+use diesel::r2d2::{ConnectionManager, Pool};
 use governor::{Quota, RateLimiter};
-use mibs::{chain_config::ChainConfig, MibsBuilder};
 use tokio::task::JoinSet;
 use tracing::info_span;
 use tracing_futures::Instrument;
-use tracing_subscriber::{filter::LevelFilter, EnvFilter, FmtSubscriber};
 
 use crate::{
-    answerer::answer_active_oracles,
-    commons::{Config, HTTP_TIMEOUT},
-    contracts::factory::CreateTokenFilter,
-    db::models,
-    listener::Listener,
+    chains::{start_chain, Chains, ChainsContext},
+    cli::{
+        answer::answer,
+        connect_db,
+        list_oracles::list_oracles,
+        reset_checkpoint::{fetch_head_block, reset_checkpoint},
+        validate_config::validate_config,
+        Command, USAGE,
+    },
+    commons::{
+        ChainConfig, Config, DevnetConfig, DEFILLAMA_API_URL, DEFILLAMA_CACHE_TTL,
+        DEFILLAMA_COINS_URL, DEFILLAMA_RETRY_MAX_ELAPSED_TIME, DEFILLAMA_STABLECOINS_URL,
+        DEFILLAMA_YIELDS_URL, HTTP_TIMEOUT, IPFS_FETCH_TIMEOUT, IPFS_MAX_DOCUMENT_SIZE,
+        IPFS_PIN_TIMEOUT, SHUTDOWN_TIMEOUT,
+    },
+    config::load_config,
+    db::DbConnection,
+    defillama::{DefiLlamaClient, DefiLlamaHttp, DefiLlamaService},
+    devnet::Devnet,
+    ipfs::IpfsFetcher,
+    listener::build_leader_election,
+    logging::setup_logging,
+    rate_limiter::{set_defillama_rate_limiter, SharedRateLimiter},
+    secrets::{wait_for_rotated_secret, ResolvedSecret, SecretManagers},
+    shutdown::wait_for_termination,
+    vault::{keep_vault_token_renewed, VaultClient},
+    webhooks::deliver_webhook_events,
 };
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 
+#[cfg(feature = "postgres")]
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+#[cfg(feature = "sqlite")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations-sqlite");
 
-const DEFAULT_LOGS_POLLING_INTERVAL_SECONDS: u64 = 30;
 const MAX_CALLS_PER_SECOND_DEFILLAMA: u32 = 7;
 
-fn setup_logging() -> anyhow::Result<()> {
-    let subscriber = FmtSubscriber::builder()
-        .json()
-        .with_span_list(true)
-        .with_current_span(false)
-        .with_env_filter(
-            EnvFilter::builder()
-                .with_default_directive(LevelFilter::INFO.into())
-                .from_env()
-                .context("could not get log level")?,
-        )
-        .with_ansi(true)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).context("tracing initialization failed")
-}
-
 pub async fn main() {
-    if let Err(error) = setup_logging().context("could not initialize logging system") {
-        tracing::error!("{:#}", error);
-        exit(1);
-    }
+    let command = match Command::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{:#}\n\n{}", error, USAGE);
+            exit(2);
+        }
+    };
 
-    let alt_config_path = if let Some(alt_config_path) = env::var("CONFIG_PATH").ok() {
+    let alt_config_path = if let Ok(alt_config_path) = env::var("CONFIG_PATH") {
         let mut path = PathBuf::new();
         path.push(alt_config_path);
         Some(path)
     } else {
         None
     };
-    let config: Config =
-        match get_config("defillama-answerer", alt_config_path).context("could not read config") {
-            Ok(config) => config,
-            Err(error) => {
-                tracing::error!("{:#}", error);
-                exit(1);
-            }
-        };
+    let config: anyhow::Result<Config> =
+        load_config(alt_config_path).context("could not read config");
 
-    tracing::info!("connecting to database");
-    let db_connection_pool = match db::connect(&config.db_connection_string) {
-        Ok(db_connection_pool) => db_connection_pool,
+    // logging is configured from the config, so errors reading it are logged with the
+    // default setup
+    let logging_config = config
+        .as_ref()
+        .ok()
+        .and_then(|config| config.logging.clone())
+        .unwrap_or_default();
+    if let Err(error) = setup_logging(logging_config, command.logs_to_stderr())
+        .context("could not initialize logging system")
+    {
+        eprintln!("{:#}", error);
+        exit(1);
+    }
+    let mut config = match config {
+        Ok(config) => config,
         Err(error) => {
             tracing::error!("{:#}", error);
             exit(1);
         }
     };
 
-    {
-        let mut db_connection = match db_connection_pool
-            .get()
-            .context("could not get connection to database to run migrations")
-        {
-            Ok(db_connection) => db_connection,
-            Err(error) => {
+    let vault_client = match config.vault.as_ref() {
+        Some(vault_config) => {
+            tracing::info!("resolving secrets from vault at {}", vault_config.address);
+            let vault_client = match VaultClient::new(vault_config) {
+                Ok(vault_client) => Arc::new(vault_client),
+                Err(error) => {
+                    tracing::error!("could not initialize vault client: {:#}", error);
+                    exit(1);
+                }
+            };
+            if let Err(error) = vault_client
+                .resolve_config_secrets(&mut config)
+                .await
+                .context("could not resolve secrets from vault")
+            {
                 tracing::error!("{:#}", error);
                 exit(1);
             }
-        };
+            Some(vault_client)
+        }
+        None => None,
+    };
 
-        tracing::info!("running pending database migrations");
-        db_connection.run_pending_migrations(MIGRATIONS).unwrap();
+    let resolved_secrets = match config.secret_managers.clone() {
+        Some(secret_managers_config) => {
+            tracing::info!("resolving secrets from secret managers");
+            let secret_managers = match SecretManagers::new(&secret_managers_config) {
+                Ok(secret_managers) => Arc::new(secret_managers),
+                Err(error) => {
+                    tracing::error!("could not initialize secret managers: {:#}", error);
+                    exit(1);
+                }
+            };
+            match secret_managers
+                .resolve_config_secrets(&mut config)
+                .await
+                .context("could not resolve secrets from secret managers")
+            {
+                Ok(secrets) => Some((secret_managers, secrets)),
+                Err(error) => {
+                    tracing::error!("{:#}", error);
+                    exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    match command {
+        Command::Serve => serve(config, vault_client, resolved_secrets).await,
+        Command::ValidateConfig => {
+            let report = validate_config(&config).await;
+            println!("{}", serde_json::to_string_pretty(&report).unwrap()); // this should never panic
+            if !report.valid {
+                exit(1);
+            }
+        }
+        Command::ListOracles { chain_id, format } => {
+            let output = connect_db(&config).and_then(|db_connection_pool| {
+                db::blocking(|| list_oracles(db_connection_pool, chain_id, format))
+            });
+            match output.context("could not list oracles") {
+                Ok(output) => println!("{}", output),
+                Err(error) => {
+                    tracing::error!("{:#}", error);
+                    exit(1);
+                }
+            }
+        }
+        Command::Answer {
+            chain_id,
+            address,
+            ledger_hd_path,
+        } => {
+            let result = async {
+                let db_connection_pool = connect_db(&config)?;
+                let defillama = build_defillama_client(&config, db_connection_pool.clone())?;
+                answer(
+                    &config,
+                    chain_id,
+                    address,
+                    ledger_hd_path,
+                    db_connection_pool,
+                    defillama,
+                )
+                .await
+            };
+            match result.await.context(format!(
+                "could not answer oracle 0x{:x} on chain {}",
+                address, chain_id
+            )) {
+                Ok(()) => tracing::info!("oracle 0x{:x} handled", address),
+                Err(error) => {
+                    tracing::error!("{:#}", error);
+                    exit(1);
+                }
+            }
+        }
+        Command::ResetCheckpoint {
+            chain_id,
+            block_number,
+            assume_yes,
+        } => {
+            let result = async {
+                let chain_config = config
+                    .chain_configs
+                    .get(&chain_id)
+                    .context(format!("chain {} is not configured", chain_id))?;
+                let head_block_number = fetch_head_block(chain_config).await?;
+                let db_connection_pool = connect_db(&config)?;
+                db::blocking(|| {
+                    reset_checkpoint(
+                        db_connection_pool,
+                        chain_id,
+                        chain_config,
+                        block_number,
+                        head_block_number,
+                        assume_yes,
+                        &mut io::stdin().lock(),
+                        &mut io::stderr(),
+                    )
+                })
+            };
+            match result.await.context("could not reset checkpoint") {
+                Ok(output) => println!("{}", output),
+                Err(error) => {
+                    tracing::error!("{:#}", error);
+                    exit(1);
+                }
+            }
+        }
     }
+}
 
-    tracing::info!("ipfs gateway endpoint: {}", config.ipfs_gateway_endpoint);
-    let ipfs_gateway_http_client =
-        match HttpClient::builder(config.ipfs_gateway_endpoint, HTTP_TIMEOUT).build() {
-            Ok(ipfs_gateway_http_client) => Arc::new(ipfs_gateway_http_client),
+// the rate limit is per process, on top of the one shared with other processes when
+// configured
+fn build_defillama_client(
+    config: &Config,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+) -> anyhow::Result<DefiLlamaClient> {
+    if let Some(shared_rate_limit) = config.defillama_shared_rate_limit.clone() {
+        tracing::info!(
+            "sharing defillama rate limit of {} requests per second with other processes",
+            shared_rate_limit.requests_per_second
+        );
+        set_defillama_rate_limiter(SharedRateLimiter::new(
+            shared_rate_limit,
+            db_connection_pool,
+        ));
+    }
+
+    // base urls need a trailing slash to keep their own path when joined with a request's
+    let http_client = |base_url: &str| -> anyhow::Result<Arc<HttpClient>> {
+        let base_url = format!("{}/", base_url.trim_end_matches('/'));
+        Ok(Arc::new(
+            HttpClient::builder(base_url, HTTP_TIMEOUT)
+                .rate_limiter(RateLimiter::direct(Quota::per_second(
+                    NonZeroU32::new(MAX_CALLS_PER_SECOND_DEFILLAMA).unwrap(),
+                )))
+                .build()?,
+        ))
+    };
+    let defillama_config = config.defillama.clone().unwrap_or_default();
+    let url = |url: Option<String>, default: &str| {
+        let url = url.unwrap_or(default.to_owned());
+        http_client(&url).context(format!("could not build defillama client for {}", url))
+    };
+    let mirrors = defillama_config
+        .mirrors
+        .unwrap_or_default()
+        .into_iter()
+        .map(|mirror| url(Some(mirror), DEFILLAMA_API_URL))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let cache_ttl = defillama_config
+        .cache_ttl_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFILLAMA_CACHE_TTL);
+    let retry_max_elapsed_time = defillama_config
+        .retry_max_elapsed_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(DEFILLAMA_RETRY_MAX_ELAPSED_TIME);
+    Ok(DefiLlamaClient::live(Arc::new(
+        DefiLlamaHttp::new(
+            url(defillama_config.api_url, DEFILLAMA_API_URL)?,
+            url(defillama_config.coins_url, DEFILLAMA_COINS_URL)?,
+            url(defillama_config.stablecoins_url, DEFILLAMA_STABLECOINS_URL)?,
+            url(defillama_config.yields_url, DEFILLAMA_YIELDS_URL)?,
+        )
+        .with_cache_ttl(cache_ttl)
+        .with_retries(retry_max_elapsed_time)
+        .with_mirrors(DefiLlamaService::Api, mirrors),
+    )))
+}
+
+fn build_ipfs_fetcher(config: &Config) -> anyhow::Result<IpfsFetcher> {
+    tracing::info!("data cdn endpoint: {}", config.data_cdn_endpoint);
+    let data_cdn = HttpClient::builder(&config.data_cdn_endpoint, HTTP_TIMEOUT)
+        .build()
+        .context(format!(
+            "could not build data cdn client for {}",
+            config.data_cdn_endpoint
+        ))?;
+
+    let gateways = std::iter::once(&config.ipfs_gateway_endpoint)
+        .chain(config.ipfs_gateway_endpoints.iter().flatten())
+        .map(|endpoint| {
+            tracing::info!("ipfs gateway endpoint: {}", endpoint);
+            HttpClient::builder(endpoint, HTTP_TIMEOUT)
+                .build()
+                .map(Arc::new)
+                .context(format!(
+                    "could not build ipfs gateway client for {}",
+                    endpoint
+                ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let ipfs_fetcher = IpfsFetcher::new(Arc::new(data_cdn), gateways)
+        .with_limits(
+            config
+                .ipfs_fetch_timeout_seconds
+                .map(Duration::from_secs)
+                .unwrap_or(IPFS_FETCH_TIMEOUT),
+            config
+                .ipfs_max_document_size_bytes
+                .unwrap_or(IPFS_MAX_DOCUMENT_SIZE),
+        )
+        .with_trustless_retrieval(config.ipfs_trustless_retrieval.unwrap_or(false));
+
+    let Some(pinning_endpoint) = &config.ipfs_pinning_endpoint else {
+        return Ok(ipfs_fetcher);
+    };
+    tracing::info!("ipfs pinning endpoint: {}", pinning_endpoint);
+    // pinning makes the node fetch the whole document first, which can take a while
+    let pinning_node = HttpClient::builder(
+        format!("{}/", pinning_endpoint.trim_end_matches('/')),
+        IPFS_PIN_TIMEOUT,
+    )
+    .build()
+    .context(format!(
+        "could not build ipfs pinning client for {}",
+        pinning_endpoint
+    ))?;
+    Ok(ipfs_fetcher.with_pinning_node(Arc::new(pinning_node)))
+}
+
+async fn start_devnet(
+    devnet_config: DevnetConfig,
+    chain_configs: &mut HashMap<u64, ChainConfig>,
+) -> anyhow::Result<Devnet> {
+    let devnet = Devnet::start(devnet_config)
+        .await
+        .context("could not start the devnet")?;
+    if chain_configs.contains_key(&devnet.chain_id()) {
+        anyhow::bail!(
+            "chain {} is used by the devnet and can't be configured as well",
+            devnet.chain_id()
+        );
+    }
+    chain_configs.insert(devnet.chain_id(), devnet.chain_config()?);
+    Ok(devnet)
+}
+
+async fn serve(
+    mut config: Config,
+    vault_client: Option<Arc<VaultClient>>,
+    resolved_secrets: Option<(Arc<SecretManagers>, Vec<ResolvedSecret>)>,
+) {
+    let dry_run = config.dry_run.unwrap_or(false);
+    if dry_run {
+        tracing::warn!("running in dry run mode, answers will be computed but never submitted");
+    }
+
+    tracing::info!("connecting to database");
+    let db_pool_config = config.db_pool.clone().unwrap_or_default();
+    let db_connection_pool =
+        match db::blocking(|| db::connect(&config.db_connection_string, &db_pool_config)) {
+            Ok(db_connection_pool) => db_connection_pool,
             Err(error) => {
                 tracing::error!("{:#}", error);
                 exit(1);
             }
         };
 
-    tracing::info!("data cdn endpoint: {}", config.data_cdn_endpoint);
-    let data_cdn_http_client =
-        match HttpClient::builder(config.data_cdn_endpoint, HTTP_TIMEOUT).build() {
-            Ok(data_cdn_http_client) => Arc::new(data_cdn_http_client),
+    {
+        let mut db_connection = match db_connection_pool
+            .get()
+            .context("could not get connection to database to run migrations")
+        {
+            Ok(db_connection) => db_connection,
             Err(error) => {
                 tracing::error!("{:#}", error);
                 exit(1);
             }
         };
 
+        tracing::info!("running pending database migrations");
+        db_connection.run_pending_migrations(MIGRATIONS).unwrap();
+    }
+
+    let defillama = match build_defillama_client(&config, db_connection_pool.clone()) {
+        Ok(defillama) => defillama,
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            exit(1);
+        }
+    };
+
+    let ipfs_fetcher = match build_ipfs_fetcher(&config) {
+        Ok(ipfs_fetcher) => Arc::new(ipfs_fetcher),
+        Err(error) => {
+            tracing::error!("{:#}", error);
+            exit(1);
+        }
+    };
+
     tracing::info!("data manager endpoint: {}", config.data_manager.endpoint);
     let data_manager_http_client =
         match HttpClient::builder(config.data_manager.endpoint, HTTP_TIMEOUT)
@@ -140,110 +449,189 @@ pub async fn main() {
             }
         };
 
-    let defillama_http_client = match HttpClient::builder("https://api.llama.fi", HTTP_TIMEOUT)
-        .rate_limiter(RateLimiter::direct(Quota::per_second(
-            NonZeroU32::new(MAX_CALLS_PER_SECOND_DEFILLAMA).unwrap(),
-        )))
-        .build()
-    {
-        Ok(defillama_http_client) => defillama_http_client,
-        Err(error) => {
-            tracing::error!("{:#}", error);
-            exit(1);
-        }
-    };
-    let defillama_http_client = Arc::new(defillama_http_client);
-
     let mut join_set = JoinSet::new();
+    // kept apart so that in-flight answers can be waited for on shutdown
+    let mut answering_tasks = JoinSet::new();
 
-    let mut mibs_builder = MibsBuilder::new();
-    for (chain_id, chain_config) in config.chain_configs.into_iter() {
-        let cloned_chain_config = chain_config.clone();
-        let rpc_endpoint = chain_config.rpc_endpoint.as_str();
-
-        tracing::info!(
-            "setting up mibs config for chain with id {} with rpc endpoint: {}",
-            chain_id,
-            rpc_endpoint
+    if let Some(vault_client) = vault_client.clone() {
+        join_set.spawn(
+            keep_vault_token_renewed(vault_client).instrument(info_span!("vault-token-renewal")),
         );
+    }
 
-        let checkpoint_block_number = get_checkpoint_block_number(
-            chain_id,
-            db_connection_pool.clone(),
-            chain_config.factory.deployment_block,
+    for webhook in config.webhooks.take().into_iter().flatten() {
+        join_set.spawn(deliver_webhook_events(webhook).instrument(info_span!("webhook")));
+    }
+
+    #[cfg(feature = "postgres")]
+    if let Some(pg_notify) = config.pg_notify.take() {
+        join_set.spawn(
+            pg_notify::notify_oracle_events(pg_notify, db_connection_pool.clone())
+                .instrument(info_span!("pg-notify")),
         );
+    }
+    #[cfg(feature = "sqlite")]
+    if config.pg_notify.is_some() {
+        tracing::error!("postgres notifications can't be sent with the sqlite backend");
+        exit(1);
+    }
 
-        let rpc_url = chain_config.rpc_endpoint;
-        let provider = Arc::new(get_provider(chain_id, rpc_url.clone()));
-        let signer = Arc::new(get_signer(
-            chain_id,
-            rpc_url,
-            chain_config.answerer_private_key,
-        ));
+    if let Some(alerts_config) = config.alerts.take() {
+        if let Err(error) = alerts::start_sinks(alerts_config, &mut join_set) {
+            tracing::error!("{:#}", error);
+            exit(1);
+        }
+    }
 
-        let chain_config_builder = ChainConfig::builder(
+    // anvil is stopped once the devnet is dropped, when shutting down
+    let devnet = match config.devnet.take() {
+        Some(_) if !config.dev_mode.unwrap_or(false) => {
+            tracing::error!("the devnet can only be started in dev mode");
+            exit(1);
+        }
+        Some(devnet_config) => match start_devnet(devnet_config, &mut config.chain_configs).await {
+            Ok(devnet) => Some(Arc::new(devnet)),
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let chains_context = ChainsContext {
+        dev_mode: config.dev_mode.unwrap_or(false),
+        dry_run,
+        record_defillama_responses: config.record_defillama_responses.unwrap_or(true),
+        persist_indexed_logs: config.persist_indexed_logs.unwrap_or(false),
+        leader_election: config.leader_election.as_ref().map(|leader_election| {
+            let leader_election = build_leader_election(leader_election);
+            tracing::info!(
+                "electing scanning leaders as instance {}",
+                leader_election.instance_id
+            );
+            Arc::new(leader_election)
+        }),
+        // secrets in the config were already resolved
+        vault_client: None,
+        db_connection_pool: db_connection_pool.clone(),
+        ipfs_fetcher,
+        data_manager_http_client,
+        defillama: defillama.clone(),
+    };
+    let (chains, mut chain_additions) = Chains::new();
+    let chains = Arc::new(chains);
+    for (chain_id, chain_config) in config.chain_configs.into_iter() {
+        match start_chain(
+            &chains_context,
             chain_id,
-            provider,
-            checkpoint_block_number,
-            Filter::new()
-                .address(vec![chain_config.factory.address])
-                .event(CreateTokenFilter::abi_signature().deref()),
-            Listener::new(
-                chain_id,
-                chain_config.template_id,
-                signer.clone(),
-                db_connection_pool.clone(),
-                data_cdn_http_client.clone(),
-                data_manager_http_client.clone(),
-                ipfs_gateway_http_client.clone(),
-                defillama_http_client.clone(),
-            ),
+            chain_config,
+            &mut join_set,
+            &mut answering_tasks,
         )
-        .past_events_query_max_rps(Some(1))
-        .past_events_query_range(chain_config.logs_blocks_range)
-        .present_events_polling_interval(Duration::from_secs(
-            chain_config
-                .logs_polling_interval_seconds
-                .unwrap_or(DEFAULT_LOGS_POLLING_INTERVAL_SECONDS),
-        ))
-        .skip_past(config.dev_mode);
+        .await
+        {
+            Ok(chain) => chains.insert(chain_id, chain),
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                exit(1);
+            }
+        }
+    }
+    // the demo oracle's creation log must come after the devnet chain's scanners started
+    if let Some(devnet) = devnet.clone() {
+        join_set.spawn(
+            async move {
+                if let Err(error) = devnet.create_demo_oracle().await {
+                    tracing::error!("could not create the demo oracle: {:#}", error);
+                }
+                Ok(())
+            }
+            .instrument(info_span!("devnet")),
+        );
+    }
+    // chains added at runtime might reference vault secrets
+    let chains_context = ChainsContext {
+        vault_client,
+        ..chains_context
+    };
 
+    if let Some(grpc_config) = config.grpc.take() {
         join_set.spawn(
-            answer_active_oracles(
-                config.dev_mode.unwrap_or(false),
-                chain_id,
-                cloned_chain_config,
-                signer,
+            grpc::serve(
+                grpc_config,
+                config.api.operators.clone(),
+                chains.clone(),
                 db_connection_pool.clone(),
-                defillama_http_client.clone(),
+                defillama.clone(),
             )
-            .instrument(info_span!("answerer", chain_id)),
+            .instrument(info_span!("grpc-server")),
         );
-
-        mibs_builder = mibs_builder.chain_config(chain_config_builder.build());
     }
 
-    join_set.spawn(
-        async {
-            mibs_builder
-                .build()
-                .scan()
-                .await
-                .map_err(|err| anyhow::anyhow!(err))
-        }
-        .instrument(info_span!("mibs")),
-    );
     join_set.spawn(
         api::serve(
-            config.api.host,
-            config.api.port,
-            defillama_http_client.clone(),
+            config.api,
+            chains.clone(),
+            db_connection_pool.clone(),
+            defillama.clone(),
         )
         .instrument(info_span!("api-server")),
     );
 
-    // wait forever unless some task stops with an error
-    while let Some(join_result) = join_set.join_next().await {
+    // wait until a termination signal is received or a secret is rotated, unless some
+    // task stops with an error before that
+    let termination = wait_for_termination();
+    tokio::pin!(termination);
+    // rotated secrets are picked up by restarting, which is left to the supervisor
+    let secret_rotation = async move {
+        match resolved_secrets {
+            Some((secret_managers, secrets)) if !secrets.is_empty() => {
+                match secret_managers.refresh_interval() {
+                    Some(refresh_interval) => {
+                        wait_for_rotated_secret(secret_managers, secrets, refresh_interval).await
+                    }
+                    None => pending().await,
+                }
+            }
+            _ => pending().await,
+        }
+    };
+    tokio::pin!(secret_rotation);
+    let mut secrets_rotated = false;
+    loop {
+        let join_result = tokio::select! {
+            result = &mut termination => {
+                if let Err(error) = result {
+                    tracing::error!("could not listen for termination signals: {:#}", error);
+                    exit(1);
+                }
+                break;
+            }
+            _ = &mut secret_rotation => {
+                secrets_rotated = true;
+                break;
+            }
+            Some(join_result) = join_set.join_next() => join_result,
+            Some(join_result) = answering_tasks.join_next() => join_result,
+            Some(addition) = chain_additions.recv() => {
+                let result = if chains.contains(addition.chain_id) {
+                    Err(anyhow::anyhow!("chain {} already running", addition.chain_id))
+                } else {
+                    start_chain(
+                        &chains_context,
+                        addition.chain_id,
+                        *addition.chain_config,
+                        &mut join_set,
+                        &mut answering_tasks,
+                    )
+                    .await
+                    .map(|chain| chains.insert(addition.chain_id, chain))
+                };
+                let _ = addition.result.send(result);
+                continue;
+            }
+        };
         match join_result {
             Ok(result) => {
                 if let Err(error) = result {
@@ -251,73 +639,54 @@ pub async fn main() {
                     exit(1);
                 }
             }
+            // tasks of removed chains are aborted
+            Err(error) if error.is_cancelled() => {}
             Err(error) => {
                 tracing::error!("an error happened while joining a task: {:#}", error);
                 exit(1);
             }
         }
     }
-}
 
-fn get_checkpoint_block_number(
-    chain_id: u64,
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    factory_deployment_block: u64,
-) -> u64 {
-    let mut db_connection = match db_connection_pool.get() {
-        Ok(conn) => conn,
-        Err(err) => {
-            tracing::error!("could not get database connection to get checkpoint block: {err:#}");
-            exit(1);
+    let shutdown_timeout = config
+        .shutdown_timeout_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(SHUTDOWN_TIMEOUT);
+    tracing::info!(
+        "shutting down, waiting up to {}s for in-flight answers",
+        shutdown_timeout.as_secs()
+    );
+    // nothing else holds state worth waiting for: checkpoints and acknowledged
+    // oracles are stored as soon as they're processed
+    chains.trigger_shutdown();
+    join_set.abort_all();
+    let answering_tasks_completion = async {
+        while let Some(join_result) = answering_tasks.join_next().await {
+            match join_result {
+                Ok(Err(error)) => {
+                    tracing::error!("an answering task stopped with an error: {:#}", error)
+                }
+                Err(error) => {
+                    tracing::error!("an error happened while joining a task: {:#}", error)
+                }
+                Ok(Ok(())) => {}
+            }
         }
     };
-
-    let checkpoint_block = match models::Checkpoint::get_for_chain_id(&mut db_connection, chain_id)
+    if tokio::time::timeout(shutdown_timeout, answering_tasks_completion)
+        .await
+        .is_err()
     {
-        Ok(checkpoint_block) => checkpoint_block,
-        Err(err) => {
-            tracing::error!("could not get checkpoint block: {err:#}");
-            exit(1);
-        }
-    };
-
-    checkpoint_block
-        .map(|checkpoint| {
-            // realistically, the following should never happen
-            u64::try_from(checkpoint.block_number).expect(
-                format!(
-                    "could not convert checkpoint block number {} to unsigned integer",
-                    checkpoint.block_number
-                )
-                .as_str(),
-            )
-        })
-        .unwrap_or(factory_deployment_block)
-}
-
-fn get_provider(chain_id: u64, rpc_url: String) -> Provider<Http> {
-    match Provider::<Http>::try_from(rpc_url.clone()) {
-        Ok(provider) => provider,
-        Err(err) => {
-            tracing::error!("could not get provider for chain {chain_id}: {err:#}");
-            exit(1);
-        }
+        tracing::warn!(
+            "in-flight answers didn't complete in time, they'll be recovered on the next start"
+        );
+    }
+    drop(devnet);
+    tracing::info!("shut down");
+    if secrets_rotated {
+        tracing::warn!(
+            "exiting with an error so that the service is restarted with the rotated secrets"
+        );
+        exit(1);
     }
-}
-
-fn get_signer(
-    chain_id: u64,
-    rpc_url: String,
-    answerer_private_key: String,
-) -> SignerMiddleware<Provider<Http>, LocalWallet> {
-    let answerer_wallet = match answerer_private_key.parse::<LocalWallet>().context("t") {
-        Ok(wallet) => wallet,
-        Err(err) => {
-            tracing::error!("could not parse private key to local wallet: {err:#}");
-            exit(1);
-        }
-    };
-
-    let provider = get_provider(chain_id, rpc_url.clone());
-    SignerMiddleware::new(provider, answerer_wallet.with_chain_id(chain_id))
 }
@@ -1,23 +1,24 @@
 use std::{
+    str::FromStr,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use answerer_framework::OracleDetector;
 use anyhow::Context;
+use async_trait::async_trait;
 use backoff::ExponentialBackoffBuilder;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use carrot_commons::{data, http_client::HttpClient};
-use diesel::{
-    prelude::*,
-    r2d2::{ConnectionManager, Pool},
-};
+use diesel::r2d2::{ConnectionManager, Pool};
 use ethers::{
     abi::RawLog,
     contract::{EthLogDecode, Multicall},
-    middleware::SignerMiddleware,
-    providers::{Http, Provider},
-    signers::LocalWallet,
-    types::{Address, Log, U256},
+    middleware::{Middleware, SignerMiddleware},
+    providers::Provider,
+    types::{Address, Log, U256, U64},
 };
+use percent_encoding::percent_decode_str;
 use tokio::task::JoinSet;
 use tracing::info_span;
 use tracing_futures::Instrument;
@@ -29,23 +30,72 @@ use crate::{
         factory::FactoryEvents,
         kpi_token::KPIToken,
     },
-    db::models::{self},
+    db::{self, models, DbConnection},
+    defillama::DefiLlamaClient,
+    events::{self, OracleEventKind},
+    ipfs::IpfsFetcher,
+    metrics,
+    quorum::QuorumReader,
+    rpc::FallbackHttp,
+    signer::AnswererSigner,
     specification::{self, Specification},
 };
 
+// templates usually point to a specification document by cid, but it can also be
+// embedded directly as raw json or a data uri
+#[derive(Debug, PartialEq)]
+pub enum SpecificationSource {
+    Cid(String),
+    Inline(Specification),
+}
+
+impl FromStr for SpecificationSource {
+    type Err = anyhow::Error;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let raw = raw.trim();
+        if raw.starts_with('{') {
+            return Ok(Self::Inline(
+                serde_json::from_str(raw).context("could not parse inline specification")?,
+            ));
+        }
+
+        let Some(data_uri) = raw.strip_prefix("data:") else {
+            return Ok(Self::Cid(raw.to_owned()));
+        };
+        let (metadata, payload) = data_uri
+            .split_once(',')
+            .context("malformed specification data uri")?;
+        let document = if metadata.ends_with(";base64") {
+            STANDARD
+                .decode(payload)
+                .context("could not decode base64 specification data uri")?
+        } else {
+            percent_decode_str(payload).collect()
+        };
+        Ok(Self::Inline(
+            serde_json::from_slice(&document).context("could not parse specification data uri")?,
+        ))
+    }
+}
+
 pub struct DefiLlamaOracleData {
     address: Address,
     measurement_timestamp: SystemTime,
-    specification_cid: String,
+    specification: SpecificationSource,
     expiration: SystemTime,
+    creation_timestamp: Option<SystemTime>,
 }
 
 pub async fn parse_kpi_token_creation_log(
     chain_id: u64,
-    signer: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    quorum_reader: &QuorumReader,
     log: Log,
     oracle_template_id: u64,
+    multicall_address: Option<Address>,
 ) -> anyhow::Result<Vec<DefiLlamaOracleData>> {
+    let block_number = log.block_number;
     let raw_log = RawLog {
         topics: log.topics,
         data: log.data.to_vec(),
@@ -59,7 +109,8 @@ pub async fn parse_kpi_token_creation_log(
     };
 
     let mut data = Vec::new();
-    let mut multicall = Multicall::new_with_chain_id(signer.clone(), None, Some(chain_id))?;
+    let mut multicall =
+        Multicall::new_with_chain_id(signer.clone(), multicall_address, Some(chain_id))?;
     let kpi_token = KPIToken::new(token_address, signer.clone());
     let (oracle_addresses, kpi_token_expiration) = multicall
         .add_call(kpi_token.oracles(), false)
@@ -70,6 +121,18 @@ pub async fn parse_kpi_token_creation_log(
             "could not get oracles and expiration for kpi token 0x{:x}",
             token_address
         ))?;
+    let kpi_token_expiration = quorum_reader
+        .confirm(
+            "kpi token expiration",
+            kpi_token_expiration,
+            |provider| async move {
+                Ok(KPIToken::new(token_address, provider)
+                    .expiration()
+                    .call()
+                    .await?)
+            },
+        )
+        .await?;
     let kpi_token_expiration = UNIX_EPOCH + Duration::from_secs(kpi_token_expiration.as_u64());
     multicall.clear_calls();
     for oracle_address in oracle_addresses.into_iter() {
@@ -81,6 +144,25 @@ pub async fn parse_kpi_token_creation_log(
             .await
         {
             Ok((finalized, template)) => {
+                let finalized = match quorum_reader
+                    .confirm("finalization status", finalized, |provider| async move {
+                        Ok(DefiLlamaOracle::new(oracle_address, provider)
+                            .finalized()
+                            .call()
+                            .await?)
+                    })
+                    .await
+                {
+                    Ok(finalized) => finalized,
+                    Err(error) => {
+                        tracing::error!(
+                            "could not confirm finalization status for oracle at address {}, skipping - {:#}",
+                            oracle_address,
+                            error
+                        );
+                        continue;
+                    }
+                };
                 if finalized {
                     tracing::info!(
                         "oracle with address 0x{:x} already finalized, skipping",
@@ -108,6 +190,17 @@ pub async fn parse_kpi_token_creation_log(
                         continue;
                     }
                 };
+                let specification = match specification.parse::<SpecificationSource>() {
+                    Ok(specification) => specification,
+                    Err(error) => {
+                        tracing::error!(
+                            "invalid inline specification for oracle at address {}, skipping - {:#}",
+                            oracle_address,
+                            error
+                        );
+                        continue;
+                    }
+                };
 
                 let measurement_timestamp = match oracle.measurement_timestamp().await {
                     Ok(measurement_timestamp) => measurement_timestamp,
@@ -120,14 +213,38 @@ pub async fn parse_kpi_token_creation_log(
                         continue;
                     }
                 };
+                let measurement_timestamp = match quorum_reader
+                    .confirm(
+                        "measurement timestamp",
+                        measurement_timestamp,
+                        |provider| async move {
+                            Ok(DefiLlamaOracle::new(oracle_address, provider)
+                                .measurement_timestamp()
+                                .call()
+                                .await?)
+                        },
+                    )
+                    .await
+                {
+                    Ok(measurement_timestamp) => measurement_timestamp,
+                    Err(error) => {
+                        tracing::error!(
+                            "could not confirm measurement timestamp for oracle at address {}, skipping - {:#}",
+                            oracle_address,
+                            error
+                        );
+                        continue;
+                    }
+                };
                 let measurement_timestamp =
                     UNIX_EPOCH + Duration::from_secs(measurement_timestamp.as_u64());
 
                 data.push(DefiLlamaOracleData {
                     address: oracle_address,
                     measurement_timestamp,
-                    specification_cid: specification,
+                    specification,
                     expiration: kpi_token_expiration,
+                    creation_timestamp: None,
                 });
             }
             Err(error) => {
@@ -140,17 +257,44 @@ pub async fn parse_kpi_token_creation_log(
         };
     }
 
+    // the creation block's timestamp is only used to track the acknowledgement
+    // latency, so failing to fetch it shouldn't prevent oracles from being handled
+    if !data.is_empty() {
+        match get_block_timestamp(signer, block_number).await {
+            Ok(creation_timestamp) => {
+                for oracle_data in data.iter_mut() {
+                    oracle_data.creation_timestamp = Some(creation_timestamp);
+                }
+            }
+            Err(error) => {
+                tracing::warn!("could not get creation block timestamp: {:#}", error);
+            }
+        }
+    }
+
     Ok(data)
 }
 
+async fn get_block_timestamp(
+    signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    block_number: Option<U64>,
+) -> anyhow::Result<SystemTime> {
+    let block_number = block_number.context("log has no block number")?;
+    let block = signer
+        .get_block(block_number)
+        .await
+        .context(format!("could not get block {}", block_number))?
+        .context(format!("block {} not found", block_number))?;
+    Ok(UNIX_EPOCH + Duration::from_secs(block.timestamp.as_u64()))
+}
+
 pub async fn acknowledge_active_oracles(
     chain_id: u64,
     oracles_data: Vec<DefiLlamaOracleData>,
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    data_cdn_http_client: Arc<HttpClient>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
     data_manager_http_client: Arc<HttpClient>,
-    ipfs_gateway_http_client: Arc<HttpClient>,
-    defillama_http_client: Arc<HttpClient>,
+    ipfs_fetcher: Arc<IpfsFetcher>,
+    defillama: DefiLlamaClient,
 ) {
     let mut join_set = JoinSet::new();
     for data in oracles_data.into_iter() {
@@ -160,10 +304,9 @@ pub async fn acknowledge_active_oracles(
                 chain_id,
                 data,
                 db_connection_pool.clone(),
-                data_cdn_http_client.clone(),
                 data_manager_http_client.clone(),
-                ipfs_gateway_http_client.clone(),
-                defillama_http_client.clone(),
+                ipfs_fetcher.clone(),
+                defillama.clone(),
             )
             .instrument(tracing::error_span!("ack", chain_id, oracle_address)),
         );
@@ -186,56 +329,149 @@ pub async fn acknowledge_active_oracles(
     }
 }
 
+// detects oracles created from the defillama oracle template, fetching their
+// specification and storing them as active oracles
+pub struct DefiLlamaOracleDetector {
+    pub chain_id: u64,
+    pub template_id: u64,
+    pub multicall_address: Option<Address>,
+    pub signer: Arc<SignerMiddleware<Provider<FallbackHttp>, AnswererSigner>>,
+    pub quorum_reader: Arc<QuorumReader>,
+    pub db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    pub data_manager_http_client: Arc<HttpClient>,
+    pub ipfs_fetcher: Arc<IpfsFetcher>,
+    pub defillama: DefiLlamaClient,
+}
+
+#[async_trait]
+impl OracleDetector for DefiLlamaOracleDetector {
+    async fn on_creation_log(&self, log: Log) -> anyhow::Result<usize> {
+        let block_number = log.block_number;
+        let oracles_data = parse_kpi_token_creation_log(
+            self.chain_id,
+            self.signer.clone(),
+            &self.quorum_reader,
+            log,
+            self.template_id,
+            self.multicall_address,
+        )
+        .await?;
+
+        let oracles_data_len = oracles_data.len();
+        if oracles_data_len > 0 {
+            tracing::info!(
+                "{} oracle creation(s) detected on block {}",
+                oracles_data_len,
+                block_number.unwrap_or_default()
+            );
+        }
+
+        acknowledge_active_oracles(
+            self.chain_id,
+            oracles_data,
+            self.db_connection_pool.clone(),
+            self.data_manager_http_client.clone(),
+            self.ipfs_fetcher.clone(),
+            self.defillama.clone(),
+        )
+        .await;
+
+        Ok(oracles_data_len)
+    }
+}
+
 pub async fn acknowledge_active_oracle(
     chain_id: u64,
     oracle_data: DefiLlamaOracleData,
-    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
-    data_cdn_http_client: Arc<HttpClient>,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
     data_manager_http_client: Arc<HttpClient>,
-    ipfs_gateway_http_client: Arc<HttpClient>,
-    defillama_http_client: Arc<HttpClient>,
+    ipfs_fetcher: Arc<IpfsFetcher>,
+    defillama: DefiLlamaClient,
 ) -> anyhow::Result<()> {
-    match data::fetch_json_with_retry::<Specification>(
-        oracle_data.specification_cid.clone(),
-        data_cdn_http_client.clone(),
-        ipfs_gateway_http_client.clone(),
-        ExponentialBackoffBuilder::new()
-            .with_max_elapsed_time(Some(FETCH_SPECIFICATION_JSON_MAX_ELAPSED_TIME))
-            .build(),
-    )
-    .await
+    // blocks after the checkpoint are scanned again after a restart
     {
+        let mut db_connection = db::blocking(|| db_connection_pool.get())
+            .context("could not get new connection from pool")?;
+        if db::blocking(|| {
+            models::ActiveOracle::get(&mut db_connection, chain_id, oracle_data.address)
+        })
+        .context("could not check whether the oracle was already acknowledged")?
+        .is_some()
+        {
+            tracing::debug!(
+                "oracle with address 0x{:x} already acknowledged, skipping",
+                oracle_data.address
+            );
+            return Ok(());
+        }
+        // removed oracles must not come back when their creation log is scanned again
+        if db::blocking(|| {
+            models::ArchivedOracle::is_removed(&mut db_connection, chain_id, oracle_data.address)
+        })
+        .context("could not check whether the oracle was removed")?
+        {
+            tracing::info!(
+                "oracle with address 0x{:x} was removed by an operator, skipping",
+                oracle_data.address
+            );
+            return Ok(());
+        }
+    }
+    metrics::record_oracle_detected(chain_id);
+
+    let specification = match &oracle_data.specification {
+        SpecificationSource::Cid(cid) => {
+            fetch_specification(cid, &db_connection_pool, &ipfs_fetcher).await
+        }
+        SpecificationSource::Inline(specification) => Ok(specification.clone()),
+    };
+    match specification {
         Ok(specification) => {
-            if !specification::validate(&specification, defillama_http_client).await {
+            if !specification::validate(&specification, defillama).await {
                 tracing::error!("specification validation failed for oracle at address 0x{:x}, this won't be handled", oracle_data.address);
                 return Ok(());
             }
 
-            let database_connection = &mut db_connection_pool
-                .get()
+            let database_connection = &mut db::blocking(|| db_connection_pool.get())
                 .context("could not get new connection from pool")?;
 
-            models::ActiveOracle::create(
-                database_connection,
-                oracle_data.address,
-                chain_id,
-                oracle_data.measurement_timestamp,
-                specification,
-                oracle_data.expiration,
-            )
+            db::blocking(|| {
+                models::ActiveOracle::create(
+                    database_connection,
+                    oracle_data.address,
+                    chain_id,
+                    oracle_data.measurement_timestamp,
+                    specification,
+                    oracle_data.expiration,
+                )
+            })
             .context("could not insert new active oracle into database")?;
 
-            let cid = oracle_data.specification_cid;
-            tokio::spawn(
-                store_cid_ipfs(cid.clone(), data_manager_http_client.clone())
-                    .instrument(info_span!("storing", cid)),
-            );
+            // inline specifications live on-chain, there's no document to retain
+            if let SpecificationSource::Cid(cid) = oracle_data.specification {
+                tokio::spawn(
+                    store_cid_ipfs(cid.clone(), data_manager_http_client.clone())
+                        .instrument(info_span!("storing", cid)),
+                );
+                if ipfs_fetcher.pins() {
+                    tokio::spawn(
+                        pin_cid(cid.clone(), ipfs_fetcher.clone())
+                            .instrument(info_span!("pinning", cid)),
+                    );
+                }
+            }
 
             tracing::info!(
                 "oracle with address 0x{:x} saved to database",
                 oracle_data.address
             );
 
+            metrics::record_oracle_acknowledged(chain_id);
+            if let Some(creation_timestamp) = oracle_data.creation_timestamp {
+                metrics::observe_acknowledgement(chain_id, creation_timestamp);
+            }
+            events::emit(chain_id, oracle_data.address, OracleEventKind::Detected);
+
             Ok(())
         }
         Err(error) => {
@@ -245,6 +481,46 @@ pub async fn acknowledge_active_oracle(
     }
 }
 
+// specifications are looked up in the database cache first, falling back to the
+// data cdn and ipfs gateways
+async fn fetch_specification(
+    cid: &str,
+    db_connection_pool: &Pool<ConnectionManager<DbConnection>>,
+    ipfs_fetcher: &IpfsFetcher,
+) -> anyhow::Result<Specification> {
+    let mut db_connection = db::blocking(|| db_connection_pool.get())
+        .context("could not get new connection from pool")?;
+    match db::blocking(|| models::CachedSpecification::get(&mut db_connection, cid)) {
+        Ok(Some(cached)) => {
+            tracing::debug!("specification with cid {} found in cache", cid);
+            return Ok(cached.specification);
+        }
+        Ok(None) => {}
+        Err(error) => {
+            tracing::warn!(
+                "could not read cached specification with cid {}, fetching it: {:#}",
+                cid,
+                error
+            );
+        }
+    }
+
+    let specification = ipfs_fetcher
+        .fetch_json_with_retry::<Specification>(
+            cid,
+            ExponentialBackoffBuilder::new()
+                .with_max_elapsed_time(Some(FETCH_SPECIFICATION_JSON_MAX_ELAPSED_TIME))
+                .build(),
+        )
+        .await?;
+    if let Err(error) = db::blocking(|| {
+        models::CachedSpecification::create(&mut db_connection, cid, specification.clone())
+    }) {
+        tracing::warn!("{:#}", error);
+    }
+    Ok(specification)
+}
+
 async fn store_cid_ipfs(cid: String, data_manager_http_client: Arc<HttpClient>) {
     match data::store_cid_ipfs_with_retry(
         cid.clone(),
@@ -263,3 +539,67 @@ async fn store_cid_ipfs(cid: String, data_manager_http_client: Arc<HttpClient>)
         }
     }
 }
+
+async fn pin_cid(cid: String, ipfs_fetcher: Arc<IpfsFetcher>) {
+    if let Err(err) = ipfs_fetcher
+        .pin_with_retry(
+            &cid,
+            ExponentialBackoffBuilder::new()
+                .with_max_elapsed_time(Some(STORE_CID_MAX_ELAPSED_TIME))
+                .build(),
+        )
+        .await
+    {
+        tracing::error!("could not pin cid {cid} on the ipfs node: {err:?}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    use crate::specification::{handlers::tvl::TvlPayload, Specification};
+
+    use super::SpecificationSource;
+
+    #[test]
+    fn specification_source() {
+        let specification = Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        });
+        let json = serde_json::to_string(&specification).unwrap();
+
+        assert_eq!(
+            "bafkfoo".parse::<SpecificationSource>().unwrap(),
+            SpecificationSource::Cid("bafkfoo".to_owned())
+        );
+        assert_eq!(
+            format!(" {json} ").parse::<SpecificationSource>().unwrap(),
+            SpecificationSource::Inline(specification.clone())
+        );
+        assert_eq!(
+            format!("data:application/json;base64,{}", STANDARD.encode(&json))
+                .parse::<SpecificationSource>()
+                .unwrap(),
+            SpecificationSource::Inline(specification.clone())
+        );
+        assert_eq!(
+            format!(
+                "data:application/json,{}",
+                json.replace('{', "%7B").replace('}', "%7D")
+            )
+            .parse::<SpecificationSource>()
+            .unwrap(),
+            SpecificationSource::Inline(specification)
+        );
+
+        assert!("{\"foo\":1}".parse::<SpecificationSource>().is_err());
+        assert!("data:application/json;base64,!!"
+            .parse::<SpecificationSource>()
+            .is_err());
+        assert!("data:application/json"
+            .parse::<SpecificationSource>()
+            .is_err());
+    }
+}
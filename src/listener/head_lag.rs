@@ -0,0 +1,165 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use ethers::{middleware::Middleware, providers::Provider};
+use tokio::time::interval;
+
+use crate::{commons::HEAD_LAG_CHECK_INTERVAL, metrics, rpc::FallbackHttp};
+
+use super::Listener;
+
+// tracks for how long the head lag has been over the threshold, so that a single slow
+// batch or a burst of blocks doesn't flag indexing as stale
+struct Staleness {
+    threshold_blocks: u64,
+    window: Duration,
+    lagging_since: Option<Instant>,
+    stale: bool,
+}
+
+impl Staleness {
+    fn new(threshold_blocks: u64, window: Duration) -> Self {
+        Self {
+            threshold_blocks,
+            window,
+            lagging_since: None,
+            stale: false,
+        }
+    }
+
+    // returns the new staleness status only when it changes
+    fn update(&mut self, lag: u64, now: Instant) -> Option<bool> {
+        let stale = if lag > self.threshold_blocks {
+            let lagging_since = *self.lagging_since.get_or_insert(now);
+            now.duration_since(lagging_since) >= self.window
+        } else {
+            self.lagging_since = None;
+            false
+        };
+        if stale == self.stale {
+            return None;
+        }
+        self.stale = stale;
+        Some(stale)
+    }
+}
+
+// compares the chain head with the last block processed by the scanners, so that
+// indexing silently stalling (e.g. a scanner stuck on a failing request while the
+// checkpoint stops moving) gets noticed. until the scanners report their first block
+// the starting checkpoint is used as the last processed one
+pub async fn monitor_head_lag(
+    chain_id: u64,
+    listener: Listener,
+    provider: Arc<Provider<FallbackHttp>>,
+    start_block: u64,
+    threshold_blocks: u64,
+    staleness_window: Duration,
+) -> anyhow::Result<()> {
+    let mut interval = interval(HEAD_LAG_CHECK_INTERVAL);
+    let mut staleness = Staleness::new(threshold_blocks, staleness_window);
+
+    tracing::info!(
+        "reporting indexing as stale when lagging more than {} blocks behind the head for {}s",
+        threshold_blocks,
+        staleness_window.as_secs()
+    );
+
+    loop {
+        interval.tick().await;
+
+        // the circuit breaker already reported the chain as degraded
+        if provider.as_ref().as_ref().is_degraded() {
+            continue;
+        }
+        // followers don't process blocks, the leader reports the chain's lag
+        if !listener.is_leading() {
+            continue;
+        }
+
+        let head = match provider.get_block_number().await {
+            Ok(head) => head.as_u64(),
+            Err(error) => {
+                tracing::error!("could not get current block number: {:#}", error);
+                continue;
+            }
+        };
+        let last_processed_block = listener.last_processed_block().unwrap_or(start_block);
+        let lag = head.saturating_sub(last_processed_block);
+        metrics::set_chain_head_lag(chain_id, lag);
+
+        match staleness.update(lag, Instant::now()) {
+            Some(true) => {
+                tracing::error!(
+                    "indexing stale, last processed block {} is {} blocks behind the head, ADDRESS IMMEDIATELY",
+                    last_processed_block,
+                    lag
+                );
+                metrics::set_chain_indexing_stale(chain_id, true);
+            }
+            Some(false) => {
+                tracing::info!(
+                    "indexing caught up, last processed block {} is {} blocks behind the head",
+                    last_processed_block,
+                    lag
+                );
+                metrics::set_chain_indexing_stale(chain_id, false);
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::Staleness;
+
+    #[test]
+    fn staleness() {
+        let mut staleness = Staleness::new(100, Duration::from_secs(600));
+        let start = Instant::now();
+
+        assert_eq!(staleness.update(10, start), None);
+        // lagging, but not for long enough yet
+        assert_eq!(staleness.update(200, start), None);
+        assert_eq!(
+            staleness.update(200, start + Duration::from_secs(300)),
+            None
+        );
+        assert_eq!(
+            staleness.update(200, start + Duration::from_secs(600)),
+            Some(true)
+        );
+        // only transitions are reported
+        assert_eq!(
+            staleness.update(300, start + Duration::from_secs(900)),
+            None
+        );
+        assert_eq!(
+            staleness.update(50, start + Duration::from_secs(960)),
+            Some(false)
+        );
+
+        // catching up even once restarts the window
+        assert_eq!(
+            staleness.update(200, start + Duration::from_secs(1_000)),
+            None
+        );
+        assert_eq!(
+            staleness.update(50, start + Duration::from_secs(1_300)),
+            None
+        );
+        assert_eq!(
+            staleness.update(200, start + Duration::from_secs(1_400)),
+            None
+        );
+        assert_eq!(
+            staleness.update(200, start + Duration::from_secs(1_700)),
+            None
+        );
+    }
+}
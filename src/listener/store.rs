@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use answerer_framework::scanner::ScanStore;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
+use ethers::types::{Address, Log};
+
+use crate::db::{models, DbConnection};
+
+// keeps the scanners' progress in the checkpoints, scanned ranges, scanned factories,
+// indexed logs and scanner leases tables
+pub struct PgScanStore {
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+}
+
+impl PgScanStore {
+    pub fn new(db_connection_pool: Pool<ConnectionManager<DbConnection>>) -> Self {
+        Self { db_connection_pool }
+    }
+
+    fn connection(&self) -> anyhow::Result<PooledConnection<ConnectionManager<DbConnection>>> {
+        Ok(self.db_connection_pool.get()?)
+    }
+}
+
+impl ScanStore for PgScanStore {
+    fn checkpoint(&self, chain_id: u64) -> anyhow::Result<Option<u64>> {
+        Ok(
+            models::Checkpoint::get_for_chain_id(&mut *self.connection()?, chain_id)?
+                .map(|checkpoint| checkpoint.block_number as u64),
+        )
+    }
+
+    fn update_checkpoint(&self, chain_id: u64, block_number: u64) -> anyhow::Result<()> {
+        models::Checkpoint::update(&mut *self.connection()?, chain_id, block_number as i64)
+    }
+
+    fn record_scanned_range(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<()> {
+        models::ScannedRange::record(&mut *self.connection()?, chain_id, from_block, to_block)
+    }
+
+    fn scanned_ranges(&self, chain_id: u64) -> anyhow::Result<Vec<(u64, u64)>> {
+        Ok(
+            models::ScannedRange::get_all_for_chain_id(&mut *self.connection()?, chain_id)?
+                .into_iter()
+                .map(|range| (range.from_block as u64, range.to_block as u64))
+                .collect(),
+        )
+    }
+
+    fn record_scanned_factory(&self, chain_id: u64, address: Address) -> anyhow::Result<()> {
+        models::ScannedFactory::record(&mut *self.connection()?, chain_id, address)
+    }
+
+    fn scanned_factories(&self, chain_id: u64) -> anyhow::Result<Vec<Address>> {
+        Ok(
+            models::ScannedFactory::get_all_for_chain_id(&mut *self.connection()?, chain_id)?
+                .into_iter()
+                .map(|factory| factory.address.0)
+                .collect(),
+        )
+    }
+
+    fn persist_log(&self, chain_id: u64, log: &Log) -> anyhow::Result<()> {
+        models::IndexedLog::create(&mut *self.connection()?, chain_id, log)
+    }
+
+    // indexed logs that can't be turned back into logs are skipped
+    fn persisted_logs(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<Log>> {
+        Ok(models::IndexedLog::get_all_for_chain_id_between(
+            &mut *self.connection()?,
+            chain_id,
+            from_block,
+            to_block,
+        )?
+        .into_iter()
+        .filter_map(|indexed_log| {
+            indexed_log
+                .to_log()
+                .inspect_err(|error| tracing::error!("{:#}", error))
+                .ok()
+        })
+        .collect())
+    }
+
+    fn try_acquire_lease(
+        &self,
+        chain_id: u64,
+        holder: &str,
+        duration: Duration,
+    ) -> anyhow::Result<bool> {
+        models::ScannerLease::try_acquire(&mut *self.connection()?, chain_id, holder, duration)
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fmt::Debug,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use serde::Serialize;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Level, Subscriber,
+};
+use tracing_subscriber::{
+    filter::LevelFilter,
+    fmt::writer::{BoxMakeWriter, MakeWriterExt},
+    layer::{self, SubscriberExt},
+    registry::LookupSpan,
+    EnvFilter, FmtSubscriber, Layer,
+};
+use utoipa::ToSchema;
+
+use crate::commons::{LogFormat, LoggingConfig, LOG_FILE_MAX_FILES, LOG_FILE_MAX_SIZE_MB};
+
+// logs always go to stdout, or stderr if asked to, and to the configured file on top
+// of that. RUST_LOG, when set, takes precedence over the levels from the config
+pub fn setup_logging(config: LoggingConfig, to_stderr: bool) -> anyhow::Result<()> {
+    let level = match config.level.as_deref() {
+        Some(level) => level
+            .parse::<LevelFilter>()
+            .context(format!("invalid log level {}", level))?,
+        None => LevelFilter::INFO,
+    };
+    let filter_builder = EnvFilter::builder().with_default_directive(level.into());
+    let filter = if env::var_os(EnvFilter::DEFAULT_ENV).is_some() {
+        filter_builder
+            .from_env()
+            .context("could not get log level")?
+    } else {
+        let mut filter = filter_builder.parse_lossy("");
+        for (target, level) in config.targets.unwrap_or_default() {
+            filter = filter.add_directive(
+                format!("{}={}", target, level)
+                    .parse()
+                    .context(format!("invalid log level {} for target {}", level, target))?,
+            );
+        }
+        filter
+    };
+
+    // escape codes would end up in the file otherwise
+    let ansi = config.file.is_none();
+    let console = if to_stderr {
+        BoxMakeWriter::new(io::stderr)
+    } else {
+        BoxMakeWriter::new(io::stdout)
+    };
+    let writer = match config.file {
+        Some(file_config) => {
+            let file = RotatingFile::open(
+                file_config.path.clone(),
+                file_config.max_size_mb.unwrap_or(LOG_FILE_MAX_SIZE_MB) * 1_024 * 1_024,
+                file_config.max_files.unwrap_or(LOG_FILE_MAX_FILES),
+            )
+            .context(format!(
+                "could not open log file {}",
+                file_config.path.display()
+            ))?;
+            BoxMakeWriter::new(console.and(Mutex::new(file)))
+        }
+        None => console,
+    };
+
+    let builder = FmtSubscriber::builder()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(ansi);
+    let subscriber: Box<dyn Subscriber + Send + Sync> = match config.format.unwrap_or_default() {
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .with_span_list(true)
+                .with_current_span(false)
+                .finish()
+                .with(ChainErrors),
+        ),
+        LogFormat::Pretty => Box::new(builder.pretty().finish().with(ChainErrors)),
+    };
+    tracing::subscriber::set_global_default(subscriber).context("tracing initialization failed")
+}
+
+// the latest error logged by each chain's tasks, reported by the status endpoint
+static LAST_CHAIN_ERRORS: LazyLock<Mutex<HashMap<u64, ChainError>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Serialize, ToSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainError {
+    pub message: String,
+    pub timestamp: u64,
+}
+
+pub fn last_chain_error(chain_id: u64) -> Option<ChainError> {
+    LAST_CHAIN_ERRORS
+        .lock()
+        .unwrap() // this should never panic
+        .get(&chain_id)
+        .cloned()
+}
+
+// stored in the extensions of spans carrying a chain_id field
+struct SpanChainId(u64);
+
+#[derive(Default)]
+struct ChainIdVisitor(Option<u64>);
+
+impl Visit for ChainIdVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "chain_id" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn Debug) {}
+}
+
+#[derive(Default)]
+struct MessageVisitor(Option<String>);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+// keeps track of the latest error logged within each chain's spans
+struct ChainErrors;
+
+impl<S> Layer<S> for ChainErrors
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attributes: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: layer::Context<'_, S>,
+    ) {
+        let mut visitor = ChainIdVisitor::default();
+        attributes.record(&mut visitor);
+        if let (Some(chain_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(SpanChainId(chain_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: layer::Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+        let chain_id = ctx.event_scope(event).and_then(|mut scope| {
+            scope.find_map(|span| span.extensions().get::<SpanChainId>().map(|id| id.0))
+        });
+        let chain_id = match chain_id {
+            Some(chain_id) => chain_id,
+            None => return,
+        };
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        LAST_CHAIN_ERRORS
+            .lock()
+            .unwrap() // this should never panic
+            .insert(
+                chain_id,
+                ChainError {
+                    message: visitor.0.unwrap_or_default(),
+                    timestamp,
+                },
+            );
+    }
+}
+
+// a log file that's rotated once writing to it would make it grow past the max size.
+// rotated files get a numeric suffix, the most recent being .1, and only the latest
+// max files of them are kept
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files > 0 {
+            for index in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated_path = OsString::from(path.as_os_str());
+    rotated_path.push(format!(".{}", index));
+    PathBuf::from(rotated_path)
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // events are written in one go, so a line is never split across files
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, io::Write, process};
+
+    use tracing::info_span;
+    use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+    use super::{last_chain_error, rotated_path, ChainErrors, RotatingFile};
+
+    #[test]
+    fn rotate_log_file() {
+        let dir = env::temp_dir().join(format!("defillama-answerer-logs-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("answerer.log");
+
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "second\n"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+
+        // reopening appends, and the existing size counts towards the max
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+        file.write_all(b"ab\n").unwrap();
+        file.write_all(b"c\n").unwrap();
+        file.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "c\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "fourth\nab\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn track_last_chain_error() {
+        let subscriber = Registry::default().with(ChainErrors);
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = info_span!("answerer", chain_id = 4_242u64).entered();
+            tracing::error!("first error");
+            tracing::warn!("not an error");
+            {
+                let _span = info_span!("answer", oracle_address = "0x1").entered();
+                tracing::error!("second error {}", 2);
+            }
+        });
+        assert_eq!(last_chain_error(4_242).unwrap().message, "second error 2");
+        assert!(last_chain_error(4_243).is_none());
+    }
+}
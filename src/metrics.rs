@@ -0,0 +1,419 @@
+use std::{
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use prometheus::{
+    register_counter_vec_with_registry, register_histogram_vec_with_registry,
+    register_histogram_with_registry, register_int_counter_vec_with_registry,
+    register_int_gauge_vec_with_registry, CounterVec, Encoder, Histogram, HistogramVec,
+    IntCounterVec, IntGaugeVec, Registry, TextEncoder,
+};
+
+// latencies go from a few seconds in the happy path to hours when something
+// goes wrong, so the buckets are spread accordingly
+const LATENCY_BUCKETS: &[f64] = &[
+    5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1_800.0, 3_600.0, 10_800.0, 43_200.0, 86_400.0,
+];
+
+// defillama usually replies within a second, but slow responses up to the http
+// timeout are worth telling apart
+const DEFILLAMA_LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+// requests usually go through right away, waits of seconds mean the rate limit is
+// the bottleneck
+const DEFILLAMA_RATE_LIMITER_WAIT_BUCKETS: &[f64] =
+    &[0.001, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+pub static ORACLES_DETECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "oracles_detected_total",
+        "Oracle creation logs found by the scanners, excluding already known oracles",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ORACLES_ACKNOWLEDGED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "oracles_acknowledged_total",
+        "Oracles with a valid specification saved as active",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ANSWERS_COMPUTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "answers_computed_total",
+        "Answers computed for active oracles",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ANSWER_TXS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "answer_txs_total",
+        "Answer transactions by status, either submitted, confirmed or failed",
+        &["chain_id", "status"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ANSWER_GAS_USED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "answer_gas_used_total",
+        "Gas used by confirmed answer transactions",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ANSWER_FEES: LazyLock<CounterVec> = LazyLock::new(|| {
+    register_counter_vec_with_registry!(
+        "answer_fees_total",
+        "Fees paid for confirmed answer transactions, in the chain's native token",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ANSWER_FEES_USD: LazyLock<CounterVec> = LazyLock::new(|| {
+    register_counter_vec_with_registry!(
+        "answer_fees_usd_total",
+        "Fees paid for confirmed answer transactions, in usd, when the native token was priced",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+// labeled by the first segment of the path, as the rest usually contains a protocol
+// name and would make for too many series
+pub static DEFILLAMA_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec_with_registry!(
+        "defillama_request_duration_seconds",
+        "Duration of the requests made to defillama, by outcome (success, missing or error)",
+        &["endpoint", "outcome"],
+        DEFILLAMA_LATENCY_BUCKETS.to_vec(),
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static DEFILLAMA_RESPONSES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "defillama_responses_total",
+        "Responses received from defillama, by status code, or timeout and connection when none was",
+        &["endpoint", "status"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static DEFILLAMA_RATE_LIMITER_WAIT: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram_with_registry!(
+        "defillama_rate_limiter_wait_seconds",
+        "Time defillama requests spent waiting for the rate limiters before being sent",
+        DEFILLAMA_RATE_LIMITER_WAIT_BUCKETS.to_vec(),
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ACKNOWLEDGEMENT_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec_with_registry!(
+        "oracle_acknowledgement_latency_seconds",
+        "Time between an oracle's creation block and its acknowledgement",
+        &["chain_id"],
+        LATENCY_BUCKETS.to_vec(),
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static FINALIZATION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec_with_registry!(
+        "oracle_finalization_latency_seconds",
+        "Time between an oracle's measurement timestamp and its finalization",
+        &["chain_id"],
+        LATENCY_BUCKETS.to_vec(),
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static ORPHANED_ANSWER_TXS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec_with_registry!(
+        "orphaned_answer_txs_total",
+        "Answer transactions that were neither mined nor in the mempool when collected",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+// endpoints are labeled by their position in the config rather than by url, as
+// urls often embed api keys
+pub static RPC_ENDPOINT_HEALTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec_with_registry!(
+        "rpc_endpoint_health_score",
+        "Health score of the rpc endpoints, from 0 to 100",
+        &["chain_id", "endpoint"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static RPC_CHAIN_DEGRADED: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec_with_registry!(
+        "rpc_chain_degraded",
+        "Whether the rpc circuit breaker of a chain is open (1) or not (0)",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static CHAIN_HEAD_LAG: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec_with_registry!(
+        "chain_head_lag_blocks",
+        "Blocks between the chain head and the last block processed by the scanners",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+pub static CHAIN_INDEXING_STALE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec_with_registry!(
+        "chain_indexing_stale",
+        "Whether the head lag of a chain has been over the threshold for too long (1) or not (0)",
+        &["chain_id"],
+        REGISTRY
+    )
+    .unwrap() // this should never panic
+});
+
+// observes the time elapsed since the given timestamp, which is clamped to
+// 0 in case of clock skews between the local machine and the chain
+fn observe_elapsed_since(histogram: &HistogramVec, chain_id: u64, since: SystemTime) {
+    let elapsed = SystemTime::now()
+        .duration_since(since)
+        .unwrap_or(Duration::ZERO);
+    histogram
+        .with_label_values(&[&chain_id.to_string()])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn observe_acknowledgement(chain_id: u64, creation_timestamp: SystemTime) {
+    observe_elapsed_since(&ACKNOWLEDGEMENT_LATENCY, chain_id, creation_timestamp);
+}
+
+pub fn observe_finalization(chain_id: u64, measurement_timestamp: SystemTime) {
+    observe_elapsed_since(&FINALIZATION_LATENCY, chain_id, measurement_timestamp);
+}
+
+pub fn record_oracle_detected(chain_id: u64) {
+    ORACLES_DETECTED
+        .with_label_values(&[&chain_id.to_string()])
+        .inc();
+}
+
+pub fn record_oracle_acknowledged(chain_id: u64) {
+    ORACLES_ACKNOWLEDGED
+        .with_label_values(&[&chain_id.to_string()])
+        .inc();
+}
+
+pub fn record_answer_computed(chain_id: u64) {
+    ANSWERS_COMPUTED
+        .with_label_values(&[&chain_id.to_string()])
+        .inc();
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AnswerTxStatus {
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+impl AnswerTxStatus {
+    fn name(&self) -> &'static str {
+        match self {
+            AnswerTxStatus::Submitted => "submitted",
+            AnswerTxStatus::Confirmed => "confirmed",
+            AnswerTxStatus::Failed => "failed",
+        }
+    }
+}
+
+pub fn record_answer_tx(chain_id: u64, status: AnswerTxStatus) {
+    ANSWER_TXS
+        .with_label_values(&[&chain_id.to_string(), status.name()])
+        .inc();
+}
+
+pub fn record_answer_fee(chain_id: u64, gas_used: u64, fee: f64, fee_usd: Option<f64>) {
+    let chain_id = chain_id.to_string();
+    ANSWER_GAS_USED
+        .with_label_values(&[&chain_id])
+        .inc_by(gas_used);
+    ANSWER_FEES.with_label_values(&[&chain_id]).inc_by(fee);
+    if let Some(fee_usd) = fee_usd {
+        ANSWER_FEES_USD
+            .with_label_values(&[&chain_id])
+            .inc_by(fee_usd);
+    }
+}
+
+fn defillama_endpoint(path: &str) -> &str {
+    path.trim_start_matches('/')
+        .split(['/', '?'])
+        .next()
+        .unwrap_or_default()
+}
+
+pub fn observe_defillama_request(path: &str, outcome: &str, duration: Duration) {
+    DEFILLAMA_REQUEST_DURATION
+        .with_label_values(&[defillama_endpoint(path), outcome])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_defillama_response(path: &str, status: &str) {
+    DEFILLAMA_RESPONSES
+        .with_label_values(&[defillama_endpoint(path), status])
+        .inc();
+}
+
+pub fn observe_defillama_rate_limiter_wait(duration: Duration) {
+    DEFILLAMA_RATE_LIMITER_WAIT.observe(duration.as_secs_f64());
+}
+
+pub fn record_orphaned_answer_tx(chain_id: u64) {
+    ORPHANED_ANSWER_TXS
+        .with_label_values(&[&chain_id.to_string()])
+        .inc();
+}
+
+pub fn set_rpc_endpoint_health(chain_id: u64, endpoint: usize, score: u32) {
+    RPC_ENDPOINT_HEALTH
+        .with_label_values(&[&chain_id.to_string(), &endpoint.to_string()])
+        .set(score as i64);
+}
+
+pub fn set_rpc_chain_degraded(chain_id: u64, degraded: bool) {
+    RPC_CHAIN_DEGRADED
+        .with_label_values(&[&chain_id.to_string()])
+        .set(degraded as i64);
+}
+
+pub fn set_chain_head_lag(chain_id: u64, lag: u64) {
+    CHAIN_HEAD_LAG
+        .with_label_values(&[&chain_id.to_string()])
+        .set(lag as i64);
+}
+
+pub fn set_chain_indexing_stale(chain_id: u64, stale: bool) {
+    CHAIN_INDEXING_STALE
+        .with_label_values(&[&chain_id.to_string()])
+        .set(stale as i64);
+}
+
+pub fn encode() -> anyhow::Result<String> {
+    // make sure the metrics are registered even if never observed
+    LazyLock::force(&ORACLES_DETECTED);
+    LazyLock::force(&ORACLES_ACKNOWLEDGED);
+    LazyLock::force(&ANSWERS_COMPUTED);
+    LazyLock::force(&ANSWER_TXS);
+    LazyLock::force(&ANSWER_GAS_USED);
+    LazyLock::force(&ANSWER_FEES);
+    LazyLock::force(&ANSWER_FEES_USD);
+    LazyLock::force(&DEFILLAMA_REQUEST_DURATION);
+    LazyLock::force(&DEFILLAMA_RESPONSES);
+    LazyLock::force(&DEFILLAMA_RATE_LIMITER_WAIT);
+    LazyLock::force(&ACKNOWLEDGEMENT_LATENCY);
+    LazyLock::force(&FINALIZATION_LATENCY);
+    LazyLock::force(&ORPHANED_ANSWER_TXS);
+    LazyLock::force(&RPC_ENDPOINT_HEALTH);
+    LazyLock::force(&RPC_CHAIN_DEGRADED);
+    LazyLock::force(&CHAIN_HEAD_LAG);
+    LazyLock::force(&CHAIN_INDEXING_STALE);
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .context("could not encode metrics")?;
+    String::from_utf8(buffer).context("could not convert encoded metrics to string")
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        encode, observe_acknowledgement, observe_defillama_rate_limiter_wait,
+        observe_defillama_request, observe_finalization, record_answer_fee, record_answer_tx,
+        record_defillama_response, AnswerTxStatus,
+    };
+
+    #[test]
+    fn observe_and_encode() {
+        observe_acknowledgement(100, SystemTime::now() - Duration::from_secs(20));
+        observe_finalization(100, SystemTime::now() - Duration::from_secs(200));
+        // timestamps in the future are clamped
+        observe_finalization(1, SystemTime::now() + Duration::from_secs(200));
+
+        let encoded = encode().unwrap();
+        assert!(encoded.contains(
+            "oracle_acknowledgement_latency_seconds_bucket{chain_id=\"100\",le=\"30\"} 1"
+        ));
+        assert!(encoded
+            .contains("oracle_finalization_latency_seconds_bucket{chain_id=\"100\",le=\"120\"} 0"));
+        assert!(encoded
+            .contains("oracle_finalization_latency_seconds_bucket{chain_id=\"100\",le=\"300\"} 1"));
+        assert!(encoded
+            .contains("oracle_finalization_latency_seconds_bucket{chain_id=\"1\",le=\"5\"} 1"));
+    }
+
+    #[test]
+    fn record_answering_and_encode() {
+        record_answer_tx(999_999, AnswerTxStatus::Submitted);
+        record_answer_tx(999_999, AnswerTxStatus::Confirmed);
+        record_answer_fee(999_999, 21_000, 0.5, None);
+        record_answer_fee(999_999, 21_000, 0.25, Some(10.0));
+        observe_defillama_request("/foo/bar", "success", Duration::from_millis(300));
+        observe_defillama_request("foo?bar=baz", "success", Duration::from_millis(20));
+        record_defillama_response("/foo/bar", "503");
+        record_defillama_response("/foo/baz", "503");
+        record_defillama_response("/foo", "timeout");
+        observe_defillama_rate_limiter_wait(Duration::from_millis(30));
+
+        let encoded = encode().unwrap();
+        assert!(encoded.contains("answer_txs_total{chain_id=\"999999\",status=\"submitted\"} 1"));
+        assert!(encoded.contains("answer_txs_total{chain_id=\"999999\",status=\"confirmed\"} 1"));
+        assert!(encoded.contains("answer_gas_used_total{chain_id=\"999999\"} 42000"));
+        assert!(encoded.contains("answer_fees_total{chain_id=\"999999\"} 0.75"));
+        assert!(encoded.contains("answer_fees_usd_total{chain_id=\"999999\"} 10"));
+        assert!(encoded.contains(
+            "defillama_request_duration_seconds_count{endpoint=\"foo\",outcome=\"success\"} 2"
+        ));
+        assert!(encoded.contains("defillama_responses_total{endpoint=\"foo\",status=\"503\"} 2"));
+        assert!(
+            encoded.contains("defillama_responses_total{endpoint=\"foo\",status=\"timeout\"} 1")
+        );
+        // other tests' requests are observed too
+        assert!(encoded.contains("defillama_rate_limiter_wait_seconds_count"));
+    }
+}
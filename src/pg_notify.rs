@@ -0,0 +1,85 @@
+use anyhow::Context;
+use diesel::{
+    r2d2::{ConnectionManager, Pool},
+    sql_types::Text,
+    PgConnection, RunQueryDsl,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    commons::{PgNotifyConfig, PG_NOTIFY_CHANNEL},
+    db,
+    events::{self, OracleEvent},
+};
+
+// sends the event as json on the channel, to be picked up by whoever is listening on
+// it. notifications are only delivered once the surrounding transaction commits
+pub fn notify(
+    connection: &mut PgConnection,
+    channel: &str,
+    event: &OracleEvent,
+) -> anyhow::Result<()> {
+    let payload = serde_json::to_string(event).context("could not serialize event")?;
+    diesel::sql_query("SELECT pg_notify($1, $2)")
+        .bind::<Text, _>(channel)
+        .bind::<Text, _>(payload)
+        .execute(connection)
+        .context(format!(
+            "could not notify {} event for oracle {} on channel {}",
+            event.kind.name(),
+            event.oracle_address,
+            channel
+        ))?;
+    Ok(())
+}
+
+// lets lightweight consumers react to oracle lifecycle events with nothing but a
+// connection to the database, by listening on the configured channel
+pub async fn notify_oracle_events(
+    pg_notify: PgNotifyConfig,
+    db_connection_pool: Pool<ConnectionManager<PgConnection>>,
+) -> anyhow::Result<()> {
+    let channel = pg_notify
+        .channel
+        .unwrap_or_else(|| PG_NOTIFY_CHANNEL.to_owned());
+    let mut events = events::subscribe();
+
+    tracing::info!(
+        "notifying {} oracle events on postgres channel {}",
+        pg_notify
+            .events
+            .as_ref()
+            .map(|events| events.join(", "))
+            .unwrap_or("all".to_owned()),
+        channel
+    );
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "postgres notifications are lagging behind, {} event(s) were skipped",
+                    skipped
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        if pg_notify
+            .events
+            .as_ref()
+            .is_some_and(|events| !events.iter().any(|name| name == event.kind.name()))
+        {
+            continue;
+        }
+        if let Err(error) = db::blocking(|| {
+            let mut db_connection = db_connection_pool
+                .get()
+                .context("could not get new connection from pool")?;
+            notify(&mut db_connection, &channel, &event)
+        }) {
+            tracing::error!("{:#}", error);
+        }
+    }
+}
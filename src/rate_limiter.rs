@@ -0,0 +1,85 @@
+use std::{sync::OnceLock, time::Duration};
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use tokio::time::sleep;
+
+use crate::{
+    commons::SharedRateLimitConfig,
+    db::{self, models::RateLimitBucket, DbConnection},
+};
+
+const DEFAULT_DEFILLAMA_BUCKET: &str = "defillama";
+
+// set at startup when the defillama quota is shared with other replicas or services
+static DEFILLAMA_RATE_LIMITER: OnceLock<SharedRateLimiter> = OnceLock::new();
+
+// token bucket stored in postgres, so that every process using the same bucket
+// collectively stays within the configured rate. in-process limiters still apply
+// on top of it
+pub struct SharedRateLimiter {
+    bucket: String,
+    capacity: f64,
+    requests_per_second: f64,
+    db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+}
+
+impl SharedRateLimiter {
+    pub fn new(
+        config: SharedRateLimitConfig,
+        db_connection_pool: Pool<ConnectionManager<DbConnection>>,
+    ) -> Self {
+        Self {
+            bucket: config
+                .bucket
+                .unwrap_or_else(|| DEFAULT_DEFILLAMA_BUCKET.to_owned()),
+            capacity: config.burst.unwrap_or(1.0).max(1.0),
+            requests_per_second: config.requests_per_second,
+            db_connection_pool,
+        }
+    }
+
+    pub async fn until_ready(&self) {
+        let retry_interval = Duration::from_secs_f64(1.0 / self.requests_per_second.max(0.001));
+        loop {
+            let acquired = db::blocking(|| {
+                self.db_connection_pool
+                    .get()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|mut db_connection| {
+                        RateLimitBucket::try_acquire(
+                            &mut db_connection,
+                            &self.bucket,
+                            self.capacity,
+                            self.requests_per_second,
+                        )
+                    })
+            });
+            match acquired {
+                Ok(true) => return,
+                Ok(false) => sleep(retry_interval).await,
+                Err(error) => {
+                    // the in-process limiter still applies, so letting the request
+                    // through is better than stalling every defillama call
+                    tracing::warn!(
+                        "could not use shared rate limit bucket {}, skipping it: {:#}",
+                        self.bucket,
+                        error
+                    );
+                    return;
+                }
+            }
+        }
+    }
+}
+
+pub fn set_defillama_rate_limiter(rate_limiter: SharedRateLimiter) {
+    if DEFILLAMA_RATE_LIMITER.set(rate_limiter).is_err() {
+        tracing::warn!("defillama shared rate limiter already set");
+    }
+}
+
+pub async fn defillama_until_ready() {
+    if let Some(rate_limiter) = DEFILLAMA_RATE_LIMITER.get() {
+        rate_limiter.until_ready().await;
+    }
+}
@@ -0,0 +1,494 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    num::NonZeroU32,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use answerer_framework::scanner::Degradable;
+use anyhow::Context;
+use async_trait::async_trait;
+use ethers::providers::{
+    Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError,
+};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::metrics;
+
+const MAX_HEALTH_SCORE: u32 = 100;
+const HEALTH_SCORE_FAILURE_PENALTY: u32 = 10;
+
+#[derive(Debug)]
+struct EndpointHealth {
+    // goes down quickly on failures and recovers slowly on successes, so that
+    // endpoints that keep flapping are not picked over steadier ones
+    score: u32,
+    consecutive_failures: u32,
+}
+
+struct Endpoint {
+    url: String,
+    client: Http,
+    health: Mutex<EndpointHealth>,
+    // caps the requests sent to the endpoint, including retries coming from failovers
+    rate_limiter: Option<DefaultDirectRateLimiter>,
+}
+
+impl Debug for Endpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("url", &self.url)
+            .field("health", &self.health)
+            .field("rate_limited", &self.rate_limiter.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    // failed requests in a row, each of which might have been tried on several endpoints
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub enum FallbackHttpError {
+    Http(HttpClientError),
+    // the request was not sent at all as the chain's endpoints are degraded
+    CircuitOpen,
+}
+
+impl Display for FallbackHttpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FallbackHttpError::Http(error) => Display::fmt(error, f),
+            FallbackHttpError::CircuitOpen => {
+                write!(f, "rpc circuit breaker open, request not sent")
+            }
+        }
+    }
+}
+
+impl Error for FallbackHttpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FallbackHttpError::Http(error) => Some(error),
+            FallbackHttpError::CircuitOpen => None,
+        }
+    }
+}
+
+impl RpcError for FallbackHttpError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            FallbackHttpError::Http(error) => error.as_error_response(),
+            FallbackHttpError::CircuitOpen => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            FallbackHttpError::Http(error) => error.as_serde_error(),
+            FallbackHttpError::CircuitOpen => None,
+        }
+    }
+}
+
+impl From<FallbackHttpError> for ProviderError {
+    fn from(error: FallbackHttpError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(error))
+    }
+}
+
+// json rpc transport spreading requests over a list of endpoints. all requests go to
+// the active endpoint, which is swapped for the healthiest of the others once it
+// fails the given number of times in a row. used by both the logs scanners and the
+// answerer, so that a misbehaving node doesn't stall either of them. clones share the
+// endpoints' health, so a single instance should be cloned for all of a chain's providers.
+// when requests keep failing on all endpoints, an optional circuit breaker stops sending
+// them for a cooldown period, after which a single failure trips it again
+#[derive(Debug, Clone)]
+pub struct FallbackHttp {
+    chain_id: u64,
+    endpoints: Arc<Vec<Endpoint>>,
+    active: Arc<AtomicUsize>,
+    failover_threshold: u32,
+    circuit_breaker: Arc<Mutex<CircuitBreaker>>,
+    circuit_breaker_threshold: Option<u32>,
+    circuit_breaker_cooldown: Duration,
+}
+
+impl FallbackHttp {
+    // endpoints come with the maximum requests per second they accept, if any
+    pub fn new(
+        chain_id: u64,
+        endpoints: Vec<(String, Option<u32>)>,
+        failover_threshold: u32,
+    ) -> anyhow::Result<Self> {
+        if endpoints.is_empty() {
+            anyhow::bail!("at least one rpc endpoint is needed");
+        }
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(url, max_rps)| {
+                Ok(Endpoint {
+                    client: Http::from_str(&url)
+                        .context(format!("could not parse rpc endpoint {}", url))?,
+                    url,
+                    health: Mutex::new(EndpointHealth {
+                        score: MAX_HEALTH_SCORE,
+                        consecutive_failures: 0,
+                    }),
+                    rate_limiter: max_rps.map(|max_rps| {
+                        RateLimiter::direct(Quota::per_second(
+                            NonZeroU32::new(max_rps.max(1)).unwrap(), // this should never panic
+                        ))
+                    }),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let fallback_http = Self {
+            chain_id,
+            endpoints: Arc::new(endpoints),
+            active: Arc::new(AtomicUsize::new(0)),
+            failover_threshold: failover_threshold.max(1),
+            circuit_breaker: Arc::new(Mutex::new(CircuitBreaker::default())),
+            circuit_breaker_threshold: None,
+            circuit_breaker_cooldown: Duration::ZERO,
+        };
+        for index in 0..fallback_http.endpoints.len() {
+            metrics::set_rpc_endpoint_health(chain_id, index, MAX_HEALTH_SCORE);
+        }
+        metrics::set_rpc_chain_degraded(chain_id, false);
+        Ok(fallback_http)
+    }
+
+    pub fn with_circuit_breaker(mut self, threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker_threshold = Some(threshold.max(1));
+        self.circuit_breaker_cooldown = cooldown;
+        self
+    }
+
+    // true while the circuit breaker is open, in which case requests fail right away
+    pub fn is_degraded(&self) -> bool {
+        self.circuit_breaker
+            .lock()
+            .unwrap()
+            .open_until
+            .is_some_and(|open_until| Instant::now() < open_until)
+    }
+
+    fn record_request_success(&self) {
+        let mut circuit_breaker = self.circuit_breaker.lock().unwrap();
+        if circuit_breaker.open_until.take().is_some() {
+            tracing::info!("rpc endpoints of chain {} recovered", self.chain_id);
+            metrics::set_rpc_chain_degraded(self.chain_id, false);
+        }
+        circuit_breaker.consecutive_failures = 0;
+    }
+
+    fn record_request_failure(&self) {
+        let threshold = match self.circuit_breaker_threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        let mut circuit_breaker = self.circuit_breaker.lock().unwrap();
+        circuit_breaker.consecutive_failures += 1;
+        if circuit_breaker.consecutive_failures < threshold {
+            return;
+        }
+        // concurrent requests sent right before the breaker tripped don't extend the cooldown
+        if circuit_breaker
+            .open_until
+            .is_some_and(|open_until| Instant::now() < open_until)
+        {
+            return;
+        }
+        if circuit_breaker.open_until.is_none() {
+            metrics::set_rpc_chain_degraded(self.chain_id, true);
+        }
+        circuit_breaker.open_until = Some(Instant::now() + self.circuit_breaker_cooldown);
+        tracing::error!(
+            "chain {} degraded, {} rpc requests failed in a row, pausing requests for {}s",
+            self.chain_id,
+            circuit_breaker.consecutive_failures,
+            self.circuit_breaker_cooldown.as_secs()
+        );
+    }
+
+    pub fn active_url(&self) -> &str {
+        &self.endpoints[self.active.load(Ordering::Relaxed)].url
+    }
+
+    pub fn health_scores(&self) -> Vec<u32> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.health.lock().unwrap().score)
+            .collect()
+    }
+
+    fn record_success(&self, index: usize) {
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        health.consecutive_failures = 0;
+        if health.score < MAX_HEALTH_SCORE {
+            health.score += 1;
+            metrics::set_rpc_endpoint_health(self.chain_id, index, health.score);
+        }
+    }
+
+    // returns true if the failure caused the active endpoint to be rotated
+    fn record_failure(&self, index: usize) -> bool {
+        let consecutive_failures = {
+            let mut health = self.endpoints[index].health.lock().unwrap();
+            health.consecutive_failures += 1;
+            health.score = health.score.saturating_sub(HEALTH_SCORE_FAILURE_PENALTY);
+            metrics::set_rpc_endpoint_health(self.chain_id, index, health.score);
+            health.consecutive_failures
+        };
+        if self.endpoints.len() == 1 || consecutive_failures < self.failover_threshold {
+            return false;
+        }
+
+        // ties are broken in favor of the endpoints coming right after the failing one
+        let next = (1..self.endpoints.len())
+            .map(|offset| (index + offset) % self.endpoints.len())
+            .rev()
+            .max_by_key(|candidate| self.endpoints[*candidate].health.lock().unwrap().score)
+            .unwrap(); // this should never panic
+        if self
+            .active
+            .compare_exchange(index, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            // another request already rotated away from the failing endpoint
+            return true;
+        }
+        self.endpoints[index]
+            .health
+            .lock()
+            .unwrap()
+            .consecutive_failures = 0;
+        tracing::warn!(
+            "rpc endpoint {} failed {} times in a row, switching to {}",
+            self.endpoints[index].url,
+            consecutive_failures,
+            self.endpoints[next].url
+        );
+        true
+    }
+}
+
+// only transport failures and garbled responses count against an endpoint, json rpc
+// errors (e.g. reverts) are legitimate responses
+fn is_endpoint_failure(error: &HttpClientError) -> bool {
+    !matches!(error, HttpClientError::JsonRpcError(_))
+}
+
+impl Degradable for FallbackHttp {
+    fn is_degraded(&self) -> bool {
+        FallbackHttp::is_degraded(self)
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FallbackHttp {
+    type Error = FallbackHttpError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        if self.is_degraded() {
+            return Err(FallbackHttpError::CircuitOpen);
+        }
+
+        // params are serialized upfront so that the request can be sent again to
+        // another endpoint
+        let params = serde_json::to_value(params).map_err(|err| {
+            FallbackHttpError::Http(HttpClientError::SerdeJson {
+                err,
+                text: "could not serialize request params".to_owned(),
+            })
+        })?;
+        let mut attempts = 0;
+        loop {
+            let index = self.active.load(Ordering::Relaxed);
+            if let Some(rate_limiter) = &self.endpoints[index].rate_limiter {
+                rate_limiter.until_ready().await;
+            }
+            match self.endpoints[index]
+                .client
+                .request(method, params.clone())
+                .await
+            {
+                Ok(response) => {
+                    self.record_success(index);
+                    self.record_request_success();
+                    return Ok(response);
+                }
+                Err(error) => {
+                    attempts += 1;
+                    if !is_endpoint_failure(&error) {
+                        self.record_request_success();
+                        return Err(FallbackHttpError::Http(error));
+                    }
+                    if self.record_failure(index) && attempts < self.endpoints.len() {
+                        continue;
+                    }
+                    self.record_request_failure();
+                    return Err(FallbackHttpError::Http(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use ethers::{
+        providers::{Middleware, Provider},
+        types::U64,
+    };
+    use wiremock::{
+        matchers::{body_partial_json, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::FallbackHttp;
+
+    async fn block_number_server(block_number: Option<u64>) -> MockServer {
+        let server = MockServer::start().await;
+        let response = match block_number {
+            Some(block_number) => ResponseTemplate::new(200).set_body_string(format!(
+                r#"{{"jsonrpc":"2.0","id":0,"result":"0x{:x}"}}"#,
+                block_number
+            )),
+            None => ResponseTemplate::new(502).set_body_string("bad gateway"),
+        };
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "method": "eth_blockNumber" }),
+            ))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn rotation() {
+        let failing_server = block_number_server(None).await;
+        let healthy_server = block_number_server(Some(10)).await;
+        let fallback_http = FallbackHttp::new(
+            1,
+            vec![(failing_server.uri(), None), (healthy_server.uri(), None)],
+            2,
+        )
+        .unwrap();
+        let provider = Provider::new(fallback_http);
+
+        // the first failure doesn't reach the threshold
+        assert!(provider.get_block_number().await.is_err());
+        assert_eq!(provider.as_ref().active_url(), failing_server.uri());
+
+        // the second one rotates the endpoint and the request is sent again
+        assert_eq!(provider.get_block_number().await.unwrap(), U64::from(10));
+        assert_eq!(provider.as_ref().active_url(), healthy_server.uri());
+        assert_eq!(provider.as_ref().health_scores(), vec![80, 100]);
+    }
+
+    #[test]
+    fn rotation_healthiest() {
+        let fallback_http = FallbackHttp::new(
+            1,
+            vec![
+                ("http://127.0.0.1:1111".to_owned(), None),
+                ("http://127.0.0.1:2222".to_owned(), None),
+                ("http://127.0.0.1:3333".to_owned(), None),
+            ],
+            1,
+        )
+        .unwrap();
+
+        // ties go to the next endpoint
+        assert!(fallback_http.record_failure(0));
+        assert_eq!(fallback_http.active_url(), "http://127.0.0.1:2222");
+
+        // the first endpoint is skipped as it's less healthy than the third one
+        assert!(fallback_http.record_failure(1));
+        assert_eq!(fallback_http.active_url(), "http://127.0.0.1:3333");
+        assert_eq!(fallback_http.health_scores(), vec![90, 90, 100]);
+        fallback_http.record_success(0);
+        assert!(fallback_http.record_failure(2));
+        assert_eq!(fallback_http.active_url(), "http://127.0.0.1:1111");
+    }
+
+    #[tokio::test]
+    async fn json_rpc_errors_are_not_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"jsonrpc":"2.0","id":0,"error":{"code":3,"message":"execution reverted"}}"#,
+            ))
+            .mount(&server)
+            .await;
+        let other_server = block_number_server(Some(10)).await;
+        let provider = Provider::new(
+            FallbackHttp::new(1, vec![(server.uri(), None), (other_server.uri(), None)], 1)
+                .unwrap(),
+        );
+
+        assert!(provider.get_block_number().await.is_err());
+        assert_eq!(provider.as_ref().active_url(), server.uri());
+        assert_eq!(provider.as_ref().health_scores(), vec![100, 100]);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker() {
+        let server = block_number_server(None).await;
+        let provider = Provider::new(
+            FallbackHttp::new(1, vec![(server.uri(), None)], 1)
+                .unwrap()
+                .with_circuit_breaker(2, Duration::from_millis(200)),
+        );
+
+        assert!(provider.get_block_number().await.is_err());
+        assert!(!provider.as_ref().is_degraded());
+        assert!(provider.get_block_number().await.is_err());
+        assert!(provider.as_ref().is_degraded());
+
+        // requests are not sent while the breaker is open
+        let received = server.received_requests().await.unwrap().len();
+        assert!(provider.get_block_number().await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), received);
+
+        // after the cooldown a single failure trips it again
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(!provider.as_ref().is_degraded());
+        assert!(provider.get_block_number().await.is_err());
+        assert!(provider.as_ref().is_degraded());
+    }
+
+    #[tokio::test]
+    async fn rate_limit() {
+        let server = block_number_server(Some(10)).await;
+        let provider =
+            Provider::new(FallbackHttp::new(1, vec![(server.uri(), Some(2))], 1).unwrap());
+
+        // the first two requests fit in the burst, the third one has to wait
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(provider.get_block_number().await.unwrap(), U64::from(10));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(400));
+    }
+}
@@ -0,0 +1,259 @@
+pub mod aws;
+pub mod gcp;
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Context;
+use tokio::time::sleep;
+
+use crate::commons::{Config, PrivateKeyConfig, SecretManagersConfig};
+
+use self::{aws::AwsSecretsManager, gcp::GcpSecretManager};
+
+// config values starting with these prefixes are resolved from the matching secret
+// manager at startup. the rest of the value is the secret's id, optionally followed
+// by #<key> to pick a field of a json secret, e.g. aws-sm:answerer/gnosis#private_key
+const AWS_REFERENCE_PREFIX: &str = "aws-sm:";
+const GCP_REFERENCE_PREFIX: &str = "gcp-sm:";
+
+// kept around to tell when the secret was rotated
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedSecret {
+    pub reference: String,
+    pub value: String,
+}
+
+pub struct SecretManagers {
+    aws: Option<AwsSecretsManager>,
+    gcp: Option<GcpSecretManager>,
+    refresh_interval: Option<Duration>,
+}
+
+impl SecretManagers {
+    pub fn new(config: &SecretManagersConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            aws: config
+                .aws
+                .as_ref()
+                .map(AwsSecretsManager::new)
+                .transpose()?,
+            gcp: config.gcp.as_ref().map(GcpSecretManager::new).transpose()?,
+            refresh_interval: config.refresh_interval_seconds.map(Duration::from_secs),
+        })
+    }
+
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh_interval
+    }
+
+    // returns none for values that aren't secret manager references
+    async fn read(&self, reference: &str) -> anyhow::Result<Option<String>> {
+        let (secret, key) = match reference.rsplit_once('#') {
+            Some((secret, key)) => (secret, Some(key)),
+            None => (reference, None),
+        };
+        let value = if let Some(secret_id) = secret.strip_prefix(AWS_REFERENCE_PREFIX) {
+            self.aws
+                .as_ref()
+                .context("aws secrets manager is not configured")?
+                .get_secret_value(secret_id)
+                .await?
+        } else if let Some(name) = secret.strip_prefix(GCP_REFERENCE_PREFIX) {
+            self.gcp
+                .as_ref()
+                .context("gcp secret manager is not configured")?
+                .access_secret_version(name)
+                .await?
+        } else {
+            return Ok(None);
+        };
+
+        match key {
+            Some(key) => {
+                let mut fields: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(&value)
+                        .context(format!("secret {} is not a json object", secret))?;
+                match fields.remove(key) {
+                    Some(serde_json::Value::String(value)) => Ok(Some(value)),
+                    Some(_) => anyhow::bail!("secret {} key {} is not a string", secret, key),
+                    None => anyhow::bail!("secret {} has no key {}", secret, key),
+                }
+            }
+            None => Ok(Some(value)),
+        }
+    }
+
+    async fn resolve(
+        &self,
+        value: &mut String,
+        resolved: &mut Vec<ResolvedSecret>,
+    ) -> anyhow::Result<()> {
+        if let Some(secret) = self.read(value).await? {
+            resolved.push(ResolvedSecret {
+                reference: value.clone(),
+                value: secret.clone(),
+            });
+            *value = secret;
+        }
+        Ok(())
+    }
+
+    pub async fn resolve_config_secrets(
+        &self,
+        config: &mut Config,
+    ) -> anyhow::Result<Vec<ResolvedSecret>> {
+        let mut resolved = Vec::new();
+        self.resolve(&mut config.db_connection_string, &mut resolved)
+            .await
+            .context("could not resolve db connection string")?;
+        self.resolve(&mut config.data_manager.api_key, &mut resolved)
+            .await
+            .context("could not resolve data manager api key")?;
+        for (chain_id, chain_config) in config.chain_configs.iter_mut() {
+            for private_key in chain_config.answerer_private_key.iter_mut().chain(
+                chain_config
+                    .additional_answerer_private_keys
+                    .iter_mut()
+                    .flatten(),
+            ) {
+                if let PrivateKeyConfig::Raw(private_key) = private_key {
+                    self.resolve(private_key, &mut resolved)
+                        .await
+                        .context(format!(
+                            "could not resolve answerer private key for chain {}",
+                            chain_id
+                        ))?;
+                }
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+// resolves once any of the secrets was rotated, so that the service can restart and
+// pick up the new values. secrets that can't be read are retried on the next refresh
+pub async fn wait_for_rotated_secret(
+    secret_managers: Arc<SecretManagers>,
+    secrets: Vec<ResolvedSecret>,
+    refresh_interval: Duration,
+) {
+    loop {
+        sleep(refresh_interval).await;
+        for secret in secrets.iter() {
+            match secret_managers.read(&secret.reference).await {
+                Ok(Some(value)) if value != secret.value => {
+                    tracing::warn!("secret {} was rotated", secret.reference);
+                    return;
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    tracing::error!("could not refresh secret {}: {:#}", secret.reference, error)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, sync::Arc, time::Duration};
+
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::commons::{GcpSecretManagerConfig, SecretManagersConfig};
+
+    use super::{wait_for_rotated_secret, ResolvedSecret, SecretManagers};
+
+    fn secret_response(value: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "name": "projects/answerer/secrets/db/versions/1",
+            "payload": { "data": STANDARD.encode(value) },
+        }))
+    }
+
+    async fn secret_managers(mock_server: &MockServer, token_env: &str) -> SecretManagers {
+        env::set_var(token_env, "token");
+        SecretManagers::new(&SecretManagersConfig {
+            aws: None,
+            gcp: Some(GcpSecretManagerConfig {
+                access_token_env: Some(token_env.to_owned()),
+                endpoint: Some(mock_server.uri()),
+            }),
+            refresh_interval_seconds: None,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_references() {
+        let mock_server = MockServer::start().await;
+        let secret_managers = secret_managers(&mock_server, "GCP_TOKEN_READ_REFERENCES").await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/v1/projects/answerer/secrets/db/versions/latest:access",
+            ))
+            .and(header("Authorization", "Bearer token"))
+            .respond_with(secret_response("postgres://db"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/projects/answerer/secrets/keys/versions/2:access"))
+            .respond_with(secret_response("{\"gnosis\": \"0x01\"}"))
+            .mount(&mock_server)
+            .await;
+
+        assert_eq!(
+            secret_managers
+                .read("gcp-sm:projects/answerer/secrets/db")
+                .await
+                .unwrap(),
+            Some("postgres://db".to_owned())
+        );
+        assert_eq!(
+            secret_managers
+                .read("gcp-sm:projects/answerer/secrets/keys/versions/2#gnosis")
+                .await
+                .unwrap(),
+            Some("0x01".to_owned())
+        );
+        assert!(secret_managers
+            .read("gcp-sm:projects/answerer/secrets/keys/versions/2#sepolia")
+            .await
+            .is_err());
+        assert!(secret_managers.read("aws-sm:answerer/db").await.is_err());
+        assert_eq!(secret_managers.read("postgres://db").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn detect_rotated_secret() {
+        let mock_server = MockServer::start().await;
+        let secret_managers = secret_managers(&mock_server, "GCP_TOKEN_DETECT_ROTATED").await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/v1/projects/answerer/secrets/db/versions/latest:access",
+            ))
+            .respond_with(secret_response("postgres://rotated"))
+            .mount(&mock_server)
+            .await;
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            wait_for_rotated_secret(
+                Arc::new(secret_managers),
+                vec![ResolvedSecret {
+                    reference: "gcp-sm:projects/answerer/secrets/db".to_owned(),
+                    value: "postgres://db".to_owned(),
+                }],
+                Duration::from_millis(10),
+            ),
+        )
+        .await
+        .unwrap();
+    }
+}
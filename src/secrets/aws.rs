@@ -0,0 +1,116 @@
+use std::{convert::Infallible, str::FromStr};
+
+use anyhow::Context;
+use rusoto_core::{signature::SignedRequest, Client, Region, RusotoError};
+use serde::Deserialize;
+
+use crate::commons::AwsSecretsManagerConfig;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct GetSecretValueResponse {
+    secret_string: Option<String>,
+}
+
+// minimal client for secrets manager's GetSecretValue action, signed with the
+// credentials found in the environment like the kms signer
+pub struct AwsSecretsManager {
+    client: Client,
+    region: Region,
+}
+
+impl AwsSecretsManager {
+    pub fn new(config: &AwsSecretsManagerConfig) -> anyhow::Result<Self> {
+        let region = match config.endpoint.clone() {
+            Some(endpoint) => Region::Custom {
+                name: config.region.clone(),
+                endpoint,
+            },
+            None => Region::from_str(&config.region)
+                .context(format!("invalid aws region {}", config.region))?,
+        };
+        Ok(Self::with_client(Client::shared(), region))
+    }
+
+    fn with_client(client: Client, region: Region) -> Self {
+        Self { client, region }
+    }
+
+    pub async fn get_secret_value(&self, secret_id: &str) -> anyhow::Result<String> {
+        let mut request = SignedRequest::new("POST", "secretsmanager", &self.region, "/");
+        request.set_content_type("application/x-amz-json-1.1".to_owned());
+        request.add_header("x-amz-target", "secretsmanager.GetSecretValue");
+        request.set_payload(Some(serde_json::to_vec(
+            &serde_json::json!({ "SecretId": secret_id }),
+        )?));
+
+        let mut response = self
+            .client
+            .sign_and_dispatch(request)
+            .await
+            .map_err(RusotoError::<Infallible>::from)
+            .context(format!("could not read aws secret {}", secret_id))?;
+        let response = response
+            .buffer()
+            .await
+            .context(format!("could not read aws secret {}", secret_id))?;
+        if !response.status.is_success() {
+            anyhow::bail!(
+                "could not read aws secret {}: {} {}",
+                secret_id,
+                response.status,
+                String::from_utf8_lossy(&response.body)
+            );
+        }
+        serde_json::from_slice::<GetSecretValueResponse>(&response.body)
+            .context(format!("could not deserialize aws secret {}", secret_id))?
+            .secret_string
+            .context(format!("aws secret {} is not a string", secret_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::client::HttpConnector;
+    use rusoto_core::{credential::StaticProvider, Client, HttpClient, Region};
+    use wiremock::{
+        matchers::{body_json, header, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::AwsSecretsManager;
+
+    #[tokio::test]
+    async fn get_secret_value() {
+        let mock_server = MockServer::start().await;
+        let secrets_manager = AwsSecretsManager::with_client(
+            Client::new_with(
+                StaticProvider::new_minimal("key".to_owned(), "secret".to_owned()),
+                // the default client only speaks https
+                HttpClient::from_connector(HttpConnector::new()),
+            ),
+            Region::Custom {
+                name: "eu-west-1".to_owned(),
+                endpoint: mock_server.uri(),
+            },
+        );
+
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "secretsmanager.GetSecretValue"))
+            .and(body_json(serde_json::json!({ "SecretId": "answerer/db" })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "SecretString": "postgres://db" })),
+            )
+            .mount(&mock_server)
+            .await;
+        assert_eq!(
+            secrets_manager
+                .get_secret_value("answerer/db")
+                .await
+                .unwrap(),
+            "postgres://db"
+        );
+        assert!(secrets_manager.get_secret_value("missing").await.is_err());
+    }
+}
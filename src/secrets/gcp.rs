@@ -0,0 +1,109 @@
+use std::env;
+
+use anyhow::Context;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use carrot_commons::http_client::HttpClient;
+use reqwest::Method;
+use serde::Deserialize;
+
+use crate::commons::{GcpSecretManagerConfig, HTTP_TIMEOUT};
+
+const DEFAULT_GCP_SECRET_MANAGER_ENDPOINT: &str = "https://secretmanager.googleapis.com";
+const GCP_METADATA_ENDPOINT: &str = "http://metadata.google.internal";
+
+#[derive(Deserialize, Debug)]
+struct AccessTokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct SecretPayload {
+    data: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct AccessSecretVersionResponse {
+    payload: SecretPayload,
+}
+
+// minimal client for secret manager's access endpoint. the access token is read
+// from the configured env variable if any, or from the metadata server of the
+// instance otherwise, which is fetched on every read since tokens expire
+pub struct GcpSecretManager {
+    http_client: HttpClient,
+    metadata_http_client: HttpClient,
+    access_token_env: Option<String>,
+}
+
+impl GcpSecretManager {
+    pub fn new(config: &GcpSecretManagerConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            http_client: HttpClient::builder(
+                config
+                    .endpoint
+                    .as_deref()
+                    .unwrap_or(DEFAULT_GCP_SECRET_MANAGER_ENDPOINT),
+                HTTP_TIMEOUT,
+            )
+            .build()?,
+            metadata_http_client: HttpClient::builder(GCP_METADATA_ENDPOINT, HTTP_TIMEOUT)
+                .build()?,
+            access_token_env: config.access_token_env.clone(),
+        })
+    }
+
+    async fn access_token(&self) -> anyhow::Result<String> {
+        if let Some(access_token_env) = self.access_token_env.as_ref() {
+            return env::var(access_token_env).context(format!(
+                "could not read gcp access token from env variable {}",
+                access_token_env
+            ));
+        }
+        Ok(self
+            .metadata_http_client
+            .request(
+                Method::GET,
+                "/computeMetadata/v1/instance/service-accounts/default/token",
+            )
+            .await?
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await
+            .context("could not get gcp access token from the metadata server")?
+            .error_for_status()
+            .context("could not get gcp access token from the metadata server")?
+            .json::<AccessTokenResponse>()
+            .await
+            .context("could not deserialize gcp access token")?
+            .access_token)
+    }
+
+    // the name is the secret version's resource name, the latest version being
+    // read when none is given
+    pub async fn access_secret_version(&self, name: &str) -> anyhow::Result<String> {
+        let name = name.trim_matches('/');
+        let name = if name.contains("/versions/") {
+            name.to_owned()
+        } else {
+            format!("{}/versions/latest", name)
+        };
+        let access_token = self.access_token().await?;
+        let response = self
+            .http_client
+            .request(Method::GET, format!("/v1/{}:access", name))
+            .await?
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .context(format!("could not read gcp secret {}", name))?
+            .error_for_status()
+            .context(format!("could not read gcp secret {}", name))?
+            .json::<AccessSecretVersionResponse>()
+            .await
+            .context(format!("could not deserialize gcp secret {}", name))?;
+        let data = STANDARD
+            .decode(response.payload.data)
+            .context(format!("could not decode gcp secret {}", name))?;
+        String::from_utf8(data).context(format!("gcp secret {} is not a string", name))
+    }
+}
@@ -0,0 +1,364 @@
+use std::{env, fmt, path::Path, str::FromStr};
+
+use anyhow::Context;
+use async_trait::async_trait;
+#[cfg(feature = "ledger")]
+use ethers::signers::{HDPath, Ledger, LedgerError};
+use ethers::{
+    signers::{AwsSigner, AwsSignerError, LocalWallet, Signer, WalletError},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Signature,
+    },
+};
+use rusoto_core::Region;
+use rusoto_kms::KmsClient;
+
+use crate::commons::{AwsKmsKeyConfig, ChainConfig, PrivateKeyConfig};
+
+// answer transactions are signed either with a local private key or with a key that
+// never leaves aws kms. manual operations can also sign through a ledger device when
+// built with the ledger feature
+#[derive(Debug)]
+pub enum AnswererSigner {
+    Local(LocalWallet),
+    AwsKms(AwsSigner),
+    #[cfg(feature = "ledger")]
+    Ledger(Ledger),
+}
+
+#[derive(Debug)]
+pub enum AnswererSignerError {
+    Local(WalletError),
+    AwsKms(AwsSignerError),
+    #[cfg(feature = "ledger")]
+    Ledger(LedgerError),
+}
+
+impl fmt::Display for AnswererSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(error) => write!(f, "local signer error: {}", error),
+            Self::AwsKms(error) => write!(f, "aws kms signer error: {}", error),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(error) => write!(f, "ledger signer error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for AnswererSignerError {}
+
+impl AnswererSigner {
+    pub fn from_private_key(chain_id: u64, private_key: &str) -> anyhow::Result<Self> {
+        let wallet = private_key
+            .parse::<LocalWallet>()
+            .context("could not parse private key to local wallet")?;
+        Ok(Self::Local(wallet.with_chain_id(chain_id)))
+    }
+
+    pub fn from_keystore(
+        chain_id: u64,
+        keystore_path: &Path,
+        password_env: &str,
+    ) -> anyhow::Result<Self> {
+        let password = env::var(password_env).context(format!(
+            "could not read keystore password from env variable {}",
+            password_env
+        ))?;
+        let wallet = LocalWallet::decrypt_keystore(keystore_path, password).context(format!(
+            "could not decrypt keystore {}",
+            keystore_path.display()
+        ))?;
+        Ok(Self::Local(wallet.with_chain_id(chain_id)))
+    }
+
+    pub fn from_private_key_config(
+        chain_id: u64,
+        config: &PrivateKeyConfig,
+    ) -> anyhow::Result<Self> {
+        match config {
+            PrivateKeyConfig::Raw(private_key) => Self::from_private_key(chain_id, private_key),
+            PrivateKeyConfig::Keystore {
+                keystore_path,
+                password_env,
+            } => Self::from_keystore(chain_id, keystore_path, password_env),
+        }
+    }
+
+    pub async fn from_aws_kms_key(chain_id: u64, config: &AwsKmsKeyConfig) -> anyhow::Result<Self> {
+        let region = Region::from_str(&config.region)
+            .context(format!("invalid aws region {}", config.region))?;
+        let signer = AwsSigner::new(KmsClient::new(region), &config.key_id, chain_id)
+            .await
+            .context(format!("could not get aws kms key {}", config.key_id))?;
+        Ok(Self::AwsKms(signer))
+    }
+
+    // the device must be unlocked with the ethereum app open
+    #[cfg(feature = "ledger")]
+    pub async fn from_ledger(chain_id: u64, hd_path: &str) -> anyhow::Result<Self> {
+        let ledger = Ledger::new(HDPath::Other(hd_path.to_owned()), chain_id)
+            .await
+            .context(format!("could not connect to ledger with path {}", hd_path))?;
+        Ok(Self::Ledger(ledger))
+    }
+}
+
+// returns the chain's answerer signers, the primary one first
+pub async fn build_answerer_signers(
+    chain_id: u64,
+    chain_config: &ChainConfig,
+) -> anyhow::Result<Vec<AnswererSigner>> {
+    let mut signers = Vec::new();
+    match (
+        chain_config.answerer_private_key.as_ref(),
+        chain_config.answerer_aws_kms_key.as_ref(),
+    ) {
+        (Some(private_key), None) => signers.push(AnswererSigner::from_private_key_config(
+            chain_id,
+            private_key,
+        )?),
+        (None, Some(aws_kms_key)) => {
+            signers.push(AnswererSigner::from_aws_kms_key(chain_id, aws_kms_key).await?)
+        }
+        _ => anyhow::bail!(
+            "exactly one of answerer_private_key and answerer_aws_kms_key must be set"
+        ),
+    }
+
+    for private_key in chain_config
+        .additional_answerer_private_keys
+        .iter()
+        .flatten()
+    {
+        signers.push(AnswererSigner::from_private_key_config(
+            chain_id,
+            private_key,
+        )?);
+    }
+    for aws_kms_key in chain_config
+        .additional_answerer_aws_kms_keys
+        .iter()
+        .flatten()
+    {
+        signers.push(AnswererSigner::from_aws_kms_key(chain_id, aws_kms_key).await?);
+    }
+
+    Ok(signers)
+}
+
+#[async_trait]
+impl Signer for AnswererSigner {
+    type Error = AnswererSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_message(message)
+                .await
+                .map_err(AnswererSignerError::Local),
+            Self::AwsKms(signer) => signer
+                .sign_message(message)
+                .await
+                .map_err(AnswererSignerError::AwsKms),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(ledger) => ledger
+                .sign_message(message)
+                .await
+                .map_err(AnswererSignerError::Ledger),
+        }
+    }
+
+    async fn sign_transaction(&self, message: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_transaction(message)
+                .await
+                .map_err(AnswererSignerError::Local),
+            Self::AwsKms(signer) => signer
+                .sign_transaction(message)
+                .await
+                .map_err(AnswererSignerError::AwsKms),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(ledger) => ledger
+                .sign_transaction(message)
+                .await
+                .map_err(AnswererSignerError::Ledger),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => wallet
+                .sign_typed_data(payload)
+                .await
+                .map_err(AnswererSignerError::Local),
+            Self::AwsKms(signer) => signer
+                .sign_typed_data(payload)
+                .await
+                .map_err(AnswererSignerError::AwsKms),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(ledger) => ledger
+                .sign_typed_data(payload)
+                .await
+                .map_err(AnswererSignerError::Ledger),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(wallet) => wallet.address(),
+            Self::AwsKms(signer) => signer.address(),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(ledger) => ledger.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(wallet) => wallet.chain_id(),
+            Self::AwsKms(signer) => signer.chain_id(),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(ledger) => ledger.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(wallet) => Self::Local(wallet.with_chain_id(chain_id)),
+            Self::AwsKms(signer) => Self::AwsKms(signer.with_chain_id(chain_id)),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(ledger) => Self::Ledger(ledger.with_chain_id(chain_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs};
+
+    use ethers::{
+        core::rand::thread_rng,
+        signers::{LocalWallet, Signer},
+    };
+    use serde_json::json;
+
+    use crate::commons::ChainConfig;
+
+    use super::{build_answerer_signers, AnswererSigner};
+
+    fn chain_config(answerer_keys: serde_json::Value) -> ChainConfig {
+        let mut chain_config = json!({
+            "rpc_endpoint": "http://127.0.0.1:8545",
+            "template_id": 1,
+            "factory": {
+                "address": "0x0000000000000000000000000000000000000001",
+                "deployment_block": 0
+            }
+        });
+        chain_config
+            .as_object_mut()
+            .unwrap()
+            .extend(answerer_keys.as_object().unwrap().clone());
+        serde_json::from_value(chain_config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn build_answerer_signers_private_keys() {
+        let primary = format!("{:064x}", 1);
+        let fallback = format!("{:064x}", 2);
+        let signers = build_answerer_signers(
+            100,
+            &chain_config(json!({
+                "answerer_private_key": primary,
+                "additional_answerer_private_keys": [fallback]
+            })),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(signers.len(), 2);
+        assert_eq!(
+            signers[0].address(),
+            AnswererSigner::from_private_key(100, &primary)
+                .unwrap()
+                .address()
+        );
+        assert_eq!(
+            signers[1].address(),
+            AnswererSigner::from_private_key(100, &fallback)
+                .unwrap()
+                .address()
+        );
+        assert!(signers.iter().all(|signer| signer.chain_id() == 100));
+    }
+
+    #[tokio::test]
+    async fn build_answerer_signers_keystore() {
+        let keystore_dir = env::temp_dir();
+        let keystore_name = format!("answerer-keystore-{}.json", std::process::id());
+        let (wallet, _) = LocalWallet::new_keystore(
+            &keystore_dir,
+            &mut thread_rng(),
+            "password",
+            Some(&keystore_name),
+        )
+        .unwrap();
+        let keystore_path = keystore_dir.join(&keystore_name);
+        env::set_var("TEST_ANSWERER_KEYSTORE_PASSWORD", "password");
+        env::set_var("TEST_ANSWERER_KEYSTORE_WRONG_PASSWORD", "wrong");
+
+        let signers = build_answerer_signers(
+            100,
+            &chain_config(json!({
+                "answerer_private_key": {
+                    "keystore_path": keystore_path,
+                    "password_env": "TEST_ANSWERER_KEYSTORE_PASSWORD"
+                }
+            })),
+        )
+        .await;
+        let wrong_password_signers = build_answerer_signers(
+            100,
+            &chain_config(json!({
+                "answerer_private_key": {
+                    "keystore_path": keystore_path,
+                    "password_env": "TEST_ANSWERER_KEYSTORE_WRONG_PASSWORD"
+                }
+            })),
+        )
+        .await;
+        fs::remove_file(&keystore_path).unwrap();
+
+        let signers = signers.unwrap();
+        assert_eq!(signers.len(), 1);
+        assert_eq!(signers[0].address(), wallet.address());
+        assert_eq!(signers[0].chain_id(), 100);
+        assert!(wrong_password_signers.is_err());
+    }
+
+    #[tokio::test]
+    async fn build_answerer_signers_ambiguous_primary() {
+        assert!(build_answerer_signers(100, &chain_config(json!({})))
+            .await
+            .is_err());
+        assert!(build_answerer_signers(
+            100,
+            &chain_config(json!({
+                "answerer_private_key": format!("{:064x}", 1),
+                "answerer_aws_kms_key": {
+                    "key_id": "alias/answerer",
+                    "region": "eu-west-1"
+                }
+            })),
+        )
+        .await
+        .is_err());
+    }
+}
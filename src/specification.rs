@@ -1,15 +1,17 @@
 pub mod handlers;
 
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, time::SystemTime};
 
 use async_trait::async_trait;
-use carrot_commons::http_client::HttpClient;
-use diesel::{sql_types::Jsonb, AsExpression, FromSqlRow};
+use diesel::{AsExpression, FromSqlRow};
 use ethers::types::U256;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::specification::handlers::tvl::TvlHandler;
+use crate::{
+    archive::HistoricalState, db::sql_types::Jsonb, defillama::DefiLlamaClient,
+    specification::handlers::tvl::TvlHandler,
+};
 
 use self::handlers::tvl::TvlPayload;
 
@@ -23,22 +25,36 @@ pub enum Specification {
 
 #[async_trait]
 pub trait Validate<'a, P: Serialize + Deserialize<'a> + Debug + PartialEq> {
-    async fn validate(payload: &P, defillama_http_client: Arc<HttpClient>) -> anyhow::Result<bool>;
+    async fn validate(payload: &P, defillama: DefiLlamaClient) -> anyhow::Result<bool>;
 }
 
 #[async_trait]
 pub trait Answer<'a, P: Serialize + Deserialize<'a> + Debug + PartialEq> {
     async fn answer(
         payload: &P,
-        defillama_http_client: Arc<HttpClient>,
+        defillama: &DefiLlamaClient,
+        historical_state: Option<&HistoricalState>,
     ) -> anyhow::Result<Option<U256>>;
 }
 
+#[async_trait]
+pub trait Reference<'a, P: Serialize + Deserialize<'a> + Debug + PartialEq> {
+    async fn reference(
+        payload: &P,
+        defillama: &DefiLlamaClient,
+        timestamp: SystemTime,
+    ) -> anyhow::Result<Option<U256>>;
+}
+
+pub trait Fallback<'a, P: Serialize + Deserialize<'a> + Debug + PartialEq> {
+    fn fallback(payload: &P) -> anyhow::Result<Option<U256>>;
+}
+
 macro_rules! impl_spec_validation_and_handling {
     ($($spec_variant: ident => $handler: ident),*) => {
-        pub async fn validate<'a>(specification: &Specification, defillama_http_client: Arc<HttpClient>) -> bool {
+        pub async fn validate(specification: &Specification, defillama: DefiLlamaClient) -> bool {
             let result = match specification {
-                $(Specification::$spec_variant(payload) => $handler::validate(&payload, defillama_http_client),)*
+                $(Specification::$spec_variant(payload) => $handler::validate(&payload, defillama),)*
             }.await;
             match result {
                 Ok(val) => val,
@@ -49,14 +65,35 @@ macro_rules! impl_spec_validation_and_handling {
             }
         }
 
-        pub async fn answer<'a>(specification: &Specification, defillama_http_client: Arc<HttpClient>) -> Option<U256> {
+        // errors are returned rather than logged so that callers can tell apart
+        // data that went missing from defillama
+        pub async fn answer(specification: &Specification, defillama: &DefiLlamaClient, historical_state: Option<&HistoricalState>) -> anyhow::Result<Option<U256>> {
+            match specification {
+                $(Specification::$spec_variant(payload) => $handler::answer(&payload, defillama, historical_state),)*
+            }.await
+        }
+
+        pub fn fallback(specification: &Specification) -> Option<U256> {
+            let result = match specification {
+                $(Specification::$spec_variant(payload) => $handler::fallback(&payload),)*
+            };
+            match result {
+                Ok(val) => val,
+                Err(error) => {
+                    tracing::error!("could not get fallback value for specification - {:#}", error);
+                    return None;
+                }
+            }
+        }
+
+        pub async fn reference(specification: &Specification, defillama: &DefiLlamaClient, timestamp: SystemTime) -> Option<U256> {
             let result = match specification {
-                $(Specification::$spec_variant(payload) => $handler::answer(&payload, defillama_http_client),)*
+                $(Specification::$spec_variant(payload) => $handler::reference(&payload, defillama, timestamp),)*
             }.await;
             match result {
                 Ok(val) => val,
                 Err(error) => {
-                    tracing::error!("answering failed for specification - {:#}", error);
+                    tracing::warn!("could not get reference value for specification - {:#}", error);
                     return None;
                 }
             }
@@ -80,6 +117,7 @@ mod test {
     fn serialize_tvl() {
         let metric = Specification::Tvl(TvlPayload {
             protocol: "aave".to_owned(),
+            fallback: None,
         });
 
         assert_eq!(
@@ -141,6 +179,7 @@ mod test {
             .unwrap(),
             Specification::Tvl(TvlPayload {
                 protocol: "foo".to_owned(),
+                fallback: None,
             })
         );
     }
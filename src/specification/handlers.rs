@@ -1 +1,109 @@
 pub mod tvl;
+
+use anyhow::Context;
+use ethers::types::U256;
+
+// scales a decimal number, as returned by defillama or given in a specification, to
+// an integer with the given decimals. the math happens directly on the number's
+// digits so that no precision is lost whatever its size, and digits past the given
+// decimals are truncated. exponents, such as in 1.5e+21, are supported
+pub fn scale_amount(raw: &str, decimals: u32) -> anyhow::Result<U256> {
+    let raw = raw.trim();
+    let (mantissa, exponent) = match raw.find(['e', 'E']) {
+        Some(index) => (
+            &raw[..index],
+            raw[index + 1..]
+                .parse::<i64>()
+                .context(format!("invalid exponent in amount {}", raw))?,
+        ),
+        None => (raw, 0),
+    };
+    let mantissa = mantissa.strip_prefix('+').unwrap_or(mantissa);
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|char| char.is_ascii_digit())
+    {
+        anyhow::bail!("invalid amount {}", raw);
+    }
+
+    let digits = format!("{}{}", integer, fraction);
+    let digits = digits.trim_start_matches('0');
+    let shift = exponent - fraction.len() as i64 + decimals as i64;
+    let digits = if shift < 0 {
+        &digits[..digits.len().saturating_sub(shift.unsigned_abs() as usize)]
+    } else {
+        digits
+    };
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+
+    let unscaled = U256::from_dec_str(digits).context(format!("amount {} is too big", raw))?;
+    if shift <= 0 {
+        return Ok(unscaled);
+    }
+    u32::try_from(shift)
+        .ok()
+        .filter(|shift| *shift <= 77)
+        .and_then(|shift| unscaled.checked_mul(U256::exp10(shift as usize)))
+        .context(format!(
+            "amount {} is too big to be scaled to {} decimals",
+            raw, decimals
+        ))
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::U256;
+
+    use super::scale_amount;
+
+    #[test]
+    fn scale() {
+        for (raw, scaled) in [
+            ("1234.5678", "1234567800000000000000"),
+            ("0", "0"),
+            ("  42\n", "42000000000000000000"),
+            (".5", "500000000000000000"),
+            ("7.", "7000000000000000000"),
+            ("1.5e+3", "1500000000000000000000"),
+            ("15E-1", "1500000000000000000"),
+            // digits past 18 decimals are truncated
+            ("0.0000000000000000019", "1"),
+            ("1e-19", "0"),
+            // well past what fits in a u128 once scaled
+            (
+                "123456789012345678901234567890.5",
+                "123456789012345678901234567890500000000000000000",
+            ),
+            (
+                "1e59",
+                "100000000000000000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        ] {
+            assert_eq!(
+                scale_amount(raw, 18).unwrap(),
+                U256::from_dec_str(scaled).unwrap(),
+                "{}",
+                raw
+            );
+        }
+
+        for raw in [
+            "",
+            ".",
+            "-1",
+            "foo",
+            "1.2.3",
+            "1e",
+            "0x10",
+            "1e60",
+            "1e1000000000000",
+        ] {
+            assert!(scale_amount(raw, 18).is_err(), "{}", raw);
+        }
+    }
+}
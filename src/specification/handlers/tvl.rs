@@ -1,55 +1,69 @@
-use std::{str::FromStr, sync::Arc};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
 use async_trait::async_trait;
-use carrot_commons::http_client::HttpClient;
 use ethers::types::U256;
-use reqwest::Method;
-use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::specification::{Answer, Validate};
+use crate::{
+    archive::HistoricalState,
+    defillama::DefiLlamaClient,
+    specification::{handlers::scale_amount, Answer, Fallback, Reference, Validate},
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
 pub struct TvlPayload {
     pub protocol: String,
+    // tvl in usd to answer with if the protocol gets delisted from defillama
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
 }
 
 pub struct TvlHandler;
 
 impl TvlHandler {
-    async fn get_current_tvl(
-        defillama_http_client: Arc<HttpClient>,
-        protocol: &String,
-    ) -> anyhow::Result<Decimal> {
-        let raw = defillama_http_client
-            .request(Method::GET, format!("/tvl/{protocol}"))
-            .await?
-            .send()
-            .await
-            .context(format!(
-                "could not get current tvl for protocol {}",
-                protocol
-            ))?
-            .text()
-            .await
-            .context(format!(
-                "could not convert raw protocol tvl response to number for protocol {}",
-                protocol
-            ))?;
-        Ok(Decimal::from_str(raw.as_str())
-            .context(format!("could not convert {} to decimal", raw))?)
+    async fn get_current_tvl(defillama: &DefiLlamaClient, protocol: &str) -> anyhow::Result<U256> {
+        let raw_tvl = defillama.protocol_tvl(protocol).await?;
+        TvlHandler::scale_tvl(&raw_tvl)
+    }
+
+    // returns the latest tvl data point recorded at or before the given timestamp
+    async fn get_historical_tvl(
+        defillama: &DefiLlamaClient,
+        protocol: &str,
+        timestamp: SystemTime,
+    ) -> anyhow::Result<Option<U256>> {
+        let tvl = defillama.historical_protocol_tvl(protocol).await?;
+
+        let timestamp = timestamp
+            .duration_since(UNIX_EPOCH)
+            .context("could not get unix timestamp")?
+            .as_secs();
+        let point = match tvl
+            .into_iter()
+            .filter(|point| point.date <= timestamp)
+            .max_by_key(|point| point.date)
+        {
+            Some(point) => point,
+            None => return Ok(None),
+        };
+        // floats are displayed in full, without exponent
+        TvlHandler::scale_tvl(&point.total_liquidity_usd.to_string()).map(Some)
+    }
+
+    fn scale_tvl(raw_tvl: &str) -> anyhow::Result<U256> {
+        scale_amount(raw_tvl, 18).context(format!(
+            "could not correctly scale tvl value {} to 18 decimals",
+            raw_tvl
+        ))
     }
 }
 
 #[async_trait]
 impl<'a> Validate<'a, TvlPayload> for TvlHandler {
-    async fn validate(
-        payload: &TvlPayload,
-        defillama_http_client: Arc<HttpClient>,
-    ) -> anyhow::Result<bool> {
-        match TvlHandler::get_current_tvl(defillama_http_client.clone(), &payload.protocol).await {
+    async fn validate(payload: &TvlPayload, defillama: DefiLlamaClient) -> anyhow::Result<bool> {
+        match TvlHandler::get_current_tvl(&defillama, &payload.protocol).await {
             Ok(_) => Ok(true),
             Err(error) => {
                 tracing::error!(
@@ -67,29 +81,41 @@ impl<'a> Validate<'a, TvlPayload> for TvlHandler {
 impl<'a> Answer<'a, TvlPayload> for TvlHandler {
     async fn answer(
         payload: &TvlPayload,
-        defillama_http_client: Arc<HttpClient>,
+        defillama: &DefiLlamaClient,
+        _: Option<&HistoricalState>,
     ) -> anyhow::Result<Option<U256>> {
-        let raw_tvl =
-            TvlHandler::get_current_tvl(defillama_http_client.clone(), &payload.protocol).await?;
-        let scaled_tvl = raw_tvl
-            .checked_mul(Decimal::new(1e18 as i64, 0))
-            .context(format!(
-                "could not correctly scale tvl value {} to 18 decimals",
-                raw_tvl
-            ))?;
-        let converted: u128 = scaled_tvl.try_into().context(format!(
-            "could not correctly truncate tvl value {} to u128",
-            scaled_tvl
-        ))?;
-        Ok(Some(U256::from(converted)))
+        Ok(Some(
+            TvlHandler::get_current_tvl(defillama, &payload.protocol).await?,
+        ))
+    }
+}
+
+impl<'a> Fallback<'a, TvlPayload> for TvlHandler {
+    fn fallback(payload: &TvlPayload) -> anyhow::Result<Option<U256>> {
+        match &payload.fallback {
+            Some(fallback) => TvlHandler::scale_tvl(fallback)
+                .map(Some)
+                .context(format!("invalid fallback {}", fallback)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> Reference<'a, TvlPayload> for TvlHandler {
+    async fn reference(
+        payload: &TvlPayload,
+        defillama: &DefiLlamaClient,
+        timestamp: SystemTime,
+    ) -> anyhow::Result<Option<U256>> {
+        TvlHandler::get_historical_tvl(defillama, &payload.protocol, timestamp).await
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
 
-    use carrot_commons::http_client::HttpClient;
     use ethers::types::U256;
     use wiremock::{
         matchers::{method, path},
@@ -97,8 +123,8 @@ mod test {
     };
 
     use crate::{
-        commons::HTTP_TIMEOUT,
-        specification::{handlers::tvl::TvlHandler, Answer},
+        defillama::DefiLlamaClient,
+        specification::{handlers::tvl::TvlHandler, Answer, Fallback, Reference},
     };
 
     use super::TvlPayload;
@@ -108,21 +134,18 @@ mod test {
         let protocol = "foo".to_owned();
         let payload = TvlPayload {
             protocol: protocol.clone(),
+            fallback: None,
         };
 
         let defillama_mock_server = MockServer::start().await;
-        let defillama_http_client = Arc::new(
-            HttpClient::builder(defillama_mock_server.uri(), HTTP_TIMEOUT)
-                .build()
-                .unwrap(),
-        );
+        let defillama = DefiLlamaClient::mock(defillama_mock_server.uri());
         Mock::given(method("GET"))
             .and(path(format!("/tvl/{protocol}")))
             .respond_with(ResponseTemplate::new(400))
             .mount(&defillama_mock_server)
             .await;
 
-        assert!(TvlHandler::answer(&payload, defillama_http_client)
+        assert!(TvlHandler::answer(&payload, &defillama, None)
             .await
             .is_err());
     }
@@ -132,14 +155,11 @@ mod test {
         let protocol = "foo".to_owned();
         let payload = TvlPayload {
             protocol: protocol.clone(),
+            fallback: None,
         };
 
         let defillama_mock_server = MockServer::start().await;
-        let defillama_http_client = Arc::new(
-            HttpClient::builder(defillama_mock_server.uri(), HTTP_TIMEOUT)
-                .build()
-                .unwrap(),
-        );
+        let defillama = DefiLlamaClient::mock(defillama_mock_server.uri());
         Mock::given(method("GET"))
             .and(path(format!("/tvl/{protocol}")))
             .respond_with(ResponseTemplate::new(200).set_body_string("1234.5678"))
@@ -147,7 +167,7 @@ mod test {
             .await;
 
         assert_eq!(
-            TvlHandler::answer(&payload, defillama_http_client.clone())
+            TvlHandler::answer(&payload, &defillama, None)
                 .await
                 .unwrap(),
             Some(U256::from_dec_str("1234567800000000000000").unwrap())
@@ -167,10 +187,76 @@ mod test {
             .await;
 
         assert_eq!(
-            TvlHandler::answer(&payload, defillama_http_client)
+            TvlHandler::answer(&payload, &defillama, None)
                 .await
                 .unwrap(),
             Some(U256::from_dec_str("1234567891011121314151").unwrap())
         );
+
+        defillama_mock_server.reset().await;
+
+        // aggregate values overflowing a u128 once scaled are still fine
+        Mock::given(method("GET"))
+            .and(path(format!("/tvl/{protocol}")))
+            .respond_with(ResponseTemplate::new(200).set_body_string("1.5e+30"))
+            .mount(&defillama_mock_server)
+            .await;
+
+        assert_eq!(
+            TvlHandler::answer(&payload, &defillama, None)
+                .await
+                .unwrap(),
+            Some(U256::from_dec_str(&format!("15{}", "0".repeat(47))).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn reference_success() {
+        let protocol = "foo".to_owned();
+        let payload = TvlPayload {
+            protocol: protocol.clone(),
+            fallback: None,
+        };
+
+        let defillama_mock_server = MockServer::start().await;
+        let defillama = DefiLlamaClient::mock(defillama_mock_server.uri());
+        Mock::given(method("GET"))
+            .and(path(format!("/protocol/{protocol}")))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"name":"foo","tvl":[{"date":1000,"totalLiquidityUSD":10.5},{"date":2000,"totalLiquidityUSD":20.25},{"date":3000,"totalLiquidityUSD":30}]}"#,
+            ))
+            .mount(&defillama_mock_server)
+            .await;
+
+        assert_eq!(
+            TvlHandler::reference(&payload, &defillama, UNIX_EPOCH + Duration::from_secs(2500))
+                .await
+                .unwrap(),
+            Some(U256::from_dec_str("20250000000000000000").unwrap())
+        );
+        assert_eq!(
+            TvlHandler::reference(&payload, &defillama, UNIX_EPOCH + Duration::from_secs(500))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn fallback() {
+        let mut payload = TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        };
+        assert_eq!(TvlHandler::fallback(&payload).unwrap(), None);
+
+        payload.fallback = Some("1234.5678".to_owned());
+        assert_eq!(
+            TvlHandler::fallback(&payload).unwrap(),
+            Some(U256::from_dec_str("1234567800000000000000").unwrap())
+        );
+
+        payload.fallback = Some("foo".to_owned());
+        assert!(TvlHandler::fallback(&payload).is_err());
     }
 }
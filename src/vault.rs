@@ -0,0 +1,308 @@
+use std::{collections::HashMap, env, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use carrot_commons::http_client::HttpClient;
+use reqwest::Method;
+use serde::Deserialize;
+use tokio::time::sleep;
+
+use crate::commons::{
+    ChainConfig, Config, PrivateKeyConfig, VaultConfig, HTTP_TIMEOUT,
+    VAULT_TOKEN_RENEWAL_RETRY_INTERVAL,
+};
+
+// config values starting with this prefix are resolved from vault at startup. the
+// rest of the value is the secret's path and key, e.g. vault:answerer/gnosis#private_key
+const VAULT_REFERENCE_PREFIX: &str = "vault:";
+const DEFAULT_VAULT_TOKEN_ENV: &str = "VAULT_TOKEN";
+const DEFAULT_VAULT_MOUNT: &str = "secret";
+
+#[derive(Deserialize, Debug)]
+struct SecretData {
+    data: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SecretResponse {
+    data: SecretData,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenAuth {
+    lease_duration: u64,
+    renewable: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenRenewalResponse {
+    auth: TokenAuth,
+}
+
+// minimal client for vault's kv v2 secrets engine
+pub struct VaultClient {
+    http_client: HttpClient,
+    mount: String,
+}
+
+impl VaultClient {
+    pub fn new(config: &VaultConfig) -> anyhow::Result<Self> {
+        let token_env = config
+            .token_env
+            .as_deref()
+            .unwrap_or(DEFAULT_VAULT_TOKEN_ENV);
+        let token = env::var(token_env).context(format!(
+            "could not read vault token from env variable {}",
+            token_env
+        ))?;
+        Self::with_token(
+            &config.address,
+            token,
+            config.mount.as_deref().unwrap_or(DEFAULT_VAULT_MOUNT),
+        )
+    }
+
+    fn with_token(address: &str, token: String, mount: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            http_client: HttpClient::builder(address, HTTP_TIMEOUT)
+                .bearer_auth_token(token)
+                .build()?,
+            mount: mount.trim_matches('/').to_owned(),
+        })
+    }
+
+    pub async fn read_secret(&self, path: &str, key: &str) -> anyhow::Result<String> {
+        let path = path.trim_matches('/');
+        let mut response = self
+            .http_client
+            .request(Method::GET, format!("/v1/{}/data/{}", self.mount, path))
+            .await?
+            .send()
+            .await
+            .context(format!("could not read vault secret {}", path))?
+            .error_for_status()
+            .context(format!("could not read vault secret {}", path))?
+            .json::<SecretResponse>()
+            .await
+            .context(format!("could not deserialize vault secret {}", path))?;
+        match response.data.data.remove(key) {
+            Some(serde_json::Value::String(secret)) => Ok(secret),
+            Some(_) => anyhow::bail!("vault secret {} key {} is not a string", path, key),
+            None => anyhow::bail!("vault secret {} has no key {}", path, key),
+        }
+    }
+
+    // values that aren't vault references are left untouched
+    pub async fn resolve(&self, value: &mut String) -> anyhow::Result<()> {
+        let reference = match value.strip_prefix(VAULT_REFERENCE_PREFIX) {
+            Some(reference) => reference,
+            None => return Ok(()),
+        };
+        let (path, key) = reference.rsplit_once('#').context(format!(
+            "malformed vault reference {}, expected {}<path>#<key>",
+            value, VAULT_REFERENCE_PREFIX
+        ))?;
+        *value = self.read_secret(path, key).await?;
+        Ok(())
+    }
+
+    pub async fn resolve_config_secrets(&self, config: &mut Config) -> anyhow::Result<()> {
+        self.resolve(&mut config.db_connection_string)
+            .await
+            .context("could not resolve db connection string")?;
+        self.resolve(&mut config.data_manager.api_key)
+            .await
+            .context("could not resolve data manager api key")?;
+        for webhook in config.webhooks.iter_mut().flatten() {
+            self.resolve(&mut webhook.secret).await.context(format!(
+                "could not resolve secret of webhook {}",
+                webhook.url
+            ))?;
+        }
+        if let Some(slack_config) = config
+            .alerts
+            .as_mut()
+            .and_then(|alerts| alerts.slack.as_mut())
+        {
+            self.resolve(&mut slack_config.webhook_url)
+                .await
+                .context("could not resolve slack webhook url")?;
+        }
+        if let Some(telegram_config) = config
+            .alerts
+            .as_mut()
+            .and_then(|alerts| alerts.telegram.as_mut())
+        {
+            self.resolve(&mut telegram_config.bot_token)
+                .await
+                .context("could not resolve telegram bot token")?;
+        }
+        if let Some(discord_config) = config
+            .alerts
+            .as_mut()
+            .and_then(|alerts| alerts.discord.as_mut())
+        {
+            for (severity, webhook_url) in discord_config.webhook_urls.iter_mut() {
+                self.resolve(webhook_url).await.context(format!(
+                    "could not resolve {} discord webhook url",
+                    severity
+                ))?;
+            }
+        }
+        if let Some(pagerduty_config) = config
+            .alerts
+            .as_mut()
+            .and_then(|alerts| alerts.pagerduty.as_mut())
+        {
+            self.resolve(&mut pagerduty_config.routing_key)
+                .await
+                .context("could not resolve pagerduty routing key")?;
+        }
+        for (chain_id, chain_config) in config.chain_configs.iter_mut() {
+            self.resolve_chain_config_secrets(*chain_id, chain_config)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn resolve_chain_config_secrets(
+        &self,
+        chain_id: u64,
+        chain_config: &mut ChainConfig,
+    ) -> anyhow::Result<()> {
+        for private_key in chain_config.answerer_private_key.iter_mut().chain(
+            chain_config
+                .additional_answerer_private_keys
+                .iter_mut()
+                .flatten(),
+        ) {
+            if let PrivateKeyConfig::Raw(private_key) = private_key {
+                self.resolve(private_key).await.context(format!(
+                    "could not resolve answerer private key for chain {}",
+                    chain_id
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    // returns the token's new ttl, or none if the token can't be renewed
+    pub async fn renew_token(&self) -> anyhow::Result<Option<Duration>> {
+        let response = self
+            .http_client
+            .request(Method::POST, "/v1/auth/token/renew-self")
+            .await?
+            .send()
+            .await
+            .context("could not renew vault token")?
+            .error_for_status()
+            .context("could not renew vault token")?
+            .json::<TokenRenewalResponse>()
+            .await
+            .context("could not deserialize vault token renewal response")?;
+        Ok(response
+            .auth
+            .renewable
+            .then(|| Duration::from_secs(response.auth.lease_duration)))
+    }
+}
+
+// secrets are only read at startup, but the token is kept alive so that the service
+// can still be restarted with it
+pub async fn keep_vault_token_renewed(vault_client: Arc<VaultClient>) -> anyhow::Result<()> {
+    loop {
+        match vault_client.renew_token().await {
+            Ok(Some(ttl)) => {
+                tracing::debug!("vault token renewed with a {}s ttl", ttl.as_secs());
+                sleep((ttl / 2).max(Duration::from_secs(1))).await;
+            }
+            Ok(None) => {
+                tracing::info!("vault token is not renewable, not renewing it");
+                return Ok(());
+            }
+            Err(error) => {
+                tracing::error!("{:#}", error);
+                sleep(VAULT_TOKEN_RENEWAL_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::VaultClient;
+
+    #[tokio::test]
+    async fn resolve() {
+        let vault_mock_server = MockServer::start().await;
+        let vault_client =
+            VaultClient::with_token(&vault_mock_server.uri(), "token".to_owned(), "secret")
+                .unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/v1/secret/data/answerer/gnosis"))
+            .and(header("Authorization", "Bearer token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"data":{"data":{"private_key":"0x01"},"metadata":{"version":1}}}"#,
+            ))
+            .mount(&vault_mock_server)
+            .await;
+
+        let mut value = "vault:answerer/gnosis#private_key".to_owned();
+        vault_client.resolve(&mut value).await.unwrap();
+        assert_eq!(value, "0x01");
+
+        // not a reference
+        let mut value = "0x02".to_owned();
+        vault_client.resolve(&mut value).await.unwrap();
+        assert_eq!(value, "0x02");
+
+        // missing key
+        let mut value = "vault:answerer/gnosis#api_key".to_owned();
+        assert!(vault_client.resolve(&mut value).await.is_err());
+
+        // malformed reference
+        let mut value = "vault:answerer/gnosis".to_owned();
+        assert!(vault_client.resolve(&mut value).await.is_err());
+
+        // missing secret
+        let mut value = "vault:answerer/polygon#private_key".to_owned();
+        assert!(vault_client.resolve(&mut value).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn renew_token() {
+        let vault_mock_server = MockServer::start().await;
+        let vault_client =
+            VaultClient::with_token(&vault_mock_server.uri(), "token".to_owned(), "secret")
+                .unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/v1/auth/token/renew-self"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"auth":{"client_token":"token","lease_duration":3600,"renewable":true}}"#,
+            ))
+            .mount(&vault_mock_server)
+            .await;
+        assert_eq!(
+            vault_client.renew_token().await.unwrap(),
+            Some(Duration::from_secs(3_600))
+        );
+
+        vault_mock_server.reset().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/auth/token/renew-self"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"auth":{"client_token":"token","lease_duration":0,"renewable":false}}"#,
+            ))
+            .mount(&vault_mock_server)
+            .await;
+        assert_eq!(vault_client.renew_token().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use backoff::{future::retry, ExponentialBackoffBuilder};
+use ethers::utils::hex;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    commons::{WebhookConfig, HTTP_TIMEOUT, WEBHOOK_DELIVERY_MAX_ELAPSED_TIME},
+    events::{self, OracleEvent},
+};
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+
+// the timestamp is part of the signed payload so that receivers can reject old
+// deliveries being replayed
+pub fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac can take keys of any size"); // this should never panic
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn deliver(
+    client: &Client,
+    webhook: &WebhookConfig,
+    event: &OracleEvent,
+) -> anyhow::Result<()> {
+    let body = serde_json::to_string(event).context("could not serialize event")?;
+    retry(
+        ExponentialBackoffBuilder::new()
+            .with_max_elapsed_time(Some(WEBHOOK_DELIVERY_MAX_ELAPSED_TIME))
+            .build(),
+        || async {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            client
+                .post(webhook.url.as_str())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(SIGNATURE_HEADER, sign(&webhook.secret, timestamp, &body))
+                .body(body.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map(|_| ())
+                .map_err(|error| backoff::Error::transient(anyhow::Error::from(error)))
+        },
+    )
+    .await
+    .context(format!(
+        "could not deliver {} event for oracle {} to webhook {}",
+        event.kind.name(),
+        event.oracle_address,
+        webhook.url
+    ))
+}
+
+// deliveries to a single webhook happen one after the other, so that events are
+// received in the same order they happened in
+pub async fn deliver_webhook_events(webhook: WebhookConfig) -> anyhow::Result<()> {
+    let client = Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .context("could not build webhooks http client")?;
+    let mut events = events::subscribe();
+
+    tracing::info!(
+        "delivering {} oracle events to webhook {}",
+        webhook
+            .events
+            .as_ref()
+            .map(|events| events.join(", "))
+            .unwrap_or("all".to_owned()),
+        webhook.url
+    );
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "webhook {} is lagging behind, {} event(s) were skipped",
+                    webhook.url,
+                    skipped
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+        if webhook
+            .events
+            .as_ref()
+            .is_some_and(|events| !events.iter().any(|name| name == event.kind.name()))
+        {
+            continue;
+        }
+        if let Err(error) = deliver(&client, &webhook, &event).await {
+            tracing::error!("{:#}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::Address;
+    use reqwest::Client;
+    use wiremock::{
+        matchers::{body_json, header, header_exists, header_regex, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use crate::{
+        commons::WebhookConfig,
+        events::{OracleEvent, OracleEventKind},
+    };
+
+    use super::{deliver, sign, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+    #[test]
+    fn sign_payload() {
+        assert_eq!(
+            sign("secret", 1_700_000_000, r#"{"type":"detected"}"#),
+            "sha256=98b80756684b263e1f9a78dbf08bb8841952554327505fcafceac116e4923b98"
+        );
+        // both the timestamp and the secret are part of the signature
+        assert_ne!(
+            sign("secret", 1_700_000_000, ""),
+            sign("secret", 1_700_000_001, "")
+        );
+        assert_ne!(
+            sign("secret", 1_700_000_000, ""),
+            sign("other", 1_700_000_000, "")
+        );
+    }
+
+    #[tokio::test]
+    async fn deliver_event() {
+        let mock_server = MockServer::start().await;
+        let webhook = WebhookConfig {
+            url: format!("{}/hooks", mock_server.uri()),
+            secret: "secret".to_owned(),
+            events: None,
+        };
+        let event = OracleEvent {
+            chain_id: 100,
+            oracle_address: format!("0x{:x}", Address::zero()),
+            timestamp: 1_700_000_000,
+            kind: OracleEventKind::Expired,
+        };
+
+        Mock::given(method("POST"))
+            .and(path("/hooks"))
+            .and(header("Content-Type", "application/json"))
+            .and(header_regex(SIGNATURE_HEADER, "^sha256=[0-9a-f]{64}$"))
+            .and(header_exists(TIMESTAMP_HEADER))
+            .and(body_json(&event))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        deliver(&Client::new(), &webhook, &event).await.unwrap();
+    }
+}
@@ -1,21 +1,22 @@
 mod commons;
 
-use std::time::{Duration, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::commons::context::TestContext;
 use anyhow::Context;
 use defillama_answerer::{
     db::{
-        models::{self, ActiveOracle},
+        models::{self, ActiveOracle, ActiveOracleSort, ArchiveReason},
+        pagination::{Pagination, SortOrder},
         schema::active_oracles,
-        DbAddress, DbTxHash, DbU256,
+        DbAddress, DbConnection, DbTimestamp, DbTxHash, DbU256,
     },
     specification::{handlers::tvl::TvlPayload, Specification},
 };
 use diesel::prelude::*;
 use ethers::{
     abi::Address,
-    types::{H256, U256},
+    types::{TransactionReceipt, H256, U256, U64},
 };
 
 #[test]
@@ -25,22 +26,28 @@ fn test_to_from_sql_specification() {
     let active_oracle = ActiveOracle {
         address: DbAddress(Address::random()),
         chain_id: 100,
-        measurement_timestamp: UNIX_EPOCH,
+        measurement_timestamp: DbTimestamp(UNIX_EPOCH),
         specification: Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
-        expiration: Some(UNIX_EPOCH + Duration::from_secs(10)),
+        expiration: Some(DbTimestamp(UNIX_EPOCH + Duration::from_secs(10))),
         answer_tx_hash: None,
         answer: None,
+        answer_attempts: 0,
+        next_answer_attempt: None,
+        answer_tx_submitted_at: None,
+        source_missing_since: None,
+        defillama_snapshot_id: None,
     };
 
     models::ActiveOracle::create(
         &mut context.db_connection,
         active_oracle.address.0,
         active_oracle.chain_id as u64,
-        active_oracle.measurement_timestamp,
+        *active_oracle.measurement_timestamp,
         active_oracle.specification.clone(),
-        active_oracle.expiration.unwrap(),
+        *active_oracle.expiration.unwrap(),
     )
     .expect("could not save active oracle to database");
 
@@ -50,7 +57,7 @@ fn test_to_from_sql_specification() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    assert_eq!(oracles.into_iter().nth(0).unwrap(), active_oracle);
+    assert_eq!(oracles.into_iter().next().unwrap(), active_oracle);
 }
 
 // this also tests the answer update
@@ -62,22 +69,28 @@ fn test_to_from_sql_answer() {
     let active_oracle = ActiveOracle {
         address: DbAddress(Address::random()),
         chain_id: 100,
-        measurement_timestamp: UNIX_EPOCH,
+        measurement_timestamp: DbTimestamp(UNIX_EPOCH),
         specification: Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
-        expiration: Some(UNIX_EPOCH + Duration::from_secs(10)),
+        expiration: Some(DbTimestamp(UNIX_EPOCH + Duration::from_secs(10))),
         answer_tx_hash: None,
         answer: Some(DbU256(answer)),
+        answer_attempts: 0,
+        next_answer_attempt: None,
+        answer_tx_submitted_at: None,
+        source_missing_since: None,
+        defillama_snapshot_id: None,
     };
 
     let mut active_oracle = models::ActiveOracle::create(
         &mut context.db_connection,
         active_oracle.address.0,
         active_oracle.chain_id as u64,
-        active_oracle.measurement_timestamp,
+        *active_oracle.measurement_timestamp,
         active_oracle.specification.clone(),
-        active_oracle.expiration.unwrap(),
+        *active_oracle.expiration.unwrap(),
     )
     .expect("could not save active oracle to database");
 
@@ -88,7 +101,7 @@ fn test_to_from_sql_answer() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    assert_eq!(oracles.into_iter().nth(0).unwrap(), active_oracle);
+    assert_eq!(oracles.into_iter().next().unwrap(), active_oracle);
 
     // update the answer in the database
     active_oracle
@@ -103,7 +116,7 @@ fn test_to_from_sql_answer() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    assert_eq!(oracles.into_iter().nth(0).unwrap(), active_oracle);
+    assert_eq!(oracles.into_iter().next().unwrap(), active_oracle);
 }
 
 #[test]
@@ -117,6 +130,7 @@ fn test_answer_tx_hash_update() {
         UNIX_EPOCH,
         Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
         UNIX_EPOCH + Duration::from_secs(10),
     )
@@ -128,7 +142,7 @@ fn test_answer_tx_hash_update() {
         .load(&mut context.db_connection)
         .expect("could not get active oracle by null answer tx hash from database");
     assert_eq!(oracles.len(), 1);
-    assert_eq!(oracles.into_iter().nth(0).unwrap(), active_oracle);
+    assert_eq!(oracles.into_iter().next().unwrap(), active_oracle);
 
     let hash = H256::random();
     active_oracle
@@ -143,7 +157,7 @@ fn test_answer_tx_hash_update() {
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
 
-    let active_oracle_from_db = oracles.into_iter().nth(0).unwrap();
+    let active_oracle_from_db = oracles.into_iter().next().unwrap();
     assert_eq!(active_oracle_from_db, active_oracle);
 
     // check that there are no more oracles with a null answer tx hash
@@ -169,6 +183,7 @@ fn test_answer_tx_hash_update_multiple_oracles() {
         UNIX_EPOCH,
         Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
         UNIX_EPOCH + Duration::from_secs(10),
     )
@@ -180,6 +195,7 @@ fn test_answer_tx_hash_update_multiple_oracles() {
         UNIX_EPOCH,
         Specification::Tvl(TvlPayload {
             protocol: "bar".to_owned(),
+            fallback: None,
         }),
         UNIX_EPOCH + Duration::from_secs(10),
     )
@@ -218,10 +234,17 @@ fn test_answer_tx_hash_update_multiple_oracles() {
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 2);
 
-    let active_oracle_1_from_db = &oracles[1];
+    // row ordering is not guaranteed, so look the oracles up by address
+    let active_oracle_1_from_db = oracles
+        .iter()
+        .find(|oracle| oracle.address == active_oracle_1.address)
+        .expect("could not find oracle 1");
     assert_eq!(active_oracle_1_from_db, &active_oracle_1);
 
-    let active_oracle_2_from_db = &oracles[0];
+    let active_oracle_2_from_db = oracles
+        .iter()
+        .find(|oracle| oracle.address == active_oracle_2.address)
+        .expect("could not find oracle 2");
     assert_eq!(active_oracle_2_from_db, &active_oracle_2);
 
     // check that there is only one oracle with a null answer tx hash now
@@ -244,13 +267,19 @@ fn test_answer_tx_hash_deletion() {
     let active_oracle = ActiveOracle {
         address: DbAddress(Address::random()),
         chain_id: 100,
-        measurement_timestamp: UNIX_EPOCH,
+        measurement_timestamp: DbTimestamp(UNIX_EPOCH),
         specification: Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
-        expiration: Some(UNIX_EPOCH + Duration::from_secs(10)),
+        expiration: Some(DbTimestamp(UNIX_EPOCH + Duration::from_secs(10))),
         answer_tx_hash: Some(DbTxHash(H256::random())),
         answer: None,
+        answer_attempts: 0,
+        next_answer_attempt: None,
+        answer_tx_submitted_at: None,
+        source_missing_since: None,
+        defillama_snapshot_id: None,
     };
     diesel::insert_into(active_oracles::table)
         .values(&active_oracle)
@@ -265,7 +294,7 @@ fn test_answer_tx_hash_deletion() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    let mut oracle_from_db = oracles.into_iter().nth(0).unwrap();
+    let mut oracle_from_db = oracles.into_iter().next().unwrap();
     assert_eq!(oracle_from_db, active_oracle);
 
     // remove its tx hash
@@ -281,7 +310,7 @@ fn test_answer_tx_hash_deletion() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    let updated_oracle_from_db = oracles.into_iter().nth(0).unwrap();
+    let updated_oracle_from_db = oracles.into_iter().next().unwrap();
     assert_eq!(updated_oracle_from_db, oracle_from_db);
     assert!(updated_oracle_from_db.answer_tx_hash.is_none());
 }
@@ -294,13 +323,19 @@ fn test_answer_deletion() {
     let active_oracle = ActiveOracle {
         address: DbAddress(Address::random()),
         chain_id: 100,
-        measurement_timestamp: UNIX_EPOCH,
+        measurement_timestamp: DbTimestamp(UNIX_EPOCH),
         specification: Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
-        expiration: Some(UNIX_EPOCH + Duration::from_secs(10)),
+        expiration: Some(DbTimestamp(UNIX_EPOCH + Duration::from_secs(10))),
         answer_tx_hash: Some(DbTxHash(H256::random())),
         answer: None,
+        answer_attempts: 0,
+        next_answer_attempt: None,
+        answer_tx_submitted_at: None,
+        source_missing_since: None,
+        defillama_snapshot_id: None,
     };
     diesel::insert_into(active_oracles::table)
         .values(&active_oracle)
@@ -314,7 +349,7 @@ fn test_answer_deletion() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    let mut oracle_from_db = oracles.into_iter().nth(0).unwrap();
+    let mut oracle_from_db = oracles.into_iter().next().unwrap();
     assert_eq!(oracle_from_db, active_oracle);
 
     // remove its answer
@@ -330,7 +365,7 @@ fn test_answer_deletion() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    let updated_oracle_from_db = oracles.into_iter().nth(0).unwrap();
+    let updated_oracle_from_db = oracles.into_iter().next().unwrap();
     assert_eq!(updated_oracle_from_db, oracle_from_db);
     assert!(updated_oracle_from_db.answer.is_none());
 }
@@ -347,6 +382,7 @@ fn test_expiration_update() {
         UNIX_EPOCH,
         Specification::Tvl(TvlPayload {
             protocol: "foo".to_owned(),
+            fallback: None,
         }),
         old_expiration,
     )
@@ -358,11 +394,11 @@ fn test_expiration_update() {
     )
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
-    assert_eq!(oracles.into_iter().nth(0).unwrap(), active_oracle);
+    assert_eq!(oracles.into_iter().next().unwrap(), active_oracle);
 
     assert_eq!(
         active_oracles::table
-            .filter(active_oracles::dsl::expiration.eq(old_expiration))
+            .filter(active_oracles::dsl::expiration.eq(DbTimestamp(old_expiration)))
             .select(ActiveOracle::as_select())
             .load(&mut context.db_connection)
             .expect("could not get active oracle by old expiration from database")
@@ -383,12 +419,12 @@ fn test_expiration_update() {
     .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
 
-    let active_oracle_from_db = oracles.into_iter().nth(0).unwrap();
+    let active_oracle_from_db = oracles.into_iter().next().unwrap();
     assert_eq!(active_oracle_from_db, active_oracle);
 
     assert_eq!(
         active_oracles::table
-            .filter(active_oracles::dsl::expiration.eq(old_expiration))
+            .filter(active_oracles::dsl::expiration.eq(DbTimestamp(old_expiration)))
             .select(ActiveOracle::as_select())
             .load(&mut context.db_connection)
             .expect("could not get active oracle by old expiration from database")
@@ -396,3 +432,812 @@ fn test_expiration_update() {
         0
     );
 }
+
+#[test]
+fn test_schedule_answer_retry() {
+    let mut context = TestContext::new("active_oracle_schedule_answer_retry");
+
+    let mut active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        100,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    assert_eq!(active_oracle.answer_attempts, 0);
+    assert!(active_oracle.next_answer_attempt.is_none());
+
+    // schedule a retry in the future, the oracle shouldn't be answerable anymore
+    let next_answer_attempt = SystemTime::now() + Duration::from_secs(3_600);
+    active_oracle
+        .schedule_answer_retry(&mut context.db_connection, next_answer_attempt)
+        .expect("could not schedule answer retry");
+    assert_eq!(active_oracle.answer_attempts, 1);
+
+    let oracles = models::ActiveOracle::get_all_answerable_for_chain_id(
+        &mut context.db_connection,
+        active_oracle.chain_id as u64,
+    )
+    .expect("could not get active oracles from database");
+    assert!(oracles.is_empty());
+
+    // clearing the retry makes the oracle answerable right away
+    active_oracle
+        .clear_answer_retry(&mut context.db_connection)
+        .expect("could not clear answer retry");
+    assert_eq!(active_oracle.answer_attempts, 1);
+    assert!(active_oracle.next_answer_attempt.is_none());
+    let oracles = models::ActiveOracle::get_all_answerable_for_chain_id(
+        &mut context.db_connection,
+        active_oracle.chain_id as u64,
+    )
+    .expect("could not get active oracles from database");
+    assert_eq!(oracles.len(), 1);
+
+    // schedule a retry in the past, the oracle should be answerable again
+    active_oracle
+        .schedule_answer_retry(&mut context.db_connection, UNIX_EPOCH)
+        .expect("could not schedule answer retry");
+    assert_eq!(active_oracle.answer_attempts, 2);
+
+    let oracles = models::ActiveOracle::get_all_answerable_for_chain_id(
+        &mut context.db_connection,
+        active_oracle.chain_id as u64,
+    )
+    .expect("could not get active oracles from database");
+    assert_eq!(oracles.len(), 1);
+    assert_eq!(oracles.into_iter().next().unwrap(), active_oracle);
+}
+
+#[test]
+fn test_get_next_measurement_timestamp() {
+    let mut context = TestContext::new("active_oracle_get_next_measurement_timestamp");
+
+    let chain_id = 100;
+    let next_measurement_timestamp =
+        models::ActiveOracle::get_next_measurement_timestamp_for_chain_id(
+            &mut context.db_connection,
+            chain_id,
+        )
+        .expect("could not get next measurement timestamp");
+    assert!(next_measurement_timestamp.is_none());
+
+    // timestamps are stored with seconds precision
+    let now = UNIX_EPOCH
+        + Duration::from_secs(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        );
+    for measurement_timestamp in [
+        UNIX_EPOCH,
+        now + Duration::from_secs(3_600),
+        now + Duration::from_secs(600),
+    ] {
+        models::ActiveOracle::create(
+            &mut context.db_connection,
+            Address::random(),
+            chain_id,
+            measurement_timestamp,
+            Specification::Tvl(TvlPayload {
+                protocol: "foo".to_owned(),
+                fallback: None,
+            }),
+            now + Duration::from_secs(7_200),
+        )
+        .expect("could not save active oracle to database");
+    }
+
+    // past measurement timestamps are ignored
+    let next_measurement_timestamp =
+        models::ActiveOracle::get_next_measurement_timestamp_for_chain_id(
+            &mut context.db_connection,
+            chain_id,
+        )
+        .expect("could not get next measurement timestamp");
+    assert_eq!(
+        next_measurement_timestamp,
+        Some(now + Duration::from_secs(600))
+    );
+
+    // other chains are ignored
+    let next_measurement_timestamp =
+        models::ActiveOracle::get_next_measurement_timestamp_for_chain_id(
+            &mut context.db_connection,
+            1,
+        )
+        .expect("could not get next measurement timestamp");
+    assert!(next_measurement_timestamp.is_none());
+}
+
+#[test]
+fn test_claim_and_release() {
+    let mut context = TestContext::new("active_oracle_claim_and_release");
+
+    let active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        100,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+
+    let claim_expiration = active_oracle
+        .claim(&mut context.db_connection, Duration::from_secs(60))
+        .expect("could not claim active oracle")
+        .expect("could not acquire active oracle claim");
+    // a claimed oracle can't be claimed again until the claim is released
+    assert!(active_oracle
+        .claim(&mut context.db_connection, Duration::from_secs(60))
+        .expect("could not claim active oracle")
+        .is_none());
+
+    models::ActiveOracle::release_claim(
+        &mut context.db_connection,
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+        claim_expiration,
+    )
+    .expect("could not release active oracle claim");
+    let expired_claim_expiration = active_oracle
+        .claim(&mut context.db_connection, Duration::ZERO)
+        .expect("could not claim active oracle")
+        .expect("could not acquire active oracle claim");
+
+    // or until the claim expires
+    let claim_expiration = active_oracle
+        .claim(&mut context.db_connection, Duration::from_secs(60))
+        .expect("could not claim active oracle")
+        .expect("could not acquire active oracle claim");
+
+    // releasing a claim that expired and was taken over leaves the new one alone
+    models::ActiveOracle::release_claim(
+        &mut context.db_connection,
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+        expired_claim_expiration,
+    )
+    .expect("could not release active oracle claim");
+    assert!(active_oracle
+        .claim(&mut context.db_connection, Duration::from_secs(60))
+        .expect("could not claim active oracle")
+        .is_none());
+
+    models::ActiveOracle::release_claim(
+        &mut context.db_connection,
+        active_oracle.chain_id as u64,
+        active_oracle.address.0,
+        claim_expiration,
+    )
+    .expect("could not release active oracle claim");
+    assert!(active_oracle
+        .claim(&mut context.db_connection, Duration::from_secs(60))
+        .expect("could not claim active oracle")
+        .is_some());
+}
+
+#[test]
+fn test_claim_next_answerable() {
+    let mut context = TestContext::new("active_oracle_claim_next_answerable");
+
+    let create = |connection: &mut DbConnection, measurement_timestamp| {
+        models::ActiveOracle::create(
+            connection,
+            Address::random(),
+            100,
+            measurement_timestamp,
+            Specification::Tvl(TvlPayload {
+                protocol: "foo".to_owned(),
+                fallback: None,
+            }),
+            SystemTime::now() + Duration::from_secs(3_600),
+        )
+        .expect("could not save active oracle to database")
+    };
+    let older = create(&mut context.db_connection, UNIX_EPOCH);
+    let newer = create(
+        &mut context.db_connection,
+        UNIX_EPOCH + Duration::from_secs(10),
+    );
+
+    let claim_next = |connection: &mut DbConnection, skipped: &[Address]| {
+        models::ActiveOracle::claim_next_answerable_for_chain_id(
+            connection,
+            100,
+            Duration::from_secs(60),
+            skipped,
+        )
+        .expect("could not claim next answerable oracle")
+        .map(|(active_oracle, claim_expiration)| (active_oracle.address.0, claim_expiration))
+    };
+
+    // oracles are claimed oldest first, and claimed ones are not picked again
+    let (address, older_claim_expiration) =
+        claim_next(&mut context.db_connection, &[]).expect("could not claim older oracle");
+    assert_eq!(address, older.address.0);
+    let (address, newer_claim_expiration) =
+        claim_next(&mut context.db_connection, &[]).expect("could not claim newer oracle");
+    assert_eq!(address, newer.address.0);
+    assert_eq!(claim_next(&mut context.db_connection, &[]), None);
+
+    for (active_oracle, claim_expiration) in [
+        (&older, older_claim_expiration),
+        (&newer, newer_claim_expiration),
+    ] {
+        models::ActiveOracle::release_claim(
+            &mut context.db_connection,
+            100,
+            active_oracle.address.0,
+            claim_expiration,
+        )
+        .expect("could not release active oracle claim");
+    }
+
+    // skipped oracles are left alone
+    let (address, newer_claim_expiration) =
+        claim_next(&mut context.db_connection, &[older.address.0])
+            .expect("could not claim newer oracle");
+    assert_eq!(address, newer.address.0);
+    models::ActiveOracle::release_claim(
+        &mut context.db_connection,
+        100,
+        newer.address.0,
+        newer_claim_expiration,
+    )
+    .expect("could not release active oracle claim");
+
+    // rows locked by another instance are skipped instead of waited on
+    #[cfg(feature = "postgres")]
+    DbConnection::establish(&context.db_url)
+        .expect("could not establish connection to database")
+        .transaction(|other_db_connection| {
+            active_oracles::table
+                .find((&older.address, &older.chain_id))
+                .select(active_oracles::dsl::address)
+                .for_update()
+                .execute(other_db_connection)?;
+            assert_eq!(
+                claim_next(&mut context.db_connection, &[]).map(|(address, _)| address),
+                Some(newer.address.0)
+            );
+            Ok::<_, diesel::result::Error>(())
+        })
+        .expect("could not lock active oracle");
+}
+
+#[test]
+fn test_get_all_with_answer_tx_submitted_before() {
+    let mut context = TestContext::new("active_oracle_get_all_with_answer_tx_submitted_before");
+
+    let chain_id = 100;
+    let mut active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+
+    // no tx hash yet
+    let oracles = models::ActiveOracle::get_all_with_answer_tx_submitted_before(
+        &mut context.db_connection,
+        chain_id,
+        SystemTime::now() + Duration::from_secs(10),
+    )
+    .expect("could not get active oracles from database");
+    assert!(oracles.is_empty());
+
+    active_oracle
+        .update_answer_tx_hash(&mut context.db_connection, H256::random())
+        .expect("could not update answer tx hash");
+    assert!(active_oracle.answer_tx_submitted_at.is_some());
+
+    // the tx was submitted after the given timestamp
+    let oracles = models::ActiveOracle::get_all_with_answer_tx_submitted_before(
+        &mut context.db_connection,
+        chain_id,
+        SystemTime::now() - Duration::from_secs(10),
+    )
+    .expect("could not get active oracles from database");
+    assert!(oracles.is_empty());
+
+    let oracles = models::ActiveOracle::get_all_with_answer_tx_submitted_before(
+        &mut context.db_connection,
+        chain_id,
+        SystemTime::now() + Duration::from_secs(10),
+    )
+    .expect("could not get active oracles from database");
+    assert_eq!(oracles, vec![active_oracle]);
+}
+
+#[test]
+fn test_mark_and_clear_source_missing() {
+    let mut context = TestContext::new("active_oracle_mark_and_clear_source_missing");
+
+    let chain_id = 100;
+    let mut active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+
+    let next_answer_attempt = SystemTime::now() + Duration::from_secs(3_600);
+    active_oracle
+        .mark_source_missing(&mut context.db_connection, next_answer_attempt)
+        .expect("could not mark source as missing");
+    let source_missing_since = active_oracle
+        .source_missing_since
+        .expect("source not marked as missing");
+
+    // the oracle isn't answerable until the next check
+    assert!(models::ActiveOracle::get_all_answerable_for_chain_id(
+        &mut context.db_connection,
+        chain_id
+    )
+    .expect("could not get answerable active oracles from database")
+    .is_empty());
+
+    // marking again keeps the first detection timestamp
+    active_oracle
+        .mark_source_missing(&mut context.db_connection, next_answer_attempt)
+        .expect("could not mark source as missing");
+    let stored_active_oracle = models::ActiveOracle::get(
+        &mut context.db_connection,
+        chain_id,
+        active_oracle.address.0,
+    )
+    .expect("could not get active oracle from database")
+    .expect("active oracle not found");
+    assert_eq!(
+        stored_active_oracle.source_missing_since,
+        Some(source_missing_since)
+    );
+
+    active_oracle
+        .clear_source_missing(&mut context.db_connection)
+        .expect("could not clear missing source");
+    let stored_active_oracle = models::ActiveOracle::get(
+        &mut context.db_connection,
+        chain_id,
+        active_oracle.address.0,
+    )
+    .expect("could not get active oracle from database")
+    .expect("active oracle not found");
+    assert!(stored_active_oracle.source_missing_since.is_none());
+}
+
+#[test]
+fn test_get_all_with_answer_tx_hash() {
+    let mut context = TestContext::new("active_oracle_get_all_with_answer_tx_hash");
+
+    let chain_id = 100;
+    let mut active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "bar".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+
+    assert!(models::ActiveOracle::get_all_with_answer_tx_hash(
+        &mut context.db_connection,
+        chain_id
+    )
+    .expect("could not get active oracles from database")
+    .is_empty());
+
+    active_oracle
+        .update_answer_tx_hash(&mut context.db_connection, H256::random())
+        .expect("could not update answer tx hash");
+    let oracles =
+        models::ActiveOracle::get_all_with_answer_tx_hash(&mut context.db_connection, chain_id)
+            .expect("could not get active oracles from database");
+    assert_eq!(oracles, vec![active_oracle]);
+}
+
+#[test]
+fn test_archive() {
+    let mut context = TestContext::new("active_oracle_archive");
+
+    let chain_id = 100;
+    let active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    let address = active_oracle.address.0;
+
+    active_oracle
+        .archive(&mut context.db_connection, ArchiveReason::Expired)
+        .expect("could not archive active oracle");
+
+    assert!(
+        models::ActiveOracle::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get active oracle from database")
+            .is_none()
+    );
+    let archived_oracles =
+        models::ArchivedOracle::get_all_for_oracle(&mut context.db_connection, chain_id, address)
+            .expect("could not get archived oracles from database");
+    assert_eq!(archived_oracles.len(), 1);
+    assert_eq!(archived_oracles[0].reason, "expired");
+    assert_eq!(
+        archived_oracles[0].expiration,
+        Some(DbTimestamp(UNIX_EPOCH + Duration::from_secs(10)))
+    );
+}
+
+#[test]
+fn test_remove() {
+    let mut context = TestContext::new("active_oracle_remove");
+
+    let chain_id = 100;
+    let active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    let address = active_oracle.address.0;
+    assert!(
+        !models::ArchivedOracle::is_removed(&mut context.db_connection, chain_id, address)
+            .expect("could not check whether the oracle was removed")
+    );
+
+    active_oracle
+        .remove(&mut context.db_connection, "alice", "campaign cancelled")
+        .expect("could not remove active oracle");
+
+    assert!(
+        models::ActiveOracle::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get active oracle from database")
+            .is_none()
+    );
+    assert!(
+        models::ArchivedOracle::is_removed(&mut context.db_connection, chain_id, address)
+            .expect("could not check whether the oracle was removed")
+    );
+    let audit_log =
+        models::AuditLogEntry::get_all_for_oracle(&mut context.db_connection, chain_id, address)
+            .expect("could not get audit log from database");
+    assert_eq!(audit_log.len(), 1);
+    assert_eq!(audit_log[0].action, "oracle_removed");
+    assert_eq!(audit_log[0].actor, "alice");
+    assert_eq!(
+        *audit_log[0].details,
+        serde_json::json!({ "reason": "campaign cancelled" })
+    );
+}
+
+#[test]
+fn test_archive_answered() {
+    let mut context = TestContext::new("active_oracle_archive_answered");
+
+    let chain_id = 100;
+    let mut active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    let address = active_oracle.address.0;
+    active_oracle
+        .update_answer(&mut context.db_connection, U256::from(42))
+        .expect("could not update answer");
+    active_oracle
+        .update_defillama_snapshot_id(&mut context.db_connection, 7)
+        .expect("could not update defillama snapshot id");
+    let tx_hash = H256::random();
+    let receipt = TransactionReceipt {
+        transaction_hash: tx_hash,
+        block_number: Some(U64::from(1_000)),
+        gas_used: Some(U256::from(21_000)),
+        effective_gas_price: Some(U256::from(2)),
+        ..Default::default()
+    };
+
+    let answered_oracle_id = active_oracle
+        .archive_answered(
+            &mut context.db_connection,
+            tx_hash,
+            Some(&receipt),
+            Some(0.5),
+        )
+        .expect("could not archive answered oracle");
+    models::AnsweredOracle::update_proof_bundle_cid(
+        &mut context.db_connection,
+        answered_oracle_id,
+        "bafkfoo",
+    )
+    .expect("could not update proof bundle cid");
+
+    assert!(
+        models::ActiveOracle::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get active oracle from database")
+            .is_none()
+    );
+    let answered_oracles =
+        models::AnsweredOracle::get_all_for_oracle(&mut context.db_connection, chain_id, address)
+            .expect("could not get answered oracles from database");
+    assert_eq!(answered_oracles.len(), 1);
+    assert_eq!(answered_oracles[0].answer, Some(DbU256(U256::from(42))));
+    assert_eq!(answered_oracles[0].answer_tx_hash, DbTxHash(tx_hash));
+    assert_eq!(answered_oracles[0].block_number, Some(1_000));
+    assert_eq!(answered_oracles[0].fee, Some(DbU256(U256::from(42_000))));
+    assert_eq!(answered_oracles[0].fee_usd, Some(0.5));
+    assert_eq!(answered_oracles[0].defillama_snapshot_id, Some(7));
+    assert_eq!(answered_oracles[0].id, answered_oracle_id);
+    assert_eq!(
+        answered_oracles[0].proof_bundle_cid,
+        Some("bafkfoo".to_owned())
+    );
+
+    // reorged answer txs never finalized the oracle
+    models::AnsweredOracle::delete_for_answer_tx(&mut context.db_connection, chain_id, tx_hash)
+        .expect("could not delete answered oracle");
+    assert!(models::AnsweredOracle::get_all_for_oracle(
+        &mut context.db_connection,
+        chain_id,
+        address
+    )
+    .expect("could not get answered oracles from database")
+    .is_empty());
+}
+
+#[test]
+fn test_get_all_expired_for_chain_id() {
+    let mut context = TestContext::new("active_oracle_get_all_expired_for_chain_id");
+
+    let chain_id = 100;
+    let expired_active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "foo".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "bar".to_owned(),
+            fallback: None,
+        }),
+        SystemTime::now() + Duration::from_secs(3_600),
+    )
+    .expect("could not save active oracle to database");
+    let mut pending_active_oracle = models::ActiveOracle::create(
+        &mut context.db_connection,
+        Address::random(),
+        chain_id,
+        UNIX_EPOCH,
+        Specification::Tvl(TvlPayload {
+            protocol: "baz".to_owned(),
+            fallback: None,
+        }),
+        UNIX_EPOCH + Duration::from_secs(10),
+    )
+    .expect("could not save active oracle to database");
+    pending_active_oracle
+        .update_answer_tx_hash(&mut context.db_connection, H256::random())
+        .expect("could not update answer tx hash");
+
+    // oracles with a pending answer tx are left alone
+    let oracles =
+        models::ActiveOracle::get_all_expired_for_chain_id(&mut context.db_connection, chain_id)
+            .expect("could not get active oracles from database");
+    assert_eq!(oracles, vec![expired_active_oracle]);
+}
+
+#[test]
+fn test_get_all_filtered() {
+    let mut context = TestContext::new("active_oracle_get_all_filtered");
+
+    let mut oracles = Vec::new();
+    for (chain_id, measurement_timestamp) in [(100, 10), (100, 20), (1, 30)] {
+        oracles.push(
+            models::ActiveOracle::create(
+                &mut context.db_connection,
+                Address::random(),
+                chain_id,
+                UNIX_EPOCH + Duration::from_secs(measurement_timestamp),
+                Specification::Tvl(TvlPayload {
+                    protocol: "foo".to_owned(),
+                    fallback: None,
+                }),
+                UNIX_EPOCH + Duration::from_secs(3_600),
+            )
+            .expect("could not save active oracle to database"),
+        );
+    }
+    let filter = models::RecordFilter {
+        limit: 10,
+        ..Default::default()
+    };
+
+    // no filters, ordered by measurement timestamp
+    let filtered = models::ActiveOracle::get_all_filtered(&mut context.db_connection, &filter)
+        .expect("could not get active oracles from database");
+    assert_eq!(filtered, oracles);
+
+    let filtered = models::ActiveOracle::get_all_filtered(
+        &mut context.db_connection,
+        &models::RecordFilter {
+            chain_id: Some(100),
+            from: Some(UNIX_EPOCH + Duration::from_secs(15)),
+            ..filter.clone()
+        },
+    )
+    .expect("could not get active oracles from database");
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0], oracles[1]);
+
+    let filtered = models::ActiveOracle::get_all_filtered(
+        &mut context.db_connection,
+        &models::RecordFilter {
+            oracle_address: Some(oracles[2].address.0),
+            ..filter.clone()
+        },
+    )
+    .expect("could not get active oracles from database");
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0], oracles[2]);
+
+    // paging
+    let filtered = models::ActiveOracle::get_all_filtered(
+        &mut context.db_connection,
+        &models::RecordFilter {
+            limit: 1,
+            offset: 1,
+            ..filter
+        },
+    )
+    .expect("could not get active oracles from database");
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0], oracles[1]);
+}
+
+#[test]
+fn test_get_page_for_chain_id() {
+    let mut context = TestContext::new("active_oracle_get_page_for_chain_id");
+
+    let mut oracles = Vec::new();
+    for (chain_id, measurement_timestamp) in [(100, 30), (100, 10), (100, 20), (1, 40)] {
+        oracles.push(
+            models::ActiveOracle::create(
+                &mut context.db_connection,
+                Address::random(),
+                chain_id,
+                UNIX_EPOCH + Duration::from_secs(measurement_timestamp),
+                Specification::Tvl(TvlPayload {
+                    protocol: "foo".to_owned(),
+                    fallback: None,
+                }),
+                UNIX_EPOCH + Duration::from_secs(3_600),
+            )
+            .expect("could not save active oracle to database"),
+        );
+    }
+    oracles[2]
+        .schedule_answer_retry(
+            &mut context.db_connection,
+            UNIX_EPOCH + Duration::from_secs(60),
+        )
+        .expect("could not schedule answer retry");
+    let pagination = Pagination {
+        limit: 2,
+        offset: 0,
+        sort: ActiveOracleSort::default(),
+        order: SortOrder::default(),
+    };
+
+    // sorted by measurement timestamp by default, the total ignoring the limit
+    let page =
+        models::ActiveOracle::get_page_for_chain_id(&mut context.db_connection, 100, &pagination)
+            .expect("could not get active oracles page from database");
+    assert_eq!(page.total, 3);
+    assert_eq!(page.records, vec![oracles.remove(1), oracles.remove(1)]);
+
+    let page = models::ActiveOracle::get_page_for_chain_id(
+        &mut context.db_connection,
+        100,
+        &Pagination {
+            offset: 2,
+            ..pagination
+        },
+    )
+    .expect("could not get active oracles page from database");
+    assert_eq!(page.total, 3);
+    assert_eq!(page.records, vec![oracles.remove(0)]);
+
+    let page = models::ActiveOracle::get_page_for_chain_id(
+        &mut context.db_connection,
+        100,
+        &Pagination {
+            sort: ActiveOracleSort::AnswerAttempts,
+            order: SortOrder::Descending,
+            ..pagination
+        },
+    )
+    .expect("could not get active oracles page from database");
+    assert_eq!(page.records[0].answer_attempts, 1);
+
+    // the total is unknown past the last page
+    let page = models::ActiveOracle::get_page_for_chain_id(
+        &mut context.db_connection,
+        100,
+        &Pagination {
+            offset: 3,
+            ..pagination
+        },
+    )
+    .expect("could not get active oracles page from database");
+    assert!(page.records.is_empty());
+    assert_eq!(page.total, 0);
+}
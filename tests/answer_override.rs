@@ -0,0 +1,132 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::{
+    models::{self, AuditAction},
+    DbU256,
+};
+use ethers::types::{Address, U256};
+
+#[test]
+fn test_propose_and_approve() {
+    let mut context = TestContext::new("answer_override_propose_and_approve");
+
+    let chain_id = 100;
+    let address = Address::random();
+
+    assert!(
+        models::AnswerOverride::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get answer override from database")
+            .is_none()
+    );
+
+    models::AnswerOverride::propose(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(10),
+        "wrong tvl".to_owned(),
+        "alice".to_owned(),
+    )
+    .expect("could not propose answer override");
+
+    // proposing again replaces the previous override
+    models::AnswerOverride::propose(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(20),
+        "wrong tvl, take 2".to_owned(),
+        "alice".to_owned(),
+    )
+    .expect("could not propose answer override");
+
+    let mut answer_override =
+        models::AnswerOverride::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get answer override from database")
+            .expect("answer override not found");
+    assert_eq!(answer_override.answer, DbU256(U256::from(20)));
+    assert!(!answer_override.is_approved());
+
+    // the proposer can't approve their own override
+    assert!(answer_override
+        .approve(&mut context.db_connection, "alice".to_owned())
+        .is_err());
+
+    answer_override
+        .approve(&mut context.db_connection, "bob".to_owned())
+        .expect("could not approve answer override");
+    let answer_override =
+        models::AnswerOverride::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get answer override from database")
+            .expect("answer override not found");
+    assert!(answer_override.is_approved());
+    assert_eq!(answer_override.approved_by, Some("bob".to_owned()));
+
+    let audit_log =
+        models::AuditLogEntry::get_all_for_oracle(&mut context.db_connection, chain_id, address)
+            .expect("could not get audit log from database");
+    assert_eq!(
+        audit_log
+            .iter()
+            .map(|entry| (entry.action.as_str(), entry.actor.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (AuditAction::AnswerOverrideProposed.as_str(), "alice"),
+            (AuditAction::AnswerOverrideProposed.as_str(), "alice"),
+            (AuditAction::AnswerOverrideApproved.as_str(), "bob"),
+        ]
+    );
+}
+
+#[test]
+fn test_approve_replaced() {
+    let mut context = TestContext::new("answer_override_approve_replaced");
+
+    let chain_id = 100;
+    let address = Address::random();
+
+    models::AnswerOverride::propose(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(10),
+        "wrong tvl".to_owned(),
+        "alice".to_owned(),
+    )
+    .expect("could not propose answer override");
+    let mut answer_override =
+        models::AnswerOverride::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get answer override from database")
+            .expect("answer override not found");
+
+    // the proposal is replaced by the approver's own before they approve the one they saw
+    models::AnswerOverride::propose(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(20),
+        "wrong tvl, take 2".to_owned(),
+        "bob".to_owned(),
+    )
+    .expect("could not propose answer override");
+
+    assert!(answer_override
+        .approve(&mut context.db_connection, "bob".to_owned())
+        .is_err());
+    assert!(!answer_override.is_approved());
+
+    let answer_override =
+        models::AnswerOverride::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get answer override from database")
+            .expect("answer override not found");
+    assert_eq!(answer_override.answer, DbU256(U256::from(20)));
+    assert!(!answer_override.is_approved());
+
+    let audit_log =
+        models::AuditLogEntry::get_all_for_oracle(&mut context.db_connection, chain_id, address)
+            .expect("could not get audit log from database");
+    assert!(audit_log
+        .iter()
+        .all(|entry| entry.action != AuditAction::AnswerOverrideApproved.as_str()));
+}
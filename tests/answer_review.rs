@@ -0,0 +1,73 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::{
+    models::{self, AuditAction},
+    DbU256,
+};
+use ethers::types::{Address, U256};
+
+#[test]
+fn test_create() {
+    let mut context = TestContext::new("answer_review_create");
+
+    let chain_id = 100;
+    let address = Address::random();
+
+    assert!(
+        models::AnswerReview::get(&mut context.db_connection, chain_id, address)
+            .expect("could not get answer review from database")
+            .is_none()
+    );
+
+    models::AnswerReview::create(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(1_000),
+        U256::from(10),
+    )
+    .expect("could not create answer review");
+
+    // holding the oracle again replaces the previous review
+    models::AnswerReview::create(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(2_000),
+        U256::from(20),
+    )
+    .expect("could not create answer review");
+
+    let answer_review = models::AnswerReview::get(&mut context.db_connection, chain_id, address)
+        .expect("could not get answer review from database")
+        .expect("answer review not found");
+    assert_eq!(answer_review.answer, DbU256(U256::from(2_000)));
+    assert_eq!(answer_review.reference_answer, DbU256(U256::from(20)));
+
+    assert_eq!(
+        models::AnswerReview::get_all_for_chain_id(&mut context.db_connection, chain_id)
+            .expect("could not get answer reviews from database")
+            .len(),
+        1
+    );
+    assert!(
+        models::AnswerReview::get_all_for_chain_id(&mut context.db_connection, chain_id + 1)
+            .expect("could not get answer reviews from database")
+            .is_empty()
+    );
+
+    let audit_log =
+        models::AuditLogEntry::get_all_for_oracle(&mut context.db_connection, chain_id, address)
+            .expect("could not get audit log from database");
+    assert_eq!(
+        audit_log
+            .iter()
+            .map(|entry| (entry.action.as_str(), entry.actor.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (AuditAction::AnswerHeldForReview.as_str(), "answerer"),
+            (AuditAction::AnswerHeldForReview.as_str(), "answerer"),
+        ]
+    );
+}
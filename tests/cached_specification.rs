@@ -0,0 +1,46 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::{
+    db::models,
+    specification::{handlers::tvl::TvlPayload, Specification},
+};
+
+#[test]
+fn test_create_get() {
+    let mut context = TestContext::new("cached_specification_create_get");
+
+    assert!(
+        models::CachedSpecification::get(&mut context.db_connection, "bafkfoo")
+            .expect("could not get cached specification from database")
+            .is_none()
+    );
+
+    let specification = Specification::Tvl(TvlPayload {
+        protocol: "foo".to_owned(),
+        fallback: None,
+    });
+    models::CachedSpecification::create(
+        &mut context.db_connection,
+        "bafkfoo",
+        specification.clone(),
+    )
+    .expect("could not cache specification");
+
+    // documents are immutable, caching one again is a no-op
+    models::CachedSpecification::create(
+        &mut context.db_connection,
+        "bafkfoo",
+        Specification::Tvl(TvlPayload {
+            protocol: "bar".to_owned(),
+            fallback: None,
+        }),
+    )
+    .expect("could not cache specification");
+
+    let cached = models::CachedSpecification::get(&mut context.db_connection, "bafkfoo")
+        .expect("could not get cached specification from database")
+        .expect("cached specification not found");
+    assert_eq!(cached.cid, "bafkfoo");
+    assert_eq!(cached.specification, specification);
+}
@@ -20,7 +20,7 @@ fn test_find() {
     assert_eq!(
         checkpoint,
         Some(Checkpoint {
-            chain_id: chain_id as i32,
+            chain_id,
             block_number
         })
     );
@@ -30,3 +30,67 @@ fn test_find() {
         .expect("could not get checkpoint from database");
     assert!(checkpoint.is_none());
 }
+
+#[test]
+fn test_get_all() {
+    let mut context = TestContext::new("get_all_checkpoints");
+
+    models::Checkpoint::update(&mut context.db_connection, 137, 20)
+        .expect("could not save checkpoint to database");
+    models::Checkpoint::update(&mut context.db_connection, 100, 10)
+        .expect("could not save checkpoint to database");
+
+    let checkpoints = models::Checkpoint::get_all(&mut context.db_connection)
+        .expect("could not get checkpoints from database");
+    assert_eq!(
+        checkpoints,
+        vec![
+            Checkpoint {
+                chain_id: 100,
+                block_number: 10
+            },
+            Checkpoint {
+                chain_id: 137,
+                block_number: 20
+            }
+        ]
+    );
+}
+
+#[test]
+fn test_reset() {
+    let mut context = TestContext::new("reset_checkpoint");
+
+    models::Checkpoint::reset(&mut context.db_connection, 100, 10, "operator")
+        .expect("could not reset checkpoint");
+    models::Checkpoint::reset(&mut context.db_connection, 100, 5, "operator")
+        .expect("could not reset checkpoint");
+
+    let checkpoint = models::Checkpoint::get_for_chain_id(&mut context.db_connection, 100)
+        .expect("could not get checkpoint from database");
+    assert_eq!(
+        checkpoint,
+        Some(Checkpoint {
+            chain_id: 100,
+            block_number: 5
+        })
+    );
+
+    // every reset is recorded along with the block it moved the checkpoint from
+    let entries = models::AuditLogEntry::get_all_for_chain(&mut context.db_connection, 100)
+        .expect("could not get audit log entries from database");
+    assert_eq!(entries.len(), 2);
+    assert!(entries
+        .iter()
+        .all(|entry| entry.action == "checkpoint_reset"
+            && entry.actor == "operator"
+            && entry.oracle_address.is_none()));
+    assert_eq!(
+        *entries[0].details,
+        serde_json::json!({ "from": null, "to": 10 })
+    );
+    assert_eq!(
+        *entries[1].details,
+        serde_json::json!({ "from": 10, "to": 5 })
+    );
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "postgres")]
+
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::{commons::DbPoolConfig, db};
+use diesel::{sql_types::Text, QueryableByName, RunQueryDsl};
+
+#[derive(QueryableByName)]
+struct Setting {
+    #[diesel(sql_type = Text)]
+    statement_timeout: String,
+}
+
+#[test]
+fn test_connect_with_pool_config() {
+    let mut context = TestContext::new("db_pool_connect_with_pool_config");
+    let url = context.db_url.clone();
+
+    let db_connection_pool = db::connect(
+        &url,
+        &DbPoolConfig {
+            max_size: Some(2),
+            min_idle: Some(1),
+            connection_timeout_seconds: Some(5),
+            statement_timeout_seconds: Some(3),
+            connect_retries: None,
+        },
+    )
+    .expect("could not connect to database");
+    assert_eq!(db_connection_pool.max_size(), 2);
+    assert_eq!(db_connection_pool.min_idle(), Some(1));
+
+    let mut db_connection = db_connection_pool
+        .get()
+        .expect("could not get connection from pool");
+    let setting: Setting = diesel::sql_query("SHOW statement_timeout")
+        .get_result(&mut db_connection)
+        .expect("could not get statement timeout");
+    assert_eq!(setting.statement_timeout, "3s");
+    // connections established outside of the pool are left alone
+    let setting: Setting = diesel::sql_query("SHOW statement_timeout")
+        .get_result(&mut context.db_connection)
+        .expect("could not get statement timeout");
+    assert_eq!(setting.statement_timeout, "0");
+    drop(db_connection);
+    drop(db_connection_pool);
+
+    // a min idle value over the max size is rejected instead of making r2d2 panic
+    assert!(db::connect(
+        &url,
+        &DbPoolConfig {
+            max_size: Some(1),
+            min_idle: Some(2),
+            ..Default::default()
+        },
+    )
+    .is_err());
+}
@@ -0,0 +1,89 @@
+mod commons;
+
+use std::time::SystemTime;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::{
+    db::{models, DbU256},
+    defillama::RecordedResponse,
+    specification::{handlers::tvl::TvlPayload, Specification},
+};
+use ethers::types::{Address, U256};
+
+#[test]
+fn test_create_and_get_latest() {
+    let mut context = TestContext::new("defillama_snapshot_create_and_get_latest");
+
+    let chain_id = 100;
+    let oracle_address = Address::random();
+    let specification = Specification::Tvl(TvlPayload {
+        protocol: "foo".to_owned(),
+        fallback: None,
+    });
+
+    // no snapshots yet
+    assert!(models::DefiLlamaSnapshot::get_latest_for_oracle(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address
+    )
+    .expect("could not get latest defillama snapshot")
+    .is_none());
+
+    let responses = vec![RecordedResponse {
+        path: "/tvl/foo".to_owned(),
+        url: None,
+        body: "1".to_owned(),
+        timestamp: SystemTime::UNIX_EPOCH,
+    }];
+    models::DefiLlamaSnapshot::create(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address,
+        specification.clone(),
+        responses,
+        None,
+    )
+    .expect("could not save defillama snapshot to database");
+
+    let responses = vec![RecordedResponse {
+        path: "/tvl/foo".to_owned(),
+        url: Some("https://api.llama.fi/tvl/foo".to_owned()),
+        body: "2".to_owned(),
+        timestamp: SystemTime::UNIX_EPOCH,
+    }];
+    let latest = models::DefiLlamaSnapshot::create(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address,
+        specification.clone(),
+        responses.clone(),
+        Some(U256::from(2)),
+    )
+    .expect("could not save defillama snapshot to database");
+
+    let snapshot = models::DefiLlamaSnapshot::get_latest_for_oracle(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address,
+    )
+    .expect("could not get latest defillama snapshot")
+    .expect("no defillama snapshot found");
+    assert_eq!(snapshot, latest);
+    assert_eq!(snapshot.specification, specification);
+    assert_eq!(snapshot.answer, Some(DbU256(U256::from(2))));
+    assert_eq!(
+        snapshot
+            .recorded_responses()
+            .expect("could not deserialize recorded responses"),
+        responses
+    );
+
+    // snapshots can also be fetched by id, as linked from the oracles answered with them
+    assert_eq!(
+        models::DefiLlamaSnapshot::get(&mut context.db_connection, latest.id)
+            .expect("could not get defillama snapshot")
+            .as_ref(),
+        Some(&latest)
+    );
+}
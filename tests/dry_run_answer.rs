@@ -0,0 +1,51 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::{models, DbAddress, DbU256};
+use ethers::types::{Address, U256};
+
+#[test]
+fn test_create_and_overwrite() {
+    let mut context = TestContext::new("dry_run_answer_create_and_overwrite");
+
+    let chain_id = 100;
+    let address = Address::random();
+
+    models::DryRunAnswer::create(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(10),
+    )
+    .expect("could not save dry run answer to database");
+
+    // a dry run answer on another chain shouldn't be taken into account
+    models::DryRunAnswer::create(&mut context.db_connection, 1, address, U256::from(30))
+        .expect("could not save dry run answer to database");
+
+    // recomputing the answer for the same oracle overwrites the previous one
+    models::DryRunAnswer::create(
+        &mut context.db_connection,
+        chain_id,
+        address,
+        U256::from(20),
+    )
+    .expect("could not save dry run answer to database");
+
+    let dry_run_answers =
+        models::DryRunAnswer::get_all_for_chain_id(&mut context.db_connection, chain_id)
+            .expect("could not get dry run answers from database");
+    assert_eq!(dry_run_answers.len(), 1);
+    assert_eq!(dry_run_answers[0].oracle_address, DbAddress(address));
+    assert_eq!(dry_run_answers[0].answer, DbU256(U256::from(20)));
+
+    let dry_run_answer = models::DryRunAnswer::get(&mut context.db_connection, chain_id, address)
+        .expect("could not get dry run answer from database")
+        .expect("dry run answer not found");
+    assert_eq!(dry_run_answer.answer, DbU256(U256::from(20)));
+    assert!(
+        models::DryRunAnswer::get(&mut context.db_connection, chain_id, Address::random())
+            .expect("could not get dry run answer from database")
+            .is_none()
+    );
+}
@@ -0,0 +1,66 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::{
+    db::models,
+    feature_gates::{Feature, FeatureGates},
+};
+
+#[test]
+fn test_set_and_delete() {
+    let mut context = TestContext::new("feature_gate_set_and_delete");
+
+    let chain_id = 100;
+    let feature = Feature::AnswerRetryBackoff;
+
+    // no gates, the default applies
+    let feature_gates = FeatureGates::load(&mut context.db_connection, chain_id)
+        .expect("could not load feature gates");
+    assert_eq!(feature_gates.is_enabled(feature), feature.default_enabled());
+
+    // toggle the gate both ways, upserting the same row
+    for enabled in [!feature.default_enabled(), feature.default_enabled()] {
+        models::FeatureGate::set(
+            &mut context.db_connection,
+            chain_id,
+            feature.as_str(),
+            enabled,
+        )
+        .expect("could not set feature gate");
+        let feature_gates = FeatureGates::load(&mut context.db_connection, chain_id)
+            .expect("could not load feature gates");
+        assert_eq!(feature_gates.is_enabled(feature), enabled);
+    }
+    assert_eq!(
+        models::FeatureGate::get_all_for_chain_id(&mut context.db_connection, chain_id)
+            .expect("could not get feature gates")
+            .len(),
+        1
+    );
+
+    // gates are scoped to their chain
+    models::FeatureGate::set(
+        &mut context.db_connection,
+        1,
+        feature.as_str(),
+        !feature.default_enabled(),
+    )
+    .expect("could not set feature gate");
+    let feature_gates = FeatureGates::load(&mut context.db_connection, chain_id)
+        .expect("could not load feature gates");
+    assert_eq!(feature_gates.is_enabled(feature), feature.default_enabled());
+
+    // deleting the gate restores the default
+    models::FeatureGate::set(
+        &mut context.db_connection,
+        chain_id,
+        feature.as_str(),
+        !feature.default_enabled(),
+    )
+    .expect("could not set feature gate");
+    models::FeatureGate::delete(&mut context.db_connection, chain_id, feature.as_str())
+        .expect("could not delete feature gate");
+    let feature_gates = FeatureGates::load(&mut context.db_connection, chain_id)
+        .expect("could not load feature gates");
+    assert_eq!(feature_gates.is_enabled(feature), feature.default_enabled());
+}
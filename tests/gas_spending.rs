@@ -0,0 +1,226 @@
+mod commons;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::models;
+use ethers::types::{Address, TransactionReceipt, H256, U256};
+
+fn receipt(gas_used: u64, effective_gas_price: u64) -> TransactionReceipt {
+    TransactionReceipt {
+        transaction_hash: H256::random(),
+        gas_used: Some(U256::from(gas_used)),
+        effective_gas_price: Some(U256::from(effective_gas_price)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_get_total_for_chain_id_since() {
+    let mut context = TestContext::new("gas_spending_get_total_for_chain_id_since");
+
+    let chain_id = 100;
+    let before = SystemTime::now() - Duration::from_secs(10);
+
+    // no spendings yet
+    let total = models::GasSpending::get_total_for_chain_id_since(
+        &mut context.db_connection,
+        chain_id,
+        UNIX_EPOCH,
+    )
+    .expect("could not get total gas spending from database");
+    assert_eq!(total, U256::zero());
+
+    for fee in [1_000u64, 2_000u64] {
+        models::GasSpending::create(
+            &mut context.db_connection,
+            chain_id,
+            Address::random(),
+            None,
+            &receipt(fee, 1),
+            None,
+        )
+        .expect("could not save gas spending to database");
+    }
+
+    // a spending on another chain shouldn't be taken into account
+    models::GasSpending::create(
+        &mut context.db_connection,
+        1,
+        Address::random(),
+        None,
+        &receipt(1_000, 5),
+        Some(1.5),
+    )
+    .expect("could not save gas spending to database");
+
+    let total = models::GasSpending::get_total_for_chain_id_since(
+        &mut context.db_connection,
+        chain_id,
+        before,
+    )
+    .expect("could not get total gas spending from database");
+    assert_eq!(total, U256::from(3_000));
+
+    // spendings before the given timestamp shouldn't be taken into account
+    let total = models::GasSpending::get_total_for_chain_id_since(
+        &mut context.db_connection,
+        chain_id,
+        SystemTime::now() + Duration::from_secs(10),
+    )
+    .expect("could not get total gas spending from database");
+    assert_eq!(total, U256::zero());
+}
+
+#[test]
+fn test_get_all_for_chain_id_between() {
+    let mut context = TestContext::new("gas_spending_get_all_for_chain_id_between");
+
+    let chain_id = 100;
+    let oracle_address = Address::random();
+    let kpi_token_address = Address::random();
+    let receipt = receipt(21_000, 2);
+
+    models::GasSpending::create(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address,
+        Some(kpi_token_address),
+        &receipt,
+        Some(0.5),
+    )
+    .expect("could not save gas spending to database");
+
+    // receipts without gas data can't be accounted for
+    assert!(models::GasSpending::create(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address,
+        None,
+        &TransactionReceipt::default(),
+        None,
+    )
+    .is_err());
+
+    let gas_spendings = models::GasSpending::get_all_for_chain_id_between(
+        &mut context.db_connection,
+        chain_id,
+        UNIX_EPOCH,
+        SystemTime::now() + Duration::from_secs(10),
+    )
+    .expect("could not get gas spendings from database");
+    assert_eq!(gas_spendings.len(), 1);
+    assert_eq!(gas_spendings[0].tx_hash.0, receipt.transaction_hash);
+    assert_eq!(gas_spendings[0].fee.0, U256::from(42_000));
+    assert_eq!(
+        gas_spendings[0]
+            .gas_used
+            .as_ref()
+            .map(|gas_used| gas_used.0),
+        Some(U256::from(21_000))
+    );
+    assert_eq!(
+        gas_spendings[0]
+            .effective_gas_price
+            .as_ref()
+            .map(|effective_gas_price| effective_gas_price.0),
+        Some(U256::from(2))
+    );
+    assert_eq!(
+        gas_spendings[0].kpi_token_address.map(|address| address.0),
+        Some(kpi_token_address)
+    );
+
+    assert!(models::GasSpending::get_all_for_chain_id_between(
+        &mut context.db_connection,
+        chain_id,
+        SystemTime::now() + Duration::from_secs(10),
+        SystemTime::now() + Duration::from_secs(20),
+    )
+    .expect("could not get gas spendings from database")
+    .is_empty());
+}
+
+#[test]
+fn test_get_all_filtered_and_get() {
+    let mut context = TestContext::new("gas_spending_get_all_filtered_and_get");
+
+    let chain_id = 100;
+    let oracle_address = Address::random();
+    let kpi_token_address = Address::random();
+    let first_receipt = receipt(21_000, 1);
+    let second_receipt = receipt(21_000, 2);
+    models::GasSpending::create(
+        &mut context.db_connection,
+        chain_id,
+        oracle_address,
+        Some(kpi_token_address),
+        &first_receipt,
+        None,
+    )
+    .expect("could not save gas spending to database");
+    models::GasSpending::create(
+        &mut context.db_connection,
+        chain_id,
+        Address::random(),
+        None,
+        &second_receipt,
+        None,
+    )
+    .expect("could not save gas spending to database");
+
+    let filter = models::RecordFilter {
+        chain_id: Some(chain_id),
+        limit: 10,
+        ..Default::default()
+    };
+    let gas_spendings =
+        models::GasSpending::get_all_filtered(&mut context.db_connection, &filter, None)
+            .expect("could not get gas spendings from database");
+    assert_eq!(gas_spendings.len(), 2);
+
+    let gas_spendings = models::GasSpending::get_all_filtered(
+        &mut context.db_connection,
+        &filter,
+        Some(kpi_token_address),
+    )
+    .expect("could not get gas spendings from database");
+    assert_eq!(gas_spendings.len(), 1);
+    assert_eq!(gas_spendings[0].tx_hash.0, first_receipt.transaction_hash);
+
+    let gas_spendings = models::GasSpending::get_all_filtered(
+        &mut context.db_connection,
+        &models::RecordFilter {
+            oracle_address: Some(oracle_address),
+            ..filter.clone()
+        },
+        None,
+    )
+    .expect("could not get gas spendings from database");
+    assert_eq!(gas_spendings.len(), 1);
+
+    assert!(models::GasSpending::get_all_filtered(
+        &mut context.db_connection,
+        &models::RecordFilter {
+            from: Some(SystemTime::now() + Duration::from_secs(10)),
+            ..filter
+        },
+        None,
+    )
+    .expect("could not get gas spendings from database")
+    .is_empty());
+
+    let gas_spending = models::GasSpending::get(
+        &mut context.db_connection,
+        chain_id,
+        second_receipt.transaction_hash,
+    )
+    .expect("could not get gas spending from database")
+    .expect("gas spending not found");
+    assert_eq!(gas_spending.fee.0, U256::from(42_000));
+    assert!(
+        models::GasSpending::get(&mut context.db_connection, 1, H256::random())
+            .expect("could not get gas spending from database")
+            .is_none()
+    );
+}
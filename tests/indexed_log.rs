@@ -0,0 +1,74 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::models;
+use ethers::types::{Address, Bytes, Log, H256, U256, U64};
+
+fn log(block_number: u64, log_index: u64) -> Log {
+    Log {
+        address: Address::random(),
+        topics: vec![H256::random(), H256::random()],
+        data: Bytes::from(vec![1, 2, 3]),
+        block_hash: Some(H256::random()),
+        block_number: Some(U64::from(block_number)),
+        transaction_hash: Some(H256::random()),
+        log_index: Some(U256::from(log_index)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_create_and_get() {
+    let mut context = TestContext::new("indexed_log_create_and_get");
+
+    let chain_id = 100;
+    let first_log = log(10, 1);
+    let second_log = log(20, 0);
+
+    models::IndexedLog::create(&mut context.db_connection, chain_id, &second_log)
+        .expect("could not save indexed log to database");
+    models::IndexedLog::create(&mut context.db_connection, chain_id, &first_log)
+        .expect("could not save indexed log to database");
+    // logs scanned again are only stored once
+    models::IndexedLog::create(&mut context.db_connection, chain_id, &first_log)
+        .expect("could not save indexed log to database");
+
+    let logs = models::IndexedLog::get_all_for_chain_id_between(
+        &mut context.db_connection,
+        chain_id,
+        0,
+        20,
+    )
+    .expect("could not get indexed logs from database")
+    .into_iter()
+    .map(|indexed_log| indexed_log.to_log().expect("could not convert indexed log"))
+    .collect::<Vec<_>>();
+    assert_eq!(logs, vec![first_log.clone(), second_log]);
+
+    let logs = models::IndexedLog::get_all_for_chain_id_between(
+        &mut context.db_connection,
+        chain_id,
+        0,
+        15,
+    )
+    .expect("could not get indexed logs from database");
+    assert_eq!(logs.len(), 1);
+
+    assert!(models::IndexedLog::get_all_for_chain_id_between(
+        &mut context.db_connection,
+        chain_id + 1,
+        0,
+        20
+    )
+    .expect("could not get indexed logs from database")
+    .is_empty());
+}
+
+#[test]
+fn test_create_pending() {
+    let mut context = TestContext::new("indexed_log_create_pending");
+
+    let mut pending_log = log(10, 0);
+    pending_log.transaction_hash = None;
+    assert!(models::IndexedLog::create(&mut context.db_connection, 100, &pending_log).is_err());
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "postgres")]
+
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::{
+    events::{OracleEvent, OracleEventKind},
+    pg_notify::notify,
+};
+use diesel::RunQueryDsl;
+
+fn event(kind: OracleEventKind) -> OracleEvent {
+    OracleEvent {
+        chain_id: 100,
+        oracle_address: "0x0000000000000000000000000000000000000001".to_owned(),
+        timestamp: 1_700_000_000,
+        kind,
+    }
+}
+
+#[test]
+fn test_notify() {
+    let mut context = TestContext::new("pg_notify_notify");
+
+    diesel::sql_query("LISTEN oracle_events")
+        .execute(&mut context.db_connection)
+        .expect("could not listen on channel");
+    notify(
+        &mut context.db_connection,
+        "oracle_events",
+        &event(OracleEventKind::Finalized {
+            tx_hash: Some("0x01".to_owned()),
+        }),
+    )
+    .expect("could not notify event");
+
+    // payloads are limited to 8000 bytes by postgres
+    assert!(notify(
+        &mut context.db_connection,
+        "oracle_events",
+        &event(OracleEventKind::Failed {
+            reason: "a".repeat(8_000),
+        }),
+    )
+    .is_err());
+
+    // which doesn't prevent further notifications
+    notify(
+        &mut context.db_connection,
+        "oracle_events",
+        &event(OracleEventKind::Detected),
+    )
+    .expect("could not notify event");
+}
@@ -0,0 +1,42 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::models;
+
+#[test]
+fn test_try_acquire() {
+    let mut context = TestContext::new("rate_limit_bucket_try_acquire");
+
+    // refilling this slowly, no token is added back during the test
+    for _ in 0..3 {
+        assert!(models::RateLimitBucket::try_acquire(
+            &mut context.db_connection,
+            "foo",
+            3.0,
+            0.0001
+        )
+        .expect("could not acquire token"));
+    }
+    assert!(
+        !models::RateLimitBucket::try_acquire(&mut context.db_connection, "foo", 3.0, 0.0001)
+            .expect("could not acquire token")
+    );
+
+    let bucket = models::RateLimitBucket::get(&mut context.db_connection, "foo")
+        .expect("could not get rate limit bucket from database")
+        .expect("rate limit bucket not found");
+    assert!(bucket.tokens < 1.0);
+
+    // buckets are independent
+    assert!(
+        models::RateLimitBucket::try_acquire(&mut context.db_connection, "bar", 1.0, 0.0001)
+            .expect("could not acquire token")
+    );
+
+    // a fast refill makes tokens available again right away
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert!(
+        models::RateLimitBucket::try_acquire(&mut context.db_connection, "foo", 3.0, 1_000.0)
+            .expect("could not acquire token")
+    );
+}
@@ -0,0 +1,41 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::models;
+use ethers::types::Address;
+
+fn factories(context: &mut TestContext, chain_id: u64) -> Vec<Address> {
+    let mut factories: Vec<Address> =
+        models::ScannedFactory::get_all_for_chain_id(&mut context.db_connection, chain_id)
+            .expect("could not get scanned factories from database")
+            .into_iter()
+            .map(|factory| factory.address.0)
+            .collect();
+    factories.sort();
+    factories
+}
+
+#[test]
+fn test_record() {
+    let mut context = TestContext::new("scanned_factory_record");
+
+    let chain_id = 100;
+    let mut addresses = vec![Address::random(), Address::random()];
+    addresses.sort();
+    for address in addresses.iter() {
+        models::ScannedFactory::record(&mut context.db_connection, chain_id, *address)
+            .expect("could not record scanned factory");
+    }
+    models::ScannedFactory::record(&mut context.db_connection, 1, Address::random())
+        .expect("could not record scanned factory");
+
+    assert_eq!(factories(&mut context, chain_id), addresses);
+
+    // recording a factory twice changes nothing
+    models::ScannedFactory::record(&mut context.db_connection, chain_id, addresses[0])
+        .expect("could not record scanned factory");
+    assert_eq!(factories(&mut context, chain_id), addresses);
+
+    // other chains are untouched
+    assert_eq!(factories(&mut context, 1).len(), 1);
+}
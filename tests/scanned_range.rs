@@ -0,0 +1,44 @@
+mod commons;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::models;
+
+fn ranges(context: &mut TestContext, chain_id: u64) -> Vec<(i64, i64)> {
+    models::ScannedRange::get_all_for_chain_id(&mut context.db_connection, chain_id)
+        .expect("could not get scanned ranges from database")
+        .into_iter()
+        .map(|range| (range.from_block, range.to_block))
+        .collect()
+}
+
+#[test]
+fn test_record() {
+    let mut context = TestContext::new("scanned_range_record");
+
+    let chain_id = 100;
+    for (from_block, to_block) in [(10, 19), (40, 49), (20, 29), (100, 109)] {
+        models::ScannedRange::record(&mut context.db_connection, chain_id, from_block, to_block)
+            .expect("could not record scanned range");
+    }
+    models::ScannedRange::record(&mut context.db_connection, 1, 0, 1_000)
+        .expect("could not record scanned range");
+
+    // adjacent ranges are merged
+    assert_eq!(
+        ranges(&mut context, chain_id),
+        vec![(10, 29), (40, 49), (100, 109)]
+    );
+
+    // a range overlapping multiple ones merges all of them
+    models::ScannedRange::record(&mut context.db_connection, chain_id, 25, 100)
+        .expect("could not record scanned range");
+    assert_eq!(ranges(&mut context, chain_id), vec![(10, 109)]);
+
+    // ranges already covered change nothing
+    models::ScannedRange::record(&mut context.db_connection, chain_id, 50, 60)
+        .expect("could not record scanned range");
+    assert_eq!(ranges(&mut context, chain_id), vec![(10, 109)]);
+
+    // other chains are untouched
+    assert_eq!(ranges(&mut context, 1), vec![(0, 1_000)]);
+}
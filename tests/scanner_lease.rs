@@ -0,0 +1,40 @@
+mod commons;
+
+use std::time::Duration;
+
+use crate::commons::context::TestContext;
+use defillama_answerer::db::models;
+
+#[test]
+fn test_try_acquire() {
+    let mut context = TestContext::new("scanner_lease_try_acquire");
+
+    let try_acquire = |connection: &mut _, chain_id, holder, duration| {
+        models::ScannerLease::try_acquire(connection, chain_id, holder, duration)
+            .expect("could not acquire scanner lease")
+    };
+
+    let lease = Duration::from_secs(60);
+    assert!(try_acquire(&mut context.db_connection, 100, "foo", lease));
+    // the holder can renew its own lease, while others can't take it over
+    assert!(try_acquire(&mut context.db_connection, 100, "foo", lease));
+    assert!(!try_acquire(&mut context.db_connection, 100, "bar", lease));
+    // leases are per chain
+    assert!(try_acquire(&mut context.db_connection, 1, "bar", lease));
+
+    let scanner_lease = models::ScannerLease::get(&mut context.db_connection, 100)
+        .expect("could not get scanner lease from database")
+        .expect("scanner lease not found");
+    assert_eq!(scanner_lease.holder, "foo");
+
+    // an expired lease can be taken over
+    assert!(try_acquire(
+        &mut context.db_connection,
+        100,
+        "foo",
+        Duration::ZERO
+    ));
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(try_acquire(&mut context.db_connection, 100, "bar", lease));
+    assert!(!try_acquire(&mut context.db_connection, 100, "foo", lease));
+}
@@ -16,6 +16,7 @@ fn test_to_from_sql() {
     let chain_id = 100;
     let specification = Specification::Tvl(TvlPayload {
         protocol: "foo".to_owned(),
+        fallback: None,
     });
 
     models::ActiveOracle::create(
@@ -33,7 +34,7 @@ fn test_to_from_sql() {
             .expect("could not get active oracles from database");
     assert_eq!(oracles.len(), 1);
     assert_eq!(
-        oracles.into_iter().nth(0).unwrap().specification,
+        oracles.into_iter().next().unwrap().specification,
         specification
     );
 }